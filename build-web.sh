@@ -0,0 +1,11 @@
+#!/bin/sh
+# Builds the WebGPU version of the viewer into `web/`, serve that directory with any static file
+# server, e.g. `python3 -m http.server -d web`.
+#
+# Needs the `wasm32-unknown-unknown` target and a `wasm-bindgen` CLI matching the `wasm-bindgen`
+# version in `Cargo.lock` (`cargo install wasm-bindgen-cli --version <version>`).
+set -e
+
+cargo build --release --target wasm32-unknown-unknown "$@"
+wasm-bindgen --target web --no-typescript --out-dir web/pkg \
+  target/wasm32-unknown-unknown/release/shadertoys-wgpu.wasm
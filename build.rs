@@ -1,11 +1,51 @@
 use spirv_builder::{MetadataPrintout, SpirvBuilder};
-use std::error::Error;
+use std::{env, error::Error, fs, path::Path};
 
 fn build_shader(path_to_crate: &str) -> Result<(), Box<dyn Error>> {
   let builder = SpirvBuilder::new(path_to_crate, "spirv-unknown-vulkan1.2")
     .print_metadata(MetadataPrintout::Full);
 
-  let _result = builder.build()?;
+  let result = builder.build()?;
+  if env::var("CARGO_CFG_TARGET_ARCH")? == "wasm32" {
+    translate_to_wgsl(result.module.unwrap_single())?;
+  }
+  Ok(())
+}
+
+/// WebGPU only accepts WGSL and has no push constants, so drop the `main_fs` entry point
+/// (leaving `main_fs_uniform`) and translate the rest of the module with naga.
+fn translate_to_wgsl(spirv_path: &Path) -> Result<(), Box<dyn Error>> {
+  use naga::{back::wgsl, front::spv, valid, AddressSpace};
+
+  let spirv = fs::read(spirv_path)?;
+  let mut module = spv::parse_u8_slice(
+    &spirv,
+    &spv::Options {
+      adjust_coordinate_space: false,
+      strict_capabilities: false,
+      block_ctx_dump_prefix: None,
+    },
+  )?;
+  module
+    .entry_points
+    .retain(|entry_point| entry_point.name != "main_fs");
+  // naga keeps unused globals around, demote the now unused push constants so the WGSL stays
+  // valid.
+  for (_, global) in module.global_variables.iter_mut() {
+    if global.space == AddressSpace::PushConstant {
+      global.space = AddressSpace::Private;
+    }
+  }
+  let info = valid::Validator::new(valid::ValidationFlags::all(), valid::Capabilities::empty())
+    .validate(&module)?;
+  let wgsl = wgsl::write_string(&module, &info, wgsl::WriterFlags::empty())?;
+
+  let wgsl_path = Path::new(&env::var("OUT_DIR")?).join("shadertoys_shaders.wgsl");
+  fs::write(&wgsl_path, wgsl)?;
+  println!(
+    "cargo:rustc-env=shadertoys_shaders.wgsl={}",
+    wgsl_path.display()
+  );
   Ok(())
 }
 
@@ -0,0 +1,64 @@
+//! Shadertoy's audio input: a channel with a 512x2 texture, the spectrum of the music in the
+//! first row and its waveform in the second, in the red channel. The host analyses a WAV file
+//! into it at the time of the frame.
+//!
+//! [`SyntheticAudio`] stands in for it when there is no music, so that visualizers still move.
+
+use crate::shader_prelude::*;
+
+/// Texels per row of the audio texture.
+pub const AUDIO_TEXTURE_WIDTH: u32 = 512;
+
+/// The level of the spectrum at `frequency`, from `0.0` (the lowest) to `1.0` (half the sample
+/// rate), in `[0, 1]` over about 70 dB.
+#[inline]
+#[must_use]
+pub fn fft<C: Channel>(channel: C, frequency: f32) -> f32 {
+  channel.texture(vec2(frequency, 0.25)).x
+}
+
+/// The waveform at `t` in `[0, 1]` over the last 512 samples, `0.5` is silence.
+#[inline]
+#[must_use]
+pub fn waveform<C: Channel>(channel: C, t: f32) -> f32 {
+  channel.texture(vec2(t, 0.75)).x
+}
+
+/// A made up spectrum and waveform with a beat, twice a second, behaves like the audio texture.
+#[derive(Copy, Clone)]
+pub struct SyntheticAudio {
+  pub time: f32,
+}
+
+impl SyntheticAudio {
+  fn value(self, uv: Vec2) -> f32 {
+    let x = uv.x.fract_gl();
+    let beat = (-6.0 * (self.time * 2.0).fract_gl()).exp();
+    if uv.y.fract_gl() < 0.5 {
+      // Falling off towards the high frequencies, with a few moving peaks.
+      let peaks = 0.5 + 0.5 * (x * 40.0 - self.time * 3.0).sin() * (x * 13.0 + self.time).sin();
+      saturate((1.0 - x).powf(2.0) * (0.4 + 0.4 * beat) + 0.25 * peaks * (1.0 - x))
+    } else {
+      let wave = (x * TAU * 4.0 + self.time * 10.0).sin() + 0.5 * (x * TAU * 11.0).sin();
+      0.5 + 0.15 * (0.5 + beat) * wave
+    }
+  }
+}
+
+impl Channel for SyntheticAudio {
+  fn resolution(self) -> Vec3 {
+    vec3(AUDIO_TEXTURE_WIDTH as f32, 2.0, 1.0)
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    Vec3::splat(self.value(uv)).extend(1.0)
+  }
+
+  fn texture_cube(self, direction: Vec3) -> Vec4 {
+    self.texture(direction.xy())
+  }
+
+  fn texture_3d(self, p: Vec3) -> Vec4 {
+    self.texture(p.xy())
+  }
+}
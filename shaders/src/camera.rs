@@ -0,0 +1,154 @@
+//! Pinhole camera used by the 3D ports, and the free camera the host can fly around with.
+//!
+//! The camera space follows the convention of most Shadertoy shaders: `x` points to the right of
+//! the screen, `y` up and `z` into the screen. A port builds its own camera with
+//! [`Camera::look_at`] and then calls [`Camera::with_free_camera`] with
+//! [`ShaderInput::free_camera`], which is a no-op unless the free camera is turned on in the host.
+//!
+//! Shaders with a `ray_fn` trace [`ViewRay`]s instead of pixels, which are relative to the same
+//! camera, see [`crate::projection`].
+
+use crate::shader_prelude::*;
+
+#[derive(Copy, Clone)]
+pub struct Camera {
+  /// Ray origin.
+  pub position: Vec3,
+  /// Camera to world transformation, the columns are the right, up and forward directions.
+  pub basis: Mat3,
+}
+
+impl Camera {
+  /// Camera at `ro` looking at `ta`, rolled by `roll` radians.
+  #[inline]
+  #[must_use]
+  pub fn look_at(ro: Vec3, ta: Vec3, roll: f32) -> Self {
+    let cw: Vec3 = (ta - ro).normalize();
+    let cp: Vec3 = vec3(roll.sin(), roll.cos(), 0.0);
+    let cu: Vec3 = cw.cross(cp).normalize();
+    let cv: Vec3 = cu.cross(cw);
+    Self {
+      position: ro,
+      basis: Mat3::from_cols(cu, cv, cw),
+    }
+  }
+
+  /// Camera at `ro` turned by `yaw` radians to the right and `pitch` radians up from looking
+  /// along `+z`.
+  #[inline]
+  #[must_use]
+  pub fn from_yaw_pitch(ro: Vec3, yaw: f32, pitch: f32) -> Self {
+    Self {
+      position: ro,
+      basis: yaw_pitch(yaw, pitch),
+    }
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn forward(&self) -> Vec3 {
+    self.basis.z_axis
+  }
+
+  /// Direction of the ray through `uv`, where `uv` is the screen position scaled so that the
+  /// vertical extent of the screen is `[-1, 1]`, e.g. `(2.0 * frag_coord - resolution.xy()) /
+  /// resolution.y`. Larger `focal_length`s give narrower fields of view.
+  #[inline]
+  #[must_use]
+  pub fn ray_direction(&self, uv: Vec2, focal_length: f32) -> Vec3 {
+    self.basis * uv.extend(focal_length).normalize()
+  }
+
+  /// The camera turned to look parallel to the ground, keeping its heading and handedness.
+  #[inline]
+  #[must_use]
+  pub fn level(self) -> Self {
+    let forward = self.basis.z_axis;
+    let heading = if forward.xz() == Vec2::ZERO {
+      Vec3::Z
+    } else {
+      vec3(forward.x, 0.0, forward.z).normalize()
+    };
+    // `look_at` gives a negative determinant, some ports build mirrored bases.
+    let handedness = -self.basis.determinant().signum();
+    Self {
+      position: self.position,
+      basis: Mat3::from_cols(
+        vec3(-heading.z, 0.0, heading.x) * handedness,
+        Vec3::Y,
+        heading,
+      ),
+    }
+  }
+
+  /// The world space origin and direction of `ray`.
+  #[inline]
+  #[must_use]
+  pub fn view_ray(self, ray: ViewRay) -> (Vec3, Vec3) {
+    let camera = if ray.level { self.level() } else { self };
+    (
+      camera.position + camera.basis * ray.origin,
+      camera.basis * ray.direction,
+    )
+  }
+
+  /// Moves and turns the camera by the free camera offsets, relative to its own orientation.
+  #[inline]
+  #[must_use]
+  pub fn with_free_camera(self, free_camera: FreeCamera) -> Self {
+    if !free_camera.enabled {
+      return self;
+    }
+    Self {
+      position: self.position + self.basis * free_camera.offset,
+      basis: self.basis * yaw_pitch(free_camera.yaw, free_camera.pitch),
+    }
+  }
+}
+
+/// A ray in camera space, traced by the `ray_fn` of a shader in place of the ray through a pixel.
+#[derive(Copy, Clone)]
+pub struct ViewRay {
+  /// Relative to the camera position.
+  pub origin: Vec3,
+  /// Normalized.
+  pub direction: Vec3,
+  /// Whether to drop the pitch and roll of the camera first, see [`Camera::level`]. Panoramas
+  /// are level so that their horizon is straight.
+  pub level: bool,
+}
+
+/// State of the host controlled free camera, see `ShaderConstants::free_camera`.
+///
+/// Everything is relative to the camera of the shader, in camera space, so the same controls
+/// work for every scene no matter its scale or where its camera is.
+#[derive(Copy, Clone, Default)]
+pub struct FreeCamera {
+  pub enabled: bool,
+  pub offset: Vec3,
+  pub yaw: f32,
+  pub pitch: f32,
+}
+
+impl FreeCamera {
+  #[inline]
+  #[must_use]
+  pub fn from_constants(constants: &ShaderConstants) -> Self {
+    Self {
+      enabled: constants.free_camera != 0,
+      offset: vec3(
+        constants.camera_offset_x,
+        constants.camera_offset_y,
+        constants.camera_offset_z,
+      ),
+      yaw: constants.camera_yaw,
+      pitch: constants.camera_pitch,
+    }
+  }
+}
+
+/// Rotation turning `+z` by `yaw` radians towards `+x` and then `pitch` radians towards `+y`.
+#[inline]
+fn yaw_pitch(yaw: f32, pitch: f32) -> Mat3 {
+  Mat3::from_rotation_y(yaw) * Mat3::from_rotation_x(-pitch)
+}
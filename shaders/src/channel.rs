@@ -0,0 +1,346 @@
+//! Shadertoy's `iChannel0..3` inputs.
+//!
+//! A [`Channel`] can be sampled like any of the GLSL sampler types, as a 2D texture (which is
+//! also how buffer outputs are read), a cubemap or a 3D volume. [`ShaderInput::channels`] holds
+//! whatever the host decided to feed a shader: [`ImageChannel`]s on the GPU, [`Unbound`] ones
+//! when rendering on the CPU or in the grid view. Ports say what they want to see instead of an
+//! unbound channel with [`Channel::or`], e.g. `channels[0].or(RgbCube { .. })`, so they render
+//! the same everywhere.
+
+use crate::shader_prelude::*;
+use spirv_std::{
+  glam::{IVec2, UVec2, UVec3},
+  image::{sample_with, Cubemap, Image2d, Image3d},
+  Sampler,
+};
+
+/// The GLSL texture lookup functions. The `_cube` and `_3d` variants are the overloads for
+/// `samplerCube` and `sampler3D`.
+pub trait Channel: Copy {
+  /// `iChannelResolution[i]`, in texels.
+  #[must_use]
+  fn resolution(self) -> Vec3;
+
+  /// `iChannelTime[i]`, how far a video has played, in seconds. Zero for still images.
+  #[must_use]
+  fn time(self) -> f32 {
+    0.0
+  }
+
+  /// `texture(sampler2D, uv)`.
+  #[must_use]
+  fn texture(self, uv: Vec2) -> Vec4;
+
+  /// `textureLod(sampler2D, uv, lod)`.
+  #[must_use]
+  fn texture_lod(self, uv: Vec2, lod: f32) -> Vec4 {
+    let _ = lod;
+    self.texture(uv)
+  }
+
+  /// `texelFetch(sampler2D, p, lod)`.
+  #[must_use]
+  fn texel_fetch(self, p: IVec2, lod: i32) -> Vec4 {
+    let size = self.resolution().xy() / exp2(lod as f32);
+    self.texture_lod((p.as_vec2() + 0.5) / size, lod as f32)
+  }
+
+  /// `texture(samplerCube, direction)`.
+  #[must_use]
+  fn texture_cube(self, direction: Vec3) -> Vec4;
+
+  /// `textureLod(samplerCube, direction, lod)`.
+  #[must_use]
+  fn texture_cube_lod(self, direction: Vec3, lod: f32) -> Vec4 {
+    let _ = lod;
+    self.texture_cube(direction)
+  }
+
+  /// `texture(sampler3D, p)`.
+  #[must_use]
+  fn texture_3d(self, p: Vec3) -> Vec4;
+
+  /// `textureLod(sampler3D, p, lod)`.
+  #[must_use]
+  fn texture_3d_lod(self, p: Vec3, lod: f32) -> Vec4 {
+    let _ = lod;
+    self.texture_3d(p)
+  }
+
+  /// Whether the host fed anything into this channel.
+  #[must_use]
+  fn is_bound(self) -> bool {
+    true
+  }
+
+  /// This channel if it is bound, `fallback` otherwise.
+  #[must_use]
+  fn or<F: Channel>(self, fallback: F) -> Or<Self, F> {
+    Or {
+      channel: self,
+      fallback,
+    }
+  }
+}
+
+/// See [`Channel::or`].
+#[derive(Copy, Clone)]
+pub struct Or<C, F> {
+  channel: C,
+  fallback: F,
+}
+
+macro_rules! or_forward {
+  ($($name:ident($($arg:ident: $ty:ty),*);)*) => {
+    $(
+      #[inline]
+      fn $name(self, $($arg: $ty),*) -> Vec4 {
+        if self.channel.is_bound() {
+          self.channel.$name($($arg),*)
+        } else {
+          self.fallback.$name($($arg),*)
+        }
+      }
+    )*
+  };
+}
+
+impl<C: Channel, F: Channel> Channel for Or<C, F> {
+  #[inline]
+  fn resolution(self) -> Vec3 {
+    if self.channel.is_bound() {
+      self.channel.resolution()
+    } else {
+      self.fallback.resolution()
+    }
+  }
+
+  #[inline]
+  fn time(self) -> f32 {
+    if self.channel.is_bound() {
+      self.channel.time()
+    } else {
+      self.fallback.time()
+    }
+  }
+
+  or_forward! {
+    texture(uv: Vec2);
+    texture_lod(uv: Vec2, lod: f32);
+    texel_fetch(p: IVec2, lod: i32);
+    texture_cube(direction: Vec3);
+    texture_cube_lod(direction: Vec3, lod: f32);
+    texture_3d(p: Vec3);
+    texture_3d_lod(p: Vec3, lod: f32);
+  }
+
+  #[inline]
+  fn is_bound(self) -> bool {
+    self.channel.is_bound() || self.fallback.is_bound()
+  }
+}
+
+/// An empty channel, which is black like on Shadertoy.
+#[derive(Copy, Clone, Default)]
+pub struct Unbound;
+
+impl Channel for Unbound {
+  fn resolution(self) -> Vec3 {
+    Vec3::ZERO
+  }
+
+  fn texture(self, _: Vec2) -> Vec4 {
+    Vec4::ZERO
+  }
+
+  fn texture_cube(self, _: Vec3) -> Vec4 {
+    Vec4::ZERO
+  }
+
+  fn texture_3d(self, _: Vec3) -> Vec4 {
+    Vec4::ZERO
+  }
+
+  fn is_bound(self) -> bool {
+    false
+  }
+}
+
+/// The same color everywhere, behaves like a 1x1 texture.
+#[derive(Copy, Clone)]
+pub struct ConstantColor {
+  pub color: Vec4,
+}
+
+impl Channel for ConstantColor {
+  fn resolution(self) -> Vec3 {
+    Vec3::ONE
+  }
+
+  fn texture(self, _: Vec2) -> Vec4 {
+    self.color
+  }
+
+  fn texture_cube(self, _: Vec3) -> Vec4 {
+    self.color
+  }
+
+  fn texture_3d(self, _: Vec3) -> Vec4 {
+    self.color
+  }
+}
+
+/// Stand-in for environment maps, the color is the absolute value of the direction. 2D lookups
+/// see it through an equirectangular projection, volume lookups use `p` as the direction.
+#[derive(Copy, Clone)]
+pub struct RgbCube {
+  pub alpha: f32,
+  pub intensity: f32,
+}
+
+impl Channel for RgbCube {
+  fn resolution(self) -> Vec3 {
+    Vec3::ONE
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    self.texture_cube(equirectangular_direction(uv))
+  }
+
+  fn texture_cube(self, direction: Vec3) -> Vec4 {
+    (direction.abs() * self.intensity).extend(self.alpha)
+  }
+
+  fn texture_3d(self, p: Vec3) -> Vec4 {
+    self.texture_cube(p)
+  }
+}
+
+/// The direction an equirectangular (latitude/longitude) map shows at `uv`, straight up at
+/// `uv.y == 0.0`.
+#[inline]
+#[must_use]
+pub fn equirectangular_direction(uv: Vec2) -> Vec3 {
+  let phi = uv.x * TAU;
+  let theta = uv.y * PI;
+  vec3(
+    theta.sin() * phi.cos(),
+    theta.cos(),
+    theta.sin() * phi.sin(),
+  )
+}
+
+/// What a shader reads from a channel, declared in its
+/// [`ShaderDefinition::channels`](crate::shader_prelude::ShaderDefinition::channels). The host
+/// binds it when it has it, the videos of `--channel<N>` replace any of them.
+#[derive(Copy, Clone)]
+pub enum ChannelInput {
+  /// Nothing, the shader uses its procedural fallback.
+  Procedural,
+  /// The audio texture of the music the host plays, see [`crate::audio`].
+  Audio,
+}
+
+/// What the host binds to a channel, packed into [`ShaderConstants::channel_sources`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ChannelKind {
+  /// Nothing, the shader uses its procedural fallback.
+  Unbound = 0,
+  Texture2d = 1,
+  Cubemap = 2,
+  Volume = 3,
+}
+
+impl ChannelKind {
+  const BITS: u32 = 8;
+
+  /// Packs the kinds of the four channels for [`ShaderConstants::channel_sources`].
+  #[must_use]
+  pub fn pack(kinds: [Self; 4]) -> u32 {
+    let mut packed = 0;
+    let mut i = 0;
+    while i < kinds.len() {
+      packed |= (kinds[i] as u32) << (i as u32 * Self::BITS);
+      i += 1;
+    }
+    packed
+  }
+
+  /// The raw kind of channel `index` in `channel_sources`, compare with `ChannelKind::X as u32`.
+  #[must_use]
+  pub fn unpack(channel_sources: u32, index: usize) -> u32 {
+    (channel_sources >> (index as u32 * Self::BITS)) & ((1 << Self::BITS) - 1)
+  }
+}
+
+/// A channel backed by the images the host bound to it. Only the image matching `kind` holds
+/// anything, the others are 1x1 placeholders.
+#[derive(Copy, Clone)]
+pub struct ImageChannel {
+  pub kind: u32,
+  /// See [`ShaderConstants::channel_time0`].
+  pub time: f32,
+  pub sampler: Sampler,
+  pub texture: Image2d,
+  pub cubemap: Cubemap,
+  pub volume: Image3d,
+}
+
+impl Channel for ImageChannel {
+  fn resolution(self) -> Vec3 {
+    if self.kind == ChannelKind::Texture2d as u32 {
+      self
+        .texture
+        .query_size_lod::<UVec2>(0)
+        .as_vec2()
+        .extend(1.0)
+    } else if self.kind == ChannelKind::Cubemap as u32 {
+      self
+        .cubemap
+        .query_size_lod::<UVec2>(0)
+        .as_vec2()
+        .extend(1.0)
+    } else if self.kind == ChannelKind::Volume as u32 {
+      self.volume.query_size_lod::<UVec3>(0).as_vec3()
+    } else {
+      Vec3::ZERO
+    }
+  }
+
+  fn time(self) -> f32 {
+    self.time
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    self.texture.sample(self.sampler, uv)
+  }
+
+  fn texture_lod(self, uv: Vec2, lod: f32) -> Vec4 {
+    self.texture.sample_by_lod(self.sampler, uv, lod)
+  }
+
+  fn texel_fetch(self, p: IVec2, lod: i32) -> Vec4 {
+    self.texture.fetch_with(p, sample_with::lod(lod))
+  }
+
+  fn texture_cube(self, direction: Vec3) -> Vec4 {
+    self.cubemap.sample(self.sampler, direction)
+  }
+
+  fn texture_cube_lod(self, direction: Vec3, lod: f32) -> Vec4 {
+    self.cubemap.sample_by_lod(self.sampler, direction, lod)
+  }
+
+  fn texture_3d(self, p: Vec3) -> Vec4 {
+    self.volume.sample(self.sampler, p)
+  }
+
+  fn texture_3d_lod(self, p: Vec3, lod: f32) -> Vec4 {
+    self.volume.sample_by_lod(self.sampler, p, lod)
+  }
+
+  fn is_bound(self) -> bool {
+    self.kind != ChannelKind::Unbound as u32
+  }
+}
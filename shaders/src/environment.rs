@@ -0,0 +1,294 @@
+//! Procedural environment maps, stand-ins for the cubemaps of the original shaders until real
+//! textures are bound to their channels.
+//!
+//! Every [`Environment`] is a [`Channel`]: cubemap lookups return the radiance in that direction,
+//! 2D lookups see the environment through an equirectangular projection and volume lookups use
+//! `p` as the direction. The results are linear and can be well above `1.0` around light
+//! sources, like the HDR cubemaps on Shadertoy.
+
+use crate::{
+  channel::{equirectangular_direction, Channel},
+  noise::LatticeHash,
+  shader_prelude::*,
+};
+
+/// Radiance arriving from a direction, see the module documentation.
+pub trait Environment: Copy {
+  /// `direction` is normalized.
+  #[must_use]
+  fn radiance(self, direction: Vec3) -> Vec3;
+}
+
+impl<E: Environment> Channel for E {
+  fn resolution(self) -> Vec3 {
+    Vec3::ONE
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    self.texture_cube(equirectangular_direction(uv))
+  }
+
+  fn texture_cube(self, direction: Vec3) -> Vec4 {
+    self.radiance(direction.normalize()).extend(1.0)
+  }
+
+  fn texture_3d(self, p: Vec3) -> Vec4 {
+    self.texture_cube(p)
+  }
+}
+
+/// Blends from `ground` below the horizon over `horizon` to `zenith` straight up.
+#[derive(Copy, Clone)]
+pub struct GradientSky {
+  pub zenith: Vec3,
+  pub horizon: Vec3,
+  pub ground: Vec3,
+  /// Larger values keep the horizon color closer to the horizon.
+  pub exponent: f32,
+}
+
+impl Environment for GradientSky {
+  fn radiance(self, direction: Vec3) -> Vec3 {
+    let y = direction.y;
+    if y >= 0.0 {
+      mix(
+        self.horizon,
+        self.zenith,
+        (1.0 - (1.0 - y).powf(self.exponent)).clamp(0.0, 1.0),
+      )
+    } else {
+      // A thin blend so the horizon doesn't end in a hard line.
+      mix(self.horizon, self.ground, smoothstep(0.0, 0.05, -y))
+    }
+  }
+}
+
+/// The clear sky model of "A Practical Analytic Model for Daylight" (Preetham, Shirley, Smits
+/// 1999), plus the sun disk.
+#[derive(Copy, Clone)]
+pub struct PreethamSky {
+  /// Normalized direction towards the sun, which should be above the horizon.
+  pub sun_direction: Vec3,
+  /// Haziness of the air, from about `2.0` (very clear) to `10.0` (hazy).
+  pub turbidity: f32,
+  /// Scales the luminance, which the model returns in kcd/m².
+  pub exposure: f32,
+  /// Color below the horizon.
+  pub ground: Vec3,
+}
+
+impl PreethamSky {
+  /// A clear sky with the sun in `sun_direction`, scaled so the sky is around `0.5`.
+  #[must_use]
+  pub fn new(sun_direction: Vec3) -> Self {
+    Self {
+      sun_direction: sun_direction.normalize(),
+      turbidity: 2.5,
+      exposure: 0.05,
+      ground: vec3(0.25, 0.23, 0.2),
+    }
+  }
+}
+
+/// The Perez et al. sky luminance distribution, `theta` is the angle of the view direction from
+/// the zenith and `gamma` the angle from the sun.
+#[inline]
+fn perez(coefficients: [f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+  let [a, b, c, d, e] = coefficients;
+  let cos_gamma = gamma.cos();
+  (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+/// CIE xyY to linear sRGB.
+#[inline]
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+  let xyz = vec3(x * luminance / y, luminance, (1.0 - x - y) * luminance / y);
+  vec3(
+    vec3(3.2406, -1.5372, -0.4986).dot(xyz),
+    vec3(-0.9689, 1.8758, 0.0415).dot(xyz),
+    vec3(0.0557, -0.2040, 1.0570).dot(xyz),
+  )
+}
+
+impl Environment for PreethamSky {
+  fn radiance(self, direction: Vec3) -> Vec3 {
+    let t = self.turbidity;
+    let sun_theta = acos_approx(self.sun_direction.y.clamp(0.0, 1.0));
+    // The model is only defined above the horizon.
+    let cos_theta = direction.y.max(0.01);
+    let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+
+    let luminance_coefficients = [
+      0.1787 * t - 1.4630,
+      -0.3554 * t + 0.4275,
+      -0.0227 * t + 5.3251,
+      0.1206 * t - 2.5771,
+      -0.0670 * t + 0.3703,
+    ];
+    let x_coefficients = [
+      -0.0193 * t - 0.2592,
+      -0.0665 * t + 0.0008,
+      -0.0004 * t + 0.2125,
+      -0.0641 * t - 0.8989,
+      -0.0033 * t + 0.0452,
+    ];
+    let y_coefficients = [
+      -0.0167 * t - 0.2608,
+      -0.0950 * t + 0.0092,
+      -0.0079 * t + 0.2102,
+      -0.0441 * t - 1.6537,
+      -0.0109 * t + 0.0529,
+    ];
+
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_theta);
+    let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+    let theta2 = sun_theta * sun_theta;
+    let theta3 = theta2 * sun_theta;
+    let zenith_x = t * t * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * sun_theta)
+      + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * sun_theta + 0.00394)
+      + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * sun_theta + 0.25886);
+    let zenith_y = t * t * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * sun_theta)
+      + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * sun_theta + 0.00516)
+      + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * sun_theta + 0.26688);
+
+    let relative =
+      |coefficients| perez(coefficients, cos_theta, gamma) / perez(coefficients, 1.0, sun_theta);
+    let luminance = zenith_luminance * relative(luminance_coefficients);
+    let x = zenith_x * relative(x_coefficients);
+    let y = zenith_y * relative(y_coefficients);
+    let mut sky = xyy_to_rgb(x, y, luminance * self.exposure).max(Vec3::ZERO);
+
+    // The sun is about half a degree wide.
+    let sun = smoothstep(0.99996, 0.99999, direction.dot(self.sun_direction));
+    sky += sun * 50.0 * vec3(1.0, 0.95, 0.85);
+
+    if direction.y < 0.0 {
+      mix(sky, self.ground, smoothstep(0.0, 0.05, -direction.y))
+    } else {
+      sky
+    }
+  }
+}
+
+/// A photo studio: a dim background lit by a large key softbox, a fill softbox on the other side
+/// and a light strip overhead.
+#[derive(Copy, Clone)]
+pub struct Studio {
+  pub background: Vec3,
+  /// Color and intensity of the key light, up front to the left.
+  pub key: Vec3,
+  /// Color and intensity of the fill light, to the right.
+  pub fill: Vec3,
+  /// Widens the edges of the lights, `0.0` gives sharp edges and `1.0` looks like a blurred
+  /// version of the studio, for rough reflections.
+  pub softness: f32,
+}
+
+impl Studio {
+  /// A neutral gray studio.
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      background: Vec3::splat(0.08),
+      key: Vec3::splat(6.0),
+      fill: vec3(1.5, 1.6, 1.8),
+      softness: 0.05,
+    }
+  }
+
+  /// A rectangle of `half_size` (tangents of the angles) around `center`.
+  #[inline]
+  fn softbox(self, direction: Vec3, center: Vec3, half_size: Vec2) -> f32 {
+    let forward = center.normalize();
+    let right = forward.cross(Vec3::Y).normalize();
+    let up = right.cross(forward);
+    let facing = direction.dot(forward);
+    if facing <= 0.0 {
+      return 0.0;
+    }
+    let local = vec2(direction.dot(right), direction.dot(up)) / facing / half_size;
+    let edge = local.abs().max_element();
+    1.0 - smoothstep(1.0 - self.softness, 1.0 + self.softness + 1e-3, edge)
+  }
+}
+
+impl Default for Studio {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Environment for Studio {
+  fn radiance(self, direction: Vec3) -> Vec3 {
+    // Slightly brighter walls than floor.
+    let mut color = self.background * (1.0 + 0.5 * smoothstep(-0.3, 0.3, direction.y));
+    color += self.key * self.softbox(direction, vec3(-0.6, 0.5, -0.6), vec2(0.4, 0.3));
+    color += self.fill * self.softbox(direction, vec3(1.0, 0.15, -0.2), vec2(0.3, 0.5));
+    // The strip light runs from the front to the back, overhead.
+    let s = self.softness;
+    let across = 1.0 - smoothstep(0.08 * (1.0 - s), 0.08 * (1.0 + s) + 1e-3, direction.x.abs());
+    let overhead = smoothstep(0.6 - 0.2 * s, 0.7, direction.y);
+    color += 0.25 * (self.key + self.fill) * across * overhead;
+    color
+  }
+}
+
+/// Stars on black, on a grid over the faces of a cube so they stay round everywhere but the
+/// cube edges.
+#[derive(Copy, Clone)]
+pub struct Starfield {
+  pub background: Vec3,
+  /// Grid cells per cube face side, at most one star per cell.
+  pub cells: f32,
+  /// Fraction of the cells with a star.
+  pub density: f32,
+  pub brightness: f32,
+  /// Picks a different set of stars.
+  pub seed: f32,
+}
+
+impl Starfield {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      background: vec3(0.0, 0.002, 0.006),
+      cells: 120.0,
+      density: 0.3,
+      brightness: 4.0,
+      seed: 0.0,
+    }
+  }
+}
+
+impl Default for Starfield {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Environment for Starfield {
+  fn radiance(self, direction: Vec3) -> Vec3 {
+    let a = direction.abs();
+    // Project onto the face of the largest component.
+    let (face, uv) = if a.x >= a.y && a.x >= a.z {
+      (direction.x.sign_gl() + 1.0, direction.yz() / a.x)
+    } else if a.y >= a.z {
+      (direction.y.sign_gl() + 3.0, direction.xz() / a.y)
+    } else {
+      (direction.z.sign_gl() + 5.0, direction.xy() / a.z)
+    };
+    let grid = (uv * 0.5 + 0.5) * self.cells;
+    let cell = grid.floor();
+    let h = vec4(cell.x, cell.y, face, self.seed).hash_vec();
+    if h.z >= self.density {
+      return self.background;
+    }
+    // Away from the cell borders, so no neighbors have to be checked.
+    let center = cell + 0.2 + 0.6 * h.xy();
+    let d = (grid - center).length();
+    let magnitude = h.z / self.density;
+    let intensity = self.brightness * magnitude * magnitude * (-d * d * 40.0).exp();
+    let tint = mix(vec3(1.0, 0.8, 0.6), vec3(0.7, 0.8, 1.0), h.w);
+    self.background + intensity * tint
+  }
+}
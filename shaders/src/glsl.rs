@@ -0,0 +1,244 @@
+//! GLSL built-in functions, so ports can keep the structure of the original code.
+//!
+//! Every function is generic over [`GenType`] (`float`, `vec2`, `vec3` and `vec4` in GLSL) and
+//! works component-wise. Where GLSL has an overload taking a `float` in place of a vector, the
+//! argument is generic over [`Broadcast`], so both `step(edge_vec, x)` and `step(0.5, x)` work.
+
+use core::ops::{Add, Div, Mul, Neg, Sub};
+use spirv_std::glam::{Mat2, Mat3, Mat4, Vec2, Vec3, Vec4};
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::Float;
+
+/// A GLSL `genType`: `f32`, [`Vec2`], [`Vec3`] or [`Vec4`].
+pub trait GenType:
+  Copy
+  + Add<Output = Self>
+  + Sub<Output = Self>
+  + Mul<Output = Self>
+  + Div<Output = Self>
+  + Mul<f32, Output = Self>
+  + Neg<Output = Self>
+{
+  #[must_use]
+  fn splat(x: f32) -> Self;
+  /// Applies `f` to every component.
+  #[must_use]
+  fn map(self, f: impl Fn(f32) -> f32) -> Self;
+  /// Applies `f` to every pair of components.
+  #[must_use]
+  fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self;
+  #[must_use]
+  fn dot(self, other: Self) -> f32;
+}
+
+impl GenType for f32 {
+  #[inline(always)]
+  fn splat(x: Self) -> Self {
+    x
+  }
+
+  #[inline(always)]
+  fn map(self, f: impl Fn(Self) -> Self) -> Self {
+    f(self)
+  }
+
+  #[inline(always)]
+  fn zip(self, other: Self, f: impl Fn(Self, Self) -> Self) -> Self {
+    f(self, other)
+  }
+
+  #[inline(always)]
+  fn dot(self, other: Self) -> Self {
+    self * other
+  }
+}
+
+macro_rules! impl_gen_type {
+  ($($ty:ident($($field:ident),+)),+ $(,)?) => {$(
+    impl GenType for $ty {
+      #[inline(always)]
+      fn splat(x: f32) -> Self {
+        Self::splat(x)
+      }
+
+      #[inline(always)]
+      fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Self::new($(f(self.$field)),+)
+      }
+
+      #[inline(always)]
+      fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self::new($(f(self.$field, other.$field)),+)
+      }
+
+      #[inline(always)]
+      fn dot(self, other: Self) -> f32 {
+        Self::dot(self, other)
+      }
+    }
+
+    impl Broadcast<$ty> for f32 {
+      #[inline(always)]
+      fn broadcast(self) -> $ty {
+        $ty::splat(self)
+      }
+    }
+  )+};
+}
+
+impl_gen_type!(Vec2(x, y), Vec3(x, y, z), Vec4(x, y, z, w));
+
+/// Arguments where GLSL accepts either a `genType` or a `float` that is used for every component.
+pub trait Broadcast<T> {
+  #[must_use]
+  fn broadcast(self) -> T;
+}
+
+impl<T: GenType> Broadcast<T> for T {
+  #[inline(always)]
+  fn broadcast(self) -> T {
+    self
+  }
+}
+
+/// GLSL `mod`, which unlike `%` takes the sign of `y`: `x - y * floor(x / y)`.
+#[inline(always)]
+#[must_use]
+pub fn mod_gl<T: GenType>(x: T, y: impl Broadcast<T>) -> T {
+  x.zip(y.broadcast(), |x, y| x - y * (x / y).floor())
+}
+
+#[inline(always)]
+#[must_use]
+pub fn fract<T: GenType>(x: T) -> T {
+  x.map(|x| x - x.floor())
+}
+
+/// Hermite interpolation between 0 and 1. Like in GLSL, the result is undefined if
+/// `edge0 >= edge1`; this implementation mirrors the curve in that case.
+#[inline(always)]
+#[must_use]
+pub fn smoothstep<T: GenType>(edge0: impl Broadcast<T>, edge1: impl Broadcast<T>, x: T) -> T {
+  let edge0 = edge0.broadcast();
+  let t = clamp(
+    (x - edge0) / (edge1.broadcast() - edge0),
+    T::splat(0.0),
+    T::splat(1.0),
+  );
+  t * t * (T::splat(3.0) - t * 2.0)
+}
+
+/// `0.0` where `x < edge`, `1.0` otherwise.
+#[inline(always)]
+#[must_use]
+pub fn step<T: GenType>(edge: impl Broadcast<T>, x: T) -> T {
+  edge
+    .broadcast()
+    .zip(x, |edge, x| if x < edge { 0.0 } else { 1.0 })
+}
+
+/// GLSL `clamp`, `min(max(x, min_val), max_val)`.
+#[inline(always)]
+#[must_use]
+pub fn clamp<T: GenType>(x: T, min_val: impl Broadcast<T>, max_val: impl Broadcast<T>) -> T {
+  min(max(x, min_val), max_val)
+}
+
+#[inline(always)]
+#[must_use]
+pub fn min<T: GenType>(x: T, y: impl Broadcast<T>) -> T {
+  x.zip(y.broadcast(), |x, y| if y < x { y } else { x })
+}
+
+#[inline(always)]
+#[must_use]
+pub fn max<T: GenType>(x: T, y: impl Broadcast<T>) -> T {
+  x.zip(y.broadcast(), |x, y| if x < y { y } else { x })
+}
+
+/// Reflects the incident vector `i` on the surface with normal `n`, which should be normalized.
+#[inline(always)]
+#[must_use]
+pub fn reflect<T: GenType>(i: T, n: T) -> T {
+  i - n * (2.0 * n.dot(i))
+}
+
+/// Refracts the incident vector `i` on the surface with normal `n` with the ratio of indices of
+/// refraction `eta`. Returns zero on total internal reflection.
+#[inline(always)]
+#[must_use]
+pub fn refract<T: GenType>(i: T, n: T, eta: f32) -> T {
+  let n_dot_i = n.dot(i);
+  let k = 1.0 - eta * eta * (1.0 - n_dot_i * n_dot_i);
+  if k < 0.0 {
+    T::splat(0.0)
+  } else {
+    i * eta - n * (eta * n_dot_i + k.sqrt())
+  }
+}
+
+/// Returns `n` if it faces away from `i` (judged by `n_ref`), `-n` otherwise.
+#[inline(always)]
+#[must_use]
+pub fn faceforward<T: GenType>(n: T, i: T, n_ref: T) -> T {
+  if n_ref.dot(i) < 0.0 {
+    n
+  } else {
+    -n
+  }
+}
+
+#[inline(always)]
+#[must_use]
+pub fn inversesqrt<T: GenType>(x: T) -> T {
+  x.map(|x| 1.0 / x.sqrt())
+}
+
+/// GLSL `atan(y, x)`.
+#[inline(always)]
+#[must_use]
+pub fn atan2<T: GenType>(y: T, x: T) -> T {
+  y.zip(x, f32::atan2)
+}
+
+#[inline(always)]
+#[must_use]
+pub fn exp2<T: GenType>(x: T) -> T {
+  x.map(f32::exp2)
+}
+
+#[inline(always)]
+#[must_use]
+pub fn log2<T: GenType>(x: T) -> T {
+  x.map(f32::log2)
+}
+
+/// Matrices that support GLSL `matrixCompMult`.
+pub trait CompMult {
+  #[must_use]
+  fn comp_mult(self, other: Self) -> Self;
+}
+
+macro_rules! impl_comp_mult {
+  ($($ty:ident($($col:ident),+)),+ $(,)?) => {$(
+    impl CompMult for $ty {
+      #[inline(always)]
+      fn comp_mult(self, other: Self) -> Self {
+        Self::from_cols($(self.$col * other.$col),+)
+      }
+    }
+  )+};
+}
+
+impl_comp_mult!(
+  Mat2(x_axis, y_axis),
+  Mat3(x_axis, y_axis, z_axis),
+  Mat4(x_axis, y_axis, z_axis, w_axis),
+);
+
+/// GLSL `matrixCompMult`, multiplies two matrices component by component.
+#[inline(always)]
+#[must_use]
+pub fn matrix_comp_mult<M: CompMult>(x: M, y: M) -> M {
+  x.comp_mult(y)
+}
@@ -1,12 +1,36 @@
 #![cfg_attr(target_arch = "spirv", no_std)]
 
+pub mod audio;
+pub mod camera;
+pub mod channel;
+pub mod environment;
+pub mod glsl;
+pub mod noise;
+pub mod params;
+pub mod projection;
+pub mod quality;
+pub mod sdf;
 pub mod shader_prelude;
+pub mod stereo;
+use channel::{ChannelKind, ImageChannel};
+use projection::Projection;
 use shader_prelude::*;
+use spirv_std::{
+  glam::UVec3,
+  image::{Cubemap, Image2d, Image3d},
+  Sampler,
+};
 pub mod shaders;
 pub mod shared_data;
+pub mod sounds;
+use shared_data::{ShaderParams, SoundConstants};
+use stereo::StereoMode;
 
-// Compute optimal grid layout (rows, cols) for cell count while attempting to keep the aspect ratio close to the provided aspect ratio.
-fn optimal_grid(cell_count: usize, aspect: Vec2) -> (usize, usize) {
+/// Compute optimal grid layout (rows, cols) for cell count while attempting to keep the aspect
+/// ratio close to the provided aspect ratio. The grid view fills it column by column from the
+/// bottom left, so the empty cells are at the top of the last column.
+#[must_use]
+pub fn optimal_grid(cell_count: usize, aspect: Vec2) -> (usize, usize) {
   // Handle edge cases for 0 or 1 cells.
   if cell_count == 0 {
     return (0, 0);
@@ -46,7 +70,12 @@ fn optimal_grid(cell_count: usize, aspect: Vec2) -> (usize, usize) {
 
 #[inline(always)]
 #[must_use]
-pub fn fs(constants: &ShaderConstants, mut frag_coord: Vec2) -> Vec4 {
+pub fn fs<C: Channel>(
+  constants: &ShaderConstants,
+  params: &ShaderParams,
+  mut frag_coord: Vec2,
+  channels: [C; 4],
+) -> Vec4 {
   let resolution = vec3(constants.width as f32, constants.height as f32, 0.0);
   let time = constants.time;
   let mut mouse = vec4(
@@ -66,13 +95,21 @@ pub fn fs(constants: &ShaderConstants, mut frag_coord: Vec2) -> Vec4 {
     mouse.w *= -1.0;
   }
 
+  frag_coord += vec2(
+    constants.tile_offset_x + constants.jitter_x,
+    constants.tile_offset_y + constants.jitter_y,
+  );
+
+  let free_camera = FreeCamera::from_constants(constants);
+  let quality = Quality::from_constants(constants);
+
   frag_coord.x %= resolution.x;
   frag_coord.y = resolution.y - frag_coord.y % resolution.y;
 
   let shader_count = shaders::SHADER_DEFINITIONS.len();
 
   let shader_index;
-  let shader_input: ShaderInput;
+  let shader_input: ShaderInput<C>;
   let shader_output = &mut ShaderResult { color: Vec4::ZERO };
 
   if constants.grid_mode == 0 {
@@ -81,6 +118,10 @@ pub fn fs(constants: &ShaderConstants, mut frag_coord: Vec2) -> Vec4 {
       time,
       frag_coord,
       mouse,
+      free_camera,
+      channels,
+      params: ParamValues::new(params),
+      quality,
     };
     shader_index = constants.shader_to_show as usize;
   } else {
@@ -109,30 +150,167 @@ pub fn fs(constants: &ShaderConstants, mut frag_coord: Vec2) -> Vec4 {
       time,
       frag_coord: cell_frag_coord,
       mouse: cell_mouse,
+      free_camera,
+      channels,
+      params: ParamValues::DEFAULTS,
+      quality,
     };
   }
 
-  if shader_index < shader_count {
-    shaders::render_shader(shader_index as u32, &shader_input, shader_output);
+  let projection = Projection::from_constants(constants);
+  // Panoramas are not stereo.
+  let stereo_mode = if projection == Projection::Screen {
+    StereoMode::from_constants(constants)
   } else {
+    StereoMode::Off
+  };
+  if shader_index >= shader_count {
     // If the shader index is out of bounds, just return a default color
     shader_output.color = Vec4::new(0.0, 0.0, 0.0, 1.0);
+  } else if constants.grid_mode != 0
+    || (projection == Projection::Screen && stereo_mode == StereoMode::Off)
+    || !shaders::has_ray_fn(shader_index as u32)
+  {
+    shaders::render_shader(shader_index as u32, &shader_input, shader_output);
+  } else {
+    let views = stereo_mode.eye_views(frag_coord, resolution.xy());
+    let mut left = Vec3::ZERO;
+    let mut right = Vec3::ZERO;
+    // A loop, so that the shaders are inlined once for both eyes.
+    for eye in views.first_eye..=views.last_eye {
+      let ray = if projection == Projection::Screen {
+        stereo::eye_ray(views.uv, eye, constants.eye_separation)
+      } else {
+        let uv = vec2(
+          frag_coord.x / resolution.x,
+          1.0 - frag_coord.y / resolution.y,
+        );
+        ViewRay {
+          origin: Vec3::ZERO,
+          direction: projection.direction(uv),
+          level: true,
+        }
+      };
+      shaders::render_ray(shader_index as u32, &shader_input, ray, shader_output);
+      if eye == 0 {
+        left = shader_output.color.truncate();
+      } else {
+        right = shader_output.color.truncate();
+      }
+    }
+    if stereo_mode == StereoMode::Anaglyph {
+      shader_output.color = stereo::anaglyph(left, right).extend(1.0);
+    }
   }
 
   let color = shader_output.color;
   Vec3::powf(color.truncate(), 2.2).extend(color.w)
 }
 
+/// Channel `index` with the images of descriptor set 0, see [`channel::ImageChannel`].
+#[inline(always)]
+fn image_channel(
+  constants: &ShaderConstants,
+  index: usize,
+  sampler: &Sampler,
+  texture: &Image2d,
+  cubemap: &Cubemap,
+  volume: &Image3d,
+) -> ImageChannel {
+  ImageChannel {
+    kind: ChannelKind::unpack(constants.channel_sources, index),
+    time: constants.channel_time(index),
+    sampler: *sampler,
+    texture: *texture,
+    cubemap: *cubemap,
+    volume: *volume,
+  }
+}
+
+/// Declares a fragment entry point taking the shader constants from `$constants`, the channel
+/// images from descriptor set 0, with a 2D texture, a cubemap and a volume per channel, and the
+/// [`ShaderParams`] from descriptor set 1.
+macro_rules! fragment_entry_point {
+  ($(#[$attr:meta])* $name:ident, #[spirv($($constants:tt)*)]) => {
+    $(#[$attr])*
+    #[allow(unused_attributes, clippy::too_many_arguments)]
+    #[spirv(fragment)]
+    pub fn $name(
+      #[spirv(frag_coord)] in_frag_coord: Vec4,
+      #[spirv($($constants)*)] constants: &ShaderConstants,
+      #[spirv(descriptor_set = 0, binding = 0)] sampler: &Sampler,
+      #[spirv(descriptor_set = 0, binding = 1)] texture0: &Image2d,
+      #[spirv(descriptor_set = 0, binding = 2)] texture1: &Image2d,
+      #[spirv(descriptor_set = 0, binding = 3)] texture2: &Image2d,
+      #[spirv(descriptor_set = 0, binding = 4)] texture3: &Image2d,
+      #[spirv(descriptor_set = 0, binding = 5)] cubemap0: &Cubemap,
+      #[spirv(descriptor_set = 0, binding = 6)] cubemap1: &Cubemap,
+      #[spirv(descriptor_set = 0, binding = 7)] cubemap2: &Cubemap,
+      #[spirv(descriptor_set = 0, binding = 8)] cubemap3: &Cubemap,
+      #[spirv(descriptor_set = 0, binding = 9)] volume0: &Image3d,
+      #[spirv(descriptor_set = 0, binding = 10)] volume1: &Image3d,
+      #[spirv(descriptor_set = 0, binding = 11)] volume2: &Image3d,
+      #[spirv(descriptor_set = 0, binding = 12)] volume3: &Image3d,
+      #[spirv(uniform, descriptor_set = 1, binding = 0)] params: &ShaderParams,
+      output: &mut Vec4,
+    ) {
+      let frag_coord = vec2(in_frag_coord.x, in_frag_coord.y);
+      let channels = [
+        image_channel(constants, 0, sampler, texture0, cubemap0, volume0),
+        image_channel(constants, 1, sampler, texture1, cubemap1, volume1),
+        image_channel(constants, 2, sampler, texture2, cubemap2, volume2),
+        image_channel(constants, 3, sampler, texture3, cubemap3, volume3),
+      ];
+      let color = fs(constants, params, frag_coord, channels);
+      *output = color;
+    }
+  };
+}
+
+fragment_entry_point!(main_fs, #[spirv(push_constant)]);
+
+fragment_entry_point!(
+  /// Same as [`main_fs`], for adapters without push constant support (e.g. WebGPU), where the
+  /// host passes the constants in a uniform buffer instead.
+  main_fs_uniform,
+  #[spirv(uniform, descriptor_set = 2, binding = 0)]
+);
+
+/// Draws the param overlay over the shader, see [`params::overlay`]. Blended with premultiplied
+/// alpha.
 #[allow(unused_attributes)]
 #[spirv(fragment)]
-pub fn main_fs(
+pub fn main_overlay_fs(
   #[spirv(frag_coord)] in_frag_coord: Vec4,
-  #[spirv(push_constant)] constants: &ShaderConstants,
+  #[spirv(uniform, descriptor_set = 1, binding = 0)] params: &ShaderParams,
   output: &mut Vec4,
 ) {
-  let frag_coord = vec2(in_frag_coord.x, in_frag_coord.y);
-  let color = fs(constants, frag_coord);
-  *output = color;
+  *output = params::overlay(params, in_frag_coord.xy());
+}
+
+/// Copies the frames the host averaged into a float texture to the screen.
+#[allow(unused_attributes)]
+#[spirv(fragment)]
+pub fn main_blit_fs(
+  #[spirv(frag_coord)] in_frag_coord: Vec4,
+  #[spirv(descriptor_set = 0, binding = 0)] image: &Image2d,
+  output: &mut Vec4,
+) {
+  *output = image.fetch(in_frag_coord.xy().as_ivec2());
+}
+
+/// Renders `constants.sample_count` samples of a sound shader into `samples`, see
+/// [`sounds::sound`].
+#[allow(unused_attributes)]
+#[spirv(compute(threads(64)))]
+pub fn main_sound_cs(
+  #[spirv(global_invocation_id)] id: UVec3,
+  #[spirv(uniform, descriptor_set = 0, binding = 0)] constants: &SoundConstants,
+  #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] samples: &mut [Vec2],
+) {
+  if id.x < constants.sample_count {
+    samples[id.x as usize] = sounds::sound(constants, id.x);
+  }
 }
 
 #[allow(unused_attributes)]
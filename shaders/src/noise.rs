@@ -0,0 +1,464 @@
+//! Hashes and noise functions shared between ports.
+//!
+//! The noise functions take the lattice hash as an argument, so a port can keep the hash from the
+//! original shader (and with it its exact output) while dropping its own copy of the noise. New
+//! code can pass one of the integer based hashes of [`LatticeHash`] instead, e.g.
+//! `gradient_noise2(p, Vec2::gradient)`, which don't suffer from the precision problems of the
+//! classic `fract(sin(x) * 43758.5453)` hashes on large inputs.
+//!
+//! What is available:
+//!
+//! | Function          | 1D | 2D | 3D | 4D |
+//! |-------------------|----|----|----|----|
+//! | integer hashes    | ✓  | ✓  | ✓  | ✓  |
+//! | value noise       | ✓  | ✓  | ✓  | ✓  |
+//! | gradient noise    | ✓  | ✓  | ✓  | ✓  |
+//! | simplex noise     |    | ✓  | ✓  |    |
+//! | Worley noise      |    | ✓  | ✓  |    |
+//!
+//! [`fbm`] and [`domain_warp2`]/[`domain_warp3`] work with any of them.
+
+use crate::shader_prelude::*;
+use spirv_std::glam::{UVec2, UVec3, UVec4};
+
+/// PCG hash, see "Hash Functions for GPU Rendering" (Jarzynski, Olano 2020).
+#[inline]
+#[must_use]
+pub fn pcg(v: u32) -> u32 {
+  let state = v.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+  let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277_803_737);
+  (word >> 22) ^ word
+}
+
+#[inline]
+#[must_use]
+pub fn pcg2d(v: UVec2) -> UVec2 {
+  let mut x = v.x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  let mut y = v.y.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  x = x.wrapping_add(y.wrapping_mul(1_664_525));
+  y = y.wrapping_add(x.wrapping_mul(1_664_525));
+  x ^= x >> 16;
+  y ^= y >> 16;
+  x = x.wrapping_add(y.wrapping_mul(1_664_525));
+  y = y.wrapping_add(x.wrapping_mul(1_664_525));
+  x ^= x >> 16;
+  y ^= y >> 16;
+  UVec2::new(x, y)
+}
+
+#[inline]
+#[must_use]
+pub fn pcg3d(v: UVec3) -> UVec3 {
+  let mut x = v.x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  let mut y = v.y.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  let mut z = v.z.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  x = x.wrapping_add(y.wrapping_mul(z));
+  y = y.wrapping_add(z.wrapping_mul(x));
+  z = z.wrapping_add(x.wrapping_mul(y));
+  x ^= x >> 16;
+  y ^= y >> 16;
+  z ^= z >> 16;
+  x = x.wrapping_add(y.wrapping_mul(z));
+  y = y.wrapping_add(z.wrapping_mul(x));
+  z = z.wrapping_add(x.wrapping_mul(y));
+  UVec3::new(x, y, z)
+}
+
+#[inline]
+#[must_use]
+pub fn pcg4d(v: UVec4) -> UVec4 {
+  let mut x = v.x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  let mut y = v.y.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  let mut z = v.z.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  let mut w = v.w.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  x = x.wrapping_add(y.wrapping_mul(w));
+  y = y.wrapping_add(z.wrapping_mul(x));
+  z = z.wrapping_add(x.wrapping_mul(y));
+  w = w.wrapping_add(y.wrapping_mul(z));
+  x ^= x >> 16;
+  y ^= y >> 16;
+  z ^= z >> 16;
+  w ^= w >> 16;
+  x = x.wrapping_add(y.wrapping_mul(w));
+  y = y.wrapping_add(z.wrapping_mul(x));
+  z = z.wrapping_add(x.wrapping_mul(y));
+  w = w.wrapping_add(y.wrapping_mul(z));
+  UVec4::new(x, y, z, w)
+}
+
+const PRIME32_2: u32 = 2_246_822_519;
+const PRIME32_3: u32 = 3_266_489_917;
+const PRIME32_4: u32 = 668_265_263;
+const PRIME32_5: u32 = 374_761_393;
+
+#[inline(always)]
+const fn xxhash32_round(h: u32) -> u32 {
+  PRIME32_4.wrapping_mul(h.rotate_left(17))
+}
+
+#[inline(always)]
+const fn xxhash32_avalanche(mut h: u32) -> u32 {
+  h = PRIME32_2.wrapping_mul(h ^ (h >> 15));
+  h = PRIME32_3.wrapping_mul(h ^ (h >> 13));
+  h ^ (h >> 16)
+}
+
+/// The xxHash32 variant from "Hash Functions for GPU Rendering", which hashes one word per round.
+/// It doesn't produce the same values as the xxHash32 of the bytes.
+#[inline]
+#[must_use]
+pub const fn xxhash32(p: u32) -> u32 {
+  xxhash32_avalanche(xxhash32_round(p.wrapping_add(PRIME32_5)))
+}
+
+#[inline]
+#[must_use]
+pub const fn xxhash32_2d(p: UVec2) -> u32 {
+  let h = p
+    .y
+    .wrapping_add(PRIME32_5)
+    .wrapping_add(p.x.wrapping_mul(PRIME32_3));
+  xxhash32_avalanche(xxhash32_round(h))
+}
+
+#[inline]
+#[must_use]
+pub const fn xxhash32_3d(p: UVec3) -> u32 {
+  let mut h = p
+    .z
+    .wrapping_add(PRIME32_5)
+    .wrapping_add(p.x.wrapping_mul(PRIME32_3));
+  h = xxhash32_round(h).wrapping_add(p.y.wrapping_mul(PRIME32_3));
+  xxhash32_avalanche(xxhash32_round(h))
+}
+
+#[inline]
+#[must_use]
+pub const fn xxhash32_4d(p: UVec4) -> u32 {
+  let mut h = p
+    .w
+    .wrapping_add(PRIME32_5)
+    .wrapping_add(p.x.wrapping_mul(PRIME32_3));
+  h = xxhash32_round(h).wrapping_add(p.y.wrapping_mul(PRIME32_3));
+  h = xxhash32_round(h).wrapping_add(p.z.wrapping_mul(PRIME32_3));
+  xxhash32_avalanche(xxhash32_round(h))
+}
+
+/// Maps a hash to `[0, 1)` using its 24 high bits, which is all an `f32` can represent exactly.
+#[inline(always)]
+#[must_use]
+pub fn unit_float(h: u32) -> f32 {
+  (h >> 8) as f32 * (1.0 / 16_777_216.0)
+}
+
+/// The classic `fract(sin(x) * scale)` hash, with the scale of the shader it comes from.
+///
+/// It's cheap, but repeats and loses precision quickly for inputs far away from the origin, and
+/// the result depends on the precision of `sin` on the GPU.
+#[inline(always)]
+#[must_use]
+pub fn sin_hash(x: f32, scale: f32) -> f32 {
+  (x.sin() * scale).fract_gl()
+}
+
+/// [`sin_hash`] of `dot(p, k)`, the usual way to hash a vector with it, e.g.
+/// `sin_hash_dot(p, vec2(12.9898, 78.233), 43758.5453)`.
+#[inline(always)]
+#[must_use]
+pub fn sin_hash_dot<T: GenType>(p: T, k: T, scale: f32) -> f32 {
+  sin_hash(p.dot(k), scale)
+}
+
+/// Hashes of lattice points, i.e. coordinates with integer values, based on [`pcg`],
+/// [`pcg2d`], [`pcg3d`] and [`pcg4d`]. Fractional parts are truncated.
+pub trait LatticeHash: GenType {
+  /// A value in `[0, 1)`.
+  #[must_use]
+  fn hash(self) -> f32;
+  /// One value in `[0, 1)` per component.
+  #[must_use]
+  fn hash_vec(self) -> Self;
+  /// One value in `[-1, 1)` per component, for gradient noise.
+  #[must_use]
+  fn gradient(self) -> Self {
+    self.hash_vec() * 2.0 - Self::splat(1.0)
+  }
+}
+
+impl LatticeHash for f32 {
+  #[inline]
+  fn hash(self) -> Self {
+    unit_float(pcg((self as i32).cast_unsigned()))
+  }
+
+  #[inline]
+  fn hash_vec(self) -> Self {
+    self.hash()
+  }
+}
+
+macro_rules! impl_lattice_hash {
+  ($($ty:ident($pcg:ident, $as_ivec:ident, $as_uvec:ident, $($field:ident),+)),+ $(,)?) => {$(
+    impl LatticeHash for $ty {
+      #[inline]
+      fn hash(self) -> f32 {
+        unit_float($pcg(self.$as_ivec().$as_uvec()).x)
+      }
+
+      #[inline]
+      fn hash_vec(self) -> Self {
+        let h = $pcg(self.$as_ivec().$as_uvec());
+        Self::new($(unit_float(h.$field)),+)
+      }
+    }
+  )+};
+}
+
+impl_lattice_hash!(
+  Vec2(pcg2d, as_ivec2, as_uvec2, x, y),
+  Vec3(pcg3d, as_ivec3, as_uvec3, x, y, z),
+  Vec4(pcg4d, as_ivec4, as_uvec4, x, y, z, w),
+);
+
+/// The cubic Hermite curve `3f² - 2f³` used to interpolate between lattice points.
+#[inline(always)]
+#[must_use]
+pub fn hermite<T: GenType>(f: T) -> T {
+  f * f * (T::splat(3.0) - f * 2.0)
+}
+
+/// Bilinear interpolation of the four corners of a lattice cell, `corner` gets the corner offset.
+#[inline(always)]
+fn interpolate_square(u: Vec2, corner: impl Fn(Vec2) -> f32) -> f32 {
+  mix(
+    mix(corner(vec2(0.0, 0.0)), corner(vec2(1.0, 0.0)), u.x),
+    mix(corner(vec2(0.0, 1.0)), corner(vec2(1.0, 1.0)), u.x),
+    u.y,
+  )
+}
+
+/// Trilinear interpolation of the eight corners of a lattice cell, `corner` gets the corner offset.
+#[inline(always)]
+fn interpolate_cube(u: Vec3, corner: impl Fn(Vec3) -> f32) -> f32 {
+  mix(
+    interpolate_square(u.xy(), |o| corner(o.extend(0.0))),
+    interpolate_square(u.xy(), |o| corner(o.extend(1.0))),
+    u.z,
+  )
+}
+
+/// Value noise in `[0, 1]`, interpolating `hash` of the lattice points.
+#[inline]
+#[must_use]
+pub fn value_noise1(x: f32, hash: impl Fn(f32) -> f32) -> f32 {
+  let i = x.floor();
+  let u = hermite(x.fract_gl());
+  mix(hash(i), hash(i + 1.0), u)
+}
+
+#[inline]
+#[must_use]
+pub fn value_noise2(p: Vec2, hash: impl Fn(Vec2) -> f32) -> f32 {
+  let i = p.floor();
+  let u = hermite(p.fract_gl());
+  interpolate_square(u, |o| hash(i + o))
+}
+
+#[inline]
+#[must_use]
+pub fn value_noise3(p: Vec3, hash: impl Fn(Vec3) -> f32) -> f32 {
+  let i = p.floor();
+  let u = hermite(p.fract_gl());
+  interpolate_cube(u, |o| hash(i + o))
+}
+
+#[inline]
+#[must_use]
+pub fn value_noise4(p: Vec4, hash: impl Fn(Vec4) -> f32) -> f32 {
+  let i = p.floor();
+  let u = hermite(p.fract_gl());
+  mix(
+    interpolate_cube(u.xyz(), |o| hash(i + o.extend(0.0))),
+    interpolate_cube(u.xyz(), |o| hash(i + o.extend(1.0))),
+    u.w,
+  )
+}
+
+/// Gradient ("Perlin") noise in about `[-1, 1]`, `hash` returns the gradient at a lattice point.
+/// The noise is zero on the lattice points.
+#[inline]
+#[must_use]
+pub fn gradient_noise1(x: f32, hash: impl Fn(f32) -> f32) -> f32 {
+  let i = x.floor();
+  let f = x.fract_gl();
+  mix(hash(i) * f, hash(i + 1.0) * (f - 1.0), hermite(f))
+}
+
+#[inline]
+#[must_use]
+pub fn gradient_noise2(p: Vec2, hash: impl Fn(Vec2) -> Vec2) -> f32 {
+  let i = p.floor();
+  let f = p.fract_gl();
+  interpolate_square(hermite(f), |o| hash(i + o).dot(f - o))
+}
+
+#[inline]
+#[must_use]
+pub fn gradient_noise3(p: Vec3, hash: impl Fn(Vec3) -> Vec3) -> f32 {
+  let i = p.floor();
+  let f = p.fract_gl();
+  interpolate_cube(hermite(f), |o| hash(i + o).dot(f - o))
+}
+
+#[inline]
+#[must_use]
+pub fn gradient_noise4(p: Vec4, hash: impl Fn(Vec4) -> Vec4) -> f32 {
+  let i = p.floor();
+  let f = p.fract_gl();
+  let u = hermite(f);
+  let slice = |w: f32| {
+    interpolate_cube(u.xyz(), |o| {
+      let o = o.extend(w);
+      hash(i + o).dot(f - o)
+    })
+  };
+  mix(slice(0.0), slice(1.0), u.w)
+}
+
+/// Simplex noise in about `[-1, 1]`, `hash` returns the gradient at a lattice point.
+///
+/// Based on <https://www.shadertoy.com/view/Msf3WH> by Inigo Quilez.
+#[inline]
+#[must_use]
+pub fn simplex_noise2(p: Vec2, hash: impl Fn(Vec2) -> Vec2) -> f32 {
+  const K1: f32 = 0.366025404; // (sqrt(3)-1)/2;
+  const K2: f32 = 0.211324865; // (3-sqrt(3))/6;
+  let i: Vec2 = (p + Vec2::splat((p.x + p.y) * K1)).floor();
+  let a: Vec2 = p - i + Vec2::splat((i.x + i.y) * K2);
+  let o: Vec2 = if a.x > a.y {
+    vec2(1.0, 0.0)
+  } else {
+    vec2(0.0, 1.0)
+  };
+  let b: Vec2 = a - o + Vec2::splat(K2);
+  let c: Vec2 = a - Vec2::splat(1.0 - 2.0 * K2);
+  let h: Vec3 = (Vec3::splat(0.5) - vec3(a.dot(a), b.dot(b), c.dot(c))).max(Vec3::ZERO);
+  let n: Vec3 = (h * h * h * h)
+    * vec3(
+      a.dot(hash(i + Vec2::ZERO)),
+      b.dot(hash(i + o)),
+      c.dot(hash(i + Vec2::splat(1.0))),
+    );
+  n.dot(Vec3::splat(70.0))
+}
+
+/// Based on <https://www.shadertoy.com/view/XsX3zB> by Nikita Miropolskiy.
+#[inline]
+#[must_use]
+pub fn simplex_noise3(p: Vec3, hash: impl Fn(Vec3) -> Vec3) -> f32 {
+  const F3: f32 = 1.0 / 3.0;
+  const G3: f32 = 1.0 / 6.0;
+  let s = (p + Vec3::splat(p.dot(Vec3::splat(F3)))).floor();
+  let x = p - s + Vec3::splat(s.dot(Vec3::splat(G3)));
+  // Which of the six simplices of the cube we are in.
+  let e = step(Vec3::ZERO, x - x.yzx());
+  let i1 = e * (Vec3::ONE - e.zxy());
+  let i2 = Vec3::ONE - e.zxy() * (Vec3::ONE - e);
+  let x1 = x - i1 + Vec3::splat(G3);
+  let x2 = x - i2 + Vec3::splat(2.0 * G3);
+  let x3 = x - Vec3::splat(1.0 - 3.0 * G3);
+  let mut w =
+    (Vec4::splat(0.6) - vec4(x.dot(x), x1.dot(x1), x2.dot(x2), x3.dot(x3))).max(Vec4::ZERO);
+  let d = vec4(
+    hash(s).dot(x),
+    hash(s + i1).dot(x1),
+    hash(s + i2).dot(x2),
+    hash(s + Vec3::ONE).dot(x3),
+  );
+  w *= w;
+  w *= w;
+  (d * w).dot(Vec4::splat(26.0))
+}
+
+/// Cellular noise: the distances to the closest and second closest feature points, one per
+/// lattice cell, placed at `hash` (in `[0, 1)`) within their cell.
+#[inline]
+#[must_use]
+pub fn worley_noise2(p: Vec2, hash: impl Fn(Vec2) -> Vec2) -> Vec2 {
+  let i = p.floor();
+  let f = p.fract_gl();
+  let mut d = Vec2::splat(8.0);
+  for y in -1..2 {
+    for x in -1..2 {
+      let o = vec2(x as f32, y as f32);
+      let r = (o + hash(i + o) - f).length();
+      if r < d.x {
+        d = vec2(r, d.x);
+      } else if r < d.y {
+        d.y = r;
+      }
+    }
+  }
+  d
+}
+
+#[inline]
+#[must_use]
+pub fn worley_noise3(p: Vec3, hash: impl Fn(Vec3) -> Vec3) -> Vec2 {
+  let i = p.floor();
+  let f = p.fract_gl();
+  let mut d = Vec2::splat(8.0);
+  for z in -1..2 {
+    for y in -1..2 {
+      for x in -1..2 {
+        let o = vec3(x as f32, y as f32, z as f32);
+        let r = (o + hash(i + o) - f).length();
+        if r < d.x {
+          d = vec2(r, d.x);
+        } else if r < d.y {
+          d.y = r;
+        }
+      }
+    }
+  }
+  d
+}
+
+/// Fractional Brownian motion: sums `octaves` layers of `noise`, starting with `amplitude` and
+/// scaling it by `gain` after every octave. `next_octave` maps the position to the next octave,
+/// usually a rotation and a scale by about two.
+#[inline]
+#[must_use]
+pub fn fbm<T: Copy>(
+  mut p: T,
+  octaves: u32,
+  mut amplitude: f32,
+  gain: f32,
+  noise: impl Fn(T) -> f32,
+  next_octave: impl Fn(T) -> T,
+) -> f32 {
+  let mut total = 0.0;
+  for _ in 0..octaves {
+    total += noise(p) * amplitude;
+    p = next_octave(p);
+    amplitude *= gain;
+  }
+  total
+}
+
+/// Domain warping, `f(p + strength * q)` where `q` is `f` sampled at offset positions.
+///
+/// See <https://iquilezles.org/articles/warp/>.
+#[inline]
+#[must_use]
+pub fn domain_warp2(p: Vec2, strength: f32, f: impl Fn(Vec2) -> f32) -> f32 {
+  let q = vec2(f(p), f(p + vec2(5.2, 1.3)));
+  f(p + strength * q)
+}
+
+#[inline]
+#[must_use]
+pub fn domain_warp3(p: Vec3, strength: f32, f: impl Fn(Vec3) -> f32) -> f32 {
+  let q = vec3(f(p), f(p + vec3(5.2, 1.3, 2.8)), f(p + vec3(1.7, 9.2, 4.1)));
+  f(p + strength * q)
+}
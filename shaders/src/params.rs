@@ -0,0 +1,197 @@
+//! Tunable parameters of the shaders.
+//!
+//! A shader lists its [`Param`]s in [`ShaderDefinition::params`] and reads their current values
+//! from [`ShaderInput::params`]. The host can change them while the shader is shown, it passes
+//! the values in a [`ShaderParams`] uniform block. In the grid view every shader gets its
+//! defaults.
+
+use crate::{shader_prelude::*, shared_data::ShaderParams};
+
+/// Number of params a shader can have.
+pub const MAX_PARAMS: usize = 16;
+
+/// A named knob of a shader, declared as a `const` and listed in its definition:
+///
+/// ```ignore
+/// const NUM_STEPS: Param = Param::int(0, "Steps", 8, 1, 32);
+///
+/// pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+///   name: "Seascape",
+///   author: "Alexander Alekseev",
+///   license: "CC BY-NC-SA 3.0",
+///   link: Some("https://www.shadertoy.com/view/Ms2SD1"),
+///   params: &[NUM_STEPS],
+///   channels: [ChannelInput::Procedural; 4],
+/// };
+///
+/// let num_steps = render_instruction.params.int(&NUM_STEPS);
+/// ```
+#[derive(Copy, Clone)]
+pub struct Param {
+  pub name: &'static str,
+  /// Index of the value in [`ShaderParams::values`], the params of a shader use the slots from
+  /// `0` on in the order of [`ShaderDefinition::params`].
+  pub slot: usize,
+  pub kind: ParamKind,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ParamKind {
+  Float { default: f32, min: f32, max: f32 },
+  Int { default: i32, min: i32, max: i32 },
+  Bool { default: bool },
+  Color { default: Vec3 },
+}
+
+impl Param {
+  #[must_use]
+  pub const fn float(slot: usize, name: &'static str, default: f32, min: f32, max: f32) -> Self {
+    Self {
+      name,
+      slot,
+      kind: ParamKind::Float { default, min, max },
+    }
+  }
+
+  #[must_use]
+  pub const fn int(slot: usize, name: &'static str, default: i32, min: i32, max: i32) -> Self {
+    Self {
+      name,
+      slot,
+      kind: ParamKind::Int { default, min, max },
+    }
+  }
+
+  #[must_use]
+  pub const fn bool(slot: usize, name: &'static str, default: bool) -> Self {
+    Self {
+      name,
+      slot,
+      kind: ParamKind::Bool { default },
+    }
+  }
+
+  /// A linear RGB color.
+  #[must_use]
+  pub const fn color(slot: usize, name: &'static str, default: Vec3) -> Self {
+    Self {
+      name,
+      slot,
+      kind: ParamKind::Color { default },
+    }
+  }
+
+  /// The default value, encoded like in [`ShaderParams::values`]: numbers and booleans (as `0.0`
+  /// or `1.0`) in `x`, colors in `xyz`.
+  #[must_use]
+  pub fn default_value(&self) -> Vec4 {
+    match self.kind {
+      ParamKind::Float { default, .. } => vec4(default, 0.0, 0.0, 0.0),
+      ParamKind::Int { default, .. } => vec4(default as f32, 0.0, 0.0, 0.0),
+      ParamKind::Bool { default } => vec4(f32::from(u8::from(default)), 0.0, 0.0, 0.0),
+      ParamKind::Color { default } => default.extend(0.0),
+    }
+  }
+}
+
+/// Values of the params of a shader.
+#[derive(Copy, Clone)]
+pub struct ParamValues {
+  values: [Vec4; MAX_PARAMS],
+  /// Whether `values` holds anything, otherwise the defaults are used.
+  bound: bool,
+}
+
+impl ParamValues {
+  /// The defaults of every param.
+  pub const DEFAULTS: Self = Self {
+    values: [Vec4::ZERO; MAX_PARAMS],
+    bound: false,
+  };
+
+  #[must_use]
+  pub fn new(params: &ShaderParams) -> Self {
+    Self {
+      values: params.values,
+      bound: params.count != 0,
+    }
+  }
+
+  #[inline]
+  fn value(&self, param: &Param) -> Vec4 {
+    if self.bound {
+      self.values[param.slot]
+    } else {
+      param.default_value()
+    }
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn float(&self, param: &Param) -> f32 {
+    self.value(param).x
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn int(&self, param: &Param) -> i32 {
+    self.value(param).x as i32
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn bool(&self, param: &Param) -> bool {
+    self.value(param).x != 0.0
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn color(&self, param: &Param) -> Vec3 {
+    self.value(param).xyz()
+  }
+}
+
+/// Left and top margin of the overlay, in pixels.
+const OVERLAY_MARGIN: f32 = 16.0;
+const OVERLAY_ROW_HEIGHT: f32 = 20.0;
+const OVERLAY_SLIDER_SIZE: Vec2 = vec2(200.0, 10.0);
+
+/// The param overlay at `frag_coord` (in pixels from the top left corner), premultiplied: one
+/// slider per param, the selected one highlighted. The names and values are shown by the host.
+#[must_use]
+pub fn overlay(params: &ShaderParams, frag_coord: Vec2) -> Vec4 {
+  if params.overlay == 0 {
+    return Vec4::ZERO;
+  }
+  let p = frag_coord - OVERLAY_MARGIN;
+  let panel_height = params.count as f32 * OVERLAY_ROW_HEIGHT;
+  if p.x < -4.0 || p.y < -4.0 || p.x > OVERLAY_SLIDER_SIZE.x + 4.0 || p.y > panel_height {
+    return Vec4::ZERO;
+  }
+  let background = vec4(0.0, 0.0, 0.0, 0.6);
+
+  #[expect(clippy::cast_sign_loss)]
+  let row = (p.y / OVERLAY_ROW_HEIGHT).max(0.0) as u32;
+  if row >= params.count {
+    return background;
+  }
+  let y =
+    p.y - row as f32 * OVERLAY_ROW_HEIGHT - 0.5 * (OVERLAY_ROW_HEIGHT - OVERLAY_SLIDER_SIZE.y);
+  if p.x < 0.0 || y < 0.0 || p.x > OVERLAY_SLIDER_SIZE.x || y > OVERLAY_SLIDER_SIZE.y {
+    return background;
+  }
+  let position = params.sliders[row as usize / 4][row as usize % 4];
+  let selected = row == params.selected;
+  let color = if p.x <= position * OVERLAY_SLIDER_SIZE.x {
+    if selected {
+      vec3(1.0, 0.6, 0.1)
+    } else {
+      Vec3::splat(0.8)
+    }
+  } else if selected {
+    vec3(0.35, 0.22, 0.08)
+  } else {
+    Vec3::splat(0.25)
+  };
+  color.extend(1.0)
+}
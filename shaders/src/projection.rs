@@ -0,0 +1,80 @@
+//! Panoramas of the 3D shaders: every pixel of the frame becomes a [`ViewRay`] from the camera of
+//! the shader, traced by its `ray_fn` (see `shaders::HAS_RAY_FN`). The host renders skyboxes and
+//! 360° stills this way.
+//!
+//! Directions are in camera space, `x` to the right, `y` up and `z` forward, which is also the
+//! orientation of cubemap lookups. The camera is levelled first.
+
+use crate::shader_prelude::*;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum Projection {
+  /// The camera of the shader, through its `shader_fn`.
+  Screen = 0,
+  /// Longitude from -180° on the left to 180° on the right, with the forward direction in the
+  /// middle, and latitude from 90° at the top to -90° at the bottom.
+  Equirectangular = 1,
+  PositiveX = 2,
+  NegativeX = 3,
+  PositiveY = 4,
+  NegativeY = 5,
+  PositiveZ = 6,
+  NegativeZ = 7,
+}
+
+impl Projection {
+  /// The faces of a cubemap, in the order of its layers.
+  pub const CUBE_FACES: [Self; 6] = [
+    Self::PositiveX,
+    Self::NegativeX,
+    Self::PositiveY,
+    Self::NegativeY,
+    Self::PositiveZ,
+    Self::NegativeZ,
+  ];
+
+  /// [`ShaderConstants::projection`], out of range values are [`Projection::Screen`].
+  #[must_use]
+  pub fn from_constants(constants: &ShaderConstants) -> Self {
+    match constants.projection {
+      1 => Self::Equirectangular,
+      2 => Self::PositiveX,
+      3 => Self::NegativeX,
+      4 => Self::PositiveY,
+      5 => Self::NegativeY,
+      6 => Self::PositiveZ,
+      7 => Self::NegativeZ,
+      _ => Self::Screen,
+    }
+  }
+
+  /// The direction of the ray through `uv`, the position in the frame from `(0, 0)` at the top
+  /// left to `(1, 1)` at the bottom right. [`Projection::Screen`] looks forward everywhere, its
+  /// rays come from the shader.
+  #[must_use]
+  pub fn direction(self, uv: Vec2) -> Vec3 {
+    // Cube face coordinates, `t` grows downwards like texture coordinates.
+    let s = uv.x * 2.0 - 1.0;
+    let t = uv.y * 2.0 - 1.0;
+    let direction = match self {
+      Self::Screen => Vec3::Z,
+      Self::Equirectangular => {
+        let longitude = s * PI;
+        let latitude = -t * FRAC_PI_2;
+        vec3(
+          latitude.cos() * longitude.sin(),
+          latitude.sin(),
+          latitude.cos() * longitude.cos(),
+        )
+      },
+      Self::PositiveX => vec3(1.0, -t, -s),
+      Self::NegativeX => vec3(-1.0, -t, s),
+      Self::PositiveY => vec3(s, 1.0, t),
+      Self::NegativeY => vec3(s, -1.0, -t),
+      Self::PositiveZ => vec3(s, -t, 1.0),
+      Self::NegativeZ => vec3(-s, -t, -1.0),
+    };
+    direction.normalize()
+  }
+}
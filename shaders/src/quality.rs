@@ -0,0 +1,65 @@
+//! The quality level the host asks for, in place of the compile time `HW_PERFORMANCE` and `AA`
+//! switches of the original shaders.
+//!
+//! [`Quality::High`] renders like the originals on Shadertoy. Shaders scale their supersampling,
+//! march steps and noise octaves with it, and the host lowers it to keep up its frame rate.
+
+use crate::shared_data::ShaderConstants;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u32)]
+pub enum Quality {
+  Low = 0,
+  Medium = 1,
+  High = 2,
+  Ultra = 3,
+}
+
+impl Quality {
+  pub const ALL: [Self; 4] = [Self::Low, Self::Medium, Self::High, Self::Ultra];
+
+  /// [`ShaderConstants::quality`], out of range values are [`Quality::Ultra`].
+  #[must_use]
+  pub fn from_constants(constants: &ShaderConstants) -> Self {
+    match constants.quality {
+      0 => Self::Low,
+      1 => Self::Medium,
+      2 => Self::High,
+      _ => Self::Ultra,
+    }
+  }
+
+  /// Supersampling samples per pixel along each axis, for shaders that do `AA` x `AA`
+  /// supersampling.
+  #[must_use]
+  pub fn samples_per_axis(self) -> u32 {
+    match self {
+      Self::Low | Self::Medium => 1,
+      Self::High => 2,
+      Self::Ultra => 3,
+    }
+  }
+
+  /// The number of steps of a raymarching or volume loop, `steps` at [`Quality::High`].
+  #[must_use]
+  pub fn steps(self, steps: u32) -> u32 {
+    let quarters = match self {
+      Self::Low => 2,
+      Self::Medium => 3,
+      Self::High => 4,
+      Self::Ultra => 6,
+    };
+    (steps * quarters / 4).max(1)
+  }
+
+  /// The number of noise octaves, `octaves` at [`Quality::High`]. [`Quality::Low`] drops the
+  /// finest octave, [`Quality::Ultra`] adds one.
+  #[must_use]
+  pub fn octaves(self, octaves: u32) -> u32 {
+    match self {
+      Self::Low => octaves.saturating_sub(1).max(1),
+      Self::Medium | Self::High => octaves,
+      Self::Ultra => octaves + 1,
+    }
+  }
+}
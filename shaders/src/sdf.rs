@@ -0,0 +1,645 @@
+//! Signed distance functions and the raymarching code around them.
+//!
+//! The primitives are the ones from Inigo Quilez's "Raymarching - Primitives"
+//! (<https://www.shadertoy.com/view/Xds3zN>, see also
+//! <https://iquilezles.org/articles/distfunctions/>). Every primitive is centered at the origin,
+//! translate and rotate `p` to place it. Scenes implement [`Sdf`], which closures
+//! `Fn(Vec3) -> f32` do automatically, and can then use [`Marcher`], [`calc_normal`],
+//! [`soft_shadow`] and [`ambient_occlusion`].
+
+use crate::shader_prelude::*;
+
+/// `dot(v, v)`.
+#[inline(always)]
+#[must_use]
+pub fn dot2<T: GenType>(v: T) -> f32 {
+  v.dot(v)
+}
+
+/// `a.x * b.x - a.y * b.y`.
+#[inline(always)]
+#[must_use]
+pub fn ndot(a: Vec2, b: Vec2) -> f32 {
+  a.x * b.x - a.y * b.y
+}
+
+// 2D primitives
+
+/// Circle of radius `r`.
+#[inline]
+#[must_use]
+pub fn sd_circle(p: Vec2, r: f32) -> f32 {
+  p.length() - r
+}
+
+/// Rectangle with half extents `b`.
+#[inline]
+#[must_use]
+pub fn sd_box2(p: Vec2, b: Vec2) -> f32 {
+  let d: Vec2 = p.abs() - b;
+  d.max(Vec2::ZERO).length() + d.x.max(d.y).min(0.0)
+}
+
+/// Line segment from `a` to `b`.
+#[inline]
+#[must_use]
+pub fn sd_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+  let pa: Vec2 = p - a;
+  let ba: Vec2 = b - a;
+  let h: f32 = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+  (pa - ba * h).length()
+}
+
+// 3D primitives
+
+/// The plane `y = 0`, facing up.
+#[inline]
+#[must_use]
+pub fn sd_plane(p: Vec3) -> f32 {
+  p.y
+}
+
+/// Sphere of radius `s`.
+#[inline]
+#[must_use]
+pub fn sd_sphere(p: Vec3, s: f32) -> f32 {
+  p.length() - s
+}
+
+/// Box with half extents `b`.
+#[inline]
+#[must_use]
+pub fn sd_box(p: Vec3, b: Vec3) -> f32 {
+  let d: Vec3 = p.abs() - b;
+  d.x.max(d.y.max(d.z)).min(0.0) + d.max(Vec3::ZERO).length()
+}
+
+/// The edges of a box with half extents `b`, `e` thick.
+#[inline]
+#[must_use]
+pub fn sd_bounding_box(mut p: Vec3, b: Vec3, e: f32) -> f32 {
+  p = p.abs() - b;
+  let q: Vec3 = (p + Vec3::splat(e)).abs() - Vec3::splat(e);
+
+  (vec3(p.x, q.y, q.z).max(Vec3::ZERO).length() + p.x.max(q.y.max(q.z)).min(0.0))
+    .min(vec3(q.x, p.y, q.z).max(Vec3::ZERO).length() + q.x.max(p.y.max(q.z)).min(0.0))
+    .min(vec3(q.x, q.y, p.z).max(Vec3::ZERO).length() + q.x.max(q.y.max(p.z)).min(0.0))
+}
+/// Ellipsoid with radii `r`. Only a bound, not an exact distance.
+#[inline]
+#[must_use]
+pub fn sd_ellipsoid(p: Vec3, r: Vec3) -> f32 {
+  let k0: f32 = (p / r).length();
+  let k1: f32 = (p / (r * r)).length();
+  k0 * (k0 - 1.0) / k1
+}
+
+/// Torus in the `xz` plane, `t` is (major radius, minor radius).
+#[inline]
+#[must_use]
+pub fn sd_torus(p: Vec3, t: Vec2) -> f32 {
+  (vec2(p.xz().length() - t.x, p.y)).length() - t.y
+}
+
+/// Torus arc of major radius `ra` and minor radius `rb`, `sc` is the sin/cos of the arc's
+/// half angle.
+#[inline]
+#[must_use]
+pub fn sd_capped_torus(mut p: Vec3, sc: Vec2, ra: f32, rb: f32) -> f32 {
+  p.x = p.x.abs();
+  let k: f32 = if sc.y * p.x > sc.x * p.y {
+    p.xy().dot(sc)
+  } else {
+    p.xy().length()
+  };
+  (p.dot(p) + ra * ra - 2.0 * ra * k).sqrt() - rb
+}
+
+/// Hexagonal prism along `z`, `h` is (radius, half length).
+#[inline]
+#[must_use]
+pub fn sd_hex_prism(mut p: Vec3, h: Vec2) -> f32 {
+  let k: Vec3 = vec3(-0.8660254, 0.5, 0.57735);
+  p = p.abs();
+  p = (p.xy() - 2.0 * k.xy().dot(p.xy()).min(0.0) * k.xy()).extend(p.z);
+  let d: Vec2 = vec2(
+    (p.xy() - vec2(p.x.clamp(-k.z * h.x, k.z * h.x), h.x)).length() * (p.y - h.x).sign_gl(),
+    p.z - h.y,
+  );
+  d.x.max(d.y).min(0.0) + d.max(Vec2::ZERO).length()
+}
+
+/// Octogonal prism along `z` with radius `r` and half length `h`.
+#[inline]
+#[must_use]
+pub fn sd_octogon_prism(mut p: Vec3, r: f32, h: f32) -> f32 {
+  let k: Vec3 = vec3(
+    -0.9238795325, // sqrt(2+sqrt(2))/2
+    0.3826834323,  // sqrt(2-sqrt(2))/2
+    0.4142135623,  // sqrt(2)-1
+  );
+  // reflections
+  p = p.abs();
+  p = (p.xy() - 2.0 * vec2(k.x, k.y).dot(p.xy()).min(0.0) * vec2(k.x, k.y)).extend(p.z);
+  p = (p.xy() - 2.0 * vec2(-k.x, k.y).dot(p.xy()).min(0.0) * vec2(-k.x, k.y)).extend(p.z);
+  // polygon side
+  p = (p.xy() - vec2(p.x.clamp(-k.z * r, k.z * r), r)).extend(p.z);
+  let d: Vec2 = vec2(p.xy().length() * p.y.sign_gl(), p.z - h);
+  d.x.max(d.y).min(0.0) + d.max(Vec2::ZERO).length()
+}
+
+/// Capsule from `a` to `b` with radius `r`.
+#[inline]
+#[must_use]
+pub fn sd_capsule(p: Vec3, a: Vec3, b: Vec3, r: f32) -> f32 {
+  let pa: Vec3 = p - a;
+  let ba: Vec3 = b - a;
+  let h: f32 = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+  (pa - ba * h).length() - r
+}
+
+/// Cone along `y` with rounded ends of radius `r1` at the base and `r2` at height `h`.
+#[inline]
+#[must_use]
+pub fn sd_round_cone_vertical(p: Vec3, r1: f32, r2: f32, h: f32) -> f32 {
+  let q: Vec2 = vec2(p.xz().length(), p.y);
+
+  let b: f32 = (r1 - r2) / h;
+  let a: f32 = (1.0 - b * b).sqrt();
+  let k: f32 = q.dot(vec2(-b, a));
+
+  if k < 0.0 {
+    return q.length() - r1;
+  }
+  if k > a * h {
+    return (q - vec2(0.0, h)).length() - r2;
+  }
+
+  q.dot(vec2(a, b)) - r1
+}
+
+/// Cone with rounded ends of radius `r1` at `a` and `r2` at `b`.
+#[inline]
+#[must_use]
+pub fn sd_round_cone(p: Vec3, a: Vec3, b: Vec3, r1: f32, r2: f32) -> f32 {
+  // sampling independent computations (only depend on shape)
+  let ba: Vec3 = b - a;
+  let l2: f32 = ba.dot(ba);
+  let rr: f32 = r1 - r2;
+  let a2: f32 = l2 - rr * rr;
+  let il2: f32 = 1.0 / l2;
+
+  // sampling dependant computations
+  let pa: Vec3 = p - a;
+  let y: f32 = pa.dot(ba);
+  let z: f32 = y - l2;
+  let x2: f32 = dot2(pa * l2 - ba * y);
+  let y2: f32 = y * y * l2;
+  let z2: f32 = z * z * l2;
+
+  // single square root!
+  let k: f32 = rr.sign_gl() * rr * rr * x2;
+  if z.sign_gl() * a2 * z2 > k {
+    return (x2 + z2).sqrt() * il2 - r2;
+  }
+  if y.sign_gl() * a2 * y2 < k {
+    return (x2 + y2).sqrt() * il2 - r1;
+  }
+  ((x2 * a2 * il2).sqrt() + y * rr) * il2 - r1
+}
+
+/// Triangular prism along `z`, `h` is (side length, half length).
+#[inline]
+#[must_use]
+pub fn sd_tri_prism(mut p: Vec3, mut h: Vec2) -> f32 {
+  let k: f32 = 3.0_f32.sqrt();
+  h.x *= 0.5 * k;
+  p = (p.xy() / h.x).extend(p.z);
+  p.x = p.x.abs() - 1.0;
+  p.y += 1.0 / k;
+  if p.x + k * p.y > 0.0 {
+    p = (vec2(p.x - k * p.y, -k * p.x - p.y) / 2.0).extend(p.z);
+  }
+  p.x -= p.x.clamp(-2.0, 0.0);
+  let d1: f32 = p.xy().length() * (-p.y).sign_gl() * h.x;
+  let d2: f32 = p.z.abs() - h.y;
+  vec2(d1, d2).max(Vec2::ZERO).length() + d1.max(d2).min(0.0)
+}
+
+/// Cylinder along `y`, `h` is (radius, half height).
+#[inline]
+#[must_use]
+pub fn sd_cylinder_vertical(p: Vec3, h: Vec2) -> f32 {
+  let d: Vec2 = vec2(p.xz().length(), p.y).abs() - h;
+  d.x.max(d.y).min(0.0) + d.max(Vec2::ZERO).length()
+}
+
+/// Cylinder from `a` to `b` with radius `r`.
+#[inline]
+#[must_use]
+pub fn sd_cylinder(p: Vec3, a: Vec3, b: Vec3, r: f32) -> f32 {
+  let pa: Vec3 = p - a;
+  let ba: Vec3 = b - a;
+  let baba: f32 = ba.dot(ba);
+  let paba: f32 = pa.dot(ba);
+
+  let x: f32 = (pa * baba - ba * paba).length() - r * baba;
+  let y: f32 = (paba - baba * 0.5).abs() - baba * 0.5;
+  let x2: f32 = x * x;
+  let y2: f32 = y * y * baba;
+  let d: f32 = if x.max(y) < 0.0 {
+    -x2.min(y2)
+  } else {
+    (if x > 0.0 { x2 } else { 0.0 }) + if y > 0.0 { y2 } else { 0.0 }
+  };
+  d.sign_gl() * d.abs().sqrt() / baba
+}
+
+/// Cone along `y` with its tip at the origin and height `h`, `c` is the sin/cos of the
+/// opening angle.
+#[inline]
+#[must_use]
+pub fn sd_cone(p: Vec3, c: Vec2, h: f32) -> f32 {
+  let q: Vec2 = h * vec2(c.x, -c.y) / c.y;
+  let w: Vec2 = vec2(p.xz().length(), p.y);
+
+  let a: Vec2 = w - q * (w.dot(q) / q.dot(q)).clamp(0.0, 1.0);
+  let b: Vec2 = w - q * vec2((w.x / q.x).clamp(0.0, 1.0), 1.0);
+  let k: f32 = q.y.sign_gl();
+  let d: f32 = a.dot(a).min(b.dot(b));
+  let s: f32 = (k * (w.x * q.y - w.y * q.x)).max(k * (w.y - q.y));
+  d.sqrt() * s.sign_gl()
+}
+
+/// Truncated cone along `y` with half height `h`, radius `r1` at the bottom and `r2` at the top.
+#[inline]
+#[must_use]
+pub fn sd_capped_cone_vertical(p: Vec3, h: f32, r1: f32, r2: f32) -> f32 {
+  let q: Vec2 = vec2(p.xz().length(), p.y);
+
+  let k1: Vec2 = vec2(r2, h);
+  let k2: Vec2 = vec2(r2 - r1, 2.0 * h);
+  let ca: Vec2 = vec2(
+    q.x - q.x.min(if q.y < 0.0 { r1 } else { r2 }),
+    q.y.abs() - h,
+  );
+  let cb: Vec2 = q - k1 + k2 * ((k1 - q).dot(k2) / dot2(k2)).clamp(0.0, 1.0);
+  let s: f32 = if cb.x < 0.0 && ca.y < 0.0 { -1.0 } else { 1.0 };
+  s * dot2(ca).min(dot2(cb)).sqrt()
+}
+
+/// Truncated cone with radius `ra` at `a` and `rb` at `b`.
+#[inline]
+#[must_use]
+pub fn sd_capped_cone(p: Vec3, a: Vec3, b: Vec3, ra: f32, rb: f32) -> f32 {
+  let rba: f32 = rb - ra;
+  let baba: f32 = (b - a).dot(b - a);
+  let papa: f32 = (p - a).dot(p - a);
+  let paba: f32 = (p - a).dot(b - a) / baba;
+
+  let x: f32 = (papa - paba * paba * baba).sqrt();
+
+  let cax: f32 = 0.0_f32.max(x - (if paba < 0.5 { ra } else { rb }));
+  let cay: f32 = (paba - 0.5).abs() - 0.5;
+
+  let k: f32 = rba * rba + baba;
+  let f: f32 = ((rba * (x - ra) + paba * baba) / k).clamp(0.0, 1.0);
+
+  let cbx: f32 = x - ra - f * rba;
+  let cby: f32 = paba - f;
+
+  let s: f32 = if cbx < 0.0 && cay < 0.0 { -1.0 } else { 1.0 };
+
+  s * (cax * cax + cay * cay * baba)
+    .min(cbx * cbx + cby * cby * baba)
+    .sqrt()
+}
+
+/// Spherical sector of radius `ra`, `c` is the sin/cos of its angle.
+#[inline]
+#[must_use]
+pub fn sd_solid_angle(pos: Vec3, c: Vec2, ra: f32) -> f32 {
+  let p: Vec2 = vec2(pos.xz().length(), pos.y);
+  let l: f32 = p.length() - ra;
+  let m: f32 = (p - c * p.dot(c).clamp(0.0, ra)).length();
+  l.max(m * (c.y * p.x - c.x * p.y).sign_gl())
+}
+
+/// Octahedron with its vertices at distance `s` from the origin.
+#[inline]
+#[must_use]
+pub fn sd_octahedron(mut p: Vec3, s: f32) -> f32 {
+  p = p.abs();
+  let m: f32 = p.x + p.y + p.z - s;
+  let q: Vec3 = if 3.0 * p.x < m {
+    p
+  } else if 3.0 * p.y < m {
+    p.yzx()
+  } else if 3.0 * p.z < m {
+    p.zxy()
+  } else {
+    return m * 0.57735027;
+  };
+  let k: f32 = (0.5 * (q.z - q.y + s)).clamp(0.0, s);
+  vec3(q.x, q.y - s + k, q.z - k).length()
+}
+
+/// Pyramid of height `h` on a unit square base.
+#[inline]
+#[must_use]
+pub fn sd_pyramid(mut p: Vec3, h: f32) -> f32 {
+  let m2: f32 = h * h + 0.25;
+
+  // symmetry
+  p = p.xz().abs().extend(p.y).xzy();
+  p = if p.z > p.x { p.zx() } else { p.xz() }.extend(p.y).xzy();
+  p = (p.xz() - Vec2::splat(0.5)).extend(p.y).xzy();
+
+  // project into face plane (2D)
+  let q: Vec3 = vec3(p.z, h * p.y - 0.5 * p.x, h * p.x + 0.5 * p.y);
+
+  let s: f32 = (-q.x).max(0.0);
+  let t: f32 = ((q.y - 0.5 * p.z) / (m2 + 0.25)).clamp(0.0, 1.0);
+
+  let a: f32 = m2 * (q.x + s) * (q.x + s) + q.y * q.y;
+  let b: f32 = m2 * (q.x + 0.5 * t) * (q.x + 0.5 * t) + (q.y - m2 * t) * (q.y - m2 * t);
+
+  let d2: f32 = if q.y.min(-q.x * m2 - q.y * 0.5) > 0.0 {
+    0.0
+  } else {
+    a.min(b)
+  };
+
+  // recover 3D and scale, and add sign
+  ((d2 + q.z * q.z) / m2).sqrt() * q.z.max(-p.y).sign_gl()
+}
+
+/// Rounded rhombus in the `xz` plane with semi axes `la` and `lb`, half height `h` and corner
+/// radius `ra`.
+#[inline]
+#[must_use]
+pub fn sd_rhombus(mut p: Vec3, la: f32, lb: f32, h: f32, ra: f32) -> f32 {
+  p = p.abs();
+  let b: Vec2 = vec2(la, lb);
+  let f: f32 = (ndot(b, b - 2.0 * p.xz()) / b.dot(b)).clamp(-1.0, 1.0);
+  let q: Vec2 = vec2(
+    (p.xz() - 0.5 * b * vec2(1.0 - f, 1.0 + f)).length()
+      * (p.x * b.y + p.z * b.x - b.x * b.y).sign_gl()
+      - ra,
+    p.y - h,
+  );
+  q.x.max(q.y).min(0.0) + q.max(Vec2::ZERO).length()
+}
+
+// Operators
+
+/// Union of two `(distance, material)` pairs, keeping the material of the closer one.
+#[inline]
+#[must_use]
+pub fn op_u(d1: Vec2, d2: Vec2) -> Vec2 {
+  if d1.x < d2.x {
+    d1
+  } else {
+    d2
+  }
+}
+
+#[inline(always)]
+#[must_use]
+pub fn op_union(d1: f32, d2: f32) -> f32 {
+  d1.min(d2)
+}
+
+/// Cuts `d1` out of `d2`.
+#[inline(always)]
+#[must_use]
+pub fn op_subtraction(d1: f32, d2: f32) -> f32 {
+  (-d1).max(d2)
+}
+
+#[inline(always)]
+#[must_use]
+pub fn op_intersection(d1: f32, d2: f32) -> f32 {
+  d1.max(d2)
+}
+
+/// Polynomial smooth minimum, `k` is the size of the blend region.
+#[inline]
+#[must_use]
+pub fn op_smooth_union(d1: f32, d2: f32, k: f32) -> f32 {
+  let h: f32 = (0.5 + 0.5 * (d2 - d1) / k).clamp(0.0, 1.0);
+  mix(d2, d1, h) - k * h * (1.0 - h)
+}
+
+/// Smooth version of [`op_subtraction`].
+#[inline]
+#[must_use]
+pub fn op_smooth_subtraction(d1: f32, d2: f32, k: f32) -> f32 {
+  op_smooth_intersection(-d1, d2, k)
+}
+
+/// Polynomial smooth maximum, `k` is the size of the blend region.
+#[inline]
+#[must_use]
+pub fn op_smooth_intersection(d1: f32, d2: f32, k: f32) -> f32 {
+  let h: f32 = (0.5 + 0.5 * (d2 - d1) / k).clamp(0.0, 1.0);
+  mix(d1, d2, h) + k * h * (1.0 - h)
+}
+
+/// Rounds the edges of a shape by `r`, making it that much bigger.
+#[inline(always)]
+#[must_use]
+pub fn op_round(d: f32, r: f32) -> f32 {
+  d - r
+}
+
+/// Hollows a shape out, leaving a shell of `thickness`.
+#[inline(always)]
+#[must_use]
+pub fn op_onion(d: f32, thickness: f32) -> f32 {
+  d.abs() - thickness
+}
+
+// Domain repetition
+
+/// Repeats space every `period`, returns the position relative to the closest cell center.
+#[inline]
+#[must_use]
+pub fn op_rep(p: Vec3, period: Vec3) -> Vec3 {
+  p - period * (p / period).round()
+}
+
+/// Like [`op_rep`], but only for the cells between `-limit` and `limit` (in cells).
+#[inline]
+#[must_use]
+pub fn op_rep_limited(p: Vec3, period: f32, limit: Vec3) -> Vec3 {
+  p - period * (p / period).round().clamp(-limit, limit)
+}
+
+/// Repeats space in cells of `size` starting at the origin. Returns the position relative to the
+/// cell's center and the cell's integer id.
+#[inline]
+#[must_use]
+pub fn op_rep_cell(p: Vec3, size: Vec3) -> (Vec3, Vec3) {
+  let id = (p / size).floor();
+  (p.rem_euclid(size) - size * 0.5, id)
+}
+
+/// Repeats the plane `repetitions` times around the origin, returns the position in the sector
+/// that contains the positive `x` axis. From <http://mercury.sexy/hg_sdf>.
+#[inline]
+#[must_use]
+pub fn op_rep_polar(p: Vec2, repetitions: f32) -> Vec2 {
+  let angle: f32 = TAU / repetitions;
+  let a: f32 = (p.y.atan2(p.x) + angle / 2.0).rem_euclid(angle) - angle / 2.0;
+  vec2(a.cos(), a.sin()) * p.length()
+}
+
+// Raymarching
+
+/// A distance field.
+pub trait Sdf {
+  /// Signed distance from `p` to the closest surface.
+  fn distance(&self, p: Vec3) -> f32;
+}
+
+impl<F: Fn(Vec3) -> f32> Sdf for F {
+  #[inline(always)]
+  fn distance(&self, p: Vec3) -> f32 {
+    self(p)
+  }
+}
+
+/// Sphere tracing.
+#[derive(Copy, Clone)]
+pub struct Marcher {
+  pub max_steps: u32,
+  /// A surface is hit when the distance is below `precision * t`, so far away surfaces need less
+  /// precision.
+  pub precision: f32,
+}
+
+impl Default for Marcher {
+  fn default() -> Self {
+    Self {
+      max_steps: 128,
+      precision: 0.0001,
+    }
+  }
+}
+
+impl Marcher {
+  /// Marches the ray from `ro` in the direction `rd` (normalized) between `t_min` and `t_max`,
+  /// returns the distance along the ray of the surface hit, if any.
+  #[inline]
+  #[must_use]
+  pub fn march(&self, sdf: &impl Sdf, ro: Vec3, rd: Vec3, t_min: f32, t_max: f32) -> Option<f32> {
+    let mut t: f32 = t_min;
+    let mut i = 0;
+    while i < self.max_steps && t < t_max {
+      let h: f32 = sdf.distance(ro + rd * t);
+      if h.abs() < self.precision * t {
+        return Some(t);
+      }
+      t += h;
+      i += 1;
+    }
+    None
+  }
+}
+
+/// The surface normal at `pos`, from the gradient of the distance field sampled at the corners of
+/// a tetrahedron `eps` in size. See <https://iquilezles.org/articles/normalsSDF/>.
+#[inline]
+#[must_use]
+pub fn calc_normal(sdf: &impl Sdf, pos: Vec3, eps: f32) -> Vec3 {
+  // A loop instead of four calls, so the distance function is only inlined once.
+  let mut n: Vec3 = Vec3::ZERO;
+  for i in 0..4 {
+    let e: Vec3 = 0.5773
+      * (2.0
+        * vec3(
+          (((i + 3) >> 1) & 1) as f32,
+          ((i >> 1) & 1) as f32,
+          (i & 1) as f32,
+        )
+        - Vec3::ONE);
+    n += e * sdf.distance(pos + eps * e);
+  }
+  n.normalize()
+}
+
+/// Soft shadow towards the light in direction `rd`, `0.0` for full shadow and `1.0` for none.
+/// Larger `k` make sharper shadows. Steps are clamped to `[0.02, 0.2]`, which suits scenes with
+/// objects about a unit in size. See <https://iquilezles.org/articles/rmshadows/>.
+#[inline]
+#[must_use]
+pub fn soft_shadow(sdf: &impl Sdf, ro: Vec3, rd: Vec3, mint: f32, tmax: f32, k: f32) -> f32 {
+  let mut res: f32 = 1.0;
+  let mut t: f32 = mint;
+  for _ in 0..24 {
+    let h: f32 = sdf.distance(ro + rd * t);
+    let s: f32 = (k * h / t).clamp(0.0, 1.0);
+    res = res.min(s * s * (3.0 - 2.0 * s));
+    t += h.clamp(0.02, 0.2);
+    if res < 0.004 || t > tmax {
+      break;
+    }
+  }
+  res.clamp(0.0, 1.0)
+}
+
+/// Like [`soft_shadow`], with Sebastian Aaltonen's improvement that avoids banding where the ray
+/// passes close to corners, and unclamped steps.
+#[inline]
+#[must_use]
+pub fn soft_shadow_improved(
+  sdf: &impl Sdf,
+  ro: Vec3,
+  rd: Vec3,
+  mint: f32,
+  tmax: f32,
+  k: f32,
+) -> f32 {
+  let mut res: f32 = 1.0;
+  let mut t: f32 = mint;
+  let mut ph: f32 = 1e10; // big, such that y = 0 on the first iteration
+  for _ in 0..32 {
+    let h: f32 = sdf.distance(ro + rd * t);
+    let y: f32 = h * h / (2.0 * ph);
+    let d: f32 = (h * h - y * y).sqrt();
+    res = res.min(k * d / (t - y).max(0.0));
+    ph = h;
+    t += h;
+    if res < 0.0001 || t > tmax {
+      break;
+    }
+  }
+  res.clamp(0.0, 1.0)
+}
+
+/// Ambient occlusion at `pos` from five samples along the normal `nor`, `1.0` for none.
+#[inline]
+#[must_use]
+pub fn ambient_occlusion(sdf: &impl Sdf, pos: Vec3, nor: Vec3) -> f32 {
+  let mut occ: f32 = 0.0;
+  let mut sca: f32 = 1.0;
+  for i in 0..5 {
+    let h: f32 = 0.01 + 0.12 * i as f32 / 4.0;
+    let d: f32 = sdf.distance(pos + h * nor);
+    occ += (h - d) * sca;
+    sca *= 0.95;
+    if occ > 0.35 {
+      break;
+    }
+  }
+  (1.0 - 3.0 * occ).clamp(0.0, 1.0)
+}
+
+/// Camera to world transformation for a camera at `ro` looking at `ta`, rolled by `cr` radians.
+/// Multiply `vec3(uv, focal_length)` with it to get a ray direction, see [`Camera::look_at`].
+#[inline]
+#[must_use]
+pub fn set_camera(ro: Vec3, ta: Vec3, cr: f32) -> Mat3 {
+  Camera::look_at(ro, ta, cr).basis
+}
@@ -3,7 +3,16 @@ use core::ops::{Add, Mul, Sub};
 /// We can't use the `f32::consts::SQRT_3` constant here because it is an unstable library feature
 pub const SQRT_3: f32 = 1.732_050_807_568_877_2;
 
-pub use crate::shared_data::ShaderConstants;
+pub use crate::{
+  audio::{fft, waveform, SyntheticAudio},
+  camera::{Camera, FreeCamera, ViewRay},
+  channel::{Channel, ChannelInput, ConstantColor, RgbCube, Unbound},
+  environment::{GradientSky, PreethamSky, Starfield, Studio},
+  glsl::*,
+  params::{Param, ParamValues},
+  quality::Quality,
+  shared_data::ShaderConstants,
+};
 pub use spirv_std::{
   arch::Derivative,
   glam::{
@@ -18,39 +27,20 @@ pub use spirv_std::{
 #[cfg(target_arch = "spirv")]
 pub use spirv_std::num_traits::Float;
 
-pub trait SampleCube: Copy {
-  fn sample_cube(self, p: Vec3) -> Vec4;
-}
-
-#[derive(Copy, Clone)]
-pub struct ConstantColor {
-  pub color: Vec4,
-}
-
-impl SampleCube for ConstantColor {
-  fn sample_cube(self, _: Vec3) -> Vec4 {
-    self.color
-  }
-}
-
-#[derive(Copy, Clone)]
-pub struct RgbCube {
-  pub alpha: f32,
-  pub intensity: f32,
-}
-
-impl SampleCube for RgbCube {
-  fn sample_cube(self, p: Vec3) -> Vec4 {
-    (p.abs() * self.intensity).extend(self.alpha)
-  }
-}
-
-pub struct ShaderInput {
+pub struct ShaderInput<C> {
   pub resolution: Vec3,
   pub time: f32,
   pub frag_coord: Vec2,
   /// https://www.shadertoy.com/view/Mss3zH
   pub mouse: Vec4,
+  /// Pass to [`Camera::with_free_camera`] to let the host move the camera.
+  pub free_camera: FreeCamera,
+  /// `iChannel0..3`, see [`crate::channel`].
+  pub channels: [C; 4],
+  /// Current values of [`ShaderDefinition::params`].
+  pub params: ParamValues,
+  /// How much work to spend per pixel, see [`crate::quality`].
+  pub quality: Quality,
 }
 
 pub struct ShaderResult {
@@ -59,6 +49,17 @@ pub struct ShaderResult {
 
 pub struct ShaderDefinition {
   pub name: &'static str,
+  /// Who wrote the original, empty where it doesn't say.
+  pub author: &'static str,
+  /// The license of the original: what its comment states, Shadertoy's default
+  /// `CC BY-NC-SA 3.0` otherwise.
+  pub license: &'static str,
+  /// The Shadertoy page of the original, `None` for shaders written for this repository.
+  pub link: Option<&'static str>,
+  /// Knobs the host lets the user turn, see [`crate::params`].
+  pub params: &'static [Param],
+  /// What the shader reads from `iChannel0..3`.
+  pub channels: [ChannelInput; 4],
 }
 
 #[inline(always)]
@@ -78,6 +79,9 @@ pub fn saturate(a: f32) -> f32 {
 }
 
 /// Based on: https://seblagarde.wordpress.com/2014/12/01/inverse-trigonometric-functions-gpu-optimization-for-amd-gcn-architecture/
+///
+/// The absolute error is below `6.2e-3` radians over `[-1, 1]`, largest around `0.0`, and the
+/// result is exact at `-1.0` and `1.0`.
 #[inline]
 #[must_use]
 pub fn acos_approx(v: f32) -> f32 {
@@ -92,15 +96,6 @@ pub fn acos_approx(v: f32) -> f32 {
   }
 }
 
-#[inline(always)]
-#[must_use]
-pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
-  // Scale, bias and saturate x to 0..1 range
-  let x = saturate((x - edge0) / (edge1 - edge0));
-  // Evaluate polynomial
-  x * x * 2.0f32.mul_add(-x, 3.0)
-}
-
 #[inline(always)]
 #[must_use]
 pub fn mix<X: Copy + Mul<A, Output = X> + Add<Output = X> + Sub<Output = X>, A: Copy>(
@@ -188,135 +183,48 @@ pub trait VecExt {
   fn sign_gl(self) -> Self;
 }
 
-impl VecExt for Vec2 {
-  #[inline]
-  fn sin(self) -> Self {
-    vec2(self.x.sin(), self.y.sin())
-  }
-
-  #[inline]
-  fn cos(self) -> Self {
-    vec2(self.x.cos(), self.y.cos())
-  }
-
-  #[inline]
-  fn powf_vec(self, p: Self) -> Self {
-    vec2(self.x.powf(p.x), self.y.powf(p.y))
-  }
-
-  #[inline]
-  fn sqrt(self) -> Self {
-    vec2(self.x.sqrt(), self.y.sqrt())
-  }
-
-  #[inline]
-  fn ln(self) -> Self {
-    vec2(self.x.ln(), self.y.ln())
-  }
-
-  #[inline]
-  fn step(self, other: Self) -> Self {
-    vec2(self.x.step(other.x), self.y.step(other.y))
-  }
-
-  #[inline]
-  fn sign_gl(self) -> Self {
-    vec2(self.x.sign_gl(), self.y.sign_gl())
-  }
-}
-
-impl VecExt for Vec3 {
-  #[inline]
-  fn sin(self) -> Self {
-    vec3(self.x.sin(), self.y.sin(), self.z.sin())
-  }
-
-  #[inline]
-  fn cos(self) -> Self {
-    vec3(self.x.cos(), self.y.cos(), self.z.cos())
-  }
-
-  #[inline]
-  fn powf_vec(self, p: Self) -> Self {
-    vec3(self.x.powf(p.x), self.y.powf(p.y), self.z.powf(p.z))
-  }
-
-  #[inline]
-  fn sqrt(self) -> Self {
-    vec3(self.x.sqrt(), self.y.sqrt(), self.z.sqrt())
-  }
-
-  #[inline]
-  fn ln(self) -> Self {
-    vec3(self.x.ln(), self.y.ln(), self.z.ln())
-  }
-
-  #[inline]
-  fn step(self, other: Self) -> Self {
-    vec3(
-      self.x.step(other.x),
-      self.y.step(other.y),
-      self.z.step(other.z),
-    )
-  }
-
-  #[inline]
-  fn sign_gl(self) -> Self {
-    vec3(self.x.sign_gl(), self.y.sign_gl(), self.z.sign_gl())
-  }
+macro_rules! impl_vec_ext {
+  ($($ty:ident($($field:ident),+)),+ $(,)?) => {$(
+    impl VecExt for $ty {
+      #[inline]
+      fn sin(self) -> Self {
+        Self::new($(self.$field.sin()),+)
+      }
+
+      #[inline]
+      fn cos(self) -> Self {
+        Self::new($(self.$field.cos()),+)
+      }
+
+      #[inline]
+      fn powf_vec(self, p: Self) -> Self {
+        Self::new($(self.$field.powf(p.$field)),+)
+      }
+
+      #[inline]
+      fn sqrt(self) -> Self {
+        Self::new($(self.$field.sqrt()),+)
+      }
+
+      #[inline]
+      fn ln(self) -> Self {
+        Self::new($(self.$field.ln()),+)
+      }
+
+      #[inline]
+      fn step(self, other: Self) -> Self {
+        Self::new($(self.$field.step(other.$field)),+)
+      }
+
+      #[inline]
+      fn sign_gl(self) -> Self {
+        Self::new($(self.$field.sign_gl()),+)
+      }
+    }
+  )+};
 }
 
-impl VecExt for Vec4 {
-  #[inline]
-  fn sin(self) -> Self {
-    vec4(self.x.sin(), self.y.sin(), self.z.sin(), self.w.sin())
-  }
-
-  #[inline]
-  fn cos(self) -> Self {
-    vec4(self.x.cos(), self.y.cos(), self.z.cos(), self.w.cos())
-  }
-
-  #[inline]
-  fn powf_vec(self, p: Self) -> Self {
-    vec4(
-      self.x.powf(p.x),
-      self.y.powf(p.y),
-      self.z.powf(p.z),
-      self.w.powf(p.w),
-    )
-  }
-
-  #[inline]
-  fn sqrt(self) -> Self {
-    vec4(self.x.sqrt(), self.y.sqrt(), self.z.sqrt(), self.w.sqrt())
-  }
-
-  #[inline]
-  fn ln(self) -> Self {
-    vec4(self.x.ln(), self.y.ln(), self.z.ln(), self.w.ln())
-  }
-
-  #[inline]
-  fn step(self, other: Self) -> Self {
-    vec4(
-      self.x.step(other.x),
-      self.y.step(other.y),
-      self.z.step(other.z),
-      self.w.step(other.w),
-    )
-  }
-
-  #[inline]
-  fn sign_gl(self) -> Self {
-    vec4(
-      self.x.sign_gl(),
-      self.y.sign_gl(),
-      self.z.sign_gl(),
-      self.w.sign_gl(),
-    )
-  }
-}
+impl_vec_ext!(Vec2(x, y), Vec3(x, y, z), Vec4(x, y, z, w));
 
 #[inline(always)]
 pub fn discard() {
@@ -11,26 +11,60 @@
 //! */
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{noise::sin_hash, shader_prelude::*};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "A Lot of Spheres",
+  author: "Reinder Nijhoff",
+  license: "CC BY-NC-SA 4.0",
+  link: Some("https://www.shadertoy.com/view/lsX3WH"),
+  params: &[GRIDSIZE, SPEED],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
-  let color = &mut render_result.color;
-  let &ShaderInput {
-    resolution,
-    time,
-    frag_coord,
-    ..
-  } = render_instruction;
-  Inputs { resolution, time }.main_image(color, frag_coord);
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
+  Inputs::new(render_instruction)
+    .main_image(&mut render_result.color, render_instruction.frag_coord);
+}
+
+pub fn ray_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  ray: ViewRay,
+  render_result: &mut ShaderResult,
+) {
+  let inputs = Inputs::new(render_instruction);
+  let (ro, rd) = inputs.camera().view_ray(ray);
+  render_result.color = inputs.render(ro, rd).extend(1.0);
 }
 
 struct Inputs {
   resolution: Vec3,
   time: f32,
+  free_camera: FreeCamera,
+  grid_size: f32,
+  speed: f32,
+}
+
+impl Inputs {
+  fn new<C>(render_instruction: &ShaderInput<C>) -> Self {
+    let &ShaderInput {
+      resolution,
+      time,
+      free_camera,
+      params,
+      ..
+    } = render_instruction;
+    Self {
+      resolution,
+      time,
+      free_camera,
+      grid_size: params.float(&GRIDSIZE),
+      speed: params.float(&SPEED),
+    }
+  }
 }
 
 const SHADOW: bool = true;
@@ -41,10 +75,12 @@ const RAYCASTSTEPS: usize = 40;
 const EXPOSURE: f32 = 0.9;
 const EPSILON: f32 = 0.0001;
 const MAXDISTANCE: f32 = 400.0;
-const GRIDSIZE: f32 = 8.0;
+// At least GRIDSIZESMALL + 2.0, so the spheres stay inside their cell.
+const GRIDSIZE: Param = Param::float(0, "Grid size", 8.0, 7.0, 20.0);
 const GRIDSIZESMALL: f32 = 5.0;
 const MAXHEIGHT: f32 = 30.0;
-const SPEED: f32 = 0.5;
+// Seconds the camera takes to cross a grid cell.
+const SPEED: Param = Param::float(1, "Seconds per cell", 0.5, 0.1, 2.0);
 
 //
 // math functions
@@ -52,7 +88,7 @@ const SPEED: f32 = 0.5;
 
 const _MR: Mat2 = mat2(vec2(0.84147, 0.54030), vec2(0.54030, -0.84147));
 fn hash(n: f32) -> f32 {
-  (n.sin() * 43758.5453).fract_gl()
+  sin_hash(n, 43758.5453)
 }
 fn _hash2(n: f32) -> Vec2 {
   (vec2(n, n + 1.0).sin() * vec2(2.1459123, 3.3490423)).fract_gl()
@@ -119,12 +155,12 @@ impl Inputs {
     let y: f32 = s * MAXHEIGHT * (4.0 * t * (1. - t)).abs();
     let offset: Vec2 = grid + sphere_offset;
 
-    *center = vec3(offset.x, y, offset.y) + 0.5 * vec3(GRIDSIZE, 2.0, GRIDSIZE);
+    *center = vec3(offset.x, y, offset.y) + 0.5 * vec3(self.grid_size, 2.0, self.grid_size);
+  }
+  fn get_sphere_position(&self, grid: Vec2, sphere_offset: Vec2, center: &mut Vec3) {
+    let offset: Vec2 = grid + sphere_offset;
+    *center = vec3(offset.x, 0.0, offset.y) + 0.5 * vec3(self.grid_size, 2.0, self.grid_size);
   }
-}
-fn get_sphere_position(grid: Vec2, sphere_offset: Vec2, center: &mut Vec3) {
-  let offset: Vec2 = grid + sphere_offset;
-  *center = vec3(offset.x, 0.0, offset.y) + 0.5 * vec3(GRIDSIZE, 2.0, GRIDSIZE);
 }
 fn get_sphere_color(grid: Vec2) -> Vec3 {
   hash3_vec(grid + vec2(43.12 * grid.y, 12.23 * grid.x)).normalize()
@@ -157,14 +193,14 @@ impl Inputs {
     }
 
     // trace grid
-    let mut pos: Vec3 = (ro / GRIDSIZE).floor() * GRIDSIZE;
+    let mut pos: Vec3 = (ro / self.grid_size).floor() * self.grid_size;
     let ri: Vec3 = 1.0 / rd;
-    let rs: Vec3 = rd.sign_gl() * GRIDSIZE;
-    let mut dis: Vec3 = (pos - ro + 0.5 * Vec3::splat(GRIDSIZE) + rs * 0.5) * ri;
+    let rs: Vec3 = rd.sign_gl() * self.grid_size;
+    let mut dis: Vec3 = (pos - ro + 0.5 * Vec3::splat(self.grid_size) + rs * 0.5) * ri;
     let mut mm: Vec3;
 
     for _ in 0..RAYCASTSTEPS {
-      if *material > 1 || ro.xz().distance(pos.xz()) > *dist + GRIDSIZE {
+      if *material > 1 || ro.xz().distance(pos.xz()) > *dist + self.grid_size {
         break;
       }
       let mut offset: Vec2 = Vec2::ZERO;
@@ -180,7 +216,7 @@ impl Inputs {
         *material = 2;
       }
 
-      get_sphere_position(pos.xz(), offset, &mut sphere_center);
+      self.get_sphere_position(pos.xz(), offset, &mut sphere_center);
       if intersect_unit_sphere(ro, rd, sphere_center, &mut distcheck, &mut normalcheck)
         && distcheck < *dist
       {
@@ -197,11 +233,11 @@ impl Inputs {
     let mut color: Vec3 = Vec3::ZERO;
     if *material > 0 {
       *intersection = ro + rd * *dist;
-      let map: Vec2 = (intersection.xz() / GRIDSIZE).floor() * GRIDSIZE;
+      let map: Vec2 = (intersection.xz() / self.grid_size).floor() * self.grid_size;
 
       if *material == 1 || *material == 3 {
         // lightning
-        let c: Vec3 = vec3(-GRIDSIZE, 0.0, GRIDSIZE);
+        let c: Vec3 = vec3(-self.grid_size, 0.0, self.grid_size);
         for x in 0..3 {
           for y in 0..3 {
             let mapoffset: Vec2 = map + vec2([c.x, c.y, c.z][x], [c.x, c.y, c.z][y]);
@@ -225,7 +261,7 @@ impl Inputs {
                   get_sphere_offset(smapoffset, &mut soffset);
                   let mut slpos: Vec3 = Vec3::ZERO;
                   let mut sn: Vec3 = Vec3::ZERO;
-                  get_sphere_position(smapoffset, soffset, &mut slpos);
+                  self.get_sphere_position(smapoffset, soffset, &mut slpos);
                   let mut sd: f32 = 0.0;
                   if intersect_unit_sphere(
                     *intersection,
@@ -243,7 +279,7 @@ impl Inputs {
               * lcolor
               * (shadow
                 * ((lpos - *intersection).normalize().dot(*normal)).max(0.0)
-                * (1. - (lpos.distance(*intersection) / GRIDSIZE).clamp(0.0, 1.)));
+                * (1. - (lpos.distance(*intersection) / self.grid_size).clamp(0.0, 1.)));
           }
         }
       } else {
@@ -254,19 +290,13 @@ impl Inputs {
     color
   }
 
-  fn main_image(&mut self, frag_color: &mut Vec4, frag_coord: Vec2) {
-    let q: Vec2 = frag_coord / self.resolution.xy();
-    let mut p: Vec2 = Vec2::splat(-1.0) + 2.0 * q;
-    p.x *= self.resolution.x / self.resolution.y;
-
-    // camera
+  fn camera(&self) -> Camera {
     let ce: Vec3 = vec3(
       (0.232 * self.time).cos() * 10.0,
       6. + 3.0 * (0.3 * self.time).cos(),
-      GRIDSIZE * (self.time / SPEED),
+      self.grid_size * (self.time / self.speed),
     );
-    let ro: Vec3 = ce;
-    let ta: Vec3 = ro
+    let ta: Vec3 = ce
       + vec3(
         -(0.232 * self.time).sin() * 10.,
         -2.0 + (0.23 * self.time).cos(),
@@ -274,12 +304,11 @@ impl Inputs {
       );
 
     let roll: f32 = -0.15 * (0.5 * self.time).sin();
-    // camera tx
-    let cw: Vec3 = (ta - ro).normalize();
-    let cp: Vec3 = vec3(roll.sin(), roll.cos(), 0.0);
-    let cu: Vec3 = (cw.cross(cp)).normalize();
-    let cv: Vec3 = (cu.cross(cw)).normalize();
-    let mut rd: Vec3 = (p.x * cu + p.y * cv + 1.5 * cw).normalize();
+    Camera::look_at(ce, ta, roll).with_free_camera(self.free_camera)
+  }
+
+  /// The color seen along the ray, before vignetting.
+  fn render(&self, ro: Vec3, mut rd: Vec3) -> Vec3 {
     // raytrace
     let mut material: i32 = 0;
     let mut normal: Vec3 = Vec3::ZERO;
@@ -309,7 +338,17 @@ impl Inputs {
     }
 
     col = col.powf_vec(vec3(EXPOSURE, EXPOSURE, EXPOSURE));
-    col = col.clamp(Vec3::ZERO, Vec3::ONE);
+    col.clamp(Vec3::ZERO, Vec3::ONE)
+  }
+
+  fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {
+    let q: Vec2 = frag_coord / self.resolution.xy();
+    let mut p: Vec2 = Vec2::splat(-1.0) + 2.0 * q;
+    p.x *= self.resolution.x / self.resolution.y;
+
+    // camera tx
+    let camera = self.camera();
+    let mut col: Vec3 = self.render(camera.position, camera.ray_direction(p, 1.5));
     // vigneting
     col *= 0.25 + 0.75 * (16.0 * q.x * q.y * (1.0 - q.x) * (1.0 - q.y)).powf(0.15);
 
@@ -26,13 +26,21 @@
 //! */
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{noise::sin_hash_dot, sdf::sd_circle, shader_prelude::*};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "A Question of Time",
+  author: "Rigel",
+  license: "CC BY 4.0",
+  link: Some("https://www.shadertoy.com/view/lljfRD"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
@@ -61,10 +69,6 @@ fn sdf_rect(uv: Vec2, s: Vec2) -> f32 {
   let auv: Vec2 = uv.abs();
   (auv.x - s.x).max(auv.y - s.y)
 }
-// a signed distance function for a circle
-fn sdf_circle(uv: Vec2, c: Vec2, r: f32) -> f32 {
-  (uv - c).length() - r
-}
 // fills an sdf in 2d
 fn fill(d: f32, s: f32, i: f32) -> f32 {
   (smoothstep(0.0, s, d) - i).abs()
@@ -87,7 +91,7 @@ fn inversion(uv: Vec2, r: f32) -> Vec2 {
 }
 // seeded random number
 fn hash(s: Vec2) -> f32 {
-  ((s.dot(vec2(12.9898, 78.2333))).sin() * 43758.5453123).fract_gl()
+  sin_hash_dot(s, vec2(12.9898, 78.2333), 43758.5453123)
 }
 
 // this is an algorithm to construct an apollonian packing with a descartes configuration
@@ -177,7 +181,7 @@ impl Inputs {
     let a: f32 = uv_apo.y.atan2(uv_apo.x);
     let r: f32 = uv_apo.xy().length();
 
-    let circle: f32 = sdf_circle(uv, uv - uv_apo.xy(), uv_apo.z);
+    let circle: f32 = sd_circle(uv - (uv - uv_apo.xy()), uv_apo.z);
 
     // background
     let mut c: Vec3 = uv.length() * pal(0.7) * 0.2;
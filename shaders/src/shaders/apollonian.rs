@@ -14,29 +14,76 @@ use crate::shader_prelude::*;
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Apollonian Fractal",
+  author: "Inigo Quilez",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/4ds3zn"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
-  let &ShaderInput {
-    resolution,
-    time,
-    frag_coord,
-    mouse,
-    ..
-  } = render_instruction;
-  State::new(Inputs {
-    resolution,
-    time,
-    mouse,
-  })
-  .main_image(color, frag_coord);
+  State::new(Inputs::new(render_instruction)).main_image(color, render_instruction.frag_coord);
+}
+
+pub fn ray_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  ray: ViewRay,
+  render_result: &mut ShaderResult,
+) {
+  let mut state = State::new(Inputs::new(render_instruction));
+  let (ro, rd) = state.inputs.camera().view_ray(ray);
+  let anim = state.inputs.anim();
+  render_result.color = state.render(ro, rd, anim).extend(1.0);
 }
 
 struct Inputs {
   resolution: Vec3,
   time: f32,
   mouse: Vec4,
+  /// Antialiasing level, 2 at `Quality::High` (the original's `HW_PERFORMANCE`).
+  aa: u32,
+}
+
+impl Inputs {
+  fn new<C>(render_instruction: &ShaderInput<C>) -> Self {
+    let &ShaderInput {
+      resolution,
+      time,
+      mouse,
+      quality,
+      ..
+    } = render_instruction;
+    Self {
+      resolution,
+      time,
+      mouse,
+      aa: quality.samples_per_axis(),
+    }
+  }
+
+  fn anim(&self) -> f32 {
+    1.1 + 0.5 * smoothstep(-0.3, 0.3, (0.1 * self.time).cos())
+  }
+
+  fn camera(&self) -> Camera {
+    let time: f32 = self.time * 0.25 + 0.01 * self.mouse.x;
+    let ro: Vec3 = vec3(
+      2.8 * (0.1 + 0.33 * time).cos(),
+      0.4 + 0.30 * (0.37 * time).cos(),
+      2.8 * (0.5 + 0.35 * time).cos(),
+    );
+    let ta: Vec3 = vec3(
+      1.9 * (1.2 + 0.41 * time).cos(),
+      0.4 + 0.10 * (0.27 * time).cos(),
+      1.9 * (2.0 + 0.38 * time).cos(),
+    );
+    let roll: f32 = 0.2 * (0.1 * time).cos();
+    Camera::look_at(ro, ta, roll)
+  }
 }
 
 struct State {
@@ -54,15 +101,6 @@ impl State {
   }
 }
 
-const HW_PERFORMANCE: usize = 1;
-
-// Antialiasing level
-const AA: usize = if HW_PERFORMANCE == 0 {
-  1
-} else {
-  2 // Make it 3 if you have a fast machine
-};
-
 impl State {
   fn map(&mut self, mut p: Vec3, s: f32) -> f32 {
     let mut scale: f32 = 1.0;
@@ -149,38 +187,24 @@ impl State {
   }
 
   fn main_image(&mut self, frag_color: &mut Vec4, frag_coord: Vec2) {
-    let time: f32 = self.inputs.time * 0.25 + 0.01 * self.inputs.mouse.x;
-    let anim: f32 = 1.1 + 0.5 * smoothstep(-0.3, 0.3, (0.1 * self.inputs.time).cos());
+    let anim: f32 = self.inputs.anim();
+    // camera
+    let camera = self.inputs.camera();
     let mut tot: Vec3 = Vec3::ZERO;
 
-    for jj in 0..AA {
-      for ii in 0..AA {
-        let q: Vec2 = frag_coord + vec2(ii as f32, jj as f32) / AA as f32;
+    let aa = self.inputs.aa;
+    for jj in 0..aa {
+      for ii in 0..aa {
+        let q: Vec2 = frag_coord + vec2(ii as f32, jj as f32) / aa as f32;
         let p: Vec2 = (2.0 * q - self.inputs.resolution.xy()) / self.inputs.resolution.y;
 
-        // camera
-        let ro: Vec3 = vec3(
-          2.8 * (0.1 + 0.33 * time).cos(),
-          0.4 + 0.30 * (0.37 * time).cos(),
-          2.8 * (0.5 + 0.35 * time).cos(),
-        );
-        let ta: Vec3 = vec3(
-          1.9 * (1.2 + 0.41 * time).cos(),
-          0.4 + 0.10 * (0.27 * time).cos(),
-          1.9 * (2.0 + 0.38 * time).cos(),
-        );
-        let roll: f32 = 0.2 * (0.1 * time).cos();
-        let cw: Vec3 = (ta - ro).normalize();
-        let cp: Vec3 = vec3(roll.sin(), roll.cos(), 0.0);
-        let cu: Vec3 = cw.cross(cp).normalize();
-        let cv: Vec3 = cu.cross(cw).normalize();
-        let rd: Vec3 = (p.x * cu + p.y * cv + 2.0 * cw).normalize();
-
-        tot += self.render(ro, rd, anim);
+        let rd: Vec3 = camera.ray_direction(p, 2.0);
+
+        tot += self.render(camera.position, rd, anim);
       }
     }
 
-    tot /= (AA * AA) as f32;
+    tot /= (aa * aa) as f32;
 
     *frag_color = tot.extend(1.0);
   }
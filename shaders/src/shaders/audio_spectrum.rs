@@ -0,0 +1,79 @@
+//! A music visualizer: the spectrum of `iChannel0` as bars and its waveform as a line over them.
+//! The host binds the audio of `--audio <file.wav>` to it, see `src/audio.rs`, without one it
+//! shows [`SyntheticAudio`].
+
+use crate::shader_prelude::*;
+
+pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+  name: "Audio Spectrum",
+  author: "",
+  license: "MIT OR Apache-2.0",
+  link: None,
+  params: &[],
+  channels: [
+    ChannelInput::Audio,
+    ChannelInput::Procedural,
+    ChannelInput::Procedural,
+    ChannelInput::Procedural,
+  ],
+};
+
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
+  let color = &mut render_result.color;
+  let &ShaderInput {
+    resolution,
+    time,
+    frag_coord,
+    channels,
+    ..
+  } = render_instruction;
+  Inputs {
+    resolution,
+    audio: channels[0].or(SyntheticAudio { time }),
+  }
+  .main_image(color, frag_coord);
+}
+
+struct Inputs<A> {
+  resolution: Vec3,
+  audio: A,
+}
+
+const BARS: f32 = 48.0;
+
+impl<A: Channel> Inputs<A> {
+  fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {
+    let uv = frag_coord / self.resolution.xy();
+    let pixel = 1.0 / self.resolution.y;
+
+    let mut col = mix(vec3(0.02, 0.02, 0.05), vec3(0.08, 0.04, 0.12), uv.y);
+
+    // Bars over the bottom two thirds, the low frequencies get more of them as in music most
+    // of the energy is there.
+    let bar = (uv.x * BARS).floor();
+    let frequency = ((bar + 0.5) / BARS).powf(2.0);
+    let level = fft(self.audio, frequency);
+    let height = level * 0.65;
+    let in_bar = smoothstep(0.5, 0.4, ((uv.x * BARS).fract_gl() - 0.5).abs());
+    let bar_color = mix(vec3(0.1, 0.6, 1.0), vec3(1.0, 0.2, 0.5), bar / BARS);
+    let below = smoothstep(height + pixel, height, uv.y);
+    col = mix(
+      col,
+      bar_color * (0.4 + 0.8 * uv.y / height.max(0.01)),
+      below * in_bar,
+    );
+    // A glow above the bars.
+    col += bar_color * in_bar * 0.15 * (-20.0 * (uv.y - height).max(0.0)).exp() * level;
+
+    // The waveform around the middle of the top third.
+    let wave = 0.8 + 0.3 * (waveform(self.audio, uv.x) - 0.5);
+    let distance = (uv.y - wave).abs() / pixel;
+    col += vec3(0.9, 0.95, 1.0) * smoothstep(2.5, 0.5, distance);
+    col += vec3(0.3, 0.5, 1.0) * 0.3 * (-0.15 * distance).exp();
+
+    *frag_color = col.extend(1.0);
+  }
+}
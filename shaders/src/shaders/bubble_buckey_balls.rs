@@ -12,26 +12,37 @@ use crate::shader_prelude::*;
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Bubble Buckey Balls",
+  author: "mplanck",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/lslSRf"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
     time,
     frag_coord,
     mouse,
+    channels,
     ..
   } = render_instruction;
   State::new(Inputs {
     resolution,
     time,
     mouse,
-    channel0: RgbCube {
-      alpha: 1.0,
-      intensity: 0.5,
-    },
-    channel1: ConstantColor { color: Vec4::ONE },
+    // The original reflects an interior cubemap and a blurred version of it for rough
+    // surfaces.
+    channel0: channels[0].or(Studio::new()),
+    channel1: channels[1].or(Studio {
+      softness: 1.0,
+      ..Studio::new()
+    }),
   })
   .main_image(color, frag_coord);
 }
@@ -438,7 +449,7 @@ fn material(surfid: f32, surf: &mut SurfaceData) {
   }
 }
 
-impl<C0: SampleCube, C1: SampleCube> State<C0, C1> {
+impl<C0: Channel, C1: Channel> State<C0, C1> {
   fn integrate_dir_light(&self, ldir: Vec3, lcolor: Vec3, surf: SurfaceData) -> Vec3 {
     let vdir: Vec3 = (self.cam_origin - surf.point).normalize();
 
@@ -550,8 +561,8 @@ impl<C0: SampleCube, C1: SampleCube> State<C0, C1> {
     let envdir: Vec3 = vdir.reflect(surf.normal);
     let specolor: Vec4 = Vec4::splat(0.4)
       * mix(
-        self.inputs.channel0.sample_cube(envdir),
-        self.inputs.channel1.sample_cube(envdir),
+        self.inputs.channel0.texture_cube(envdir),
+        self.inputs.channel1.texture_cube(envdir),
         surf.roughness,
       );
 
@@ -1,10 +1,20 @@
 //! Ported to Rust from <https://www.shadertoy.com/view/4tdSWr>
 
-use crate::shader_prelude::*;
-
-pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition { name: "Clouds" };
-
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+use crate::{noise::simplex_noise2, shader_prelude::*};
+
+pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+  name: "Clouds",
+  author: "drift",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/4tdSWr"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
+};
+
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
@@ -38,37 +48,14 @@ fn hash(mut p: Vec2) -> Vec2 {
 }
 
 fn noise(p: Vec2) -> f32 {
-  const K1: f32 = 0.366025404; // (sqrt(3)-1)/2;
-  const K2: f32 = 0.211324865; // (3-sqrt(3))/6;
-  let i: Vec2 = (p + Vec2::splat((p.x + p.y) * K1)).floor();
-  let a: Vec2 = p - i + Vec2::splat((i.x + i.y) * K2);
-  let o: Vec2 = if a.x > a.y {
-    vec2(1.0, 0.0)
-  } else {
-    vec2(0.0, 1.0)
-  }; //vec2 of = 0.5 + 0.5*vec2(sign(a.x-a.y), sign(a.y-a.x));
-  let b: Vec2 = a - o + Vec2::splat(K2);
-  let c: Vec2 = a - Vec2::splat(1.0 - 2.0 * K2);
-  let h: Vec3 = (Vec3::splat(0.5) - vec3(a.dot(a), b.dot(b), c.dot(c))).max(Vec3::ZERO);
-  let n: Vec3 = (h * h * h * h)
-    * vec3(
-      a.dot(hash(i + Vec2::ZERO)),
-      b.dot(hash(i + o)),
-      c.dot(hash(i + Vec2::splat(1.0))),
-    );
-  n.dot(Vec3::splat(70.0))
+  simplex_noise2(p, hash)
 }
 
-fn fbm(mut n: Vec2) -> f32 {
-  let mut total: f32 = 0.0;
-  let mut amplitude: f32 = 0.1;
-  for _ in 0..7 {
-    total += noise(n) * amplitude;
+fn fbm(n: Vec2) -> f32 {
+  crate::noise::fbm(n, 7, 0.1, 0.4, noise, |n| {
     let m = M;
-    n = m.transpose() * n;
-    amplitude *= 0.4;
-  }
-  total
+    m.transpose() * n
+  })
 }
 
 // -----------------------------------------------
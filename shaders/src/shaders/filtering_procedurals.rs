@@ -19,13 +19,21 @@
 //! */
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{noise::gradient_noise3, shader_prelude::*};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Filtering Procedurals",
+  author: "Inigo Quilez",
+  license: "MIT",
+  link: Some("https://www.shadertoy.com/view/MdjGR1"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
@@ -70,40 +78,7 @@ fn hash3(mut p: Vec3) -> Vec3 {
 }
 
 fn noise(p: Vec3) -> f32 {
-  let i: Vec3 = p.floor();
-  let f: Vec3 = p.fract_gl();
-
-  let u: Vec3 = f * f * (Vec3::splat(3.0) - 2.0 * f);
-
-  mix(
-    mix(
-      mix(
-        hash3(i + vec3(0.0, 0.0, 0.0)).dot(f - vec3(0.0, 0.0, 0.0)),
-        hash3(i + vec3(1.0, 0.0, 0.0)).dot(f - vec3(1.0, 0.0, 0.0)),
-        u.x,
-      ),
-      mix(
-        hash3(i + vec3(0.0, 1.0, 0.0)).dot(f - vec3(0.0, 1.0, 0.0)),
-        hash3(i + vec3(1.0, 1.0, 0.0)).dot(f - vec3(1.0, 1.0, 0.0)),
-        u.x,
-      ),
-      u.y,
-    ),
-    mix(
-      mix(
-        hash3(i + vec3(0.0, 0.0, 1.0)).dot(f - vec3(0.0, 0.0, 1.0)),
-        hash3(i + vec3(1.0, 0.0, 1.0)).dot(f - vec3(1.0, 0.0, 1.0)),
-        u.x,
-      ),
-      mix(
-        hash3(i + vec3(0.0, 1.0, 1.0)).dot(f - vec3(0.0, 1.0, 1.0)),
-        hash3(i + vec3(1.0, 1.0, 1.0)).dot(f - vec3(1.0, 1.0, 1.0)),
-        u.x,
-      ),
-      u.y,
-    ),
-    u.z,
-  )
+  gradient_noise3(p, hash3)
 }
 
 //===============================================================================================
@@ -8,9 +8,19 @@
 
 use crate::shader_prelude::*;
 
-pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition { name: "Hearts" };
+pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+  name: "Hearts",
+  author: "Inigo Quilez",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/XsfGRn"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
+};
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
@@ -17,25 +17,39 @@
 //! // Twitter: @The_ArtOfCode
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{
+  sdf::{op_smooth_intersection, op_smooth_union, sd_sphere},
+  shader_prelude::*,
+};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Luminescence",
+  author: "Martijn Steinrucken",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/4sXBRn"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
     time,
     frag_coord,
     mouse,
+    quality,
     ..
   } = render_instruction;
   State::new(Inputs {
     resolution,
     time,
     mouse,
+    max_steps: quality.steps(MAX_STEPS),
+    volume_steps: quality.steps(VOLUME_STEPS),
   })
   .main_image(color, frag_coord);
 }
@@ -45,6 +59,9 @@ struct Inputs {
   resolution: Vec3,
   time: f32,
   mouse: Vec4,
+  /// [`MAX_STEPS`] and [`VOLUME_STEPS`] scaled by the quality level.
+  max_steps: u32,
+  volume_steps: u32,
 }
 
 struct State {
@@ -194,21 +211,6 @@ fn n31(p: f32) -> Vec3 {
   .fract_gl()
 }
 
-// DE functions from IQ
-fn smin(a: f32, b: f32, k: f32) -> f32 {
-  let h: f32 = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
-  mix(b, a, h) - k * h * (1.0 - h)
-}
-
-fn smax(a: f32, b: f32, k: f32) -> f32 {
-  let h: f32 = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
-  mix(a, b, h) + k * h * (1.0 - h)
-}
-
-fn sd_sphere(p: Vec3, pos: Vec3, s: f32) -> f32 {
-  (p - pos).length() - s
-}
-
 // From http://mercury.sexy/hg_sdf
 fn p_mod_polar(p: &mut Vec2, repetitions: f32, fix: f32) -> Vec2 {
   let angle: f32 = TAU / repetitions;
@@ -338,10 +340,10 @@ impl State {
     p.y -= ((x + x.cos()).cos() + (2.0 * x).sin() * 0.2) * 0.6;
     p = (p.xz() * (1.0 + pump * 0.2)).extend(p.y).xzy();
 
-    let d1: f32 = sd_sphere(p, vec3(0.0, 0.0, 0.0), r);
-    let d2: f32 = sd_sphere(p, vec3(0.0, -0.5, 0.0), r);
+    let d1: f32 = sd_sphere(p - vec3(0.0, 0.0, 0.0), r);
+    let d2: f32 = sd_sphere(p - vec3(0.0, -0.5, 0.0), r);
 
-    o.d = smax(d1, -d2, 0.1);
+    o.d = op_smooth_intersection(d1, -d2, 0.1);
     o.m = 1.0;
 
     if p.y < 0.5 {
@@ -360,7 +362,7 @@ impl State {
 
       let d32: f32 = (mp.xz() - vec2(0.2, 0.1)).length() - remap(0.5, -3.5, 0.1, 0.04, mp.y) * 0.5;
       d3 = d3.min(d32);
-      o.d = smin(o.d, d3, 0.5);
+      o.d = op_smooth_union(o.d, d3, 0.5);
 
       if p.y < 0.2 {
         let mut op: Vec3 = p;
@@ -370,7 +372,7 @@ impl State {
         if d4 < o.d {
           o.m = 3.0;
         }
-        o.d = smin(o.d, d4, 0.15);
+        o.d = op_smooth_union(o.d, d4, 0.15);
       }
     }
     o.pump = pump;
@@ -404,7 +406,7 @@ impl State {
     let t: f32 = self.inputs.time;
     let grid: Vec3 = vec3(6.0, 30.0, 6.0);
 
-    for _ in 0..MAX_STEPS {
+    for _ in 0..self.inputs.max_steps {
       p = r.o + r.d * d;
 
       if SINGLE {
@@ -515,7 +517,7 @@ impl State {
       if o.m == 1.0 {
         // hood color
         let mut density: f32 = 0.0;
-        for i in 0..VOLUME_STEPS {
+        for i in 0..self.inputs.volume_steps {
           let sd: f32 = sph(o.uv, cam_ray.d, Vec3::ZERO, 0.8 + i as f32 * 0.015).x;
           if sd != MAX_DISTANCE {
             let intersect: Vec2 = o.uv.xz() + cam_ray.d.xz() * sd;
@@ -524,7 +526,9 @@ impl State {
             density += self.vol_tex(o.uv, uv, 1.4 + i as f32 * 0.03, o.pump);
           }
         }
-        let vol_tex: Vec4 = self.accent.extend(density / VOLUME_STEPS as f32);
+        let vol_tex: Vec4 = self
+          .accent
+          .extend(density / self.inputs.volume_steps as f32);
 
         let mut dif: Vec3 = jelly_tex(o.uv).xyz();
         dif *= lambert.max(0.2);
@@ -14,27 +14,40 @@ use crate::shader_prelude::*;
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Mandelbrot Smooth",
+  author: "Inigo Quilez",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/4df3Rn"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
     time,
     frag_coord,
+    quality,
     ..
   } = render_instruction;
-  Inputs { resolution, time }.main_image(color, frag_coord);
+  Inputs {
+    resolution,
+    time,
+    aa: quality.samples_per_axis(),
+  }
+  .main_image(color, frag_coord);
 }
 
 struct Inputs {
   resolution: Vec3,
   time: f32,
+  /// Antialiasing level, 2 at `Quality::High`.
+  aa: u32,
 }
 
-// increase this if you have a very fast GPU
-const AA: usize = 2;
-
 impl Inputs {
   fn mandelbrot(&self, c: Vec2) -> f32 {
     if true {
@@ -76,13 +89,14 @@ impl Inputs {
   fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {
     let mut col: Vec3 = Vec3::ZERO;
 
-    for m in 0..AA {
-      for n in 0..AA {
+    let aa = self.aa;
+    for m in 0..aa {
+      for n in 0..aa {
         let p: Vec2 = (-self.resolution.xy()
-          + Vec2::splat(2.0) * (frag_coord + vec2(m as f32, n as f32) / AA as f32))
+          + Vec2::splat(2.0) * (frag_coord + vec2(m as f32, n as f32) / aa as f32))
           / self.resolution.y;
-        let w: f32 = (AA * m + n) as f32;
-        let time: f32 = self.time + 0.5 * (1.0 / 24.0) * w / (AA * AA) as f32;
+        let w: f32 = (aa * m + n) as f32;
+        let time: f32 = self.time + 0.5 * (1.0 / 24.0) * w / (aa * aa) as f32;
 
         let mut zoo: f32 = 0.62 + 0.38 * (0.07 * time).cos();
         let coa: f32 = (0.15 * (1.0 - zoo) * time).cos();
@@ -96,7 +110,7 @@ impl Inputs {
           + Vec3::splat(0.5) * (Vec3::splat(3.0 + l * 0.15) + vec3(0.0, 0.6, 1.0)).cos();
       }
     }
-    col /= (AA * AA) as f32;
+    col /= (aa * aa) as f32;
     *frag_color = col.extend(1.0);
   }
 }
@@ -13,10 +13,18 @@
 use crate::shader_prelude::*;
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
-  name: "Playing Marble",
+  name: "Miracle Snowflakes",
+  author: "Panteleymonov Aleksandr Konstantinovich",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/Xsd3zf"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
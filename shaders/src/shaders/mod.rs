@@ -4,6 +4,7 @@ mod a_lot_of_spheres;
 mod a_question_of_time;
 mod apollonian;
 mod atmosphere_system_test;
+mod audio_spectrum;
 mod bubble_buckey_balls;
 mod clouds;
 mod filtering_procedurals;
@@ -16,6 +17,7 @@ mod mandelbrot_smooth;
 mod miracle_snowflakes;
 mod morphing;
 mod moving_square;
+mod old_film;
 mod on_off_spikes;
 mod phantom_star;
 mod playing_marble;
@@ -39,21 +41,65 @@ macro_rules! match_index {
         }}
     })
 }
+pub(crate) use match_index;
+
+/// Calls the `ray_fn` of shaders marked with `(ray)`, the others render nothing.
+macro_rules! render_ray_arm {
+  (ray $shader_name:ident, $shader_input:ident, $ray:ident, $shader_output:ident) => {
+    $shader_name::ray_fn($shader_input, $ray, $shader_output)
+  };
+  ($shader_name:ident, $shader_input:ident, $ray:ident, $shader_output:ident) => {
+    ()
+  };
+}
+
+macro_rules! has_ray_fn {
+  (ray) => {
+    true
+  };
+  () => {
+    false
+  };
+}
 
 macro_rules! render_shader_macro {
-    ($($shader_name:ident),* $(,)?) => {
+    ($($shader_name:ident $(($marker:ident))?),* $(,)?) => {
         #[inline(always)]
-        pub fn render_shader(shader_index: u32, shader_input: &ShaderInput, shader_output: &mut ShaderResult) {
+        pub fn render_shader<C: Channel>(shader_index: u32, shader_input: &ShaderInput<C>, shader_output: &mut ShaderResult) {
             match_index!(shader_index; $(
                 $shader_name::shader_fn(shader_input, shader_output),
             )*)
         }
 
+        /// Traces `ray` with the `ray_fn` of the shader, see [`crate::projection`].
+        #[inline(always)]
+        pub fn render_ray<C: Channel>(shader_index: u32, shader_input: &ShaderInput<C>, ray: ViewRay, shader_output: &mut ShaderResult) {
+            match_index!(shader_index; $(
+                render_ray_arm!($($marker)? $shader_name, shader_input, ray, shader_output),
+            )*)
+        }
+
         pub const SHADER_DEFINITIONS: &[ShaderDefinition] = &[
             $(
                 $shader_name::SHADER_DEFINITION,
             )*
         ];
+
+        /// Whether each shader of [`SHADER_DEFINITIONS`] has a `ray_fn` for [`render_ray`].
+        pub const HAS_RAY_FN: &[bool] = &[
+            $(
+                has_ray_fn!($($marker)?),
+            )*
+        ];
+
+        /// [`HAS_RAY_FN`] without indexing into a constant array, for the GPU.
+        #[inline(always)]
+        #[must_use]
+        pub fn has_ray_fn(shader_index: u32) -> bool {
+            match_index!(shader_index; $(
+                has_ray_fn!($($marker)?),
+            )*)
+        }
     };
 }
 
@@ -62,28 +108,30 @@ render_shader_macro!(
   morphing,
   voxel_pac_man,
   luminescence,
-  seascape,
+  seascape(ray),
   two_tweets,
   heart,
   clouds,
   mandelbrot_smooth,
   protean_clouds,
   tileable_water_caustic,
-  apollonian,
+  apollonian(ray),
   phantom_star,
   playing_marble,
-  a_lot_of_spheres,
+  a_lot_of_spheres(ray),
   a_question_of_time,
   galaxy_of_universes,
   atmosphere_system_test,
   soft_shadow_variation,
   bubble_buckey_balls,
-  raymarching_primitives,
+  raymarching_primitives(ray),
   moving_square,
-  skyline,
+  skyline(ray),
   filtering_procedurals,
   geodesic_tiling,
   flappy_bird,
-  tokyo,
+  tokyo(ray),
   on_off_spikes,
+  audio_spectrum,
+  old_film,
 );
@@ -0,0 +1,122 @@
+//! An old film look for videos: `iChannel0` in sepia with grain, gate weave, flicker, scratches,
+//! dust and a vignette. The damage changes with the frames of the film, which follow
+//! `iChannelTime[0]`, so it plays along with the video. The host binds a video with
+//! `--channel0 <video>`, see `src/video.rs`, without one it shows a test card.
+
+use crate::{noise::LatticeHash, shader_prelude::*};
+
+pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+  name: "Old Film",
+  author: "",
+  license: "MIT OR Apache-2.0",
+  link: None,
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
+};
+
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
+  let color = &mut render_result.color;
+  let &ShaderInput {
+    resolution,
+    time,
+    frag_coord,
+    channels,
+    ..
+  } = render_instruction;
+  Inputs {
+    resolution,
+    film: channels[0].or(TestCard { time }),
+  }
+  .main_image(color, frag_coord);
+}
+
+/// Color bars, a gray ramp and a ball rolling across, a 16:9 stand-in for a video.
+#[derive(Copy, Clone)]
+struct TestCard {
+  time: f32,
+}
+
+impl Channel for TestCard {
+  fn resolution(self) -> Vec3 {
+    vec3(640.0, 360.0, 1.0)
+  }
+
+  fn time(self) -> f32 {
+    self.time
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    let uv = uv.fract_gl();
+    // White, yellow, cyan, green, magenta, red and blue.
+    let bar = (uv.x * 7.0).floor();
+    let mut col = vec3(
+      1.0 - (bar * 0.5).floor() % 2.0,
+      1.0 - step(3.5, bar),
+      1.0 - bar % 2.0,
+    ) * 0.75;
+    col = mix(col, Vec3::splat(uv.x), step(uv.y, 0.2));
+    let center = vec2(0.5 + 0.35 * (self.time * 0.7).sin(), 0.55);
+    let distance = ((uv - center) * vec2(16.0 / 9.0, 1.0)).length();
+    col = mix(col, vec3(0.1, 0.1, 0.12), smoothstep(0.205, 0.2, distance));
+    col.extend(1.0)
+  }
+
+  fn texture_cube(self, direction: Vec3) -> Vec4 {
+    self.texture(direction.xy())
+  }
+
+  fn texture_3d(self, p: Vec3) -> Vec4 {
+    self.texture(p.xy())
+  }
+}
+
+struct Inputs<C> {
+  resolution: Vec3,
+  film: C,
+}
+
+/// Frames per second of the film, the grain and the damage change at this rate.
+const FRAME_RATE: f32 = 24.0;
+
+impl<C: Channel> Inputs<C> {
+  fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {
+    let uv = frag_coord / self.resolution.xy();
+    let frame = (self.film.time() * FRAME_RATE).floor();
+
+    // The gate weave shakes the whole frame a little.
+    let weave = (vec2(frame, 1.0).hash_vec() - 0.5) * vec2(0.002, 0.004);
+    let source = self.film.texture(uv + weave).truncate();
+    let luma = source.dot(vec3(0.299, 0.587, 0.114));
+    let mut col = luma * vec3(1.0, 0.88, 0.7);
+
+    col *= 0.92 + 0.08 * vec2(frame, 2.0).hash();
+    let grain = vec3(frag_coord.x, frag_coord.y, frame).hash() - 0.5;
+    col += grain * 0.1;
+
+    // A scratch on some frames, drifting from frame to frame.
+    let scratch_x = vec2((frame / 8.0).floor(), 3.0).hash() + 0.01 * (frame * 1.7).sin();
+    let scratch = smoothstep(1.5, 0.0, (uv.x - scratch_x).abs() * self.resolution.x);
+    col = mix(
+      col,
+      vec3(0.95, 0.9, 0.8),
+      0.6 * scratch * step(0.5, vec2(frame, 4.0).hash()),
+    );
+
+    // A speck of dust on others.
+    let dust = vec2(vec2(frame, 5.0).hash(), vec2(frame, 6.0).hash());
+    let dust_distance = ((uv - dust) * self.resolution.xy()).length() / self.resolution.y;
+    col *= mix(
+      1.0,
+      smoothstep(0.002, 0.006, dust_distance),
+      step(0.7, vec2(frame, 7.0).hash()),
+    );
+
+    let vignette = 16.0 * uv.x * uv.y * (1.0 - uv.x) * (1.0 - uv.y);
+    col *= vignette.max(0.0).powf(0.35);
+
+    *frag_color = saturate_vec3(col).extend(1.0);
+  }
+}
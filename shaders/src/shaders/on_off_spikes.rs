@@ -5,25 +5,38 @@
 //! // On/Off Spikes, fragment shader by movAX13h, oct 2014
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{
+  noise::{fbm, sin_hash, value_noise1},
+  shader_prelude::*,
+};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "On/Off Spikes",
+  author: "movAX13h",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/XsBSRV"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
     time,
     frag_coord,
     mouse,
+    quality,
     ..
   } = render_instruction;
   State::new(Inputs {
     resolution,
     time,
     mouse,
+    octaves: quality.octaves(NUM_OCTAVES),
   })
   .main_image(color, frag_coord);
 }
@@ -32,6 +45,8 @@ struct Inputs {
   resolution: Vec3,
   time: f32,
   mouse: Vec4,
+  /// [`NUM_OCTAVES`] adjusted to the quality level.
+  octaves: u32,
 }
 
 struct State {
@@ -80,18 +95,15 @@ const GAMMA: f32 = 2.2;
 
 // Using the nebula function of the "Star map shader" by morgan3d
 // as environment map and light sphere texture (https://www.shadertoy.com/view/4sBXzG)
-const NUM_OCTAVES: i32 = 4;
+const NUM_OCTAVES: u32 = 4;
 fn hash(n: f32) -> f32 {
-  (n.sin() * 1e4).fract_gl()
+  sin_hash(n, 1e4)
 }
 fn hash_vec2(p: Vec2) -> f32 {
   (1e4 * (17.0 * p.x + p.y * 0.1).sin() * (0.1 + (p.y * 13.0 + p.x).sin().abs())).fract_gl()
 }
 fn noise(x: f32) -> f32 {
-  let i: f32 = x.floor();
-  let f: f32 = x.fract_gl();
-  let u: f32 = f * f * (3.0 - 2.0 * f);
-  mix(hash(i), hash(i + 1.0), u)
+  value_noise1(x, hash)
 }
 fn noise_vec2(x: Vec2) -> f32 {
   let i: Vec2 = x.floor();
@@ -103,19 +115,11 @@ fn noise_vec2(x: Vec2) -> f32 {
   let u: Vec2 = f * f * (Vec2::splat(3.0) - 2.0 * f);
   mix(a, b, u.x) + (c - a) * u.y * (1.0 - u.x) + (d - b) * u.x * u.y
 }
-fn noise2(mut x: Vec2) -> f32 {
-  let mut v: f32 = 0.0;
-  let mut a: f32 = 0.5;
+fn noise2(x: Vec2, octaves: u32) -> f32 {
   let shift: Vec2 = Vec2::splat(100.0);
   let rot: Mat2 =
     Mat2::from_cols_array(&[0.5_f32.cos(), 0.5_f32.sin(), -0.5_f32.sin(), 0.50_f32.cos()]);
-
-  for _ in 0..NUM_OCTAVES {
-    v += a * noise_vec2(x);
-    x = rot * x * 2.0 + shift;
-    a *= 0.5;
-  }
-  v
+  fbm(x, octaves, 0.5, 0.5, noise_vec2, |x| rot * x * 2.0 + shift)
 }
 fn square(x: f32) -> f32 {
   x * x
@@ -143,7 +147,7 @@ fn rotation(yaw: f32, pitch: f32) -> Mat3 {
     pitch.cos(),
   ])
 }
-fn nebula(dir: Vec3) -> Vec3 {
+fn nebula(dir: Vec3, octaves: u32) -> Vec3 {
   let purple: f32 = dir.x.abs();
   let yellow: f32 = noise(dir.y);
   let streaky_hue: Vec3 = vec3(purple + yellow, yellow * 0.7, purple);
@@ -152,10 +156,11 @@ fn nebula(dir: Vec3) -> Vec3 {
     8.0
       * (noise2(
         dir.yz() * square(dir.x) * 13.0 + dir.xy() * square(dir.z) * 7.0 + vec2(150.0, 2.0),
+        octaves,
       ))
       .powf(10.0),
   );
-  let puffy: f32 = square(noise2(dir.xz() * 4.0 + vec2(30.0, 10.0)) * dir.y);
+  let puffy: f32 = square(noise2(dir.xz() * 4.0 + vec2(30.0, 10.0), octaves) * dir.y);
 
   (puffy_hue * puffy * (1.0 - streaky) + streaky * streaky_hue)
     .clamp(Vec3::ZERO, Vec3::ONE)
@@ -385,7 +390,7 @@ impl State {
       );
     }
 
-    let neb: Vec3 = nebula(n);
+    let neb: Vec3 = nebula(n, self.inputs.octaves);
     col += self.glow.min(0.1) * neb.zxy();
 
     // HARD SHADOW with low number of rm iterations (from obj to sun)
@@ -424,7 +429,11 @@ impl State {
         }
       }
 
-      let neb1: Vec3 = nebula(rotation(0.0, self.inputs.time * 0.4).transpose() * dir).zxy();
+      let neb1: Vec3 = nebula(
+        rotation(0.0, self.inputs.time * 0.4).transpose() * dir,
+        self.inputs.octaves,
+      )
+      .zxy();
 
       col += (0.7 * self.sphere_col + self.glow * neb1)
         * (0.6 * (smoothstep(3.0, 0.0, sphere_dist)) * dist.min(sphere_dist) / sphere_dist
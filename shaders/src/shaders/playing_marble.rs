@@ -10,25 +10,34 @@ use crate::shader_prelude::*;
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Playing Marble",
+  author: "S. Guillitte",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/MtX3Ws"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
     time,
     frag_coord,
     mouse,
+    channels,
     ..
   } = render_instruction;
   Inputs {
     resolution,
     time,
     mouse,
-    channel0: RgbCube {
+    channel0: channels[0].or(RgbCube {
       alpha: 1.0,
       intensity: 1.0,
-    },
+    }),
   }
   .main_image(color, frag_coord);
 }
@@ -78,7 +87,7 @@ fn map(mut p: Vec3) -> f32 {
   res / 2.0
 }
 
-impl<C0: SampleCube> Inputs<C0> {
+impl<C0: Channel> Inputs<C0> {
   fn raymarch(&self, ro: Vec3, rd: Vec3, tminmax: Vec2) -> Vec3 {
     let mut t: f32 = tminmax.x;
     let dt: f32 = 0.02;
@@ -128,12 +137,12 @@ impl<C0: SampleCube> Inputs<C0> {
     // raymarch
     let mut col: Vec3 = self.raymarch(ro, rd, tmm);
     if tmm.x < 0.0 {
-      col = self.channel0.sample_cube(rd).xyz();
+      col = self.channel0.texture_cube(rd).xyz();
     } else {
       let mut nor: Vec3 = (ro + tmm.x * rd) / 2.;
       nor = rd.reflect(nor);
       let fre: f32 = (0.5 + nor.dot(rd).clamp(0.0, 1.0)).powf(3.0) * 1.3;
-      col += self.channel0.sample_cube(nor).xyz() * fre;
+      col += self.channel0.texture_cube(nor).xyz() * fre;
     }
 
     //shade
@@ -9,40 +9,75 @@
 //! */
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{
+  noise::{sin_hash_dot, value_noise2},
+  shader_prelude::*,
+};
 
-pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition { name: "Seascape" };
+pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+  name: "Seascape",
+  author: "Alexander Alekseev",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/Ms2SD1"),
+  params: &[NUM_STEPS, AA, SEA_WATER_COLOR],
+  channels: [ChannelInput::Procedural; 4],
+};
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
-  let color = &mut render_result.color;
-  let &ShaderInput {
-    resolution,
-    time,
-    frag_coord,
-    mouse,
-    ..
-  } = render_instruction;
-  Inputs {
-    resolution,
-    time,
-    mouse,
-  }
-  .main_image(color, frag_coord);
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
+  Inputs::new(render_instruction)
+    .main_image(&mut render_result.color, render_instruction.frag_coord);
+}
+
+pub fn ray_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  ray: ViewRay,
+  render_result: &mut ShaderResult,
+) {
+  let inputs = Inputs::new(render_instruction);
+  let time = inputs.local_time();
+  let (ori, dir) = camera(time).view_ray(ray);
+  render_result.color = inputs.get_color(ori, dir).powf(0.65).extend(1.0);
 }
 
 struct Inputs {
   resolution: Vec3,
   time: f32,
   mouse: Vec4,
+  num_steps: i32,
+  aa: bool,
+  water_color: Vec3,
+}
+
+impl Inputs {
+  fn new<C>(render_instruction: &ShaderInput<C>) -> Self {
+    let &ShaderInput {
+      resolution,
+      time,
+      mouse,
+      params,
+      ..
+    } = render_instruction;
+    Self {
+      resolution,
+      time,
+      mouse,
+      num_steps: params.int(&NUM_STEPS),
+      aa: params.bool(&AA),
+      water_color: params.color(&SEA_WATER_COLOR),
+    }
+  }
 }
 
-const NUM_STEPS: usize = 8;
+const NUM_STEPS: Param = Param::int(0, "Steps", 8, 1, 32);
 impl Inputs {
   fn epsilon_nrm(&self) -> f32 {
     0.1 / self.resolution.x
   }
 }
-const AA: bool = true;
+const AA: Param = Param::bool(1, "Antialiasing", true);
 
 // sea
 const ITER_GEOMETRY: usize = 3;
@@ -53,7 +88,8 @@ const SEA_SPEED: f32 = 0.8;
 const SEA_FREQ: f32 = 0.16;
 const SEA_BASE: Vec3 = vec3(0.0, 0.09, 0.18);
 // const SEA_WATER_COLOR: Vec3 = const_vec3!([0.8, 0.9, 0.6]) * 0.6;
-const SEA_WATER_COLOR: Vec3 = vec3(0.8 * 0.6, 0.9 * 0.6, 0.6 * 0.6);
+const SEA_WATER_COLOR: Param =
+  Param::color(2, "Water color", vec3(0.8 * 0.6, 0.9 * 0.6, 0.6 * 0.6));
 impl Inputs {
   fn sea_time(&self) -> f32 {
     1.0 + self.time * SEA_SPEED
@@ -62,6 +98,15 @@ impl Inputs {
 const OCTAVE_M: Mat2 = mat2(vec2(1.6, 1.2), vec2(-1.2, 1.6));
 
 // math
+/// The original looks along `-z` in camera space, the basis flips it to the usual `+z`.
+fn camera(time: f32) -> Camera {
+  let ang: Vec3 = vec3((time * 3.0).sin() * 0.1, time.sin() * 0.2 + 0.3, time);
+  Camera {
+    position: vec3(0.0, 3.5, time * 5.0),
+    basis: from_euler(ang).transpose() * Mat3::from_diagonal(vec3(1.0, 1.0, -1.0)),
+  }
+}
+
 fn from_euler(ang: Vec3) -> Mat3 {
   let a1: Vec2 = vec2(ang.x.sin(), ang.x.cos());
   let a2: Vec2 = vec2(ang.y.sin(), ang.y.cos());
@@ -81,20 +126,10 @@ fn from_euler(ang: Vec3) -> Mat3 {
   )
 }
 fn hash(p: Vec2) -> f32 {
-  let h: f32 = p.dot(vec2(127.1, 311.7));
-  (h.sin() * 43758.5453123).fract_gl()
+  sin_hash_dot(p, vec2(127.1, 311.7), 43758.5453123)
 }
 fn noise(p: Vec2) -> f32 {
-  let i: Vec2 = p.floor();
-  let f: Vec2 = p.fract_gl();
-  let u: Vec2 = f * f * (Vec2::splat(3.0) - 2.0 * f);
-  -1.0
-    + 2.0
-      * mix(
-        mix(hash(i + vec2(0.0, 0.0)), hash(i + vec2(1.0, 0.0)), u.x),
-        mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), u.x),
-        u.y,
-      )
+  -1.0 + 2.0 * value_noise2(p, hash)
 }
 
 // lighting
@@ -167,16 +202,16 @@ impl Inputs {
   }
 }
 
-fn get_sea_color(p: Vec3, n: Vec3, l: Vec3, eye: Vec3, dist: Vec3) -> Vec3 {
+fn get_sea_color(p: Vec3, n: Vec3, l: Vec3, eye: Vec3, dist: Vec3, water_color: Vec3) -> Vec3 {
   let mut fresnel: f32 = (1.0 - n.dot(-eye)).clamp(0.0, 1.0);
   fresnel = fresnel.powf(3.0) * 0.5;
 
   let reflected: Vec3 = get_sky_color(eye.reflect(n));
-  let refracted: Vec3 = SEA_BASE + diffuse(n, l, 80.0) * SEA_WATER_COLOR * 0.12;
+  let refracted: Vec3 = SEA_BASE + diffuse(n, l, 80.0) * water_color * 0.12;
 
   let mut color: Vec3 = mix(refracted, reflected, fresnel);
   let atten: f32 = (1.0 - dist.dot(dist) * 0.001).max(0.0);
-  color += SEA_WATER_COLOR * (p.y - SEA_HEIGHT) * 0.18 * atten;
+  color += water_color * (p.y - SEA_HEIGHT) * 0.18 * atten;
 
   color += Vec3::splat(specular(n, l, eye, 60.0));
   color
@@ -202,7 +237,7 @@ impl Inputs {
     }
     let mut hm: f32 = self.map(ori + dir * tm);
     let mut tmid: f32 = 0.0;
-    for _ in 0..NUM_STEPS {
+    for _ in 0..self.num_steps {
       tmid = mix(tm, tx, hm / (hm - hx));
       *p = ori + dir * tmid;
       let hmid: f32 = self.map(*p);
@@ -222,11 +257,14 @@ impl Inputs {
     uv = uv * 2.0 - Vec2::ONE;
     uv.x *= self.resolution.x / self.resolution.y;
     // ray
-    let ang: Vec3 = vec3((time * 3.0).sin() * 0.1, time.sin() * 0.2 + 0.3, time);
-    let ori: Vec3 = vec3(0.0, 3.5, time * 5.0);
-    let mut dir: Vec3 = uv.extend(-2.0).normalize();
-    dir.z += uv.length() * 0.14;
-    dir = from_euler(ang).transpose() * dir.normalize();
+    let camera = camera(time);
+    let mut dir: Vec3 = uv.extend(2.0).normalize();
+    dir.z -= uv.length() * 0.14;
+    dir = camera.basis * dir.normalize();
+    self.get_color(camera.position, dir)
+  }
+
+  fn get_color(&self, ori: Vec3, dir: Vec3) -> Vec3 {
     // tracing
     let mut p: Vec3 = Vec3::ZERO;
     self.height_map_tracing(ori, dir, &mut p);
@@ -236,16 +274,20 @@ impl Inputs {
     // color
     mix(
       get_sky_color(dir),
-      get_sea_color(p, n, light, dir, dist),
+      get_sea_color(p, n, light, dir, dist, self.water_color),
       smoothstep(0.0, -0.02, dir.y).powf(0.2),
     )
   }
 
+  fn local_time(&self) -> f32 {
+    self.time * 0.3 + self.mouse.x * 0.01
+  }
+
   // main
   fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {
-    let time: f32 = self.time * 0.3 + self.mouse.x * 0.01;
+    let time: f32 = self.local_time();
     let mut color: Vec3;
-    if AA {
+    if self.aa {
       color = Vec3::ZERO;
       for i in -1..=1 {
         for j in -1..=1 {
@@ -10,29 +10,36 @@
 //! -Otavio Good
 //! */
 //! ```
-use crate::shader_prelude::*;
-
-pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition { name: "Skyline" };
-
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
-  let color = &mut render_result.color;
-  let &ShaderInput {
-    resolution,
-    time,
-    frag_coord,
-    mouse,
-    ..
-  } = render_instruction;
-  State::new(Inputs {
-    resolution,
-    time,
-    mouse,
-    channel0: RgbCube {
-      alpha: 1.0,
-      intensity: 1.0,
-    },
-  })
-  .main_image(color, frag_coord);
+use crate::{channel::Or, noise::sin_hash, shader_prelude::*};
+
+pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
+  name: "Skyline",
+  author: "Otavio Good",
+  license: "CC0 1.0",
+  link: Some("https://www.shadertoy.com/view/XtsSWs"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
+};
+
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
+  State::new(Inputs::new(render_instruction))
+    .main_image(&mut render_result.color, render_instruction.frag_coord);
+}
+
+pub fn ray_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  ray: ViewRay,
+  render_result: &mut ShaderResult,
+) {
+  let mut state = State::new(Inputs::new(render_instruction));
+  state.local_time = state.inputs.time;
+  let (cam_pos, ray_vec) = state.animate().view_ray(ray);
+  // No vignette.
+  let final_color = state.trace(cam_pos, ray_vec, Vec2::ZERO);
+  render_result.color = final_color.clamp(Vec3::ZERO, Vec3::ONE).sqrt().extend(1.0);
 }
 
 struct Inputs<C0> {
@@ -42,6 +49,26 @@ struct Inputs<C0> {
   channel0: C0,
 }
 
+impl<C: Channel> Inputs<Or<C, PreethamSky>> {
+  fn new(render_instruction: &ShaderInput<C>) -> Self {
+    let &ShaderInput {
+      resolution,
+      time,
+      mouse,
+      channels,
+      ..
+    } = render_instruction;
+    Self {
+      resolution,
+      time,
+      mouse,
+      // The original reflects a daylight cubemap, a clear sky with the sun of the default
+      // scene comes close.
+      channel0: channels[0].or(PreethamSky::new(vec3(0.93, 1.0, 1.0))),
+    }
+  }
+}
+
 struct State<C0> {
   inputs: Inputs<C0>,
 
@@ -100,7 +127,7 @@ fn v21(a: Vec2) -> f32 {
   a.x + a.y * 37.0
 }
 fn hash11(a: f32) -> f32 {
-  (a.sin() * 10403.9).fract_gl()
+  sin_hash(a, 10403.9)
 }
 fn hash21(uv: Vec2) -> f32 {
   let f: f32 = uv.x + uv.y * 37.0;
@@ -498,10 +525,21 @@ fn calc_windows(
   normal.x += pits * 0.25;
 }
 
-impl<C0: SampleCube> State<C0> {
+impl<C0: Channel> State<C0> {
   // Input is UV coordinate of pixel to render.
   // Output is RGB color.
   fn ray_trace(&mut self, frag_coord: Vec2) -> Vec3 {
+    // Map uv to [-1.0..1.0]
+    let mut uv: Vec2 = frag_coord / self.inputs.resolution.xy() * 2.0 - Vec2::ONE;
+    uv /= 2.0; // zoom in
+    let camera = self.animate();
+    let aspect = self.inputs.resolution.x / self.inputs.resolution.y;
+    let ray_vec: Vec3 = camera.ray_direction(vec2(uv.x * aspect, uv.y), 1.0);
+    self.trace(camera.position, ray_vec, uv)
+  }
+
+  /// Sets up the scene and the camera for `local_time`.
+  fn animate(&mut self) -> Camera {
     self.march_count = 0.0;
     // -------------------------------- animate ---------------------------------------
     self.sun_col = vec3(258.0, 248.0, 200.0) / 3555.0;
@@ -515,10 +553,6 @@ impl<C0: SampleCube> State<C0> {
     let mut cam_up: Vec3 = Vec3::ZERO;
     let mut cam_lookat: Vec3 = Vec3::ZERO;
     // ------------------- Set up the camera rays for ray marching --------------------
-    // Map uv to [-1.0..1.0]
-    let mut uv: Vec2 = frag_coord / self.inputs.resolution.xy() * 2.0 - Vec2::ONE;
-    uv /= 2.0; // zoom in
-
     if MANUAL_CAMERA {
       // Camera up vector.
       cam_up = vec3(0.0, 1.0, 0.0);
@@ -619,12 +653,15 @@ impl<C0: SampleCube> State<C0> {
     let cam_vec: Vec3 = (cam_lookat - cam_pos).normalize();
     let side_norm: Vec3 = cam_up.cross(cam_vec).normalize();
     let up_norm: Vec3 = cam_vec.cross(side_norm);
-    let world_facing: Vec3 = cam_pos + cam_vec;
-    let world_pix: Vec3 = world_facing
-      + uv.x * side_norm * (self.inputs.resolution.x / self.inputs.resolution.y)
-      + uv.y * up_norm;
-    let ray_vec: Vec3 = (world_pix - cam_pos).normalize();
+    Camera {
+      position: cam_pos,
+      basis: Mat3::from_cols(side_norm, up_norm, cam_vec),
+    }
+  }
 
+  /// Marches the ray from `cam_pos` along `ray_vec`, `uv` is the screen position for the
+  /// vignette.
+  fn trace(&mut self, cam_pos: Vec3, ray_vec: Vec3, uv: Vec2) -> Vec3 {
     // ----------------------------- Ray march the scene ------------------------------
     let mut dist_and_mat: Vec2 = Vec2::ZERO; // Distance and material
     let mut t: f32 = 0.05; // + Hash2d(uv)*0.1;	// random dither-fade things close to the camera
@@ -932,7 +969,7 @@ impl<C0: SampleCube> State<C0> {
           * yfade
           * sun_shadow.max(0.4);
         final_color +=
-          saturate_vec3(self.inputs.channel0.sample_cube(ref_).xyz() - Vec3::splat(0.35))
+          saturate_vec3(self.inputs.channel0.texture_cube(ref_).xyz() - Vec3::splat(0.35))
             * 0.15
             * sun_shadow.max(0.2);
       }
@@ -946,7 +983,7 @@ impl<C0: SampleCube> State<C0> {
           * sun_shadow.max(0.6)
           * window_ref; //*(windowMask*0.5+0.5);
         final_color +=
-          saturate_vec3(self.inputs.channel0.sample_cube(ref_).xyz() - Vec3::splat(0.35))
+          saturate_vec3(self.inputs.channel0.texture_cube(ref_).xyz() - Vec3::splat(0.35))
             * 0.15
             * sun_shadow.max(0.25)
             * window_ref;
@@ -976,7 +1013,7 @@ impl<C0: SampleCube> State<C0> {
   }
 }
 
-impl<C0: SampleCube> State<C0> {
+impl<C0: Channel> State<C0> {
   // This function breaks the image down into blocks and scans
   // through them, rendering 1 block at a time. It's for non-
   // realtime things that take a long time to render.
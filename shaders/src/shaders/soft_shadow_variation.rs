@@ -16,40 +16,48 @@
 //! // Go to lines 54 to compare both.
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{
+  sdf::{sd_box, sd_plane},
+  shader_prelude::*,
+};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Soft Shadow Variation",
+  author: "",
+  license: "CC BY-NC-SA 3.0",
+  link: Some("https://www.shadertoy.com/view/lsKcDD"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
     time,
     frag_coord,
+    free_camera,
+    quality,
     ..
   } = render_instruction;
-  Inputs { resolution, time }.main_image(color, frag_coord);
+  Inputs {
+    resolution,
+    time,
+    free_camera,
+    aa: quality.samples_per_axis(),
+  }
+  .main_image(color, frag_coord);
 }
 
 struct Inputs {
   resolution: Vec3,
   time: f32,
-}
-
-// make this 1 is your machine is too slow
-const AA: usize = 2;
-
-//------------------------------------------------------------------
-
-fn sd_plane(p: Vec3) -> f32 {
-  p.y
-}
-
-fn sd_box(p: Vec3, b: Vec3) -> f32 {
-  let d: Vec3 = p.abs() - b;
-  d.x.max(d.y.max(d.z)).min(0.0) + d.max(Vec3::ZERO).length()
+  free_camera: FreeCamera,
+  /// Antialiasing level, 2 at `Quality::High`.
+  aa: u32,
 }
 
 //------------------------------------------------------------------
@@ -182,14 +190,6 @@ fn render(ro: Vec3, rd: Vec3, technique: bool) -> Vec3 {
   col
 }
 
-fn set_camera(ro: Vec3, ta: Vec3, cr: f32) -> Mat3 {
-  let cw: Vec3 = (ta - ro).normalize();
-  let cp: Vec3 = vec3(cr.sin(), cr.cos(), 0.0);
-  let cu: Vec3 = cw.cross(cp).normalize();
-  let cv: Vec3 = cu.cross(cw).normalize();
-  Mat3::from_cols(cu, cv, cw)
-}
-
 impl Inputs {
   fn main_image(&mut self, frag_color: &mut Vec4, frag_coord: Vec2) {
     // camera
@@ -197,20 +197,22 @@ impl Inputs {
     let ro: Vec3 = vec3(3.0 * (0.1 * an).cos(), 1.0, -3.0 * (0.1 * an).sin());
     let ta: Vec3 = vec3(0.0, -0.4, 0.0);
     // camera-to-world transformation
-    let ca: Mat3 = set_camera(ro, ta, 0.0);
+    let camera = Camera::look_at(ro, ta, 0.0).with_free_camera(self.free_camera);
+    let ro: Vec3 = camera.position;
 
     let technique = (self.time / 2.0).fract_gl() > 0.5;
 
     let mut tot: Vec3 = Vec3::ZERO;
 
-    for m in 0..AA {
-      for n in 0..AA {
+    let aa = self.aa;
+    for m in 0..aa {
+      for n in 0..aa {
         // pixel coordinates
-        let o: Vec2 = vec2(m as f32, n as f32) / AA as f32 - Vec2::splat(0.5);
+        let o: Vec2 = vec2(m as f32, n as f32) / aa as f32 - Vec2::splat(0.5);
         let p: Vec2 = (-self.resolution.xy() + 2.0 * (frag_coord + o)) / self.resolution.y;
 
         // ray direction
-        let rd: Vec3 = ca * p.extend(2.0).normalize();
+        let rd: Vec3 = camera.ray_direction(p, 2.0);
 
         // render
         let mut col: Vec3 = render(ro, rd, technique);
@@ -221,7 +223,7 @@ impl Inputs {
         tot += col;
       }
     }
-    tot /= (AA * AA) as f32;
+    tot /= (aa * aa) as f32;
 
     *frag_color = tot.extend(1.0);
   }
@@ -13,13 +13,21 @@
 //! // I have never been in Tokyo btw.
 //! ```
 
-use crate::shader_prelude::*;
+use crate::{noise::sin_hash, shader_prelude::*};
 
 pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {
   name: "Tokyo by Night",
+  author: "Reinder Nijhoff",
+  license: "CC BY-NC-SA 4.0",
+  link: Some("https://www.shadertoy.com/view/Xtf3zn"),
+  params: &[],
+  channels: [ChannelInput::Procedural; 4],
 };
 
-pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderResult) {
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {
   let color = &mut render_result.color;
   let &ShaderInput {
     resolution,
@@ -30,6 +38,21 @@ pub fn shader_fn(render_instruction: &ShaderInput, render_result: &mut ShaderRes
   State::new(Inputs { resolution, time }).main_image(color, frag_coord);
 }
 
+pub fn ray_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  ray: ViewRay,
+  render_result: &mut ShaderResult,
+) {
+  let &ShaderInput {
+    resolution, time, ..
+  } = render_instruction;
+  let mut state = State::new(Inputs { resolution, time });
+  let (ro, rd) = state.camera().view_ray(ray);
+  // Without the rain streaks, which are drawn on the screen.
+  let col = state.render(ro, rd, rd.xy(), 0.25);
+  render_result.color = post_process(col).extend(1.0);
+}
+
 #[derive(Clone, Copy)]
 struct Inputs {
   resolution: Vec3,
@@ -84,7 +107,7 @@ impl State {
 // noises
 
 fn hash(n: f32) -> f32 {
-  (n.sin() * 687.3123).fract_gl()
+  sin_hash(n, 687.3123)
 }
 
 fn noise(x: Vec2) -> f32 {
@@ -453,17 +476,7 @@ impl State {
   //----------------------------------------------------------------------
   // main
 
-  fn main_image(&mut self, frag_color: &mut Vec4, frag_coord: Vec2) {
-    let mut q: Vec2 = frag_coord / self.inputs.resolution.xy();
-    let mut p: Vec2 = -Vec2::ONE + 2.0 * q;
-    p.x *= self.inputs.resolution.x / self.inputs.resolution.y;
-
-    if q.y < 0.12 || q.y >= 0.88 {
-      *frag_color = vec4(0.0, 0.0, 0.0, 1.0);
-      return;
-    }
-
-    // camera
+  fn camera(&self) -> Camera {
     let z: f32 = self.time();
     let x: f32 = -10.9 + 1. * (self.time() * 0.2).sin();
     let ro: Vec3 = vec3(x, 1.3 + 0.3 * (self.time() * 0.26).cos(), z - 1.);
@@ -476,12 +489,19 @@ impl State {
     let ww: Vec3 = (ta - ro).normalize();
     let uu: Vec3 = ww.cross(vec3(0.0, 1.0, 0.0)).normalize();
     let vv: Vec3 = uu.cross(ww).normalize();
-    let rd: Vec3 = (-p.x * uu + p.y * vv + 2.2 * ww).normalize();
+    Camera {
+      position: ro,
+      basis: Mat3::from_cols(-uu, vv, ww),
+    }
+  }
 
+  /// The color seen along the ray, before post processing. `dither` varies the start of the
+  /// march, `f` is the brightness of the rain.
+  fn render(&mut self, ro: Vec3, rd: Vec3, dither: Vec2, f: f32) -> Vec3 {
     let mut col: Vec3 = BACKGROUND_COLOR;
 
     // raymarch
-    let ints: f32 = self.intersect(ro + random_start(p) * rd, rd);
+    let ints: f32 = self.intersect(ro + random_start(dither) * rd, rd);
     if ints > -0.5 {
       // calculate reflectance
       let mut r: f32 = 0.09;
@@ -518,23 +538,35 @@ impl State {
       }
     }
 
-    // Rain (by Dave Hoskins)
-    let st: Vec2 =
-      256. * (p * vec2(0.5, 0.01) + vec2(self.time() * 0.13 - q.y * 0.6, self.time() * 0.13));
-    let mut f: f32 = noise(st) * noise(st * 0.773) * 1.55;
-    f = 0.25 + (f.abs().powf(13.0) * 13.0).clamp(0.0, q.y * 0.14);
-
     if self.lint1.w > 0.0 {
       col +=
         (f * LIGHTINTENSITY * (-self.lint1.w * 7.0).exp()) * self.get_light_color(self.lint1.xyz());
     }
 
-    col += 0.25 * f * (Vec3::splat(0.2) + BACKGROUND_COLOR);
+    col + 0.25 * f * (Vec3::splat(0.2) + BACKGROUND_COLOR)
+  }
+
+  fn main_image(&mut self, frag_color: &mut Vec4, frag_coord: Vec2) {
+    let mut q: Vec2 = frag_coord / self.inputs.resolution.xy();
+    let mut p: Vec2 = -Vec2::ONE + 2.0 * q;
+    p.x *= self.inputs.resolution.x / self.inputs.resolution.y;
+
+    if q.y < 0.12 || q.y >= 0.88 {
+      *frag_color = vec4(0.0, 0.0, 0.0, 1.0);
+      return;
+    }
+
+    // camera
+    let camera = self.camera();
+    let rd: Vec3 = camera.ray_direction(p, 2.2);
 
-    // post processing
-    col = col.clamp(Vec3::ZERO, Vec3::ONE).powf(0.4545);
-    col *= 1.2 * vec3(1.0, 0.99, 0.95);
-    col = (1.06 * col - Vec3::splat(0.03)).clamp(Vec3::ZERO, Vec3::ONE);
+    // Rain (by Dave Hoskins)
+    let st: Vec2 =
+      256. * (p * vec2(0.5, 0.01) + vec2(self.time() * 0.13 - q.y * 0.6, self.time() * 0.13));
+    let mut f: f32 = noise(st) * noise(st * 0.773) * 1.55;
+    f = 0.25 + (f.abs().powf(13.0) * 13.0).clamp(0.0, q.y * 0.14);
+
+    let mut col: Vec3 = post_process(self.render(camera.position, rd, p, f));
     q.y = (q.y - 0.12) * (1. / 0.76);
     col *= Vec3::splat(0.5)
       + Vec3::splat(0.5) * (16.0 * q.x * q.y * (1.0 - q.x) * (1.0 - q.y)).powf(0.1);
@@ -542,3 +574,9 @@ impl State {
     *frag_color = col.extend(1.0);
   }
 }
+
+fn post_process(mut col: Vec3) -> Vec3 {
+  col = col.clamp(Vec3::ZERO, Vec3::ONE).powf(0.4545);
+  col *= 1.2 * vec3(1.0, 0.99, 0.95);
+  (1.06 * col - Vec3::splat(0.03)).clamp(Vec3::ZERO, Vec3::ONE)
+}
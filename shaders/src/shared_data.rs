@@ -1,3 +1,4 @@
+use crate::{params::MAX_PARAMS, shader_prelude::Vec4};
 use bytemuck::{Pod, Zeroable};
 
 #[repr(C)]
@@ -22,4 +23,94 @@ pub struct ShaderConstants {
   pub drag_end_y: f32,
   pub mouse_left_pressed: u32,
   pub mouse_left_clicked: u32,
+
+  // Free camera state, see `camera::FreeCamera`.
+  /// Boolean value indicating whether the free camera overrides the camera of 3D shaders.
+  pub free_camera: u32,
+  pub camera_offset_x: f32,
+  pub camera_offset_y: f32,
+  pub camera_offset_z: f32,
+  pub camera_yaw: f32,
+  pub camera_pitch: f32,
+
+  /// What the host bound to each channel, see `channel::ChannelKind::pack`.
+  pub channel_sources: u32,
+  /// `iChannelTime`, how far the videos bound to the channels have played, in seconds. Scalars
+  /// rather than an array, whose elements would need a 16-byte stride in the uniform block.
+  pub channel_time0: f32,
+  pub channel_time1: f32,
+  pub channel_time2: f32,
+  pub channel_time3: f32,
+
+  /// See `quality::Quality`.
+  pub quality: u32,
+
+  /// Sub-pixel offset added to the fragment coordinates, in pixels. The host moves it around
+  /// while it averages frames.
+  pub jitter_x: f32,
+  pub jitter_y: f32,
+
+  /// Position of the rendered tile in the frame, in pixels, added to the fragment coordinates.
+  /// Zero unless the host renders a frame larger than its target in tiles, `width` and `height`
+  /// are those of the whole frame then.
+  pub tile_offset_x: f32,
+  pub tile_offset_y: f32,
+
+  /// How pixels map to rays, see `projection::Projection`.
+  pub projection: u32,
+
+  /// See `stereo::StereoMode`.
+  pub stereo: u32,
+  /// Distance between the eyes of stereo views, in the units of the scene.
+  pub eye_separation: f32,
+}
+
+impl ShaderConstants {
+  /// `iChannelTime[index]`, see [`ShaderConstants::channel_time0`].
+  #[inline]
+  #[must_use]
+  pub const fn channel_time(&self, index: usize) -> f32 {
+    match index {
+      0 => self.channel_time0,
+      1 => self.channel_time1,
+      2 => self.channel_time2,
+      _ => self.channel_time3,
+    }
+  }
+}
+
+/// The values of the params of the shown shader, and what the overlay shows about them. Bound as
+/// a uniform block, see `params::ParamValues` and `params::overlay`.
+///
+/// Not `Pod` because of the vectors, the host writes it as plain `[f32; 4]`s and `u32`s.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ShaderParams {
+  /// Numbers and booleans in `x`, colors in `xyz`, see `params::Param::default_value`.
+  pub values: [Vec4; MAX_PARAMS],
+  /// Positions of the overlay sliders in `[0, 1]`, four per vector.
+  pub sliders: [Vec4; MAX_PARAMS / 4],
+  /// Number of params of the shown shader, `0` means the shaders use their defaults.
+  pub count: u32,
+  /// The param being edited.
+  pub selected: u32,
+  /// Boolean value indicating whether the overlay is shown.
+  pub overlay: u32,
+  /// Uniform blocks are a multiple of 16 bytes.
+  pub padding: u32,
+}
+
+/// Which samples of which sound `main_sound_cs` renders, see `sounds::render_sound`. Bound as a
+/// uniform block.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct SoundConstants {
+  /// Index into `sounds::SOUND_DEFINITIONS`.
+  pub sound_index: u32,
+  /// The sample of the first invocation, counted from the start of the sound.
+  pub first_sample: u32,
+  /// The number of samples to render, the output buffer holds at least as many.
+  pub sample_count: u32,
+  /// Samples per second.
+  pub sample_rate: f32,
 }
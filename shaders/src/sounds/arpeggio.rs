@@ -0,0 +1,51 @@
+//! A minor arpeggio over a bass line, eight notes per second, bouncing between the left and the
+//! right channel. Shows what the sound shaders can do without any samples.
+
+use super::{SoundDefinition, SoundInput};
+use crate::shader_prelude::*;
+
+pub const SOUND_DEFINITION: SoundDefinition = SoundDefinition { name: "Arpeggio" };
+
+const NOTES_PER_SECOND: f32 = 8.0;
+/// Semitones above the root of the chord, one bar of sixteen notes.
+const PATTERN: [f32; 16] = [
+  0.0, 3.0, 7.0, 12.0, 15.0, 12.0, 7.0, 3.0, 0.0, 7.0, 12.0, 19.0, 15.0, 12.0, 7.0, 3.0,
+];
+/// Roots of the chords, one per bar: Am, F, C, G.
+const CHORDS: [f32; 4] = [0.0, -4.0, 3.0, -2.0];
+/// A3.
+const BASE_FREQUENCY: f32 = 220.0;
+
+pub fn sound_fn(sound_input: &SoundInput) -> Vec2 {
+  let time = sound_input.time;
+  let beat = time * NOTES_PER_SECOND;
+  #[expect(clippy::cast_sign_loss, reason = "time is never negative")]
+  let note = beat as usize;
+  let bar = note / PATTERN.len();
+  let root = CHORDS[bar % CHORDS.len()];
+  // Seconds since the note started.
+  let age = beat.fract_gl() / NOTES_PER_SECOND;
+
+  let lead = tone(root + PATTERN[note % PATTERN.len()], time, age, 4);
+  let bass_age = time - (bar * PATTERN.len()) as f32 / NOTES_PER_SECOND;
+  let bass = tone(root - 12.0, time, bass_age, 2) * 0.7;
+
+  // Odd notes more to the right.
+  let pan = 0.4f32.mul_add((note % 2) as f32, 0.3);
+  vec2(lead * (1.0 - pan), lead * pan) + Vec2::splat(bass * 0.5)
+}
+
+/// A plucked note `semitones` above [`BASE_FREQUENCY`], `age` seconds after it started, with
+/// the first `harmonics` odd harmonics of a square wave.
+fn tone(semitones: f32, time: f32, age: f32, harmonics: u32) -> f32 {
+  let frequency = BASE_FREQUENCY * exp2(semitones / 12.0);
+  let mut value = 0.0;
+  for harmonic in 0..harmonics {
+    let n = (2 * harmonic + 1) as f32;
+    // Keeps the argument of `sin` small as time grows.
+    value += (TAU * (frequency * n * time).fract_gl()).sin() / n;
+  }
+  // A short attack against clicks, then a decay.
+  let envelope = smoothstep(0.0, 0.005, age) * (-6.0 * age).exp();
+  value * envelope * 0.7
+}
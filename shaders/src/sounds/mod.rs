@@ -0,0 +1,60 @@
+//! Sound shaders, Shadertoy's `mainSound(int samp, float time) -> vec2`. Each one returns the
+//! left and right channel in `[-1, 1]` at a point in time, the host evaluates them for every
+//! sample of a WAV file, on the GPU with `main_sound_cs` or on the CPU.
+
+use crate::{shader_prelude::*, shaders::match_index, shared_data::SoundConstants};
+
+mod arpeggio;
+mod sine_bell;
+
+pub struct SoundDefinition {
+  pub name: &'static str,
+}
+
+/// What a sound shader is evaluated at.
+#[derive(Copy, Clone)]
+pub struct SoundInput {
+  /// The sample, counted from the start of the sound.
+  pub sample: u32,
+  /// In seconds, `sample / sample_rate`.
+  pub time: f32,
+  /// Samples per second, `iSampleRate`.
+  pub sample_rate: f32,
+}
+
+macro_rules! render_sound_macro {
+    ($($sound_name:ident),* $(,)?) => {
+        #[inline(always)]
+        #[must_use]
+        pub fn render_sound(sound_index: u32, sound_input: &SoundInput) -> Vec2 {
+            match_index!(sound_index; $(
+                $sound_name::sound_fn(sound_input),
+            )*)
+        }
+
+        pub const SOUND_DEFINITIONS: &[SoundDefinition] = &[
+            $(
+                $sound_name::SOUND_DEFINITION,
+            )*
+        ];
+    };
+}
+
+render_sound_macro!(sine_bell, arpeggio);
+
+/// Sample `index` of the block of `constants`, clamped to `[-1, 1]` like on Shadertoy. Silent
+/// for out of range sounds.
+#[inline(always)]
+#[must_use]
+pub fn sound(constants: &SoundConstants, index: u32) -> Vec2 {
+  if constants.sound_index as usize >= SOUND_DEFINITIONS.len() {
+    return Vec2::ZERO;
+  }
+  let sample = constants.first_sample + index;
+  let sound_input = SoundInput {
+    sample,
+    time: sample as f32 / constants.sample_rate,
+    sample_rate: constants.sample_rate,
+  };
+  render_sound(constants.sound_index, &sound_input).clamp(-Vec2::ONE, Vec2::ONE)
+}
@@ -0,0 +1,21 @@
+//! Shadertoy's template for new sound shaders.
+//!
+//! Original code:
+//! ```glsl
+//! vec2 mainSound( int samp, float time )
+//! {
+//!     // A 440 Hz wave that attenuates quickly overt time
+//!     return vec2( sin(6.2831*440.0*time)*exp(-3.0*time) );
+//! }
+//! ```
+
+use super::{SoundDefinition, SoundInput};
+use crate::shader_prelude::*;
+
+pub const SOUND_DEFINITION: SoundDefinition = SoundDefinition { name: "Sine Bell" };
+
+pub fn sound_fn(sound_input: &SoundInput) -> Vec2 {
+  let time = sound_input.time;
+  // A 440 Hz wave that attenuates quickly overt time
+  Vec2::splat((TAU * 440.0 * time).sin() * (-3.0 * time).exp())
+}
@@ -0,0 +1,104 @@
+//! Stereo views of the 3D shaders, for looking at them without VR hardware: the pixels become
+//! [`ViewRay`]s from two eyes [`ShaderConstants::eye_separation`] apart, traced by the `ray_fn`
+//! of the shader (see `shaders::HAS_RAY_FN`). Other shaders render as usual.
+//!
+//! The eyes look parallel along the camera's forward direction, so the views converge at
+//! infinity and everything closer appears in front of the screen.
+
+use crate::shader_prelude::*;
+
+/// Focal length of the eye views, see [`Camera::ray_direction`]. The ports use their own between
+/// 1.5 and 2.5 for their screen views, this is the most common one.
+pub const FOCAL_LENGTH: f32 = 2.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum StereoMode {
+  Off = 0,
+  /// The left eye in the left half of the frame, the right eye in the right half, for parallel
+  /// viewing or side-by-side 3D displays.
+  SideBySide = 1,
+  /// The left eye in the top half of the frame, the right eye in the bottom half.
+  TopBottom = 2,
+  /// Both eyes over the whole frame, for red-cyan glasses, see [`anaglyph`].
+  Anaglyph = 3,
+}
+
+/// The eyes that see a pixel, `0` is the left one and `1` the right one, and where the pixel is
+/// in their view.
+#[derive(Copy, Clone, Debug)]
+pub struct EyeViews {
+  pub first_eye: u32,
+  pub last_eye: u32,
+  /// Scaled like for [`Camera::ray_direction`].
+  pub uv: Vec2,
+}
+
+impl StereoMode {
+  pub const ALL: [Self; 4] = [Self::Off, Self::SideBySide, Self::TopBottom, Self::Anaglyph];
+
+  /// [`ShaderConstants::stereo`], out of range values are [`StereoMode::Off`].
+  #[must_use]
+  pub fn from_constants(constants: &ShaderConstants) -> Self {
+    match constants.stereo {
+      1 => Self::SideBySide,
+      2 => Self::TopBottom,
+      3 => Self::Anaglyph,
+      _ => Self::Off,
+    }
+  }
+
+  /// The eyes that see the pixel at `frag_coord`, measured from the bottom left like
+  /// [`ShaderInput::frag_coord`]. [`StereoMode::Off`] has only the left eye, over the whole
+  /// frame.
+  #[must_use]
+  pub fn eye_views(self, mut frag_coord: Vec2, mut resolution: Vec2) -> EyeViews {
+    let (first_eye, last_eye) = match self {
+      Self::Off => (0, 0),
+      Self::SideBySide => {
+        resolution.x *= 0.5;
+        if frag_coord.x < resolution.x {
+          (0, 0)
+        } else {
+          frag_coord.x -= resolution.x;
+          (1, 1)
+        }
+      },
+      Self::TopBottom => {
+        resolution.y *= 0.5;
+        if frag_coord.y < resolution.y {
+          (1, 1)
+        } else {
+          frag_coord.y -= resolution.y;
+          (0, 0)
+        }
+      },
+      Self::Anaglyph => (0, 1),
+    };
+    EyeViews {
+      first_eye,
+      last_eye,
+      uv: (2.0 * frag_coord - resolution) / resolution.y,
+    }
+  }
+}
+
+/// The ray through `uv` seen from `eye` of [`EyeViews`].
+#[inline]
+#[must_use]
+pub fn eye_ray(uv: Vec2, eye: u32, eye_separation: f32) -> ViewRay {
+  let side = if eye == 0 { -0.5 } else { 0.5 };
+  ViewRay {
+    origin: vec3(side * eye_separation, 0.0, 0.0),
+    direction: uv.extend(FOCAL_LENGTH).normalize(),
+    level: false,
+  }
+}
+
+/// Red from the luminance of the `left` eye, green and blue from the `right` eye. The luminance
+/// rivals less with the right eye than the red channel alone.
+#[inline]
+#[must_use]
+pub fn anaglyph(left: Vec3, right: Vec3) -> Vec3 {
+  vec3(left.dot(vec3(0.299, 0.587, 0.114)), right.y, right.z)
+}
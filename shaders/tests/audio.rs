@@ -0,0 +1,91 @@
+//! Tests for the `audio` module.
+
+use bytemuck::Zeroable;
+use shadertoys_shaders::{
+  audio::{fft, waveform, SyntheticAudio, AUDIO_TEXTURE_WIDTH},
+  channel::{Channel, Unbound},
+  fs,
+  params::MAX_PARAMS,
+  shader_prelude::*,
+  shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderParams,
+};
+
+/// A channel whose texels are their `uv.y`.
+#[derive(Copy, Clone)]
+struct RowChannel;
+
+impl Channel for RowChannel {
+  fn resolution(self) -> Vec3 {
+    vec3(AUDIO_TEXTURE_WIDTH as f32, 2.0, 1.0)
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    Vec4::splat(uv.y)
+  }
+
+  fn texture_cube(self, _: Vec3) -> Vec4 {
+    Vec4::ZERO
+  }
+
+  fn texture_3d(self, _: Vec3) -> Vec4 {
+    Vec4::ZERO
+  }
+}
+
+#[test]
+fn spectrum_and_waveform_are_in_their_rows() {
+  let rows = vec2(fft(RowChannel, 0.3), waveform(RowChannel, 0.3));
+  assert_eq!(rows, vec2(0.25, 0.75));
+}
+
+#[test]
+fn synthetic_audio_stays_in_range() {
+  for time in [0.0, 0.2, 1.7, 100.0] {
+    let audio = SyntheticAudio { time };
+    for i in 0..=64 {
+      let x = i as f32 / 64.0;
+      let level = fft(audio, x);
+      let wave = waveform(audio, x);
+      assert!((0.0..=1.0).contains(&level), "{time} {x}: {level}");
+      assert!((0.0..=1.0).contains(&wave), "{time} {x}: {wave}");
+    }
+    // Loud low frequencies, quiet high ones.
+    assert!(fft(audio, 0.0) > fft(audio, 0.95));
+  }
+}
+
+#[test]
+fn audio_spectrum_moves_without_music() {
+  let constants = ShaderConstants {
+    width: 200,
+    height: 100,
+    shader_to_show: SHADER_DEFINITIONS
+      .iter()
+      .position(|definition| definition.name == "Audio Spectrum")
+      .unwrap() as u32,
+    ..ShaderConstants::zeroed()
+  };
+  let params = ShaderParams {
+    values: [Vec4::ZERO; MAX_PARAMS],
+    sliders: [Vec4::ZERO; MAX_PARAMS / 4],
+    count: 0,
+    selected: 0,
+    overlay: 0,
+    padding: 0,
+  };
+  // The bottom of the first bar, which is lit at any time. `fs` takes the frame top down.
+  let frag_coord = vec2(2.0, 97.5);
+  let start = fs(&constants, &params, frag_coord, [Unbound; 4]);
+  assert!(start.x + start.y + start.z > 0.3, "{start}");
+  let later = ShaderConstants {
+    time: 0.3,
+    ..constants
+  };
+  // The waveform.
+  let top = vec2(100.5, 20.5);
+  assert_ne!(
+    fs(&constants, &params, top, [Unbound; 4]),
+    fs(&later, &params, top, [Unbound; 4])
+  );
+}
@@ -0,0 +1,76 @@
+//! Tests for the `camera` module.
+
+use shadertoys_shaders::shader_prelude::*;
+
+#[track_caller]
+fn assert_close(actual: Vec3, expected: Vec3) {
+  assert!(
+    (actual - expected).length() < 1e-5,
+    "got {actual}, expected {expected}"
+  );
+}
+
+#[test]
+fn look_at_points_the_center_ray_at_the_target() {
+  let ro = vec3(3.0, 2.0, -4.0);
+  let ta = vec3(0.0, 0.5, 0.0);
+  let camera = Camera::look_at(ro, ta, 0.0);
+  assert_close(camera.forward(), (ta - ro).normalize());
+  assert_close(camera.ray_direction(Vec2::ZERO, 2.0), (ta - ro).normalize());
+  // Rays through the top of the screen go up, no roll keeps the horizon level.
+  assert!(camera.ray_direction(vec2(0.0, 1.0), 2.0).y > camera.forward().y);
+  assert!((camera.ray_direction(vec2(1.0, 0.0), 2.0).y - camera.forward().y).abs() < 0.1);
+  assert!((camera.basis.x_axis.y).abs() < 1e-6);
+}
+
+#[test]
+fn yaw_and_pitch_turn_right_and_up() {
+  let camera = Camera::from_yaw_pitch(Vec3::ZERO, FRAC_PI_2, 0.0);
+  assert_close(camera.forward(), Vec3::X);
+  let camera = Camera::from_yaw_pitch(Vec3::ZERO, 0.0, FRAC_PI_2);
+  assert_close(camera.forward(), Vec3::Y);
+}
+
+#[test]
+fn free_camera_is_relative_to_the_shader_camera() {
+  let camera = Camera::look_at(vec3(0.0, 1.0, 0.0), vec3(0.0, 1.0, -5.0), 0.0);
+
+  let disabled = FreeCamera {
+    enabled: false,
+    offset: Vec3::ONE,
+    yaw: 1.0,
+    pitch: 0.5,
+  };
+  let same = camera.with_free_camera(disabled);
+  assert_close(same.position, camera.position);
+  assert_close(same.forward(), camera.forward());
+
+  // Moving forward by one unit and turning right by 90 degrees.
+  let free = FreeCamera {
+    enabled: true,
+    offset: vec3(0.0, 0.0, 1.0),
+    yaw: FRAC_PI_2,
+    pitch: 0.0,
+  };
+  let moved = camera.with_free_camera(free);
+  assert_close(moved.position, vec3(0.0, 1.0, -1.0));
+  assert_close(moved.forward(), camera.basis.x_axis);
+}
+
+#[test]
+fn level_cameras_keep_their_heading_and_handedness() {
+  let camera = Camera::look_at(Vec3::ZERO, vec3(1.0, -1.0, 1.0), 0.3);
+  let level = camera.level();
+  assert_close(level.forward(), vec3(1.0, 0.0, 1.0).normalize());
+  assert_close(level.basis.y_axis, Vec3::Y);
+  assert!(level.basis.determinant() * camera.basis.determinant() > 0.0);
+
+  let ray = ViewRay {
+    origin: vec3(1.0, 0.0, 0.0),
+    direction: Vec3::Z,
+    level: true,
+  };
+  let (origin, direction) = camera.view_ray(ray);
+  assert_close(origin, level.basis.x_axis);
+  assert_close(direction, level.forward());
+}
@@ -0,0 +1,165 @@
+//! Tests for the `channel` module.
+
+use bytemuck::Zeroable;
+use shadertoys_shaders::{
+  channel::{Channel, ChannelKind, ConstantColor, RgbCube, Unbound},
+  fs,
+  params::MAX_PARAMS,
+  shader_prelude::*,
+  shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderParams,
+};
+use spirv_std::glam::IVec2;
+
+#[test]
+fn unbound_channels_use_the_fallback() {
+  let fallback = ConstantColor { color: Vec4::ONE };
+  let channel = Unbound.or(fallback);
+  assert!(channel.is_bound());
+  assert_eq!(channel.texture(Vec2::ZERO), Vec4::ONE);
+  assert_eq!(channel.texture_cube(Vec3::X), Vec4::ONE);
+  assert_eq!(channel.texture_3d_lod(Vec3::ZERO, 2.0), Vec4::ONE);
+  assert_eq!(channel.resolution(), Vec3::ONE);
+
+  let color = vec4(0.1, 0.2, 0.3, 1.0);
+  let channel = ConstantColor { color }.or(fallback);
+  assert_eq!(channel.texel_fetch(IVec2::ZERO, 0), color);
+
+  assert!(!Unbound.or(Unbound).is_bound());
+  assert_eq!(Unbound.texture(Vec2::ONE), Vec4::ZERO);
+}
+
+/// A video that has played for `time` seconds.
+#[derive(Copy, Clone)]
+struct Video {
+  time: f32,
+  bound: bool,
+}
+
+impl Channel for Video {
+  fn resolution(self) -> Vec3 {
+    vec3(64.0, 36.0, 1.0)
+  }
+
+  fn time(self) -> f32 {
+    self.time
+  }
+
+  fn texture(self, _: Vec2) -> Vec4 {
+    Vec4::ONE
+  }
+
+  fn texture_cube(self, _: Vec3) -> Vec4 {
+    Vec4::ONE
+  }
+
+  fn texture_3d(self, _: Vec3) -> Vec4 {
+    Vec4::ONE
+  }
+
+  fn is_bound(self) -> bool {
+    self.bound
+  }
+}
+
+#[test]
+fn channel_time_follows_the_bound_channel() {
+  let still = ConstantColor { color: Vec4::ONE };
+  let fallback = Video {
+    time: 2.5,
+    bound: true,
+  };
+  let video = Video {
+    time: 1.0,
+    bound: true,
+  };
+  let unbound = Video {
+    bound: false,
+    ..video
+  };
+  let times = [
+    still.time(),
+    Unbound.or(fallback).time(),
+    video.or(fallback).time(),
+    unbound.or(fallback).time(),
+  ];
+  assert_eq!(Vec4::from_array(times), vec4(0.0, 2.5, 1.0, 2.5));
+}
+
+#[test]
+fn old_film_follows_the_video() {
+  let constants = ShaderConstants {
+    width: 64,
+    height: 36,
+    shader_to_show: SHADER_DEFINITIONS
+      .iter()
+      .position(|definition| definition.name == "Old Film")
+      .unwrap() as u32,
+    ..ShaderConstants::zeroed()
+  };
+  let params = ShaderParams {
+    values: [Vec4::ZERO; MAX_PARAMS],
+    sliders: [Vec4::ZERO; MAX_PARAMS / 4],
+    count: 0,
+    selected: 0,
+    overlay: 0,
+    padding: 0,
+  };
+  let frame = |time: f32, video_time: f32| {
+    let video = Video {
+      time: video_time,
+      bound: true,
+    };
+    (0..36)
+      .flat_map(|y| (0..64).map(move |x| vec2(x as f32 + 0.5, y as f32 + 0.5)))
+      .map(|frag_coord| {
+        let constants = ShaderConstants { time, ..constants };
+        fs(&constants, &params, frag_coord, [video; 4])
+      })
+      .collect::<Vec<_>>()
+  };
+  // The grain and the damage change with the frames of the video, not with the time.
+  assert_eq!(frame(0.0, 1.0), frame(5.0, 1.0));
+  assert_ne!(frame(0.0, 1.0), frame(0.0, 1.1));
+}
+
+#[test]
+fn rgb_cube_colors_by_direction() {
+  let cube = RgbCube {
+    alpha: 0.5,
+    intensity: 2.0,
+  };
+  assert_eq!(cube.texture_cube(-Vec3::X), vec4(2.0, 0.0, 0.0, 0.5));
+  assert_eq!(cube.texture_3d(Vec3::Z), vec4(0.0, 0.0, 2.0, 0.5));
+  // The top row of the equirectangular projection looks straight up.
+  let top = cube.texture(vec2(0.3, 0.0));
+  assert!((top - vec4(0.0, 2.0, 0.0, 0.5)).length() < 1e-5, "{top}");
+}
+
+#[test]
+fn channel_kinds_round_trip() {
+  let kinds = [
+    ChannelKind::Cubemap,
+    ChannelKind::Unbound,
+    ChannelKind::Volume,
+    ChannelKind::Texture2d,
+  ];
+  let packed = ChannelKind::pack(kinds);
+  for (i, kind) in kinds.into_iter().enumerate() {
+    assert_eq!(ChannelKind::unpack(packed, i), kind as u32);
+  }
+  assert_eq!(ChannelKind::pack([ChannelKind::Unbound; 4]), 0);
+}
+
+#[test]
+fn channel_time_is_read_per_channel() {
+  let constants = ShaderConstants {
+    channel_time0: 1.0,
+    channel_time1: 2.0,
+    channel_time2: 3.0,
+    channel_time3: 4.0,
+    ..ShaderConstants::zeroed()
+  };
+  let times: Vec<f32> = (0..4).map(|index| constants.channel_time(index)).collect();
+  assert_eq!(times, [1.0, 2.0, 3.0, 4.0]);
+}
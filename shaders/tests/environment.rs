@@ -0,0 +1,92 @@
+//! Tests for the `environment` module.
+
+use shadertoys_shaders::{environment::Environment, shader_prelude::*};
+
+/// Directions spread over the whole sphere.
+fn directions() -> impl Iterator<Item = Vec3> {
+  (0..32).flat_map(|i| {
+    (0..16).map(move |j| {
+      let phi = i as f32 / 32.0 * TAU;
+      let theta = (j as f32 + 0.5) / 16.0 * PI;
+      vec3(
+        theta.sin() * phi.cos(),
+        theta.cos(),
+        theta.sin() * phi.sin(),
+      )
+    })
+  })
+}
+
+#[track_caller]
+fn assert_valid(environment: impl Environment) {
+  for direction in directions() {
+    let color = environment.radiance(direction);
+    assert!(
+      color.is_finite() && color.cmpge(Vec3::ZERO).all(),
+      "{color} in direction {direction}"
+    );
+  }
+}
+
+#[test]
+fn radiance_is_finite_and_positive() {
+  assert_valid(GradientSky {
+    zenith: vec3(0.2, 0.4, 0.8),
+    horizon: Vec3::ONE,
+    ground: Vec3::splat(0.2),
+    exponent: 4.0,
+  });
+  assert_valid(PreethamSky::new(vec3(0.3, 0.2, 1.0)));
+  assert_valid(PreethamSky::new(Vec3::Y));
+  assert_valid(Studio::new());
+  assert_valid(Studio {
+    softness: 0.0,
+    ..Studio::new()
+  });
+  assert_valid(Starfield::new());
+}
+
+#[test]
+fn preetham_sky_is_blue_and_brightest_at_the_sun() {
+  let sun = vec3(0.5, 0.5, 0.5).normalize();
+  let sky = PreethamSky::new(sun);
+  let zenith = sky.radiance(Vec3::Y);
+  assert!(zenith.z > zenith.x, "{zenith}");
+  let brightest = directions().map(|d| sky.radiance(d).y).fold(0.0, f32::max);
+  assert!(sky.radiance(sun).y > brightest);
+  // A low sun makes for a warmer horizon.
+  let sunset = PreethamSky::new(vec3(1.0, 0.1, 0.0));
+  let horizon = sunset.radiance(vec3(1.0, 0.05, 0.1).normalize());
+  assert!(horizon.x > horizon.z, "{horizon}");
+}
+
+#[test]
+fn studio_lights_are_brighter_than_the_background() {
+  let studio = Studio::new();
+  let key = studio.radiance(vec3(-0.6, 0.5, -0.6).normalize());
+  let back = studio.radiance(Vec3::Z);
+  assert!(key.y > 10.0 * back.y, "{key} {back}");
+  // Softer lights spread the same lights wider, just above the key light is lit then.
+  let forward = vec3(-0.6, 0.5, -0.6).normalize();
+  let up = forward.cross(Vec3::Y).normalize().cross(forward);
+  let edge = (forward + 0.36 * up).normalize();
+  let soft = Studio {
+    softness: 1.0,
+    ..studio
+  };
+  assert!(soft.radiance(edge).y > studio.radiance(edge).y);
+}
+
+#[test]
+fn starfield_is_mostly_dark() {
+  let stars = Starfield::new();
+  let lit = directions()
+    .filter(|&d| stars.radiance(d).y > 10.0 * stars.background.y + 0.01)
+    .count();
+  assert!(lit > 0 && lit < directions().count() / 4, "{lit}");
+  // Environments are channels too.
+  assert_eq!(
+    stars.texture_cube(Vec3::X * 2.0),
+    stars.radiance(Vec3::X).extend(1.0)
+  );
+}
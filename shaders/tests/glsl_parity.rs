@@ -0,0 +1,358 @@
+//! Checks the GLSL-like helpers in `shader_prelude` against the GLSL specification.
+//!
+//! The reference implementations below follow the definitions in the GLSL ES 3.00 specification
+//! (section 8), evaluated in `f64`. Where the specification leaves a result undefined (NaN,
+//! infinities, `smoothstep` with `edge0 >= edge1`), the tests pin down what our implementation
+//! does instead, so ports don't silently change behavior.
+//!
+//! Differences between Rust (as compiled on the host and by rust-gpu) and GLSL worth knowing:
+//!
+//! - `%` on floats truncates like C's `fmod`, GLSL `mod` floors. Use [`mod_gl`] for the GLSL
+//!   behavior. [`FloatExt::rem_euclid`] is only the same for positive divisors, for negative ones
+//!   it still returns a non-negative result while GLSL takes the sign of the divisor.
+//! - On the host, `x.rem_euclid(y)` resolves to the inherent `f32::rem_euclid` from `std`, under
+//!   rust-gpu (`no_std`) it resolves to [`FloatExt::rem_euclid`]. Both agree, but only because
+//!   the trait mirrors `std`.
+//! - `f32::fract` keeps the sign (`-0.25` for `-1.25`), GLSL `fract` does not (`0.75`). Use
+//!   [`FloatExt::fract_gl`] or [`fract`].
+//! - `f32::signum` returns `1.0` for `0.0`, GLSL `sign` returns `0.0`. Use [`FloatExt::sign_gl`].
+//! - `f32::min`/`f32::max` ignore NaN, GLSL leaves it undefined. The GLSL layer's [`min`] and
+//!   [`max`] return the first argument when a comparison involves NaN.
+//! - `acos` is often replaced by [`acos_approx`], see [`acos_approx_error_bound`] for its error.
+
+// Exact results are part of what is being checked.
+#![allow(clippy::float_cmp)]
+
+use shadertoys_shaders::shader_prelude::*;
+
+/// GLSL reference implementations, straight from the specification.
+mod reference {
+  pub fn mod_(x: f64, y: f64) -> f64 {
+    x - y * (x / y).floor()
+  }
+
+  pub fn fract(x: f64) -> f64 {
+    x - x.floor()
+  }
+
+  pub fn sign(x: f64) -> f64 {
+    if x > 0.0 {
+      1.0
+    } else if x < 0.0 {
+      -1.0
+    } else {
+      0.0
+    }
+  }
+
+  pub fn step(edge: f64, x: f64) -> f64 {
+    if x < edge {
+      0.0
+    } else {
+      1.0
+    }
+  }
+
+  pub fn mix(x: f64, y: f64, a: f64) -> f64 {
+    x * (1.0 - a) + y * a
+  }
+
+  pub fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+  }
+
+  pub fn reflect(i: [f64; 3], n: [f64; 3]) -> [f64; 3] {
+    let d = dot(n, i);
+    [
+      i[0] - 2.0 * d * n[0],
+      i[1] - 2.0 * d * n[1],
+      i[2] - 2.0 * d * n[2],
+    ]
+  }
+
+  pub fn refract(i: [f64; 3], n: [f64; 3], eta: f64) -> [f64; 3] {
+    let d = dot(n, i);
+    let k = 1.0 - eta * eta * (1.0 - d * d);
+    if k < 0.0 {
+      [0.0; 3]
+    } else {
+      let s = eta * d + k.sqrt();
+      [
+        eta * i[0] - s * n[0],
+        eta * i[1] - s * n[1],
+        eta * i[2] - s * n[2],
+      ]
+    }
+  }
+
+  fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+  }
+}
+
+/// Operands covering both signs, zero, fractions and values close to integers.
+const VALUES: [f32; 15] = [
+  -7.5, -3.0, -2.25, -1.0, -0.75, -0.1, -0.0, 0.0, 0.1, 0.5, 0.999, 1.0, 2.25, 3.0, 12.345,
+];
+
+/// Divisors for `mod`, exactly representable so quotients that should be integers are.
+const DIVISORS: [f32; 7] = [-3.0, -2.25, -0.75, 0.5, 1.0, 2.25, 3.0];
+
+#[track_caller]
+fn assert_close(actual: f32, expected: f64, what: &str) {
+  let tolerance = 1e-5 * expected.abs().max(1.0);
+  assert!(
+    (f64::from(actual) - expected).abs() <= tolerance,
+    "{what}: got {actual}, GLSL gives {expected}"
+  );
+}
+
+fn vec3_to_array(v: Vec3) -> [f64; 3] {
+  [f64::from(v.x), f64::from(v.y), f64::from(v.z)]
+}
+
+#[test]
+fn mod_gl_matches_glsl_mod() {
+  for x in VALUES {
+    for y in DIVISORS {
+      let expected = reference::mod_(f64::from(x), f64::from(y));
+      assert_close(mod_gl(x, y), expected, &format!("mod({x}, {y})"));
+      let v = mod_gl(vec2(x, -x), y);
+      assert_close(v.x, expected, &format!("mod(vec2({x}), {y}).x"));
+    }
+  }
+  // The sign follows the divisor.
+  assert_eq!(mod_gl(-1.0, 3.0), 2.0);
+  assert_eq!(mod_gl(1.0, -3.0), -2.0);
+}
+
+#[test]
+fn rem_euclid_matches_glsl_mod_for_positive_divisors_only() {
+  for x in VALUES {
+    for y in DIVISORS.into_iter().filter(|&y| y > 0.0) {
+      let expected = reference::mod_(f64::from(x), f64::from(y));
+      assert_close(
+        FloatExt::rem_euclid(x, y),
+        expected,
+        &format!("rem_euclid({x}, {y})"),
+      );
+    }
+  }
+  // GLSL: mod(5.0, -3.0) == -1.0, but `rem_euclid` is never negative.
+  assert_eq!(FloatExt::rem_euclid(5.0, -3.0), 2.0);
+  assert_eq!(mod_gl(5.0, -3.0), -1.0);
+  // The host's `std` version, which shaders get when compiled for the CPU, agrees.
+  assert_eq!(f32::rem_euclid(5.0, -3.0), 2.0);
+}
+
+#[test]
+fn fract_matches_glsl() {
+  for x in VALUES {
+    let expected = reference::fract(f64::from(x));
+    assert_close(x.fract_gl(), expected, &format!("fract_gl({x})"));
+    assert_close(fract(x), expected, &format!("fract({x})"));
+    assert_close(
+      fract(vec4(x, x, x, x)).w,
+      expected,
+      &format!("fract(vec4({x}))"),
+    );
+  }
+  // Unlike `f32::fract`, which keeps the sign.
+  assert_eq!((-1.25f32).fract_gl(), 0.75);
+  assert_eq!((-1.25f32).fract(), -0.25);
+}
+
+#[test]
+fn sign_matches_glsl() {
+  for x in VALUES {
+    assert_close(
+      x.sign_gl(),
+      reference::sign(f64::from(x)),
+      &format!("sign({x})"),
+    );
+  }
+  assert_eq!(vec3(-2.0, 0.0, 2.0).sign_gl(), vec3(-1.0, 0.0, 1.0));
+  // `f32::signum` treats zero as positive.
+  assert_eq!(0.0f32.sign_gl(), 0.0);
+  assert_eq!(0.0f32.signum(), 1.0);
+}
+
+#[test]
+fn step_matches_glsl() {
+  for edge in VALUES {
+    for x in VALUES {
+      let expected = reference::step(f64::from(edge), f64::from(x));
+      assert_close(step(edge, x), expected, &format!("step({edge}, {x})"));
+      assert_close(edge.step(x), expected, &format!("{edge}.step({x})"));
+      assert_close(
+        Vec2::splat(edge).step(Vec2::splat(x)).y,
+        expected,
+        &format!("vec2({edge}).step({x})"),
+      );
+    }
+  }
+  // The edge itself counts as "above".
+  assert_eq!(step(0.5, 0.5), 1.0);
+  // Scalar edge with a vector, like GLSL's `step(float, vec3)`.
+  assert_eq!(step(0.5, vec3(0.0, 0.5, 1.0)), vec3(0.0, 1.0, 1.0));
+}
+
+#[test]
+fn mix_matches_glsl() {
+  for x in VALUES {
+    for y in VALUES {
+      for a in [-0.5, 0.0, 0.25, 0.5, 1.0, 1.5] {
+        let expected = reference::mix(f64::from(x), f64::from(y), f64::from(a));
+        assert_close(mix(x, y, a), expected, &format!("mix({x}, {y}, {a})"));
+      }
+    }
+  }
+  assert_eq!(
+    mix(Vec3::ZERO, Vec3::ONE, vec3(0.0, 0.5, 1.0)),
+    vec3(0.0, 0.5, 1.0)
+  );
+}
+
+#[test]
+fn smoothstep_matches_glsl() {
+  for (edge0, edge1) in [(0.0, 1.0), (-1.0, 1.0), (0.25, 0.3)] {
+    for x in VALUES {
+      let expected = reference::smoothstep(f64::from(edge0), f64::from(edge1), f64::from(x));
+      assert_close(
+        smoothstep(edge0, edge1, x),
+        expected,
+        &format!("smoothstep({edge0}, {edge1}, {x})"),
+      );
+    }
+  }
+  assert_eq!(
+    smoothstep(0.0, 1.0, vec3(-1.0, 0.5, 2.0)),
+    vec3(0.0, 0.5, 1.0)
+  );
+}
+
+#[test]
+fn smoothstep_with_reversed_edges_mirrors_the_curve() {
+  // Undefined in GLSL, but ports rely on the common GPU behavior of evaluating the formula as is.
+  for x in VALUES {
+    let reversed = smoothstep(1.0, 0.0, x);
+    let expected = reference::smoothstep(1.0, 0.0, f64::from(x));
+    assert_close(reversed, expected, &format!("smoothstep(1, 0, {x})"));
+    assert_close(
+      reversed,
+      1.0 - reference::smoothstep(0.0, 1.0, f64::from(x)),
+      &format!("1 - smoothstep(0, 1, {x})"),
+    );
+  }
+}
+
+#[test]
+fn non_finite_inputs() {
+  // NaN propagates through the arithmetic helpers.
+  assert!(f32::NAN.fract_gl().is_nan());
+  assert!(fract(f32::NAN).is_nan());
+  assert!(mod_gl(f32::NAN, 2.0).is_nan());
+  assert!(mod_gl(1.0, f32::NAN).is_nan());
+  assert!(mix(0.0, f32::NAN, 0.5).is_nan());
+  assert!(smoothstep(0.0, 1.0, f32::NAN).is_nan());
+  // Comparisons with NaN are false, so the "else" branches win.
+  assert_eq!(f32::NAN.sign_gl(), 1.0);
+  assert_eq!(step(f32::NAN, 0.0), 1.0);
+  assert_eq!(step(0.0, f32::NAN), 1.0);
+  assert!(min(f32::NAN, 1.0).is_nan());
+  assert_eq!(min(1.0, f32::NAN), 1.0);
+
+  // Infinities behave like very large numbers where the result is meaningful.
+  assert_eq!(smoothstep(0.0, 1.0, f32::INFINITY), 1.0);
+  assert_eq!(smoothstep(0.0, 1.0, f32::NEG_INFINITY), 0.0);
+  assert_eq!(step(0.0, f32::INFINITY), 1.0);
+  assert_eq!(f32::NEG_INFINITY.sign_gl(), -1.0);
+  assert_eq!(clamp(f32::INFINITY, 0.0, 1.0), 1.0);
+  assert!(mod_gl(f32::INFINITY, 2.0).is_nan());
+  assert!(f32::INFINITY.fract_gl().is_nan());
+}
+
+#[test]
+fn clamp_min_max_broadcast_scalars() {
+  let v = vec3(-1.0, 0.5, 2.0);
+  assert_eq!(clamp(v, 0.0, 1.0), vec3(0.0, 0.5, 1.0));
+  assert_eq!(min(v, 0.0), vec3(-1.0, 0.0, 0.0));
+  assert_eq!(max(v, 0.0), vec3(0.0, 0.5, 2.0));
+  assert_eq!(
+    clamp(v, Vec3::ZERO, vec3(0.1, 0.2, 0.3)),
+    vec3(0.0, 0.2, 0.3)
+  );
+}
+
+#[test]
+fn reflect_refract_faceforward_match_glsl() {
+  let i = vec3(1.0, -1.0, 0.5).normalize();
+  let n = vec3(0.0, 1.0, 0.0);
+  let expected = reference::reflect(vec3_to_array(i), vec3_to_array(n));
+  let actual = vec3_to_array(reflect(i, n));
+  for (a, e) in actual.iter().zip(expected) {
+    assert_close(*a as f32, e, "reflect");
+  }
+  for eta in [0.5, 1.0 / 1.33, 1.0, 1.33, 2.5] {
+    let expected = reference::refract(vec3_to_array(i), vec3_to_array(n), f64::from(eta));
+    let actual = vec3_to_array(refract(i, n, eta));
+    for (a, e) in actual.iter().zip(expected) {
+      assert_close(*a as f32, e, &format!("refract(eta = {eta})"));
+    }
+  }
+  // Total internal reflection.
+  assert_eq!(refract(i, n, 2.5), Vec3::ZERO);
+
+  assert_eq!(faceforward(n, i, n), n);
+  assert_eq!(faceforward(n, -i, n), -n);
+}
+
+#[test]
+fn exp2_log2_inversesqrt_atan2() {
+  for x in [0.25f32, 1.0, 3.0, 100.0] {
+    assert_close(exp2(x), 2f64.powf(f64::from(x)), "exp2");
+    assert_close(log2(x), f64::from(x).log2(), "log2");
+    assert_close(inversesqrt(x), 1.0 / f64::from(x).sqrt(), "inversesqrt");
+  }
+  // GLSL `atan(y, x)` covers all four quadrants.
+  assert_close(
+    atan2(1.0, -1.0),
+    3.0 * core::f64::consts::FRAC_PI_4,
+    "atan2",
+  );
+  assert_eq!(atan2(vec2(0.0, 1.0), vec2(1.0, 0.0)), vec2(0.0, FRAC_PI_2));
+}
+
+#[test]
+fn matrix_comp_mult_is_component_wise() {
+  let a = mat2(vec2(1.0, 2.0), vec2(3.0, 4.0));
+  let b = mat2(vec2(5.0, 6.0), vec2(7.0, 8.0));
+  assert_eq!(
+    matrix_comp_mult(a, b),
+    mat2(vec2(5.0, 12.0), vec2(21.0, 32.0))
+  );
+}
+
+/// `acos_approx` trades accuracy for speed: its absolute error stays below `6.2e-3` radians over
+/// `[-1, 1]`, the worst case is around `0.0`. It is exact at `-1.0` and `1.0`.
+#[test]
+fn acos_approx_error_bound() {
+  let samples = 20_000;
+  let mut max_error = 0.0f64;
+  for i in 0..=samples {
+    let v = (-1.0 + 2.0 * f64::from(i) / f64::from(samples)) as f32;
+    let error = (f64::from(acos_approx(v)) - f64::from(v).acos()).abs();
+    max_error = max_error.max(error);
+  }
+  assert!(max_error < 6.2e-3, "max error {max_error}");
+  assert!(
+    max_error > 6.0e-3,
+    "the documented bound is no longer tight"
+  );
+
+  assert_eq!(acos_approx(1.0), 0.0);
+  assert!((acos_approx(-1.0) - PI).abs() < 1e-6);
+  // Outside of the domain the result is NaN, like GLSL's `acos`.
+  assert!(acos_approx(1.5).is_nan());
+}
@@ -0,0 +1,26 @@
+//! Tests for the layout of the grid view, see `optimal_grid`.
+
+use shadertoys_shaders::{optimal_grid, shader_prelude::*};
+
+#[test]
+fn every_cell_count_fits() {
+  for cell_count in 1..=64 {
+    for aspect in [vec2(16.0, 9.0), vec2(1.0, 1.0), vec2(9.0, 16.0)] {
+      let (rows, cols) = optimal_grid(cell_count, aspect);
+      assert!(rows * cols >= cell_count, "{cell_count} cells at {aspect}");
+      // Less than a column is left empty.
+      assert!(
+        rows * cols - cell_count < rows,
+        "{cell_count} cells at {aspect}"
+      );
+    }
+  }
+  assert_eq!(optimal_grid(0, vec2(16.0, 9.0)), (0, 0));
+}
+
+#[test]
+fn grids_follow_the_aspect_ratio() {
+  assert_eq!(optimal_grid(30, vec2(1920.0, 1080.0)), (4, 8));
+  assert_eq!(optimal_grid(30, vec2(1080.0, 1920.0)), (8, 4));
+  assert_eq!(optimal_grid(16, vec2(1.0, 1.0)), (4, 4));
+}
@@ -0,0 +1,387 @@
+//! Tests for the `noise` module.
+//!
+//! The `*_matches_port` tests keep a copy of the noise functions as the ports had them before
+//! switching to the shared ones, and check that the results are bit for bit the same.
+
+// Exact results are part of what is being checked.
+#![allow(clippy::float_cmp)]
+
+use shadertoys_shaders::{noise::*, shader_prelude::*};
+use spirv_std::glam::{UVec2, UVec3, UVec4};
+
+/// Points on a grid covering a few lattice cells on both sides of the origin, including the
+/// lattice points themselves.
+fn grid2() -> impl Iterator<Item = Vec2> {
+  (-12..=12).flat_map(|y| (-12..=12).map(move |x| vec2(x as f32, y as f32) * 0.25 + 0.01))
+}
+
+fn grid3() -> impl Iterator<Item = Vec3> {
+  (-6..=6).flat_map(|z| grid2().step_by(3).map(move |p| p.extend(z as f32 * 0.37)))
+}
+
+#[test]
+fn integer_hashes_reference_values() {
+  assert_eq!(pcg(0), 0x07bb_2fe2);
+  assert_eq!(pcg(1), 0xa8be_ea3c);
+  assert_eq!(pcg(42), 0x48f4_32ff);
+  assert_eq!(pcg(u32::MAX), 0xe62a_4902);
+  assert_eq!(
+    pcg3d(UVec3::new(0, 0, 0)),
+    UVec3::new(0x9baf_d7c6, 0xa8e8_8a6b, 0x3f15_482c)
+  );
+  assert_eq!(
+    pcg3d(UVec3::new(1, 2, 3)),
+    UVec3::new(0xfa9f_79a6, 0x48f2_f44c, 0x596f_5ab1)
+  );
+  assert_eq!(xxhash32(0), 0x3456_0f83);
+  assert_eq!(xxhash32(1), 0x9485_c89b);
+  assert_eq!(xxhash32(42), 0x79f5_d4d6);
+  assert_eq!(xxhash32_3d(UVec3::new(1, 2, 3)), 0x0e7e_e77e);
+}
+
+#[test]
+fn integer_hashes_depend_on_every_component() {
+  for i in 1..64 {
+    assert_ne!(pcg2d(UVec2::new(i, 0)), pcg2d(UVec2::new(0, i)));
+    assert_ne!(pcg4d(UVec4::new(0, 0, 0, i)), pcg4d(UVec4::new(0, 0, i, 0)));
+    assert_ne!(xxhash32_2d(UVec2::new(i, 0)), xxhash32_2d(UVec2::new(0, i)));
+    assert_ne!(
+      xxhash32_4d(UVec4::new(i, 1, 2, 3)),
+      xxhash32_4d(UVec4::new(i, 1, 3, 2))
+    );
+  }
+}
+
+#[test]
+fn lattice_hashes_are_uniform() {
+  let n = 4096;
+  let mean = (0..n).map(|i| (i as f32).hash()).sum::<f32>() / n as f32;
+  assert!((mean - 0.5).abs() < 0.02, "mean {mean}");
+
+  let mut buckets = [0; 8];
+  for y in -32..32 {
+    for x in -32..32 {
+      let h = vec2(x as f32, y as f32).hash_vec();
+      assert!(h.cmpge(Vec2::ZERO).all() && h.cmplt(Vec2::ONE).all());
+      let bucket = (0..8).rfind(|&b| h.x >= b as f32 / 8.0).unwrap();
+      buckets[bucket] += 1;
+    }
+  }
+  for count in buckets {
+    assert!((count as f32 - 512.0).abs() < 80.0, "buckets {buckets:?}");
+  }
+
+  assert_eq!(unit_float(0), 0.0);
+  assert!(unit_float(u32::MAX) < 1.0);
+  let g = vec4(1.0, -2.0, 3.0, -4.0).gradient();
+  assert!(g.cmpge(Vec4::NEG_ONE).all() && g.cmplt(Vec4::ONE).all());
+}
+
+#[test]
+fn value_noise_interpolates_the_hash() {
+  for i in -4..4 {
+    let x = i as f32;
+    assert_eq!(value_noise1(x, f32::hash), x.hash());
+  }
+  for p in grid2().map(Vec2::floor) {
+    assert_eq!(value_noise2(p, Vec2::hash), p.hash());
+  }
+  for p in grid3().map(Vec3::floor) {
+    assert_eq!(value_noise3(p, Vec3::hash), p.hash());
+  }
+  for p in grid3() {
+    let v = value_noise3(p, Vec3::hash);
+    assert!((0.0..=1.0).contains(&v), "{v} at {p}");
+    let v = value_noise4(p.extend(p.x * 0.7), Vec4::hash);
+    assert!((0.0..=1.0).contains(&v), "{v} at {p}");
+  }
+}
+
+#[test]
+fn gradient_noise_is_zero_on_the_lattice() {
+  for p in grid2().map(Vec2::floor) {
+    assert_eq!(gradient_noise1(p.x, f32::gradient), 0.0);
+    assert_eq!(gradient_noise2(p, Vec2::gradient), 0.0);
+    assert_eq!(gradient_noise3(p.extend(-2.0), Vec3::gradient), 0.0);
+    assert_eq!(
+      gradient_noise4(p.extend(1.0).extend(3.0), Vec4::gradient),
+      0.0
+    );
+  }
+  for p in grid3() {
+    for v in [
+      gradient_noise2(p.xy(), Vec2::gradient),
+      gradient_noise3(p, Vec3::gradient),
+      gradient_noise4(p.extend(p.z * 1.3), Vec4::gradient),
+    ] {
+      assert!((-1.0..=1.0).contains(&v), "{v} at {p}");
+    }
+  }
+}
+
+#[test]
+fn noise_is_continuous() {
+  let e = 1e-3;
+  for p in grid3() {
+    let dx = vec3(e, 0.0, 0.0);
+    for (a, b) in [
+      (
+        value_noise3(p, Vec3::hash),
+        value_noise3(p + dx, Vec3::hash),
+      ),
+      (
+        gradient_noise3(p, Vec3::gradient),
+        gradient_noise3(p + dx, Vec3::gradient),
+      ),
+      (
+        simplex_noise2(p.xy(), Vec2::gradient),
+        simplex_noise2(p.xy() + dx.xy(), Vec2::gradient),
+      ),
+      (
+        simplex_noise3(p, Vec3::gradient),
+        simplex_noise3(p + dx, Vec3::gradient),
+      ),
+      (
+        worley_noise3(p, Vec3::hash_vec).x,
+        worley_noise3(p + dx, Vec3::hash_vec).x,
+      ),
+    ] {
+      assert!((a - b).abs() < 0.05, "{a} vs {b} at {p}");
+    }
+  }
+}
+
+#[test]
+fn simplex_noise_range() {
+  let (mut lo, mut hi) = (0.0f32, 0.0f32);
+  for p in grid3() {
+    for v in [
+      simplex_noise2(p.xy() * 3.1, Vec2::gradient),
+      simplex_noise3(p * 2.3, Vec3::gradient),
+    ] {
+      // Slightly more than 1 is possible with the corners' gradients pointing the same way.
+      assert!(v.abs() < 1.1, "{v} at {p}");
+      lo = lo.min(v);
+      hi = hi.max(v);
+    }
+  }
+  // Not squashed towards zero either.
+  assert!(lo < -0.3 && hi > 0.3, "range [{lo}, {hi}]");
+}
+
+#[test]
+fn worley_noise_distances() {
+  for p in grid2() {
+    let d = worley_noise2(p, Vec2::hash_vec);
+    assert!(d.x <= d.y, "{d} at {p}");
+    // The feature point of the own cell is at most a cell diagonal away.
+    assert!(d.x <= core::f32::consts::SQRT_2, "{d} at {p}");
+    // Feature points are at distance zero from themselves.
+    let feature = p.floor() + p.floor().hash_vec();
+    assert!(worley_noise2(feature, Vec2::hash_vec).x < 1e-5);
+  }
+  for p in grid3() {
+    let d = worley_noise3(p, Vec3::hash_vec);
+    assert!(d.x <= d.y && d.x <= SQRT_3, "{d} at {p}");
+  }
+}
+
+#[test]
+fn fbm_sums_octaves() {
+  // With constant noise the result is the sum of the amplitudes.
+  let total = fbm(Vec2::ZERO, 4, 0.5, 0.5, |_| 1.0, |p| p * 2.0);
+  assert_eq!(total, 0.5 + 0.25 + 0.125 + 0.0625);
+  assert_eq!(fbm(Vec2::ZERO, 0, 0.5, 0.5, |_| 1.0, |p| p), 0.0);
+  for p in grid2() {
+    let v = fbm(
+      p,
+      5,
+      0.5,
+      0.5,
+      |p| gradient_noise2(p, Vec2::gradient),
+      |p| p * 2.01,
+    );
+    assert!(v.abs() <= 1.0, "{v} at {p}");
+  }
+}
+
+#[test]
+fn domain_warp_without_strength_is_identity() {
+  let f = |p: Vec2| value_noise2(p, Vec2::hash);
+  for p in grid2() {
+    assert_eq!(domain_warp2(p, 0.0, f), f(p));
+    assert!((0.0..=1.0).contains(&domain_warp2(p, 4.0, f)));
+  }
+  let f = |p: Vec3| gradient_noise3(p, Vec3::gradient);
+  for p in grid3() {
+    assert_eq!(domain_warp3(p, 0.0, f), f(p));
+  }
+}
+
+#[test]
+fn sin_hash_matches_port() {
+  for p in grid2() {
+    assert_eq!(
+      sin_hash(p.x, 43758.5453),
+      (p.x.sin() * 43758.5453).fract_gl()
+    );
+    assert_eq!(
+      sin_hash_dot(p, vec2(12.9898, 78.2333), 43758.5453123),
+      ((p.dot(vec2(12.9898, 78.2333))).sin() * 43758.5453123).fract_gl()
+    );
+  }
+}
+
+/// `seascape`
+#[test]
+fn value_noise_matches_port() {
+  fn hash(p: Vec2) -> f32 {
+    let h: f32 = p.dot(vec2(127.1, 311.7));
+    (h.sin() * 43758.5453123).fract_gl()
+  }
+  fn noise(p: Vec2) -> f32 {
+    let i: Vec2 = p.floor();
+    let f: Vec2 = p.fract_gl();
+    let u: Vec2 = f * f * (Vec2::splat(3.0) - 2.0 * f);
+    -1.0
+      + 2.0
+        * mix(
+          mix(hash(i + vec2(0.0, 0.0)), hash(i + vec2(1.0, 0.0)), u.x),
+          mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), u.x),
+          u.y,
+        )
+  }
+  for p in grid2() {
+    assert_eq!(-1.0 + 2.0 * value_noise2(p * 3.7, hash), noise(p * 3.7));
+  }
+}
+
+/// `on_off_spikes`
+#[test]
+fn value_noise1_and_fbm_match_port() {
+  fn hash(n: f32) -> f32 {
+    (n.sin() * 1e4).fract_gl()
+  }
+  fn noise(x: f32) -> f32 {
+    let i: f32 = x.floor();
+    let f: f32 = x.fract_gl();
+    let u: f32 = f * f * (3.0 - 2.0 * f);
+    mix(hash(i), hash(i + 1.0), u)
+  }
+  fn noise_vec2(x: Vec2) -> f32 {
+    value_noise2(x, |p| hash(p.x + p.y * 57.0))
+  }
+  fn noise2(mut x: Vec2) -> f32 {
+    let mut v: f32 = 0.0;
+    let mut a: f32 = 0.5;
+    let shift: Vec2 = Vec2::splat(100.0);
+    let rot: Mat2 =
+      Mat2::from_cols_array(&[0.5_f32.cos(), 0.5_f32.sin(), -0.5_f32.sin(), 0.50_f32.cos()]);
+    for _ in 0..4 {
+      v += a * noise_vec2(x);
+      x = rot * x * 2.0 + shift;
+      a *= 0.5;
+    }
+    v
+  }
+  let shift: Vec2 = Vec2::splat(100.0);
+  let rot: Mat2 =
+    Mat2::from_cols_array(&[0.5_f32.cos(), 0.5_f32.sin(), -0.5_f32.sin(), 0.50_f32.cos()]);
+  for p in grid2() {
+    assert_eq!(value_noise1(p.x * 5.3, hash), noise(p.x * 5.3));
+    assert_eq!(
+      fbm(p, 4, 0.5, 0.5, noise_vec2, |x| rot * x * 2.0 + shift),
+      noise2(p)
+    );
+  }
+}
+
+/// `clouds`
+#[test]
+fn simplex_noise_and_fbm_match_port() {
+  const M: Mat2 = mat2(vec2(1.6, 1.2), vec2(-1.2, 1.6));
+  fn hash(mut p: Vec2) -> Vec2 {
+    p = vec2(p.dot(vec2(127.1, 311.7)), p.dot(vec2(269.5, 183.3)));
+    Vec2::splat(-1.0) + 2.0 * (p.sin() * 43758.5453123).fract_gl()
+  }
+  fn noise(p: Vec2) -> f32 {
+    const K1: f32 = 0.366025404;
+    const K2: f32 = 0.211324865;
+    let i: Vec2 = (p + Vec2::splat((p.x + p.y) * K1)).floor();
+    let a: Vec2 = p - i + Vec2::splat((i.x + i.y) * K2);
+    let o: Vec2 = if a.x > a.y {
+      vec2(1.0, 0.0)
+    } else {
+      vec2(0.0, 1.0)
+    };
+    let b: Vec2 = a - o + Vec2::splat(K2);
+    let c: Vec2 = a - Vec2::splat(1.0 - 2.0 * K2);
+    let h: Vec3 = (Vec3::splat(0.5) - vec3(a.dot(a), b.dot(b), c.dot(c))).max(Vec3::ZERO);
+    let n: Vec3 = (h * h * h * h)
+      * vec3(
+        a.dot(hash(i + Vec2::ZERO)),
+        b.dot(hash(i + o)),
+        c.dot(hash(i + Vec2::splat(1.0))),
+      );
+    n.dot(Vec3::splat(70.0))
+  }
+  fn port_fbm(mut n: Vec2) -> f32 {
+    let mut total: f32 = 0.0;
+    let mut amplitude: f32 = 0.1;
+    for _ in 0..7 {
+      total += noise(n) * amplitude;
+      n = M.transpose() * n;
+      amplitude *= 0.4;
+    }
+    total
+  }
+  for p in grid2() {
+    assert_eq!(simplex_noise2(p * 2.9, hash), noise(p * 2.9));
+    assert_eq!(
+      fbm(
+        p,
+        7,
+        0.1,
+        0.4,
+        |p| simplex_noise2(p, hash),
+        |n| M.transpose() * n
+      ),
+      port_fbm(p)
+    );
+  }
+}
+
+/// `filtering_procedurals`
+#[test]
+fn gradient_noise_matches_port() {
+  fn hash3(mut p: Vec3) -> Vec3 {
+    p = vec3(
+      p.dot(vec3(127.1, 311.7, 74.7)),
+      p.dot(vec3(269.5, 183.3, 246.1)),
+      p.dot(vec3(113.5, 271.9, 124.6)),
+    );
+    -Vec3::ONE + 2.0 * (p.sin() * 13.5453123).fract_gl()
+  }
+  fn noise(p: Vec3) -> f32 {
+    let i: Vec3 = p.floor();
+    let f: Vec3 = p.fract_gl();
+    let u: Vec3 = f * f * (Vec3::splat(3.0) - 2.0 * f);
+    let g = |o: Vec3| hash3(i + o).dot(f - o);
+    mix(
+      mix(
+        mix(g(vec3(0.0, 0.0, 0.0)), g(vec3(1.0, 0.0, 0.0)), u.x),
+        mix(g(vec3(0.0, 1.0, 0.0)), g(vec3(1.0, 1.0, 0.0)), u.x),
+        u.y,
+      ),
+      mix(
+        mix(g(vec3(0.0, 0.0, 1.0)), g(vec3(1.0, 0.0, 1.0)), u.x),
+        mix(g(vec3(0.0, 1.0, 1.0)), g(vec3(1.0, 1.0, 1.0)), u.x),
+        u.y,
+      ),
+      u.z,
+    )
+  }
+  for p in grid3() {
+    assert_eq!(gradient_noise3(p * 4.1, hash3), noise(p * 4.1));
+  }
+}
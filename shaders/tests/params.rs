@@ -0,0 +1,76 @@
+//! Tests for the `params` module.
+
+use shadertoys_shaders::{
+  params::{overlay, ParamKind, MAX_PARAMS},
+  shader_prelude::*,
+  shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderParams,
+};
+
+const STEPS: Param = Param::int(0, "Steps", 8, 1, 32);
+const AA: Param = Param::bool(1, "Antialiasing", true);
+const TINT: Param = Param::color(2, "Tint", vec3(0.2, 0.4, 0.6));
+
+fn shader_params(values: &[Vec4]) -> ShaderParams {
+  let mut params = ShaderParams {
+    values: [Vec4::ZERO; MAX_PARAMS],
+    sliders: [Vec4::ZERO; MAX_PARAMS / 4],
+    count: values.len() as u32,
+    selected: 0,
+    overlay: 0,
+    padding: 0,
+  };
+  params.values[..values.len()].copy_from_slice(values);
+  params
+}
+
+#[test]
+fn unbound_values_are_the_defaults() {
+  for values in [ParamValues::DEFAULTS, ParamValues::new(&shader_params(&[]))] {
+    assert_eq!(values.int(&STEPS), 8);
+    assert!(values.bool(&AA));
+    assert_eq!(values.color(&TINT), vec3(0.2, 0.4, 0.6));
+  }
+}
+
+#[test]
+fn bound_values_come_from_their_slot() {
+  let values = ParamValues::new(&shader_params(&[
+    vec4(3.0, 0.0, 0.0, 0.0),
+    Vec4::ZERO,
+    vec4(1.0, 0.5, 0.0, 0.0),
+  ]));
+  assert_eq!(values.int(&STEPS), 3);
+  assert!(!values.bool(&AA));
+  assert_eq!(values.color(&TINT), vec3(1.0, 0.5, 0.0));
+}
+
+#[test]
+fn shader_params_are_valid() {
+  for definition in SHADER_DEFINITIONS {
+    assert!(definition.params.len() <= MAX_PARAMS, "{}", definition.name);
+    for (slot, param) in definition.params.iter().enumerate() {
+      assert_eq!(param.slot, slot, "{}: {}", definition.name, param.name);
+      let in_range = match param.kind {
+        ParamKind::Float { default, min, max } => min < max && (min..=max).contains(&default),
+        ParamKind::Int { default, min, max } => min < max && (min..=max).contains(&default),
+        ParamKind::Bool { .. } | ParamKind::Color { .. } => true,
+      };
+      assert!(in_range, "{}: {}", definition.name, param.name);
+    }
+  }
+}
+
+#[test]
+fn overlay_is_only_drawn_when_shown() {
+  let mut params = shader_params(&[Vec4::ZERO, Vec4::ZERO]);
+  params.sliders[0] = vec4(1.0, 0.0, 0.0, 0.0);
+  // Inside the first slider.
+  let p = vec2(20.0, 22.0);
+  assert_eq!(overlay(&params, p), Vec4::ZERO);
+  params.overlay = 1;
+  // The selected param, filled up to the end.
+  assert_eq!(overlay(&params, p), vec4(1.0, 0.6, 0.1, 1.0));
+  // Far from the sliders.
+  assert_eq!(overlay(&params, vec2(500.0, 500.0)), Vec4::ZERO);
+}
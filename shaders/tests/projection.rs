@@ -0,0 +1,69 @@
+//! Tests for the `projection` module.
+
+use bytemuck::Zeroable;
+use shadertoys_shaders::{
+  projection::Projection,
+  shader_prelude::*,
+  shaders::{HAS_RAY_FN, SHADER_DEFINITIONS},
+};
+
+#[track_caller]
+fn assert_close(actual: Vec3, expected: Vec3) {
+  assert!(
+    (actual - expected).length() < 1e-5,
+    "got {actual}, expected {expected}"
+  );
+}
+
+#[test]
+fn cube_faces_look_along_their_axis() {
+  let axes = [
+    Vec3::X,
+    Vec3::NEG_X,
+    Vec3::Y,
+    Vec3::NEG_Y,
+    Vec3::Z,
+    Vec3::NEG_Z,
+  ];
+  for (face, axis) in Projection::CUBE_FACES.into_iter().zip(axes) {
+    assert_close(face.direction(vec2(0.5, 0.5)), axis);
+  }
+  // The top of the side faces is up.
+  assert!(Projection::PositiveZ.direction(vec2(0.5, 0.0)).y > 0.0);
+  assert!(Projection::NegativeX.direction(vec2(0.5, 0.0)).y > 0.0);
+}
+
+#[test]
+fn cube_faces_meet_at_their_edges() {
+  for v in [0.0, 0.3, 1.0] {
+    let right = Projection::PositiveZ.direction(vec2(1.0, v));
+    assert_close(Projection::PositiveX.direction(vec2(0.0, v)), right);
+    let left = Projection::PositiveZ.direction(vec2(0.0, v));
+    assert_close(Projection::NegativeX.direction(vec2(1.0, v)), left);
+  }
+  let top = Projection::PositiveZ.direction(vec2(0.5, 0.0));
+  assert_close(Projection::PositiveY.direction(vec2(0.5, 1.0)), top);
+}
+
+#[test]
+fn equirectangular_covers_the_sphere() {
+  let equirectangular = Projection::Equirectangular;
+  assert_close(equirectangular.direction(vec2(0.5, 0.5)), Vec3::Z);
+  assert_close(equirectangular.direction(vec2(0.75, 0.5)), Vec3::X);
+  assert_close(equirectangular.direction(vec2(0.0, 0.5)), Vec3::NEG_Z);
+  assert_close(equirectangular.direction(vec2(0.3, 0.0)), Vec3::Y);
+  assert_close(equirectangular.direction(vec2(0.3, 1.0)), Vec3::NEG_Y);
+}
+
+#[test]
+fn projections_round_trip_through_the_constants() {
+  let mut constants = ShaderConstants::zeroed();
+  let all = [Projection::Screen, Projection::Equirectangular];
+  for projection in all.into_iter().chain(Projection::CUBE_FACES) {
+    constants.projection = projection as u32;
+    assert_eq!(Projection::from_constants(&constants), projection);
+  }
+  constants.projection = 42;
+  assert_eq!(Projection::from_constants(&constants), Projection::Screen);
+  assert_eq!(HAS_RAY_FN.len(), SHADER_DEFINITIONS.len());
+}
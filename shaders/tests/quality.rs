@@ -0,0 +1,36 @@
+//! Tests for the `quality` module.
+
+use bytemuck::Zeroable;
+use shadertoys_shaders::shader_prelude::*;
+
+#[test]
+fn high_matches_the_original_shaders() {
+  assert_eq!(Quality::High.samples_per_axis(), 2);
+  assert_eq!(Quality::High.steps(100), 100);
+  assert_eq!(Quality::High.octaves(4), 4);
+}
+
+#[test]
+fn work_grows_with_the_level() {
+  for pair in Quality::ALL.windows(2) {
+    let [lower, higher] = [pair[0], pair[1]];
+    assert!(lower < higher);
+    assert!(lower.samples_per_axis() <= higher.samples_per_axis());
+    assert!(lower.steps(8) < higher.steps(8));
+    assert!(lower.octaves(4) <= higher.octaves(4));
+  }
+  // Never down to nothing.
+  assert_eq!(Quality::Low.steps(1), 1);
+  assert_eq!(Quality::Low.octaves(1), 1);
+}
+
+#[test]
+fn levels_round_trip_through_the_constants() {
+  let mut constants = ShaderConstants::zeroed();
+  for level in Quality::ALL {
+    constants.quality = level as u32;
+    assert_eq!(Quality::from_constants(&constants), level);
+  }
+  constants.quality = 17;
+  assert_eq!(Quality::from_constants(&constants), Quality::Ultra);
+}
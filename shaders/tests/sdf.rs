@@ -0,0 +1,114 @@
+//! Tests for the `sdf` module.
+
+use shadertoys_shaders::{sdf::*, shader_prelude::*};
+
+#[track_caller]
+fn assert_close(actual: f32, expected: f32) {
+  assert!(
+    (actual - expected).abs() < 1e-4,
+    "got {actual}, expected {expected}"
+  );
+}
+
+#[test]
+fn primitives_are_exact_along_the_axes() {
+  let p = vec3(2.0, 0.0, 0.0);
+  assert_close(sd_sphere(p, 0.5), 1.5);
+  assert_close(sd_box(p, vec3(0.5, 1.0, 1.0)), 1.5);
+  assert_close(sd_box(Vec3::ZERO, vec3(0.5, 1.0, 1.0)), -0.5);
+  assert_close(sd_torus(p, vec2(1.0, 0.25)), 0.75);
+  assert_close(
+    sd_capsule(p, vec3(0.0, -1.0, 0.0), vec3(0.0, 1.0, 0.0), 0.5),
+    1.5,
+  );
+  assert_close(sd_cylinder_vertical(p, vec2(0.5, 1.0)), 1.5);
+  assert_close(
+    sd_cylinder(p, vec3(0.0, -1.0, 0.0), vec3(0.0, 1.0, 0.0), 0.5),
+    1.5,
+  );
+  assert_close(sd_octahedron(p, 1.0), 1.0);
+  assert_close(sd_plane(vec3(0.0, -0.5, 3.0)), -0.5);
+  assert_close(sd_circle(vec2(0.0, 3.0), 1.0), 2.0);
+  assert_close(sd_box2(vec2(3.0, 4.0) + 1.0, Vec2::ONE), 5.0);
+  assert_close(sd_segment(vec2(0.5, 1.0), Vec2::ZERO, vec2(1.0, 0.0)), 1.0);
+}
+
+#[test]
+fn smooth_operators_approach_the_sharp_ones() {
+  for (a, b) in [(0.3, 1.2), (-0.4, 0.1), (2.0, -1.0)] {
+    assert_close(op_smooth_union(a, b, 1e-4), op_union(a, b));
+    assert_close(op_smooth_intersection(a, b, 1e-4), op_intersection(a, b));
+    assert_close(op_smooth_subtraction(a, b, 1e-4), op_subtraction(a, b));
+    // The smooth versions only ever add material (union) or remove it (intersection).
+    assert!(op_smooth_union(a, b, 0.5) <= op_union(a, b));
+    assert!(op_smooth_intersection(a, b, 0.5) >= op_intersection(a, b));
+  }
+  assert_eq!(op_u(vec2(1.0, 3.0), vec2(0.5, 7.0)), vec2(0.5, 7.0));
+}
+
+#[test]
+fn repetition_maps_into_a_cell() {
+  let period = Vec3::splat(2.0);
+  let p = vec3(5.2, -3.1, 0.4);
+  let q = op_rep(p, period);
+  assert!(q.abs().cmple(period * 0.5).all(), "{q}");
+  assert_close(sd_sphere(q, 0.5), sd_sphere(p - vec3(6.0, -4.0, 0.0), 0.5));
+
+  let (q, id) = op_rep_cell(p, period);
+  assert_eq!(id, vec3(2.0, -2.0, 0.0));
+  assert!((q - (p - id * period - period * 0.5)).length() < 1e-5);
+
+  // Far away cells are clamped to the last one.
+  let q = op_rep_limited(vec3(21.0, 0.0, 0.0), 2.0, Vec3::splat(2.0));
+  assert_close(q.x, 17.0);
+
+  let q = op_rep_polar(vec2(0.0, 1.0), 4.0);
+  assert!((q - vec2(1.0, 0.0)).length() < 1e-5, "{q}");
+}
+
+#[test]
+fn marcher_finds_the_sphere() {
+  let scene = |p: Vec3| sd_sphere(p - vec3(0.0, 0.0, 5.0), 1.0);
+  let marcher = Marcher::default();
+  let t = marcher
+    .march(&scene, Vec3::ZERO, Vec3::Z, 0.0, 20.0)
+    .unwrap();
+  assert_close(t, 4.0);
+  assert_eq!(marcher.march(&scene, Vec3::ZERO, Vec3::X, 0.0, 20.0), None);
+  // Not within the range.
+  assert_eq!(marcher.march(&scene, Vec3::ZERO, Vec3::Z, 0.0, 3.0), None);
+
+  let n = calc_normal(&scene, vec3(0.0, 0.0, 4.0), 0.0005);
+  assert!((n - vec3(0.0, 0.0, -1.0)).length() < 1e-3, "{n}");
+}
+
+#[test]
+fn shadows_and_occlusion() {
+  let scene = |p: Vec3| op_union(sd_plane(p), sd_sphere(p - vec3(0.0, 0.6, 0.0), 0.5));
+  let up = Vec3::Y;
+  // Right below the sphere the light from above is blocked, far away it isn't.
+  assert!(soft_shadow(&scene, vec3(0.0, 0.01, 0.0), up, 0.02, 5.0, 8.0) < 0.01);
+  assert_close(
+    soft_shadow(&scene, vec3(5.0, 0.01, 0.0), up, 0.02, 5.0, 8.0),
+    1.0,
+  );
+  assert!(soft_shadow_improved(&scene, vec3(0.0, 0.01, 0.0), up, 0.02, 5.0, 8.0) < 0.01);
+  assert_close(
+    soft_shadow_improved(&scene, vec3(5.0, 0.01, 0.0), up, 0.02, 5.0, 8.0),
+    1.0,
+  );
+
+  assert_close(ambient_occlusion(&scene, vec3(5.0, 0.0, 0.0), up), 1.0);
+  assert!(ambient_occlusion(&scene, Vec3::ZERO, up) < 0.5);
+}
+
+#[test]
+fn camera_looks_at_the_target() {
+  let ro = vec3(3.0, 2.0, -4.0);
+  let ta = vec3(0.0, 0.5, 0.0);
+  let ca = set_camera(ro, ta, 0.0);
+  let forward = ca * Vec3::Z;
+  assert!((forward - (ta - ro).normalize()).length() < 1e-5);
+  // No roll keeps the horizon level.
+  assert_close((ca * Vec3::X).y, 0.0);
+}
@@ -0,0 +1,52 @@
+//! Tests for the `sounds` module.
+
+use shadertoys_shaders::{
+  shader_prelude::*,
+  shared_data::SoundConstants,
+  sounds::{sound, SOUND_DEFINITIONS},
+};
+
+fn constants(name: &str, first_sample: u32) -> SoundConstants {
+  SoundConstants {
+    sound_index: SOUND_DEFINITIONS
+      .iter()
+      .position(|definition| definition.name == name)
+      .unwrap() as u32,
+    first_sample,
+    sample_count: 0,
+    sample_rate: 44_100.0,
+  }
+}
+
+#[test]
+fn samples_are_at_their_time() {
+  let bell = constants("Sine Bell", 44_100);
+  // A quarter period of 440 Hz after one second.
+  let quarter_period = 44_100 / 440 / 4;
+  let sample = sound(&bell, quarter_period);
+  assert_eq!(sample, Vec2::splat(sample.x));
+  assert!((sample.x - (-3.0_f32).exp()).abs() < 0.01, "{sample}");
+}
+
+#[test]
+fn sounds_stay_in_range() {
+  for definition in SOUND_DEFINITIONS {
+    let constants = constants(definition.name, 0);
+    let peak = (0..44_100)
+      .step_by(7)
+      .map(|index| sound(&constants, index).abs().max_element())
+      .fold(0.0, f32::max);
+    assert!(peak > 0.1 && peak <= 1.0, "{}: {peak}", definition.name);
+  }
+}
+
+#[test]
+fn unknown_sounds_are_silent() {
+  let constants = SoundConstants {
+    sound_index: SOUND_DEFINITIONS.len() as u32,
+    first_sample: 100,
+    sample_count: 1,
+    sample_rate: 44_100.0,
+  };
+  assert_eq!(sound(&constants, 0), Vec2::ZERO);
+}
@@ -0,0 +1,88 @@
+//! Tests for the `stereo` module.
+
+use bytemuck::Zeroable;
+use shadertoys_shaders::{
+  channel::Unbound,
+  fs,
+  params::MAX_PARAMS,
+  shader_prelude::*,
+  shaders::{has_ray_fn, HAS_RAY_FN, SHADER_DEFINITIONS},
+  shared_data::ShaderParams,
+  stereo::{eye_ray, StereoMode},
+};
+
+const DEFAULT_PARAMS: ShaderParams = ShaderParams {
+  values: [Vec4::ZERO; MAX_PARAMS],
+  sliders: [Vec4::ZERO; MAX_PARAMS / 4],
+  count: 0,
+  selected: 0,
+  overlay: 0,
+  padding: 0,
+};
+
+fn shader_index(name: &str) -> u32 {
+  SHADER_DEFINITIONS
+    .iter()
+    .position(|definition| definition.name == name)
+    .unwrap() as u32
+}
+
+#[test]
+fn side_by_side_splits_the_frame() {
+  let resolution = vec2(200.0, 50.0);
+  let left = StereoMode::SideBySide.eye_views(vec2(50.0, 25.0), resolution);
+  assert_eq!((left.first_eye, left.last_eye), (0, 0));
+  assert_eq!(left.uv, Vec2::ZERO);
+  let right = StereoMode::SideBySide.eye_views(vec2(200.0, 50.0), resolution);
+  assert_eq!((right.first_eye, right.last_eye), (1, 1));
+  assert_eq!(right.uv, vec2(2.0, 1.0));
+
+  // The left eye is on top, `frag_coord` grows upwards.
+  let top = StereoMode::TopBottom.eye_views(vec2(100.0, 40.0), resolution);
+  assert_eq!((top.first_eye, top.last_eye), (0, 0));
+  let anaglyph = StereoMode::Anaglyph.eye_views(vec2(100.0, 25.0), resolution);
+  assert_eq!((anaglyph.first_eye, anaglyph.last_eye), (0, 1));
+  assert_eq!(anaglyph.uv, Vec2::ZERO);
+}
+
+#[test]
+fn eyes_are_apart_and_look_forward() {
+  let left = eye_ray(Vec2::ZERO, 0, 0.2);
+  let right = eye_ray(Vec2::ZERO, 1, 0.2);
+  assert_eq!(left.origin, vec3(-0.1, 0.0, 0.0));
+  assert_eq!(right.origin, vec3(0.1, 0.0, 0.0));
+  assert_eq!(left.direction, Vec3::Z);
+  assert!(!left.level);
+}
+
+#[test]
+fn eyes_see_the_same_without_separation() {
+  let constants = ShaderConstants {
+    width: 200,
+    height: 100,
+    time: 2.0,
+    shader_to_show: shader_index("Apollonian Fractal"),
+    quality: Quality::Low as u32,
+    stereo: StereoMode::SideBySide as u32,
+    ..ShaderConstants::zeroed()
+  };
+  let left = fs(&constants, &DEFAULT_PARAMS, vec2(40.5, 50.5), [Unbound; 4]);
+  let right = fs(&constants, &DEFAULT_PARAMS, vec2(140.5, 50.5), [Unbound; 4]);
+  assert_eq!(left, right);
+  assert_ne!(left.truncate(), Vec3::ZERO);
+
+  let anaglyph = ShaderConstants {
+    stereo: StereoMode::Anaglyph as u32,
+    ..constants
+  };
+  let [r, g, b, _] = fs(&anaglyph, &DEFAULT_PARAMS, vec2(90.5, 50.5), [Unbound; 4]).to_array();
+  assert!(r > 0.0 && g > 0.0 && b > 0.0);
+}
+
+#[test]
+fn has_ray_fn_matches_the_table() {
+  for (index, has) in HAS_RAY_FN.iter().enumerate() {
+    assert_eq!(has_ray_fn(index as u32), *has);
+  }
+  assert!(has_ray_fn(shader_index("Raymarching Primitives")));
+}
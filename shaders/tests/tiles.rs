@@ -0,0 +1,55 @@
+//! Tests for rendering a frame in tiles, see `ShaderConstants::tile_offset_x`.
+
+use bytemuck::Zeroable;
+use shadertoys_shaders::{
+  channel::Unbound, fs, params::MAX_PARAMS, shader_prelude::*, shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderParams,
+};
+
+const DEFAULT_PARAMS: ShaderParams = ShaderParams {
+  values: [Vec4::ZERO; MAX_PARAMS],
+  sliders: [Vec4::ZERO; MAX_PARAMS / 4],
+  count: 0,
+  selected: 0,
+  overlay: 0,
+  padding: 0,
+};
+
+fn frame(shader_to_show: usize, grid_mode: bool) -> ShaderConstants {
+  ShaderConstants {
+    width: 640,
+    height: 360,
+    time: 1.5,
+    grid_mode: u32::from(grid_mode),
+    shader_to_show: shader_to_show as u32,
+    quality: Quality::Low as u32,
+    ..ShaderConstants::zeroed()
+  }
+}
+
+/// The bits of the pixel at `frag_coord` of the whole frame, and of the same pixel of the tile at
+/// `offset`. Bits, as some shaders output NaNs in places.
+fn whole_and_tiled(constants: ShaderConstants, frag_coord: Vec2, offset: Vec2) -> [[u32; 4]; 2] {
+  let tile = ShaderConstants {
+    tile_offset_x: offset.x,
+    tile_offset_y: offset.y,
+    ..constants
+  };
+  [
+    fs(&constants, &DEFAULT_PARAMS, frag_coord, [Unbound; 4]),
+    fs(&tile, &DEFAULT_PARAMS, frag_coord - offset, [Unbound; 4]),
+  ]
+  .map(|color| color.to_array().map(f32::to_bits))
+}
+
+#[test]
+fn tiles_match_the_whole_frame() {
+  let frag_coord = vec2(300.5, 200.5);
+  let offset = vec2(256.0, 128.0);
+  for shader in 0..SHADER_DEFINITIONS.len().min(4) {
+    let [whole, tiled] = whole_and_tiled(frame(shader, false), frag_coord, offset);
+    assert_eq!(whole, tiled, "{}", SHADER_DEFINITIONS[shader].name);
+  }
+  let [whole, tiled] = whole_and_tiled(frame(0, true), frag_coord, offset);
+  assert_eq!(whole, tiled, "grid");
+}
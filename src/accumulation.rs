@@ -0,0 +1,230 @@
+//! Progressive accumulation: while time is paused every frame renders the shader once more with
+//! a different sub-pixel jitter and averages it into a float texture, which is then copied to
+//! the screen with `main_blit_fs`. Anything that changes the image starts over.
+//!
+//! The average is kept with blending, the new sample is weighted by `1 / (n + 1)`. The texture
+//! is `Rgba16Float`, which is blendable everywhere. Its precision limits the average to
+//! [`MAX_SAMPLES`], after that the image is left as it is.
+
+use crate::{create_render_pipeline, DebugMode};
+
+pub const MAX_SAMPLES: u32 = 256;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+pub struct Accumulation {
+  /// Renders one sample of the shader into the texture.
+  accumulate_pipeline: wgpu::RenderPipeline,
+  blit_pipeline: wgpu::RenderPipeline,
+  blit_bind_group_layout: wgpu::BindGroupLayout,
+  target: Option<Target>,
+  samples: u32,
+  /// Everything the image depends on when the samples were taken.
+  state: Vec<u8>,
+}
+
+struct Target {
+  size: (u32, u32),
+  view: wgpu::TextureView,
+  blit_bind_group: wgpu::BindGroup,
+}
+
+impl Accumulation {
+  /// `layout` and `fragment_entry_point` are those of the main render pipeline.
+  pub fn new(
+    device: &wgpu::Device,
+    debug: DebugMode,
+    shader_module: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    fragment_entry_point: &str,
+    swapchain_format: wgpu::TextureFormat,
+  ) -> Self {
+    let average = wgpu::BlendComponent {
+      src_factor: wgpu::BlendFactor::Constant,
+      dst_factor: wgpu::BlendFactor::OneMinusConstant,
+      operation: wgpu::BlendOperation::Add,
+    };
+    let accumulate_pipeline = create_render_pipeline(
+      device,
+      debug.label("accumulation render pipeline"),
+      layout,
+      shader_module,
+      fragment_entry_point,
+      FORMAT,
+      Some(wgpu::BlendState {
+        color: average,
+        alpha: average,
+      }),
+    );
+    let blit_bind_group_layout =
+      device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: debug.label("accumulation blit bind group layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+          binding: 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+          },
+          count: None,
+        }],
+      });
+    let blit_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: debug.label("accumulation blit pipeline layout"),
+      bind_group_layouts: &[&blit_bind_group_layout],
+      push_constant_ranges: &[],
+    });
+    let blit_pipeline = create_render_pipeline(
+      device,
+      debug.label("accumulation blit render pipeline"),
+      &blit_layout,
+      shader_module,
+      "main_blit_fs",
+      swapchain_format,
+      Some(wgpu::BlendState::REPLACE),
+    );
+    Self {
+      accumulate_pipeline,
+      blit_pipeline,
+      blit_bind_group_layout,
+      target: None,
+      samples: 0,
+      state: Vec::new(),
+    }
+  }
+
+  /// Drops the samples taken so far.
+  pub fn reset(&mut self) {
+    self.samples = 0;
+  }
+
+  /// Prepares the next sample of an image of `size` pixels that depends on `state`. Returns the
+  /// jitter of the sample, in pixels, or `None` if the image is done.
+  pub fn next_sample(
+    &mut self,
+    device: &wgpu::Device,
+    debug: DebugMode,
+    size: (u32, u32),
+    state: &[u8],
+  ) -> Option<[f32; 2]> {
+    if self
+      .target
+      .as_ref()
+      .is_none_or(|target| target.size != size)
+    {
+      self.target = Some(self.create_target(device, debug, size));
+      self.samples = 0;
+    }
+    if self.state != state {
+      self.state.clear();
+      self.state.extend_from_slice(state);
+      self.samples = 0;
+    }
+    (self.samples < MAX_SAMPLES).then(|| jitter(self.samples))
+  }
+
+  fn create_target(&self, device: &wgpu::Device, debug: DebugMode, size: (u32, u32)) -> Target {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: debug.label("accumulation texture"),
+      size: wgpu::Extent3d {
+        width: size.0,
+        height: size.1,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: FORMAT,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let blit_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: debug.label("accumulation blit bind group"),
+      layout: &self.blit_bind_group_layout,
+      entries: &[wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::TextureView(&view),
+      }],
+    });
+    Target {
+      size,
+      view,
+      blit_bind_group,
+    }
+  }
+
+  /// Begins the pass rendering the sample returned by [`Self::next_sample`], with the pipeline
+  /// set. The caller binds the resources of the shader and draws.
+  pub fn begin_sample<'encoder>(
+    &self,
+    encoder: &'encoder mut wgpu::CommandEncoder,
+    debug: DebugMode,
+  ) -> wgpu::RenderPass<'encoder> {
+    let target = self
+      .target
+      .as_ref()
+      .expect("`next_sample` creates the target");
+    let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: debug.label("accumulation render pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: &target.view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Load,
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+    rpass.set_pipeline(&self.accumulate_pipeline);
+    // The first sample replaces whatever was left in the texture.
+    let weight = 1.0 / f64::from(self.samples + 1);
+    rpass.set_blend_constant(wgpu::Color {
+      r: weight,
+      g: weight,
+      b: weight,
+      a: weight,
+    });
+    rpass
+  }
+
+  /// Counts the sample rendered after [`Self::begin_sample`].
+  pub fn end_sample(&mut self) {
+    self.samples += 1;
+  }
+
+  /// Draws the average into `rpass`. This changes the bind groups.
+  pub fn blit(&self, rpass: &mut wgpu::RenderPass<'_>) {
+    let Some(target) = &self.target else {
+      return;
+    };
+    rpass.set_pipeline(&self.blit_pipeline);
+    rpass.set_bind_group(0, &target.blit_bind_group, &[]);
+    rpass.draw(0..3, 0..1);
+  }
+}
+
+/// The jitter of sample `sample` of a pixel, in pixels. The first sample is in the center of the
+/// pixel, like without accumulation.
+pub fn jitter(sample: u32) -> [f32; 2] {
+  if sample == 0 {
+    [0.0, 0.0]
+  } else {
+    [halton(sample, 2) - 0.5, halton(sample, 3) - 0.5]
+  }
+}
+
+/// Element `index` of the Halton sequence in `base`, in `[0, 1)`.
+fn halton(mut index: u32, base: u32) -> f32 {
+  let mut result = 0.0;
+  let mut fraction = 1.0;
+  while index > 0 {
+    fraction /= base as f32;
+    result += fraction * (index % base) as f32;
+    index /= base;
+  }
+  result
+}
@@ -0,0 +1,206 @@
+//! The audio channel: a WAV file analysed into Shadertoy's 512x2 audio texture at the time of the
+//! frame, see `shadertoys_shaders::audio`.
+//!
+//! The analysis follows the Web Audio `AnalyserNode` behind Shadertoy's: the spectrum is the FFT
+//! of the last 1024 samples with a Blackman window, from -100 dB to -30 dB, and the waveform the
+//! last 512 samples. Unlike the node it does not smooth the spectrum over the previous frames, so
+//! a frame only depends on its time and renders the same in the viewer, in posters and on the
+//! CPU. The music plays once, silence follows.
+
+use crate::wav;
+use core::error::Error;
+use shadertoys_shaders::audio::AUDIO_TEXTURE_WIDTH;
+use std::path::Path;
+
+const WIDTH: usize = AUDIO_TEXTURE_WIDTH as usize;
+const FFT_SIZE: usize = 2 * WIDTH;
+const MIN_DECIBELS: f32 = -100.0;
+const MAX_DECIBELS: f32 = -30.0;
+
+pub struct AudioAnalysis {
+  sample_rate: f32,
+  samples: Vec<f32>,
+  /// Blackman window over [`FFT_SIZE`] samples.
+  window: Vec<f32>,
+}
+
+impl AudioAnalysis {
+  pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+    let bytes =
+      std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let wav = wav::read(&bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Self::new(wav.sample_rate as f32, wav.mono))
+  }
+
+  fn new(sample_rate: f32, samples: Vec<f32>) -> Self {
+    let window = (0..FFT_SIZE)
+      .map(|i| {
+        let x = core::f32::consts::TAU * i as f32 / FFT_SIZE as f32;
+        0.08f32.mul_add((2.0 * x).cos(), 0.5f32.mul_add(-x.cos(), 0.42))
+      })
+      .collect();
+    Self {
+      sample_rate,
+      samples,
+      window,
+    }
+  }
+
+  /// The `count` samples up to `time`, zero outside of the file.
+  #[expect(clippy::cast_possible_wrap, reason = "`count` is at most `FFT_SIZE`")]
+  fn samples_before(&self, time: f32, count: usize) -> impl Iterator<Item = f32> + '_ {
+    let end = (time * self.sample_rate).floor() as i64;
+    (end - count as i64..end).map(|index| {
+      usize::try_from(index)
+        .ok()
+        .and_then(|index| self.samples.get(index))
+        .copied()
+        .unwrap_or_default()
+    })
+  }
+
+  pub fn texture(&self, time: f32) -> AudioTexture {
+    let mut re: Vec<f32> = self
+      .samples_before(time, FFT_SIZE)
+      .zip(&self.window)
+      .map(|(sample, window)| sample * window)
+      .collect();
+    let mut im = vec![0.0; FFT_SIZE];
+    fft(&mut re, &mut im);
+    let mut texels = [[0; WIDTH]; 2];
+    for (bin, texel) in texels[0].iter_mut().enumerate() {
+      let magnitude = re[bin].hypot(im[bin]) / FFT_SIZE as f32;
+      let decibels = 20.0 * magnitude.max(f32::MIN_POSITIVE).log10();
+      *texel = to_unorm8((decibels - MIN_DECIBELS) / (MAX_DECIBELS - MIN_DECIBELS));
+    }
+    for (sample, texel) in self.samples_before(time, WIDTH).zip(&mut texels[1]) {
+      *texel = to_unorm8(0.5f32.mul_add(sample, 0.5));
+    }
+    AudioTexture {
+      rgba: texels
+        .as_flattened()
+        .iter()
+        .flat_map(|&value| [value, value, value, 255])
+        .collect(),
+    }
+  }
+}
+
+/// In-place radix-2 FFT of `re + i im`, whose length is a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+  let n = re.len();
+  let bits = n.trailing_zeros();
+  for i in 0..n {
+    let j = i.reverse_bits() >> (usize::BITS - bits);
+    if i < j {
+      re.swap(i, j);
+      im.swap(i, j);
+    }
+  }
+  let mut size = 2;
+  while size <= n {
+    let angle = -core::f32::consts::TAU / size as f32;
+    for start in (0..n).step_by(size) {
+      for k in 0..size / 2 {
+        let (sin, cos) = (angle * k as f32).sin_cos();
+        let (a, b) = (start + k, start + k + size / 2);
+        let t_re = re[b] * cos - im[b] * sin;
+        let t_im = re[b] * sin + im[b] * cos;
+        re[b] = re[a] - t_re;
+        im[b] = im[a] - t_im;
+        re[a] += t_re;
+        im[a] += t_im;
+      }
+    }
+    size *= 2;
+  }
+}
+
+#[expect(clippy::cast_sign_loss, reason = "clamped to `[0, 1]` first")]
+fn to_unorm8(value: f32) -> u8 {
+  (value.clamp(0.0, 1.0) * 255.0) as u8
+}
+
+/// The spectrum and the waveform of one frame, as RGBA8 texels with the value in the color
+/// channels, like Shadertoy's luminance texture.
+pub struct AudioTexture {
+  rgba: Vec<u8>,
+}
+
+impl AudioTexture {
+  pub const SIZE: wgpu::Extent3d = wgpu::Extent3d {
+    width: AUDIO_TEXTURE_WIDTH,
+    height: 2,
+    depth_or_array_layers: 1,
+  };
+
+  pub fn rgba(&self) -> &[u8] {
+    &self.rgba
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE_RATE: u32 = 48_000;
+
+  /// One second of a sine of `amplitude` at the centre of spectrum bin `bin`.
+  fn sine(bin: usize, amplitude: f32) -> AudioAnalysis {
+    let frequency = bin as f32 * SAMPLE_RATE as f32 / FFT_SIZE as f32;
+    let samples = (0..SAMPLE_RATE)
+      .map(|i| {
+        amplitude * (core::f32::consts::TAU * frequency * i as f32 / SAMPLE_RATE as f32).sin()
+      })
+      .collect();
+    AudioAnalysis::new(SAMPLE_RATE as f32, samples)
+  }
+
+  fn spectrum(texture: &AudioTexture) -> Vec<u8> {
+    texture.rgba()[..4 * WIDTH]
+      .iter()
+      .step_by(4)
+      .copied()
+      .collect()
+  }
+
+  #[test]
+  fn a_sine_peaks_at_its_bin() {
+    for bin in [10, 64, 300] {
+      let spectrum = spectrum(&sine(bin, 0.01).texture(0.5));
+      let peak = spectrum[bin];
+      assert!(peak > 0, "bin {bin} is silent");
+      for (other, &value) in spectrum.iter().enumerate() {
+        if other != bin {
+          assert!(
+            value < peak,
+            "bin {other} ({value}) is not below bin {bin} ({peak})"
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn textures_only_depend_on_the_time() {
+    let analysis = sine(64, 0.5);
+    let first = analysis.texture(0.5);
+    assert_eq!(first.rgba(), analysis.texture(0.5).rgba());
+    for time in [0.9, 0.0, 0.25] {
+      analysis.texture(time);
+    }
+    assert_eq!(first.rgba(), analysis.texture(0.5).rgba());
+    assert_eq!(first.rgba(), sine(64, 0.5).texture(0.5).rgba());
+  }
+
+  #[test]
+  fn silence_before_the_start() {
+    let texture = sine(64, 0.5).texture(-1.0);
+    assert_eq!(texture.rgba().len(), 4 * 2 * WIDTH);
+    let (spectrum, waveform) = texture.rgba().split_at(4 * WIDTH);
+    assert!(spectrum.chunks(4).all(|texel| texel == [0, 0, 0, 255]));
+    assert!(waveform
+      .chunks(4)
+      .all(|texel| texel == [127, 127, 127, 255]));
+  }
+}
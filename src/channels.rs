@@ -0,0 +1,573 @@
+//! Host side of the shader channels: which source feeds each `iChannel` of a shader, and the
+//! textures and bind group behind them.
+//!
+//! The channels live in descriptor set 0, a filtering sampler followed by a 2D texture, a cubemap
+//! and a volume for each of the four channels, see `fragment_entry_point!` in the shader crate.
+//! Kinds that are not in use are bound to 1x1 placeholders. In the grid view all shaders share
+//! one draw call, so every channel is unbound there and the shaders use their procedural
+//! fallbacks.
+//!
+//! The files given on the command line are [`Streams`]: the music of `--audio`, analysed into
+//! the audio texture, and the videos of `--channel<N>`, which replace whatever channel `N` of a
+//! shader would show. Their textures change with the time, the host writes them every frame with
+//! [`Channels::write_streams`]. On the CPU they are sampled as [`CpuChannel`]s.
+
+use crate::{
+  audio::{AudioAnalysis, AudioTexture},
+  video::Video,
+  DebugMode,
+};
+use core::error::Error;
+use shadertoys_shaders::{
+  channel::{Channel, ChannelInput, ChannelKind},
+  shader_prelude::{vec3, Vec2, Vec3, Vec4},
+  shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderConstants,
+};
+use std::path::Path;
+use wgpu::util::DeviceExt as _;
+
+/// What feeds a channel.
+pub enum ChannelSource {
+  /// Nothing is bound, the shader uses its procedural fallback.
+  Procedural,
+  /// The audio texture, a 2D texture, if the host has audio, see [`crate::audio`].
+  Audio,
+  /// The video of `--channel<N>` for channel `N`, a 2D texture, see [`crate::video`].
+  Video(usize),
+}
+
+/// RGBA8 texels, for cubemaps the six faces and for volumes the slices one after another.
+struct Texels {
+  size: wgpu::Extent3d,
+  data: Vec<u8>,
+}
+
+impl ChannelSource {
+  const fn kind(&self) -> ChannelKind {
+    match self {
+      Self::Procedural => ChannelKind::Unbound,
+      Self::Audio | Self::Video(_) => ChannelKind::Texture2d,
+    }
+  }
+}
+
+/// The sources of the channels `inputs` of a shader with the streams of `sizes`.
+fn sources_for(inputs: [ChannelInput; 4], sizes: StreamSizes) -> [ChannelSource; 4] {
+  let mut sources = inputs.map(|input| match input {
+    ChannelInput::Audio if sizes.audio => ChannelSource::Audio,
+    ChannelInput::Audio | ChannelInput::Procedural => ChannelSource::Procedural,
+  });
+  for (channel, size) in sizes.videos.iter().enumerate() {
+    if size.is_some() {
+      sources[channel] = ChannelSource::Video(channel);
+    }
+  }
+  sources
+}
+
+/// The files the host feeds into the channels, see the module documentation.
+#[derive(Default)]
+pub struct Streams {
+  audio: Option<AudioAnalysis>,
+  /// The audio texture at the time of the last [`Streams::seek`].
+  audio_texture: Option<AudioTexture>,
+  videos: [Option<Video>; 4],
+}
+
+/// What [`Channels`] needs to know about the [`Streams`] up front.
+#[derive(Copy, Clone)]
+pub struct StreamSizes {
+  audio: bool,
+  videos: [Option<wgpu::Extent3d>; 4],
+}
+
+impl Streams {
+  /// The usage of the options of [`Streams::parse_option`].
+  pub const USAGE: &str = "[--audio <file.wav>] [--channel<0-3> <video.y4m|directory|%04d.png>]";
+
+  /// Opens the file of `--audio` or `--channel<N>`, returns false for other options.
+  pub fn parse_option(&mut self, option: &str, value: &str) -> Result<bool, Box<dyn Error>> {
+    if option == "--audio" {
+      self.audio = Some(AudioAnalysis::load(Path::new(value))?);
+      return Ok(true);
+    }
+    let channel = option
+      .strip_prefix("--channel")
+      .and_then(|channel| channel.parse::<usize>().ok())
+      .filter(|&channel| channel < self.videos.len());
+    let Some(channel) = channel else {
+      return Ok(false);
+    };
+    self.videos[channel] = Some(Video::open(Path::new(value))?);
+    Ok(true)
+  }
+
+  pub fn sizes(&self) -> StreamSizes {
+    StreamSizes {
+      audio: self.audio.is_some(),
+      videos: self
+        .videos
+        .each_ref()
+        .map(|video| video.as_ref().map(Video::size)),
+    }
+  }
+
+  /// Analyses the audio and decodes the video frames at `time`. Errors only stop the videos that
+  /// fail from moving on.
+  pub fn seek(&mut self, time: f32) -> Result<(), Box<dyn Error>> {
+    self.audio_texture = self.audio.as_ref().map(|audio| audio.texture(time));
+    // Every video seeks, the first error is returned.
+    self
+      .videos
+      .iter_mut()
+      .flatten()
+      .map(|video| video.seek(time))
+      .fold(Ok(()), Result::and)
+  }
+
+  /// Writes `iChannelTime` at `time` for the shader at `shader_index`, zero in the grid view like
+  /// the channels there.
+  pub fn write_channel_time(
+    &self,
+    constants: &mut ShaderConstants,
+    shader_index: Option<usize>,
+    time: f32,
+  ) {
+    let [time0, time1, time2, time3] =
+      self
+        .videos
+        .each_ref()
+        .map(|video| match (shader_index, video) {
+          (Some(_), Some(video)) => video.playback_time(time),
+          _ => 0.0,
+        });
+    constants.channel_time0 = time0;
+    constants.channel_time1 = time1;
+    constants.channel_time2 = time2;
+    constants.channel_time3 = time3;
+  }
+
+  /// The channels of the shader at `shader_index`, or of the grid view if that is `None`, for
+  /// rendering on the CPU at the time of the last [`Streams::seek`] and `time`, like the GPU
+  /// sees them with [`Channels`].
+  pub fn cpu_channels(&self, shader_index: Option<usize>, time: f32) -> [CpuChannel<'_>; 4] {
+    let mut channels = [CpuChannel(None); 4];
+    let Some(index) = shader_index else {
+      return channels;
+    };
+    for (channel, input) in channels.iter_mut().zip(SHADER_DEFINITIONS[index].channels) {
+      if matches!(input, ChannelInput::Audio) {
+        *channel = CpuChannel(self.audio_texture.as_ref().map(|audio| CpuImage {
+          size: AudioTexture::SIZE,
+          rgba: audio.rgba(),
+          time: 0.0,
+        }));
+      }
+    }
+    for (channel, video) in channels.iter_mut().zip(&self.videos) {
+      if let Some(video) = video {
+        *channel = CpuChannel(Some(CpuImage {
+          size: video.size(),
+          rgba: video.rgba(),
+          time: video.playback_time(time),
+        }));
+      }
+    }
+    channels
+  }
+}
+
+/// An RGBA8 image for a [`CpuChannel`], its first row at `uv.y == 0.0`.
+#[derive(Copy, Clone)]
+pub struct CpuImage<'a> {
+  size: wgpu::Extent3d,
+  rgba: &'a [u8],
+  time: f32,
+}
+
+impl CpuImage<'_> {
+  /// The texel at `x`, `y`, wrapping around like the repeating sampler of the channels.
+  #[expect(clippy::cast_possible_wrap, reason = "textures are far smaller")]
+  fn texel(self, x: i32, y: i32) -> Vec4 {
+    let x = x.rem_euclid(self.size.width as i32) as usize;
+    let y = y.rem_euclid(self.size.height as i32) as usize;
+    let index = 4 * (y * self.size.width as usize + x);
+    Vec4::from_array(core::array::from_fn(|i| {
+      f32::from(self.rgba[index + i]) / 255.0
+    }))
+  }
+}
+
+/// A channel on the CPU: one of the [`Streams`], or unbound. Samples 2D lookups like the linear
+/// filtering of the GPU, cubemap and volume lookups are black like the placeholders there.
+#[derive(Copy, Clone)]
+pub struct CpuChannel<'a>(Option<CpuImage<'a>>);
+
+impl Channel for CpuChannel<'_> {
+  fn resolution(self) -> Vec3 {
+    self.0.map_or(Vec3::ZERO, |image| {
+      vec3(image.size.width as f32, image.size.height as f32, 1.0)
+    })
+  }
+
+  fn time(self) -> f32 {
+    self.0.map_or(0.0, |image| image.time)
+  }
+
+  fn texture(self, uv: Vec2) -> Vec4 {
+    let Some(image) = self.0 else {
+      return Vec4::ZERO;
+    };
+    let p = uv * self.resolution().truncate() - 0.5;
+    let (x, y) = (p.x.floor() as i32, p.y.floor() as i32);
+    let f = p - p.floor();
+    let bottom = image.texel(x, y).lerp(image.texel(x + 1, y), f.x);
+    let top = image.texel(x, y + 1).lerp(image.texel(x + 1, y + 1), f.x);
+    bottom.lerp(top, f.y)
+  }
+
+  fn texture_cube(self, _: Vec3) -> Vec4 {
+    Vec4::ZERO
+  }
+
+  fn texture_3d(self, _: Vec3) -> Vec4 {
+    Vec4::ZERO
+  }
+
+  fn is_bound(self) -> bool {
+    self.0.is_some()
+  }
+}
+
+/// Number of bindings per kind of image, one per channel.
+const CHANNEL_COUNT: u32 = 4;
+
+/// The channel bind group layout and the bind groups of the shaders shown so far.
+pub struct Channels {
+  pub bind_group_layout: wgpu::BindGroupLayout,
+  sampler: wgpu::Sampler,
+  /// 1x1 2D texture, cubemap and volume bound to the unused bindings.
+  placeholders: [wgpu::TextureView; 3],
+  /// Everything unbound, used in the grid view.
+  unbound: wgpu::BindGroup,
+  /// Written by [`Channels::write_streams`], if the host has audio.
+  audio: Option<wgpu::Texture>,
+  /// Written by [`Channels::write_streams`], for the channels with a video.
+  videos: [Option<wgpu::Texture>; 4],
+  /// Bind group and `ShaderConstants::channel_sources` per shader, created when a shader is
+  /// first shown.
+  shaders: Vec<Option<(wgpu::BindGroup, u32)>>,
+}
+
+impl Channels {
+  /// Creates the textures of the streams of `sizes` too.
+  pub fn new(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    debug: DebugMode,
+    sizes: StreamSizes,
+  ) -> Self {
+    let texture_entry = |binding, view_dimension| wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility: wgpu::ShaderStages::FRAGMENT,
+      ty: wgpu::BindingType::Texture {
+        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        view_dimension,
+        multisampled: false,
+      },
+      count: None,
+    };
+    let mut entries = vec![wgpu::BindGroupLayoutEntry {
+      binding: 0,
+      visibility: wgpu::ShaderStages::FRAGMENT,
+      ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+      count: None,
+    }];
+    for (kind, view_dimension) in VIEW_DIMENSIONS.into_iter().enumerate() {
+      for channel in 0..CHANNEL_COUNT {
+        entries.push(texture_entry(binding(kind, channel), view_dimension));
+      }
+    }
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: debug.label("channels bind group layout"),
+      entries: &entries,
+    });
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: debug.label("channels sampler"),
+      address_mode_u: wgpu::AddressMode::Repeat,
+      address_mode_v: wgpu::AddressMode::Repeat,
+      address_mode_w: wgpu::AddressMode::Repeat,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+    let black = |depth_or_array_layers| Texels {
+      size: wgpu::Extent3d {
+        width: 1,
+        height: 1,
+        depth_or_array_layers,
+      },
+      data: vec![0; depth_or_array_layers as usize * 4],
+    };
+    let placeholders = [
+      create_view(device, queue, debug, &black(1), VIEW_DIMENSIONS[0]),
+      create_view(device, queue, debug, &black(6), VIEW_DIMENSIONS[1]),
+      create_view(device, queue, debug, &black(1), VIEW_DIMENSIONS[2]),
+    ];
+    let mut channels = Self {
+      unbound: create_bind_group(
+        device,
+        debug,
+        &bind_group_layout,
+        &sampler,
+        &placeholders,
+        &[None, None, None, None],
+      ),
+      bind_group_layout,
+      sampler,
+      placeholders,
+      audio: sizes
+        .audio
+        .then(|| create_stream_texture(device, debug.label("audio texture"), AudioTexture::SIZE)),
+      videos: sizes.videos.map(|size| {
+        size.map(|size| create_stream_texture(device, debug.label("video texture"), size))
+      }),
+      shaders: Vec::new(),
+    };
+    channels
+      .shaders
+      .resize_with(SHADER_DEFINITIONS.len(), || None);
+    channels
+  }
+
+  /// Uploads the audio and the video frames of the last [`Streams::seek`].
+  pub fn write_streams(&self, queue: &wgpu::Queue, streams: &Streams) {
+    let write = |texture: &wgpu::Texture, rgba: &[u8]| {
+      // Frames that failed to decode are empty.
+      if !rgba.is_empty() {
+        queue.write_texture(
+          texture.as_image_copy(),
+          rgba,
+          wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(texture.width() * 4),
+            rows_per_image: None,
+          },
+          texture.size(),
+        );
+      }
+    };
+    if let (Some(texture), Some(audio)) = (&self.audio, &streams.audio_texture) {
+      write(texture, audio.rgba());
+    }
+    for (texture, video) in self.videos.iter().zip(&streams.videos) {
+      if let (Some(texture), Some(video)) = (texture, video) {
+        write(texture, video.rgba());
+      }
+    }
+  }
+
+  /// The bind group and `ShaderConstants::channel_sources` for the shader at `shader_index`, or
+  /// for the grid view if that is `None`.
+  pub fn bind_group(
+    &mut self,
+    device: &wgpu::Device,
+    debug: DebugMode,
+    shader_index: Option<usize>,
+  ) -> (&wgpu::BindGroup, u32) {
+    let Some(index) = shader_index else {
+      return (&self.unbound, 0);
+    };
+    let (bind_group, channel_sources) = self.shaders[index].get_or_insert_with(|| {
+      let sizes = StreamSizes {
+        audio: self.audio.is_some(),
+        videos: self
+          .videos
+          .each_ref()
+          .map(|video| video.as_ref().map(wgpu::Texture::size)),
+      };
+      let sources = sources_for(SHADER_DEFINITIONS[index].channels, sizes);
+      let views = sources.each_ref().map(|source| match source {
+        ChannelSource::Procedural => None,
+        ChannelSource::Audio => self.audio.as_ref().map(|texture| {
+          let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: debug.label("audio texture view"),
+            ..Default::default()
+          });
+          (0, view)
+        }),
+        ChannelSource::Video(channel) => self.videos[*channel].as_ref().map(|texture| {
+          let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: debug.label("video texture view"),
+            ..Default::default()
+          });
+          (0, view)
+        }),
+      });
+      (
+        create_bind_group(
+          device,
+          debug,
+          &self.bind_group_layout,
+          &self.sampler,
+          &self.placeholders,
+          &views,
+        ),
+        ChannelKind::pack(sources.each_ref().map(ChannelSource::kind)),
+      )
+    });
+    (bind_group, *channel_sources)
+  }
+}
+
+/// View dimensions of the three kinds of images, in binding order.
+const VIEW_DIMENSIONS: [wgpu::TextureViewDimension; 3] = [
+  wgpu::TextureViewDimension::D2,
+  wgpu::TextureViewDimension::Cube,
+  wgpu::TextureViewDimension::D3,
+];
+
+/// Binding of the image of kind `kind` (an index into [`VIEW_DIMENSIONS`]) of `channel`.
+const fn binding(kind: usize, channel: u32) -> u32 {
+  1 + kind as u32 * CHANNEL_COUNT + channel
+}
+
+fn create_view(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  debug: DebugMode,
+  texels: &Texels,
+  dimension: wgpu::TextureViewDimension,
+) -> wgpu::TextureView {
+  let texture = device.create_texture_with_data(
+    queue,
+    &wgpu::TextureDescriptor {
+      label: debug.label("channel texture"),
+      size: texels.size,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: if dimension == wgpu::TextureViewDimension::D3 {
+        wgpu::TextureDimension::D3
+      } else {
+        wgpu::TextureDimension::D2
+      },
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      usage: wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    },
+    wgpu::util::TextureDataOrder::LayerMajor,
+    &texels.data,
+  );
+  texture.create_view(&wgpu::TextureViewDescriptor {
+    label: debug.label("channel texture view"),
+    dimension: Some(dimension),
+    ..Default::default()
+  })
+}
+
+/// A 2D texture the host writes every frame, for one of the [`Streams`].
+fn create_stream_texture(
+  device: &wgpu::Device,
+  label: Option<&str>,
+  size: wgpu::Extent3d,
+) -> wgpu::Texture {
+  device.create_texture(&wgpu::TextureDescriptor {
+    label,
+    size,
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: wgpu::TextureFormat::Rgba8Unorm,
+    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    view_formats: &[],
+  })
+}
+
+/// `views` holds the kind (an index into [`VIEW_DIMENSIONS`]) and view of every bound channel.
+fn create_bind_group(
+  device: &wgpu::Device,
+  debug: DebugMode,
+  layout: &wgpu::BindGroupLayout,
+  sampler: &wgpu::Sampler,
+  placeholders: &[wgpu::TextureView; 3],
+  views: &[Option<(usize, wgpu::TextureView)>; 4],
+) -> wgpu::BindGroup {
+  let mut entries = vec![wgpu::BindGroupEntry {
+    binding: 0,
+    resource: wgpu::BindingResource::Sampler(sampler),
+  }];
+  for (kind, placeholder) in placeholders.iter().enumerate() {
+    for (channel, view) in (0..CHANNEL_COUNT).zip(views) {
+      let view = match view {
+        Some((view_kind, view)) if *view_kind == kind => view,
+        _ => placeholder,
+      };
+      entries.push(wgpu::BindGroupEntry {
+        binding: binding(kind, channel),
+        resource: wgpu::BindingResource::TextureView(view),
+      });
+    }
+  }
+  device.create_bind_group(&wgpu::BindGroupDescriptor {
+    label: debug.label("channels bind group"),
+    layout,
+    entries: &entries,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn kinds(sources: &[ChannelSource; 4]) -> [ChannelKind; 4] {
+    sources.each_ref().map(ChannelSource::kind)
+  }
+
+  #[test]
+  fn audio_is_bound_where_the_shader_declares_it() {
+    let definition = SHADER_DEFINITIONS
+      .iter()
+      .find(|definition| definition.name == "Audio Spectrum")
+      .unwrap();
+    let with_audio = StreamSizes {
+      audio: true,
+      videos: [None; 4],
+    };
+    let sources = sources_for(definition.channels, with_audio);
+    assert!(matches!(sources[0], ChannelSource::Audio));
+    assert_eq!(kinds(&sources)[1..], [ChannelKind::Unbound; 3]);
+
+    let without_audio = StreamSizes {
+      audio: false,
+      videos: [None; 4],
+    };
+    let sources = sources_for(definition.channels, without_audio);
+    assert_eq!(kinds(&sources), [ChannelKind::Unbound; 4]);
+  }
+
+  #[test]
+  fn videos_replace_the_declared_inputs() {
+    let size = wgpu::Extent3d {
+      width: 4,
+      height: 2,
+      depth_or_array_layers: 1,
+    };
+    let sizes = StreamSizes {
+      audio: true,
+      videos: [Some(size), None, None, Some(size)],
+    };
+    let inputs = [
+      ChannelInput::Audio,
+      ChannelInput::Audio,
+      ChannelInput::Procedural,
+      ChannelInput::Procedural,
+    ];
+    let sources = sources_for(inputs, sizes);
+    assert!(matches!(sources[0], ChannelSource::Video(0)));
+    assert!(matches!(sources[1], ChannelSource::Audio));
+    assert!(matches!(sources[2], ChannelSource::Procedural));
+    assert!(matches!(sources[3], ChannelSource::Video(3)));
+  }
+}
@@ -0,0 +1,258 @@
+//! Renders a contact sheet of all shaders, like the grid view but with every shader labeled with
+//! its name, e.g. for the image at the top of the README. The cells follow the same
+//! `optimal_grid` layout and order as the grid view. Cells left over at the top of the last
+//! column are filled with a legend.
+//!
+//! Run with `cargo run --release -- contact-sheet <output.png|output.exr> [options]`. The
+//! options are
+//!
+//! - `--size <width>x<height>`, 1920x1080 by default,
+//! - `--time <seconds>`, 2 by default,
+//! - `--quality <low|medium|high|ultra>`, `high` by default,
+//! - `--cpu` to run the shaders on the CPU instead of the GPU.
+//!
+//! Every cell is rendered on its own at the size of the cell, with the default params and the
+//! procedural sources of the channels, see `poster`.
+
+use crate::{
+  channels::Streams,
+  font::{self, GLYPH_HEIGHT},
+  image_writer::ImageWriter,
+  poster::{parse_quality, parse_size, GpuRenderer, Renderer},
+  DebugMode,
+};
+use bytemuck::Zeroable;
+use core::error::Error;
+use futures::executor::block_on;
+use shadertoys_shaders::{
+  optimal_grid, projection::Projection, quality::Quality, shader_prelude::vec2,
+  shaders::SHADER_DEFINITIONS, shared_data::ShaderConstants,
+};
+use std::{path::PathBuf, time::Instant};
+
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+/// The labels darken what is behind them, clamped to `[0, 1]` first, by this factor.
+const LABEL_SHADE: f32 = 0.1;
+const LEGEND_BACKGROUND: [f32; 4] = [0.01, 0.01, 0.01, 1.0];
+
+struct Options {
+  output: PathBuf,
+  width: u32,
+  height: u32,
+  time: f32,
+  quality: Quality,
+  cpu: bool,
+}
+
+impl Options {
+  fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+    let usage = "usage: contact-sheet <output.png|output.exr> [--size <width>x<height>] \
+                 [--time <seconds>] [--quality <level>] [--cpu]";
+    let [output, flags @ ..] = args else {
+      return Err(usage.into());
+    };
+    let mut options = Self {
+      output: output.into(),
+      width: 1920,
+      height: 1080,
+      time: 2.0,
+      quality: Quality::High,
+      cpu: false,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+      if flag == "--cpu" {
+        options.cpu = true;
+        continue;
+      }
+      let value = flags
+        .next()
+        .ok_or_else(|| format!("`{flag}` needs a value, {usage}"))?;
+      let invalid = || format!("invalid value `{value}` for `{flag}`");
+      match flag.as_str() {
+        "--size" => (options.width, options.height) = parse_size(value).ok_or_else(invalid)?,
+        "--time" => options.time = value.parse().map_err(|_| invalid())?,
+        "--quality" => options.quality = parse_quality(value).ok_or_else(invalid)?,
+        _ => return Err(format!("unknown option `{flag}`, {usage}").into()),
+      }
+    }
+    if !options.time.is_finite() {
+      return Err("the time must be a finite number of seconds".into());
+    }
+    Ok(options)
+  }
+}
+
+/// A rectangle of the sheet, in pixels from the top left.
+#[derive(Clone, Copy)]
+struct Cell {
+  x: u32,
+  y: u32,
+  width: u32,
+  height: u32,
+}
+
+/// The cells of a `rows` x `cols` grid over `width` x `height` pixels, with `row` counted from
+/// the bottom like in the grid view. Cells differ by a pixel where the size doesn't divide.
+struct Grid {
+  rows: u32,
+  cols: u32,
+  width: u32,
+  height: u32,
+}
+
+impl Grid {
+  fn cell(&self, row: u32, col: u32) -> Cell {
+    let x = col * self.width / self.cols;
+    let top_row = self.rows - 1 - row;
+    let y = top_row * self.height / self.rows;
+    Cell {
+      x,
+      y,
+      width: (col + 1) * self.width / self.cols - x,
+      height: (top_row + 1) * self.height / self.rows - y,
+    }
+  }
+
+  /// The cell of shader `index`, in the order of the grid view: up the columns, from the left.
+  fn shader_cell(&self, index: u32) -> Cell {
+    self.cell(index % self.rows, index / self.rows)
+  }
+}
+
+pub fn run(args: &[String], debug: DebugMode) -> Result<(), Box<dyn Error>> {
+  let options = Options::parse(args)?;
+  let shader_count = SHADER_DEFINITIONS.len();
+  let (rows, cols) = optimal_grid(
+    shader_count,
+    vec2(options.width as f32, options.height as f32),
+  );
+  let grid = Grid {
+    rows: rows as u32,
+    cols: cols as u32,
+    width: options.width,
+    height: options.height,
+  };
+  if options.width < grid.cols || options.height < grid.rows {
+    return Err(format!("the sheet must be at least {cols}x{rows} pixels").into());
+  }
+  let start = Instant::now();
+  let streams = Streams::default();
+  let mut renderer = if options.cpu {
+    Renderer::Cpu(streams.cpu_channels(None, 0.0))
+  } else {
+    // Cells are at most a pixel larger than the first.
+    let first = grid.cell(0, 0);
+    let tile = first.width.max(first.height) + 1;
+    let renderer = block_on(GpuRenderer::new(debug, None, tile, &streams))?;
+    Renderer::Gpu(Box::new(renderer))
+  };
+  let mut sheet = vec![LEGEND_BACKGROUND; (options.width * options.height) as usize];
+  for (index, definition) in SHADER_DEFINITIONS.iter().enumerate() {
+    let cell = grid.shader_cell(index as u32);
+    renderer.show(Some(index));
+    let constants = ShaderConstants {
+      width: cell.width,
+      height: cell.height,
+      time: options.time,
+      shader_to_show: index as u32,
+      quality: options.quality as u32,
+      projection: Projection::Screen as u32,
+      ..ShaderConstants::zeroed()
+    };
+    let pixels = renderer.render(constants, cell.width, cell.height)?;
+    for (row, pixels) in pixels.chunks_exact(cell.width as usize).enumerate() {
+      let at = ((cell.y + row as u32) * options.width + cell.x) as usize;
+      for (target, pixel) in sheet[at..][..pixels.len()].iter_mut().zip(pixels) {
+        // Opaque, like on Shadertoy.
+        *target = [pixel[0], pixel[1], pixel[2], 1.0];
+      }
+    }
+    label(&mut sheet, options.width, cell, definition.name);
+    println!("Rendered {}", definition.name);
+  }
+  let empty = grid.rows * grid.cols - shader_count as u32;
+  if empty > 0 {
+    let last = grid.cell(grid.rows - 1, grid.cols - 1);
+    let first = grid.cell(grid.rows - empty, grid.cols - 1);
+    let legend = Cell {
+      height: first.y + first.height - last.y,
+      ..last
+    };
+    draw_legend(&mut sheet, options.width, legend, &options);
+  }
+  let mut writer = ImageWriter::create(&options.output, options.width, options.height)?;
+  writer.write_rows(&sheet)?;
+  writer.finish()?;
+  println!(
+    "Wrote {} in {:.1?}",
+    options.output.display(),
+    start.elapsed()
+  );
+  Ok(())
+}
+
+/// A scale for text in `cell` that fits `columns` characters, at most one font pixel per 120
+/// pixels of cell height.
+fn text_scale(cell: Cell, columns: usize) -> u32 {
+  let fitting = cell.width * 9 / 10 / (columns as u32 * font::ADVANCE).max(1);
+  (cell.height / 120).min(fitting).max(1)
+}
+
+/// Writes `name` in the bottom left corner of `cell`, on a shaded strip.
+fn label(sheet: &mut [[f32; 4]], width: u32, cell: Cell, name: &str) {
+  let scale = text_scale(cell, name.chars().count());
+  let padding = 2 * scale;
+  let strip_height = GLYPH_HEIGHT * scale + 2 * padding;
+  if strip_height > cell.height {
+    return;
+  }
+  let strip_y = cell.y + cell.height - strip_height;
+  for y in strip_y..cell.y + cell.height {
+    let at = (y * width + cell.x) as usize;
+    for pixel in &mut sheet[at..][..cell.width as usize] {
+      for channel in &mut pixel[..3] {
+        *channel = channel.clamp(0.0, 1.0) * LABEL_SHADE;
+      }
+    }
+  }
+  let name = fit(name, cell.width.saturating_sub(2 * padding), scale);
+  font::draw_text(
+    sheet,
+    width,
+    (cell.x + padding, strip_y + padding),
+    scale,
+    name,
+    TEXT_COLOR,
+  );
+}
+
+/// Fills `cell` with the title, the shader count and the time of the sheet.
+fn draw_legend(sheet: &mut [[f32; 4]], width: u32, cell: Cell, options: &Options) {
+  let lines = [
+    "Shadertoys".to_owned(),
+    format!("{} shaders", SHADER_DEFINITIONS.len()),
+    "ported to Rust-GPU".to_owned(),
+    format!("at {} s", options.time),
+  ];
+  let columns = lines.iter().map(|line| line.chars().count()).max();
+  let scale = text_scale(cell, columns.unwrap_or_default());
+  let line_height = (GLYPH_HEIGHT + 4) * scale;
+  let visible = (cell.height / line_height).min(lines.len() as u32);
+  let mut y = cell.y + (cell.height - visible * line_height) / 2;
+  for line in &lines[..visible as usize] {
+    let line = fit(line, cell.width, scale);
+    let x = cell.x + (cell.width - font::text_width(line, scale)) / 2;
+    font::draw_text(sheet, width, (x, y), scale, line, TEXT_COLOR);
+    y += line_height;
+  }
+}
+
+/// The start of `text` that is at most `width` pixels wide at `scale`.
+fn fit(text: &str, width: u32, scale: u32) -> &str {
+  let columns = ((width / scale + 1) / font::ADVANCE) as usize;
+  text
+    .char_indices()
+    .nth(columns)
+    .map_or(text, |(end, _)| &text[..end])
+}
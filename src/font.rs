@@ -0,0 +1,337 @@
+//! A 5x7 pixel font for labels on rendered images, covering printable ASCII. Other characters are
+//! drawn as `?`.
+
+/// Width and height of a glyph in font pixels. Glyphs are one font pixel apart.
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+/// How far the pen moves per character.
+pub const ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+/// The rows of the glyphs of `' '` to `'~'`, the leftmost pixel in bit 4.
+const GLYPHS: [[u8; GLYPH_HEIGHT as usize]; 95] = [
+  [
+    0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000,
+  ], // space
+  [
+    0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+  ], // !
+  [
+    0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000,
+  ], // "
+  [
+    0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+  ], // #
+  [
+    0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100,
+  ], // $
+  [
+    0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+  ], // %
+  [
+    0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101,
+  ], // &
+  [
+    0b00100, 0b00100, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000,
+  ], // '
+  [
+    0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+  ], // (
+  [
+    0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+  ], // )
+  [
+    0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000,
+  ], // *
+  [
+    0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000,
+  ], // +
+  [
+    0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+  ], // ,
+  [
+    0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+  ], // -
+  [
+    0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+  ], // .
+  [
+    0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+  ], // /
+  [
+    0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+  ], // 0
+  [
+    0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+  ], // 1
+  [
+    0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+  ], // 2
+  [
+    0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+  ], // 3
+  [
+    0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+  ], // 4
+  [
+    0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+  ], // 5
+  [
+    0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+  ], // 6
+  [
+    0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+  ], // 7
+  [
+    0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+  ], // 8
+  [
+    0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+  ], // 9
+  [
+    0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+  ], // :
+  [
+    0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000,
+  ], // ;
+  [
+    0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010,
+  ], // <
+  [
+    0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000,
+  ], // =
+  [
+    0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000,
+  ], // >
+  [
+    0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+  ], // ?
+  [
+    0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110,
+  ], // @
+  [
+    0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001,
+  ], // A
+  [
+    0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+  ], // B
+  [
+    0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+  ], // C
+  [
+    0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
+  ], // D
+  [
+    0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+  ], // E
+  [
+    0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+  ], // F
+  [
+    0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+  ], // G
+  [
+    0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+  ], // H
+  [
+    0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+  ], // I
+  [
+    0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+  ], // J
+  [
+    0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+  ], // K
+  [
+    0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+  ], // L
+  [
+    0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+  ], // M
+  [
+    0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+  ], // N
+  [
+    0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+  ], // O
+  [
+    0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+  ], // P
+  [
+    0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+  ], // Q
+  [
+    0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+  ], // R
+  [
+    0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+  ], // S
+  [
+    0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+  ], // T
+  [
+    0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+  ], // U
+  [
+    0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+  ], // V
+  [
+    0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+  ], // W
+  [
+    0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+  ], // X
+  [
+    0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100,
+  ], // Y
+  [
+    0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+  ], // Z
+  [
+    0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110,
+  ], // [
+  [
+    0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000,
+  ], // \
+  [
+    0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110,
+  ], // ]
+  [
+    0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000,
+  ], // ^
+  [
+    0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111,
+  ], // _
+  [
+    0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000,
+  ], // `
+  [
+    0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111,
+  ], // a
+  [
+    0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110,
+  ], // b
+  [
+    0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110,
+  ], // c
+  [
+    0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111,
+  ], // d
+  [
+    0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110,
+  ], // e
+  [
+    0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000,
+  ], // f
+  [
+    0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+  ], // g
+  [
+    0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+  ], // h
+  [
+    0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110,
+  ], // i
+  [
+    0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100,
+  ], // j
+  [
+    0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010,
+  ], // k
+  [
+    0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+  ], // l
+  [
+    0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001,
+  ], // m
+  [
+    0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+  ], // n
+  [
+    0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110,
+  ], // o
+  [
+    0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000,
+  ], // p
+  [
+    0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001,
+  ], // q
+  [
+    0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000,
+  ], // r
+  [
+    0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110,
+  ], // s
+  [
+    0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110,
+  ], // t
+  [
+    0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101,
+  ], // u
+  [
+    0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+  ], // v
+  [
+    0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010,
+  ], // w
+  [
+    0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001,
+  ], // x
+  [
+    0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+  ], // y
+  [
+    0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111,
+  ], // z
+  [
+    0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010,
+  ], // {
+  [
+    0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+  ], // |
+  [
+    0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000,
+  ], // }
+  [
+    0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000,
+  ], // ~
+];
+
+/// Width in image pixels of `text` at `scale`, without the space after the last character.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+  (text.chars().count() as u32 * ADVANCE).saturating_sub(1) * scale
+}
+
+/// Draws `text` into the `width` pixels wide `pixels` in `color`, with its top left corner at
+/// `x`, `y` and every font pixel `scale` x `scale` image pixels. What falls outside is clipped.
+pub fn draw_text(
+  pixels: &mut [[f32; 4]],
+  width: u32,
+  (x, y): (u32, u32),
+  scale: u32,
+  text: &str,
+  color: [f32; 4],
+) {
+  let height = pixels.len() as u32 / width;
+  for (index, c) in text.chars().enumerate() {
+    let c = if c == ' ' || c.is_ascii_graphic() {
+      c
+    } else {
+      '?'
+    };
+    let left = x + index as u32 * ADVANCE * scale;
+    for (row, bits) in GLYPHS[c as usize - ' ' as usize].iter().enumerate() {
+      for column in 0..GLYPH_WIDTH {
+        if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+          continue;
+        }
+        let top = y + row as u32 * scale;
+        for pixel_y in top..(top + scale).min(height) {
+          let start = left + column * scale;
+          for pixel_x in start..(start + scale).min(width) {
+            pixels[(pixel_y * width + pixel_x) as usize] = color;
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,147 @@
+//! Host side of the free camera: WASD to move, right mouse button drag to look around.
+//!
+//! The shaders receive the accumulated offset and rotation through [`ShaderConstants`] and apply
+//! them relative to their own camera, see `shadertoys_shaders::camera::FreeCamera`.
+
+use shadertoys_shaders::shared_data::ShaderConstants;
+use std::collections::HashSet;
+use web_time::Instant;
+use winit::keyboard::KeyCode;
+
+/// Units per second, in the camera space of the shader.
+const SPEED: f32 = 1.0;
+/// Speed multiplier while shift is held.
+const FAST_SPEED_FACTOR: f32 = 5.0;
+/// Radians per pixel of mouse movement.
+const LOOK_SENSITIVITY: f32 = 0.005;
+/// Keeps the camera from flipping over when looking straight up or down.
+const MAX_PITCH: f32 = 1.5;
+/// Upper bound for the time step, so a stalled frame doesn't teleport the camera.
+const MAX_FRAME_TIME: f32 = 0.1;
+/// Keys handled by [`FreeCameraController::key`].
+const MOVEMENT_KEYS: [KeyCode; 8] = [
+  KeyCode::KeyW,
+  KeyCode::KeyS,
+  KeyCode::KeyA,
+  KeyCode::KeyD,
+  KeyCode::Space,
+  KeyCode::ControlLeft,
+  KeyCode::ShiftLeft,
+  KeyCode::ShiftRight,
+];
+
+pub struct FreeCameraController {
+  enabled: bool,
+  offset: [f32; 3],
+  yaw: f32,
+  pitch: f32,
+
+  // Input state.
+  /// Movement keys that are currently held down.
+  held_keys: HashSet<KeyCode>,
+  looking: bool,
+  last_cursor: Option<(f32, f32)>,
+  last_update: Instant,
+}
+
+impl FreeCameraController {
+  pub fn new() -> Self {
+    Self {
+      enabled: false,
+      offset: [0.0; 3],
+      yaw: 0.0,
+      pitch: 0.0,
+      held_keys: HashSet::new(),
+      looking: false,
+      last_cursor: None,
+      last_update: Instant::now(),
+    }
+  }
+
+  pub fn toggle(&mut self) {
+    self.enabled = !self.enabled;
+    println!("Free camera: {}", self.enabled);
+  }
+
+  /// Moves the camera back to where the shader puts it.
+  pub fn reset(&mut self) {
+    self.offset = [0.0; 3];
+    self.yaw = 0.0;
+    self.pitch = 0.0;
+  }
+
+  /// Updates the movement keys, other keys are ignored.
+  pub fn key(&mut self, code: KeyCode, pressed: bool) {
+    if !MOVEMENT_KEYS.contains(&code) {
+      return;
+    }
+    if pressed {
+      self.held_keys.insert(code);
+    } else {
+      self.held_keys.remove(&code);
+    }
+  }
+
+  /// Starts or stops looking around with the mouse.
+  pub fn look(&mut self, pressed: bool) {
+    self.looking = pressed;
+    self.last_cursor = None;
+  }
+
+  pub fn cursor_moved(&mut self, x: f32, y: f32) {
+    if !self.looking {
+      return;
+    }
+    if let Some((last_x, last_y)) = self.last_cursor {
+      self.yaw += (x - last_x) * LOOK_SENSITIVITY;
+      self.pitch = (self.pitch - (y - last_y) * LOOK_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+    self.last_cursor = Some((x, y));
+  }
+
+  /// Integrates the movement since the last call, once per frame.
+  pub fn update(&mut self) {
+    let now = Instant::now();
+    let dt = (now - self.last_update).as_secs_f32().min(MAX_FRAME_TIME);
+    self.last_update = now;
+    if !self.enabled {
+      return;
+    }
+
+    let held = |code| self.held_keys.contains(&code);
+    let axis = |positive, negative| {
+      f32::from(u8::from(held(positive))) - f32::from(u8::from(held(negative)))
+    };
+    let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+    let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+    // Same rotation as `Camera::with_free_camera` applies in the shader.
+    let forward = [sin_yaw * cos_pitch, sin_pitch, cos_yaw * cos_pitch];
+    let right = [cos_yaw, 0.0, -sin_yaw];
+    let up = [0.0, 1.0, 0.0];
+
+    let speed = if held(KeyCode::ShiftLeft) || held(KeyCode::ShiftRight) {
+      SPEED * FAST_SPEED_FACTOR
+    } else {
+      SPEED
+    };
+    let step = [
+      axis(KeyCode::KeyW, KeyCode::KeyS),
+      axis(KeyCode::KeyD, KeyCode::KeyA),
+      axis(KeyCode::Space, KeyCode::ControlLeft),
+    ];
+    for (i, offset) in self.offset.iter_mut().enumerate() {
+      *offset += (forward[i] * step[0] + right[i] * step[1] + up[i] * step[2]) * speed * dt;
+    }
+  }
+
+  pub fn write_constants(&self, constants: &mut ShaderConstants) {
+    constants.free_camera = u32::from(self.enabled);
+    [
+      constants.camera_offset_x,
+      constants.camera_offset_y,
+      constants.camera_offset_z,
+    ] = self.offset;
+    constants.camera_yaw = self.yaw;
+    constants.camera_pitch = self.pitch;
+  }
+}
@@ -0,0 +1,294 @@
+//! Renders a static HTML gallery of all shaders: for every entry of `SHADER_DEFINITIONS` a
+//! thumbnail and a short animated preview, with its name, author, license, the link to the
+//! original on Shadertoy and how long a frame takes to render. Running it again after adding a
+//! shader brings the gallery up to date.
+//!
+//! Run with `cargo run --release -- gallery <directory> [options]`. The options are
+//!
+//! - `--size <width>x<height>`, of the thumbnails and previews, 320x180 by default,
+//! - `--time <seconds>`, the time of the thumbnails, 2 by default,
+//! - `--duration <seconds>`, the length of the previews, which start at 0, 3 by default,
+//! - `--fps <frames>`, the frame rate of the previews, 10 by default,
+//! - `--quality <low|medium|high|ultra>`, `high` by default,
+//! - `--cpu` to run the shaders on the CPU instead of the GPU.
+//!
+//! The shaders render headless like `poster` does, with their default params and the procedural
+//! sources of their channels. The directory gets an `index.html` without scripts or external
+//! files, and for every shader `<name>.png` and the animated PNG `<name>-preview.png`, which
+//! plays in place of the thumbnail under the mouse pointer.
+
+use crate::{
+  channels::Streams,
+  image_writer::{srgb_bytes, ImageWriter},
+  poster::{parse_quality, parse_size, simplify_name, GpuRenderer, Renderer},
+  DebugMode,
+};
+use bytemuck::Zeroable;
+use core::{error::Error, fmt::Write as _, time::Duration};
+use futures::executor::block_on;
+use shadertoys_shaders::{
+  projection::Projection, quality::Quality, shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderConstants,
+};
+use std::{
+  fs::{self, File},
+  io::BufWriter,
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+struct Options {
+  output: PathBuf,
+  width: u32,
+  height: u32,
+  time: f32,
+  duration: f32,
+  fps: u16,
+  quality: Quality,
+  cpu: bool,
+}
+
+impl Options {
+  fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+    let usage = "usage: gallery <directory> [--size <width>x<height>] [--time <seconds>] \
+                 [--duration <seconds>] [--fps <frames>] [--quality <level>] [--cpu]";
+    let [output, flags @ ..] = args else {
+      return Err(usage.into());
+    };
+    let mut options = Self {
+      output: output.into(),
+      width: 320,
+      height: 180,
+      time: 2.0,
+      duration: 3.0,
+      fps: 10,
+      quality: Quality::High,
+      cpu: false,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+      if flag == "--cpu" {
+        options.cpu = true;
+        continue;
+      }
+      let value = flags
+        .next()
+        .ok_or_else(|| format!("`{flag}` needs a value, {usage}"))?;
+      let invalid = || format!("invalid value `{value}` for `{flag}`");
+      match flag.as_str() {
+        "--size" => (options.width, options.height) = parse_size(value).ok_or_else(invalid)?,
+        "--time" => options.time = value.parse().map_err(|_| invalid())?,
+        "--duration" => options.duration = value.parse().map_err(|_| invalid())?,
+        "--fps" => options.fps = value.parse().map_err(|_| invalid())?,
+        "--quality" => options.quality = parse_quality(value).ok_or_else(invalid)?,
+        _ => return Err(format!("unknown option `{flag}`, {usage}").into()),
+      }
+    }
+    if options.width == 0 || options.height == 0 || options.fps == 0 {
+      return Err("the size and the frame rate must not be zero".into());
+    }
+    if !options.time.is_finite() {
+      return Err("the time must be a finite number of seconds".into());
+    }
+    if !options.duration.is_finite() || options.duration <= 0.0 {
+      return Err("the duration must be positive".into());
+    }
+    Ok(options)
+  }
+
+  /// The number of frames of a preview.
+  #[expect(clippy::cast_sign_loss, reason = "the duration is positive")]
+  fn preview_frames(&self) -> u32 {
+    (self.duration * f32::from(self.fps)).ceil() as u32
+  }
+}
+
+pub fn run(args: &[String], debug: DebugMode) -> Result<(), Box<dyn Error>> {
+  let options = Options::parse(args)?;
+  fs::create_dir_all(&options.output)?;
+  // No audio or videos, every shader sees the procedural sources of its channels.
+  let streams = Streams::default();
+  let mut renderer = if options.cpu {
+    Renderer::Cpu(streams.cpu_channels(None, 0.0))
+  } else {
+    let tile = options.width.max(options.height);
+    let renderer = block_on(GpuRenderer::new(debug, None, tile, &streams))?;
+    Renderer::Gpu(Box::new(renderer))
+  };
+  let mut costs = Vec::new();
+  for (shader, definition) in SHADER_DEFINITIONS.iter().enumerate() {
+    let start = Instant::now();
+    renderer.show(Some(shader));
+    let file = simplify_name(definition.name);
+    let thumbnail = render_frame(&mut renderer, &options, shader, options.time)?;
+    let mut writer = ImageWriter::create(
+      &options.output.join(format!("{file}.png")),
+      options.width,
+      options.height,
+    )?;
+    writer.write_rows(&thumbnail.pixels)?;
+    writer.finish()?;
+    let mut frames = Vec::new();
+    let mut render_times = Vec::new();
+    for frame in 0..options.preview_frames() {
+      let time = frame as f32 / f32::from(options.fps);
+      let frame = render_frame(&mut renderer, &options, shader, time)?;
+      frames.push(frame.pixels);
+      render_times.push(frame.elapsed);
+    }
+    write_preview(
+      &options.output.join(format!("{file}-preview.png")),
+      &options,
+      &frames,
+    )?;
+    // The first frame pays for warming up, unless it is the only one.
+    let timed = &render_times[usize::from(render_times.len() > 1)..];
+    costs.push(timed.iter().sum::<Duration>() / timed.len() as u32);
+    println!("Rendered {} in {:.1?}", definition.name, start.elapsed());
+  }
+  let index = options.output.join("index.html");
+  fs::write(&index, html(&options, &costs))?;
+  println!("Wrote {}", index.display());
+  Ok(())
+}
+
+/// A rendered frame and how long the shader ran, see [`Renderer::render_timed`].
+struct Frame {
+  pixels: Vec<[f32; 4]>,
+  elapsed: Duration,
+}
+
+/// Renders `shader` at `time`.
+fn render_frame(
+  renderer: &mut Renderer<'_>,
+  options: &Options,
+  shader: usize,
+  time: f32,
+) -> Result<Frame, Box<dyn Error>> {
+  let constants = ShaderConstants {
+    width: options.width,
+    height: options.height,
+    time,
+    shader_to_show: shader as u32,
+    quality: options.quality as u32,
+    projection: Projection::Screen as u32,
+    ..ShaderConstants::zeroed()
+  };
+  let (mut pixels, elapsed) = renderer.render_timed(constants, options.width, options.height)?;
+  // Shadertoy shows the image pass opaque, whatever its alpha.
+  for pixel in &mut pixels {
+    pixel[3] = 1.0;
+  }
+  Ok(Frame { pixels, elapsed })
+}
+
+/// Writes `frames` as an animated PNG that loops forever.
+fn write_preview(
+  path: &Path,
+  options: &Options,
+  frames: &[Vec<[f32; 4]>],
+) -> Result<(), Box<dyn Error>> {
+  let file = BufWriter::new(File::create(path)?);
+  let mut encoder = png::Encoder::new(file, options.width, options.height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+  encoder.set_animated(frames.len() as u32, 0)?;
+  encoder.set_frame_delay(1, options.fps)?;
+  let mut writer = encoder.write_header()?;
+  for frame in frames {
+    writer.write_image_data(&srgb_bytes(frame))?;
+  }
+  writer.finish()?;
+  Ok(())
+}
+
+/// The page, with the render cost of each shader: the average time a preview frame after the
+/// first one takes to render.
+fn html(options: &Options, costs: &[Duration]) -> String {
+  let (width, height) = (options.width, options.height);
+  let device = if options.cpu { "CPU" } else { "GPU" };
+  let mut html = format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Shadertoys</title>
+<style>
+  body {{ margin: 2em; background: #111; color: #ccc; font: 14px sans-serif; }}
+  a {{ color: #8bf; }}
+  main {{ display: grid; grid-template-columns: repeat(auto-fill, minmax({width}px, 1fr)); gap: 2em 1.5em; }}
+  figure {{ margin: 0; }}
+  figcaption {{ margin-top: 0.5em; line-height: 1.5; }}
+  .frame {{ position: relative; aspect-ratio: {width} / {height}; background: #000; }}
+  .frame img {{ position: absolute; width: 100%; height: 100%; }}
+  .frame .preview {{ opacity: 0; }}
+  .frame:hover .preview {{ opacity: 1; }}
+</style>
+</head>
+<body>
+<h1>Shadertoys</h1>
+<p>{count} <a href="https://shadertoy.com">shadertoy.com</a> shaders ported to
+<a href="https://github.com/rust-gpu/rust-gpu">Rust-GPU</a>, at {time} seconds. Point at one to
+play its first {duration} seconds. Render times are for {width}x{height} pixels on the {device}
+at the {quality:?} quality level, averaged over the frames of the preview after the first one.
+{measured}</p>
+<main>
+"#,
+    count = SHADER_DEFINITIONS.len(),
+    measured = if options.cpu {
+      "They are the time the shader runs on all cores."
+    } else {
+      "They are the time from submitting the draw to the GPU finishing it, without reading the \
+       pixels back."
+    },
+    time = options.time,
+    duration = options.duration,
+    quality = options.quality,
+  );
+  for (definition, cost) in SHADER_DEFINITIONS.iter().zip(costs) {
+    let file = simplify_name(definition.name);
+    let name = escape(definition.name);
+    let title = match definition.link {
+      Some(link) => format!("<a href=\"{}\">{name}</a>", escape(link)),
+      None => name.clone(),
+    };
+    let author = if definition.author.is_empty() {
+      String::new()
+    } else {
+      format!(" by {}", escape(definition.author))
+    };
+    let origin = if definition.link.is_some() {
+      ""
+    } else {
+      ", written for this repository"
+    };
+    let _ = write!(
+      html,
+      r#"<figure>
+  <div class="frame"><img src="{file}.png" alt="{name}"><img class="preview" src="{file}-preview.png" alt="" loading="lazy"></div>
+  <figcaption><strong>{title}</strong>{author}{origin}<br>{license}, {cost:.1} ms per frame</figcaption>
+</figure>
+"#,
+      license = escape(definition.license),
+      cost = cost.as_secs_f64() * 1000.0,
+    );
+  }
+  html.push_str("</main>\n</body>\n</html>\n");
+  html
+}
+
+/// `text` with the characters that mean something in HTML replaced by references.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
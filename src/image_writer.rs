@@ -0,0 +1,240 @@
+//! Writes images a band of rows at a time, so that the whole image never has to be in memory.
+//!
+//! The format follows the extension of the path: `.png` is 8-bit sRGB, `.exr` is an uncompressed
+//! OpenEXR scanline image with linear 32-bit float channels, which keeps the values above one.
+
+use core::error::Error;
+use std::{
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+};
+
+const EXR_MAGIC: u32 = 20_000_630;
+/// Version 2, single part scanline image.
+const EXR_VERSION: u32 = 2;
+/// `FLOAT` in a channel list.
+const EXR_PIXEL_TYPE_FLOAT: i32 = 2;
+/// The channels in the order of the file, which sorts them by name.
+const EXR_CHANNELS: [(&str, usize); 4] = [("A", 3), ("B", 2), ("G", 1), ("R", 0)];
+
+pub enum ImageWriter {
+  Png(Box<png::StreamWriter<'static, BufWriter<File>>>),
+  Exr {
+    file: BufWriter<File>,
+    width: u32,
+    /// The next row to write.
+    row: u32,
+  },
+}
+
+impl ImageWriter {
+  /// Creates the file at `path` and writes the header of a `width` x `height` image.
+  pub fn create(path: &Path, width: u32, height: u32) -> Result<Self, Box<dyn Error>> {
+    let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+      Some("png") => {
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        Ok(Self::Png(Box::new(
+          encoder.write_header()?.into_stream_writer()?,
+        )))
+      },
+      Some("exr") => {
+        let mut file = BufWriter::new(File::create(path)?);
+        write_exr_header(&mut file, width, height)?;
+        Ok(Self::Exr {
+          file,
+          width,
+          row: 0,
+        })
+      },
+      _ => Err(format!("{} is neither a `.png` nor an `.exr` file", path.display()).into()),
+    }
+  }
+
+  /// Appends the rows in `pixels`, which are linear RGBA colors.
+  pub fn write_rows(&mut self, pixels: &[[f32; 4]]) -> Result<(), Box<dyn Error>> {
+    match self {
+      Self::Png(writer) => writer.write_all(&srgb_bytes(pixels))?,
+      Self::Exr { file, width, row } => {
+        for line in pixels.chunks_exact(*width as usize) {
+          file.write_all(&row.to_le_bytes())?;
+          file.write_all(&exr_row_size(*width).to_le_bytes())?;
+          for (_, component) in EXR_CHANNELS {
+            for pixel in line {
+              file.write_all(&pixel[component].to_le_bytes())?;
+            }
+          }
+          *row += 1;
+        }
+      },
+    }
+    Ok(())
+  }
+
+  /// Flushes the file, after all rows have been written.
+  pub fn finish(self) -> Result<(), Box<dyn Error>> {
+    match self {
+      Self::Png(writer) => writer.finish()?,
+      Self::Exr { mut file, .. } => file.flush()?,
+    }
+    Ok(())
+  }
+}
+
+/// Bytes of pixel data in a row.
+fn exr_row_size(width: u32) -> u32 {
+  width * (EXR_CHANNELS.len() * size_of::<f32>()) as u32
+}
+
+/// The header and the offset table. Without compression every row is its own chunk of a known
+/// size, so the offsets are known before the rows are rendered.
+fn write_exr_header(file: &mut impl Write, width: u32, height: u32) -> std::io::Result<()> {
+  let mut header = Vec::new();
+  header.extend_from_slice(&EXR_MAGIC.to_le_bytes());
+  header.extend_from_slice(&EXR_VERSION.to_le_bytes());
+  let mut attribute = |name: &str, kind: &str, value: &[u8]| {
+    for text in [name, kind] {
+      header.extend_from_slice(text.as_bytes());
+      header.push(0);
+    }
+    header.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    header.extend_from_slice(value);
+  };
+  let mut channels = Vec::new();
+  for (name, _) in EXR_CHANNELS {
+    channels.extend_from_slice(name.as_bytes());
+    channels.push(0);
+    channels.extend_from_slice(&EXR_PIXEL_TYPE_FLOAT.to_le_bytes());
+    // Not perceptually linear, and three reserved bytes.
+    channels.extend_from_slice(&[0; 4]);
+    // No subsampling along x and y.
+    channels.extend_from_slice(&1_i32.to_le_bytes());
+    channels.extend_from_slice(&1_i32.to_le_bytes());
+  }
+  channels.push(0);
+  attribute("channels", "chlist", &channels);
+  attribute("compression", "compression", &[0]);
+  // Signed, but never negative here.
+  let window: Vec<u8> = [0, 0, width - 1, height - 1]
+    .iter()
+    .flat_map(|value| value.to_le_bytes())
+    .collect();
+  attribute("dataWindow", "box2i", &window);
+  attribute("displayWindow", "box2i", &window);
+  // Increasing y.
+  attribute("lineOrder", "lineOrder", &[0]);
+  attribute("pixelAspectRatio", "float", &1.0_f32.to_le_bytes());
+  attribute("screenWindowCenter", "v2f", &[0; 8]);
+  attribute("screenWindowWidth", "float", &1.0_f32.to_le_bytes());
+  header.push(0);
+  file.write_all(&header)?;
+
+  // The row number and the size before the pixels of each row.
+  let chunk_size = 8 + u64::from(exr_row_size(width));
+  let first_chunk = header.len() as u64 + u64::from(height) * 8;
+  for row in 0..u64::from(height) {
+    file.write_all(&(first_chunk + row * chunk_size).to_le_bytes())?;
+  }
+  Ok(())
+}
+
+/// Linear RGBA colors as 8-bit sRGB, the pixel data of a PNG file.
+pub fn srgb_bytes(pixels: &[[f32; 4]]) -> Vec<u8> {
+  pixels
+    .iter()
+    .flat_map(|&[r, g, b, a]| {
+      [
+        linear_to_srgb(r),
+        linear_to_srgb(g),
+        linear_to_srgb(b),
+        unorm8(a),
+      ]
+    })
+    .collect()
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+  let value = value.clamp(0.0, 1.0);
+  unorm8(if value <= 0.003_130_8 {
+    value * 12.92
+  } else {
+    1.055 * value.powf(1.0 / 2.4) - 0.055
+  })
+}
+
+#[expect(clippy::cast_sign_loss, reason = "clamped to `[0, 1]` first")]
+fn unorm8(value: f32) -> u8 {
+  (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::{env, fs, process};
+
+  fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+  }
+
+  fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+  }
+
+  #[test]
+  fn exr_offsets_point_at_the_rows() {
+    let (width, height) = (3, 5);
+    let path = env::temp_dir().join(format!("shadertoys_image_writer_{}.exr", process::id()));
+    let pixels: Vec<[f32; 4]> = (0..width * height)
+      .map(|index| [index as f32, 2.0, 3.0, 1.0])
+      .collect();
+    let mut writer = ImageWriter::create(&path, width, height).unwrap();
+    // In two bands, like the tiles of a poster.
+    let (first, second) = pixels.split_at(2 * width as usize);
+    writer.write_rows(first).unwrap();
+    writer.write_rows(second).unwrap();
+    writer.finish().unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut header = Vec::new();
+    write_exr_header(&mut header, width, height).unwrap();
+    let table = header.len() - height as usize * 8;
+    assert_eq!(bytes[..header.len()], header);
+    assert_eq!(u32_at(&bytes, 0), EXR_MAGIC);
+    let chunk_size = 8 + u64::from(exr_row_size(width));
+    for row in 0..height {
+      let offset = u64_at(&bytes, table + row as usize * 8);
+      let at = usize::try_from(offset).unwrap();
+      assert_eq!(u32_at(&bytes, at), row);
+      assert_eq!(u32_at(&bytes, at + 4), exr_row_size(width));
+      // The `R` channel comes last.
+      let red = at + 8 + 3 * width as usize * 4;
+      let first_red = f32::from_le_bytes(bytes[red..red + 4].try_into().unwrap());
+      assert_eq!(first_red.to_bits(), ((row * width) as f32).to_bits());
+      if row + 1 == height {
+        assert_eq!(offset + chunk_size, bytes.len() as u64);
+      }
+    }
+  }
+
+  #[test]
+  fn exr_rows_hold_four_float_channels() {
+    assert_eq!(exr_row_size(1), 16);
+    assert_eq!(exr_row_size(1920), 1920 * 16);
+  }
+
+  #[test]
+  fn srgb_bytes_encode_linear_colors() {
+    assert_eq!(
+      srgb_bytes(&[[0.0, 0.5, 1.0, 0.5], [-1.0, 2.0, 0.002, 1.0]]),
+      [0, 188, 255, 128, 0, 255, 7, 255]
+    );
+  }
+}
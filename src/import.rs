@@ -0,0 +1,502 @@
+//! Scaffolds ports from Shadertoy exports, the JSON of the Shadertoy API
+//! (`https://www.shadertoy.com/api/v1/shaders/<id>?key=<key>`) saved to a file.
+//!
+//! Run with `cargo run --release -- import <export.json> [--module <name>]`. For every shader of
+//! the export it writes `shaders/src/shaders/<name>.rs` with the `//!` header with the link and
+//! the leading comment of the original, the common and image passes translated to Rust by
+//! [`translate`], and the GLSL of all passes as a comment to check the port against. What the
+//! translator flags is printed and marked with `// TODO:` comments. If the GLSL can't be
+//! translated, the boilerplate of a port takes its place: the
+//! [`ShaderDefinition`](shadertoys_shaders::shader_prelude::ShaderDefinition), `shader_fn`, an
+//! `Inputs` struct and a `main_image` that shows a gradient. It then adds the module to
+//! `shaders/src/shaders/mod.rs` and to `render_shader_macro!`, so the port shows up in the viewer
+//! right away, and formats it with `rustfmt` if that is installed.
+//!
+//! The module name is the name of the shader in snake case unless `--module` is given, which only
+//! works for exports of a single shader.
+
+use crate::{
+  json::Json,
+  poster::simplify_name,
+  translate::{self, Definition, KEYWORDS},
+};
+use core::{error::Error, fmt::Write as _};
+use shadertoys_shaders::shaders::SHADER_DEFINITIONS;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// Where the ports live.
+const SHADERS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/src/shaders");
+
+/// One shader of an export.
+struct Export {
+  id: String,
+  name: String,
+  username: String,
+  passes: Vec<Pass>,
+}
+
+/// A render pass: `Image`, `Common`, `Buffer A`, `Sound` and so on.
+struct Pass {
+  name: String,
+  kind: String,
+  code: String,
+  /// The channel and a description of each input.
+  inputs: Vec<(u32, String)>,
+}
+
+impl Export {
+  /// Reads a shader object, with or without the `Shader` wrapper of the API.
+  fn from_json(json: &Json) -> Result<Self, Box<dyn Error>> {
+    let shader = json.get("Shader").unwrap_or(json);
+    let info = shader.get("info").ok_or("no `info` in the shader")?;
+    let string = |value: &Json, key: &str| {
+      value
+        .get(key)
+        .and_then(Json::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| format!("no `{key}` string"))
+    };
+    let passes = shader
+      .get("renderpass")
+      .ok_or("no `renderpass` in the shader")?
+      .elements()
+      .iter()
+      .map(|pass| {
+        let inputs = pass
+          .get("inputs")
+          .map_or(&[][..], Json::elements)
+          .iter()
+          .map(|input| {
+            #[expect(clippy::cast_sign_loss, reason = "channels are 0 to 3")]
+            let channel = input.get("channel").and_then(Json::as_f64).unwrap_or(0.0) as u32;
+            let kind = input.get("ctype").or_else(|| input.get("type"));
+            let source = input.get("src").or_else(|| input.get("filepath"));
+            let description = [kind, source]
+              .into_iter()
+              .flatten()
+              .filter_map(Json::as_str)
+              .collect::<Vec<_>>()
+              .join(" ");
+            (channel, description)
+          })
+          .collect();
+        Ok(Pass {
+          name: string(pass, "name")?,
+          kind: string(pass, "type")?,
+          code: string(pass, "code")?,
+          inputs,
+        })
+      })
+      .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    Ok(Self {
+      id: string(info, "id")?,
+      name: string(info, "name")?,
+      username: string(info, "username").unwrap_or_default(),
+      passes,
+    })
+  }
+
+  /// The module name in snake case, avoiding digits at the start and keywords.
+  fn module_name(&self) -> String {
+    let mut module = String::new();
+    for c in self.name.chars() {
+      if c.is_ascii_alphanumeric() {
+        module.push(c.to_ascii_lowercase());
+      } else if !module.is_empty() && !module.ends_with('_') {
+        module.push('_');
+      }
+    }
+    let module = module.trim_end_matches('_');
+    if module.is_empty() {
+      format!("shader_{}", self.id.to_ascii_lowercase())
+    } else if module.starts_with(|c: char| c.is_ascii_digit()) || KEYWORDS.contains(&module) {
+      format!("shader_{module}")
+    } else {
+      module.to_owned()
+    }
+  }
+
+  /// The source of the port, and what to look at in it.
+  fn port(&self) -> Result<(String, Vec<String>), Box<dyn Error>> {
+    let image = self
+      .passes
+      .iter()
+      .find(|pass| pass.kind == "image")
+      .ok_or("no image pass")?;
+    let link = format!("https://www.shadertoy.com/view/{}", self.id);
+    let mut port = format!("//! Ported to Rust from <{link}>\n");
+    let comment = leading_comment(&image.code);
+    if comment.is_empty() {
+      writeln!(
+        port,
+        "//!\n//! By {}, under the default license of Shadertoy, Creative Commons \
+         Attribution-NonCommercial-ShareAlike 3.0 Unported.",
+        self.username
+      )?;
+    } else {
+      port.push_str("//!\n//! Original comment:\n//! ```glsl\n");
+      for line in &comment {
+        writeln!(port, "//! {line}")?;
+      }
+      port.push_str("//! ```\n");
+    }
+
+    // The common code goes in front of the image pass, as on Shadertoy.
+    let common = self.passes.iter().find(|pass| pass.kind == "common");
+    let (glsl, common_lines) = match common {
+      Some(common) => (
+        format!("{}\n{}", common.code.trim_end_matches('\n'), image.code),
+        common.code.lines().count(),
+      ),
+      None => (image.code.clone(), 0),
+    };
+    let location = |line: usize| match common {
+      Some(common) if line <= common_lines => format!("{} line {line}", common.name),
+      _ => format!("{} line {}", image.name, line - common_lines),
+    };
+    let mut notes: Vec<String> = self
+      .passes
+      .iter()
+      .filter(|pass| pass.kind != "image" && pass.kind != "common")
+      .map(|pass| format!("{} ({}) is not translated", pass.name, pass.kind))
+      .collect();
+    let definition = Definition {
+      name: &self.name,
+      author: &self.username,
+      license: license(&comment),
+      link: Some(&link),
+    };
+    match translate::translate(&glsl, &definition) {
+      Ok(translation) => {
+        writeln!(port, "\n{}", translation.rust)?;
+        notes.extend(
+          translation
+            .flags
+            .iter()
+            .map(|flag| format!("{}: {}", location(flag.line), flag.message)),
+        );
+        writeln!(
+          port,
+          "// The GLSL of \"{}\" by {}, as translated.",
+          self.name, self.username
+        )?;
+      },
+      Err(flag) => {
+        notes.push(format!(
+          "not translated, {}: {}",
+          location(flag.line),
+          flag.message
+        ));
+        write!(
+          port,
+          r#"
+use crate::shader_prelude::*;
+
+{definition}
+pub fn shader_fn<C: Channel>(
+  render_instruction: &ShaderInput<C>,
+  render_result: &mut ShaderResult,
+) {{
+  let color = &mut render_result.color;
+  let &ShaderInput {{
+    resolution,
+    time,
+    frag_coord,
+    ..
+  }} = render_instruction;
+  Inputs {{ resolution, time }}.main_image(color, frag_coord);
+}}
+
+struct Inputs {{
+  resolution: Vec3,
+  time: f32,
+}}
+
+impl Inputs {{
+  fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {{
+    // A gradient until the GLSL below is ported.
+    let uv = frag_coord / self.resolution.xy();
+    *frag_color = vec4(uv.x, uv.y, 0.5 + 0.5 * self.time.sin(), 1.0);
+  }}
+}}
+
+// The GLSL of "{name}" by {username}, to port from.
+"#,
+          name = self.name,
+          definition = definition,
+          username = self.username,
+        )?;
+      },
+    }
+    for pass in &self.passes {
+      writeln!(port, "//\n// {} ({}):", pass.name, pass.kind)?;
+      for (channel, input) in &pass.inputs {
+        writeln!(port, "// - iChannel{channel}: {input}")?;
+      }
+      port.push_str("//\n");
+      for line in pass.code.lines() {
+        let line = line.replace('\t', "    ");
+        let line = line.trim_end();
+        if line.is_empty() {
+          port.push_str("//\n");
+        } else {
+          writeln!(port, "// {line}")?;
+        }
+      }
+    }
+    Ok((port, notes))
+  }
+}
+
+/// Checks that `module`, given with `--module`, can name a module of the shader crate.
+fn valid_module(module: &str) -> Result<String, Box<dyn Error>> {
+  let mut chars = module.chars();
+  let valid = chars
+    .next()
+    .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+    && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+  if !valid {
+    Err(format!("`{module}` is not a module name, use lowercase letters, digits and `_`").into())
+  } else if KEYWORDS.contains(&module) {
+    Err(format!("`{module}` is a keyword").into())
+  } else {
+    Ok(module.to_owned())
+  }
+}
+
+/// The comment lines at the start of `code`, usually the title, the author and the license.
+fn leading_comment(code: &str) -> Vec<&str> {
+  let mut lines = Vec::new();
+  let mut in_block = false;
+  for line in code.lines() {
+    let trimmed = line.trim();
+    if in_block || trimmed.starts_with("/*") {
+      in_block = !trimmed.contains("*/");
+    } else if !trimmed.is_empty() && !trimmed.starts_with("//") {
+      break;
+    }
+    lines.push(line.trim_end());
+  }
+  while lines.last().is_some_and(|line| line.is_empty()) {
+    lines.pop();
+  }
+  while lines.first().is_some_and(|line| line.is_empty()) {
+    lines.remove(0);
+  }
+  lines
+}
+
+/// The license stated in the leading comment of a shader, as in
+/// [`ShaderDefinition::license`](shadertoys_shaders::shader_prelude::ShaderDefinition::license).
+fn license(comment: &[&str]) -> &'static str {
+  let comment = comment.join("\n");
+  let states = |names: &[&str]| names.iter().any(|name| comment.contains(name));
+  if states(&["MIT License"]) {
+    "MIT"
+  } else if states(&["CC0", "publicdomain/zero"]) {
+    "CC0 1.0"
+  } else if states(&["NonCommercial-ShareAlike 4.0", "by-nc-sa/4.0"]) {
+    "CC BY-NC-SA 4.0"
+  } else if states(&["Attribution 4.0", "licenses/by/4.0"]) {
+    "CC BY 4.0"
+  } else {
+    // Shadertoy's default, which most shaders state anyway.
+    "CC BY-NC-SA 3.0"
+  }
+}
+
+/// Adds `module` to the `mod` declarations, in order, and to the end of
+/// `render_shader_macro!`.
+fn register(registry: &str, module: &str) -> Result<String, Box<dyn Error>> {
+  let invalid = || "unexpected layout of `shaders/src/shaders/mod.rs`";
+  let declaration = format!("mod {module};\n");
+  let mut lines: Vec<&str> = registry.split_inclusive('\n').collect();
+  let mods = lines
+    .iter()
+    .position(|line| line.starts_with("mod "))
+    .ok_or_else(invalid)?;
+  let end = mods
+    + lines[mods..]
+      .iter()
+      .take_while(|line| line.starts_with("mod "))
+      .count();
+  let at = (mods..end)
+    .find(|&i| lines[i] > declaration.as_str())
+    .unwrap_or(end);
+  lines.insert(at, &declaration);
+  let mut registry = lines.concat();
+
+  let invocation = registry
+    .rfind("render_shader_macro!(\n")
+    .ok_or_else(invalid)?;
+  let close = invocation + registry[invocation..].find("\n);").ok_or_else(invalid)?;
+  registry.insert_str(close + 1, &format!("  {module},\n"));
+  Ok(registry)
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+  let usage = "usage: import <export.json> [--module <name>]";
+  let (path, module) = match args {
+    [path] => (path, None),
+    [path, flag, module] if flag == "--module" => (path, Some(valid_module(module)?)),
+    _ => return Err(usage.into()),
+  };
+  let text = fs::read_to_string(path).map_err(|e| format!("failed to read {path}: {e}"))?;
+  let json = Json::parse(&text).map_err(|e| format!("{path}: {e}"))?;
+  let exports = match &json {
+    Json::Array(shaders) => shaders
+      .iter()
+      .map(Export::from_json)
+      .collect::<Result<Vec<_>, _>>(),
+    shader => Export::from_json(shader).map(|export| vec![export]),
+  }
+  .map_err(|e| format!("{path}: {e}"))?;
+  if module.is_some() && exports.len() != 1 {
+    return Err("`--module` needs an export of a single shader".into());
+  }
+  let shaders_dir = Path::new(SHADERS_DIR);
+  let registry_path = shaders_dir.join("mod.rs");
+  let mut registry = fs::read_to_string(&registry_path)?;
+  let mut names: Vec<String> = SHADER_DEFINITIONS
+    .iter()
+    .map(|definition| simplify_name(definition.name))
+    .collect();
+  let mut ports = Vec::new();
+  for export in &exports {
+    let module = module.clone().unwrap_or_else(|| export.module_name());
+    let port_path: PathBuf = shaders_dir.join(format!("{module}.rs"));
+    if port_path.exists() {
+      return Err(format!("{} exists already", port_path.display()).into());
+    }
+    if names.contains(&simplify_name(&export.name)) {
+      return Err(format!("there is a shader named `{}` already", export.name).into());
+    }
+    names.push(simplify_name(&export.name));
+    registry = register(&registry, &module)?;
+    ports.push((port_path, export.port()?, &export.name));
+  }
+  // Nothing is written unless every shader could be scaffolded.
+  for (port_path, (port, notes), name) in &ports {
+    fs::write(port_path, port)?;
+    println!("Wrote {} for {name}", port_path.display());
+    for note in notes {
+      println!("  {note}");
+    }
+    // The translation comes out unformatted.
+    let formatted = Command::new("rustfmt")
+      .args(["--edition", "2021"])
+      .arg(port_path)
+      .current_dir(env!("CARGO_MANIFEST_DIR"))
+      .status();
+    if !formatted.is_ok_and(|status| status.success()) {
+      println!("  not formatted, run `cargo fmt`");
+    }
+  }
+  fs::write(&registry_path, registry)?;
+  println!("Registered in {}", registry_path.display());
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn export(name: &str) -> Export {
+    Export {
+      id: "XsBXWt".to_owned(),
+      name: name.to_owned(),
+      username: String::new(),
+      passes: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn module_names() {
+    assert_eq!(export("Seascape").module_name(), "seascape");
+    assert_eq!(export("Protean clouds").module_name(), "protean_clouds");
+    assert_eq!(export("  A -- B (v2)! ").module_name(), "a_b_v2");
+    assert_eq!(export("2D clouds").module_name(), "shader_2d_clouds");
+    assert_eq!(export("Loop").module_name(), "shader_loop");
+    assert_eq!(export("???").module_name(), "shader_xsbxwt");
+  }
+
+  #[test]
+  fn module_flags() {
+    assert_eq!(valid_module("my_port2").unwrap(), "my_port2");
+    assert_eq!(valid_module("_port").unwrap(), "_port");
+    for invalid in [
+      "", "2d", "MyPort", "my-port", "my port", "café", "fn", "match", "mod",
+    ] {
+      assert!(valid_module(invalid).is_err(), "`{invalid}` is accepted");
+    }
+  }
+
+  #[test]
+  fn register_keeps_the_order() {
+    let registry = include_str!(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/shaders/src/shaders/mod.rs"
+    ));
+    let registered = register(registry, "a_a_port").unwrap();
+    assert_eq!(registered.matches("mod a_a_port;\n").count(), 1);
+    let mods: Vec<&str> = registered
+      .lines()
+      .filter(|line| line.starts_with("mod "))
+      .collect();
+    assert!(mods.is_sorted());
+    assert_eq!(mods[0], "mod a_a_port;");
+    assert!(registered.ends_with("  a_a_port,\n);\n"));
+    assert_eq!(
+      registered.len(),
+      registry.len() + "mod a_a_port;\n  a_a_port,\n".len()
+    );
+
+    let registered = register(&registered, "zzz_port").unwrap();
+    let mods: Vec<&str> = registered
+      .lines()
+      .filter(|line| line.starts_with("mod "))
+      .collect();
+    assert_eq!(mods.last(), Some(&"mod zzz_port;"));
+    assert!(registered.ends_with("  a_a_port,\n  zzz_port,\n);\n"));
+
+    assert!(register("use crate::shader_prelude::*;\n", "port").is_err());
+    assert!(register("mod a;\n", "port").is_err());
+  }
+
+  #[test]
+  fn leading_comments() {
+    let code =
+      "\n// Seascape\n// by TDM\n\n/*\n * License\n */\nconst int NUM_STEPS = 8;\n// later\n";
+    assert_eq!(
+      leading_comment(code),
+      ["// Seascape", "// by TDM", "", "/*", " * License", " */"]
+    );
+    assert!(leading_comment("void main() {}\n// no").is_empty());
+    assert!(leading_comment("").is_empty());
+  }
+
+  #[test]
+  fn licenses() {
+    assert_eq!(license(&["// The MIT License", "// Copyright 2020"]), "MIT");
+    assert_eq!(license(&["// CC0: public domain"]), "CC0 1.0");
+    assert_eq!(
+      license(&[
+        "// License Creative Commons Attribution-NonCommercial-ShareAlike 4.0 International."
+      ]),
+      "CC BY-NC-SA 4.0"
+    );
+    assert_eq!(
+      license(&["// https://creativecommons.org/licenses/by/4.0/"]),
+      "CC BY 4.0"
+    );
+    assert_eq!(
+      license(&["// License Creative Commons Attribution-NonCommercial-ShareAlike 3.0 Unported"]),
+      "CC BY-NC-SA 3.0"
+    );
+    assert_eq!(license(&[]), "CC BY-NC-SA 3.0");
+  }
+}
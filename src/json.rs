@@ -0,0 +1,294 @@
+//! A small JSON reader, enough for the Shadertoy exports read by `import`.
+
+use core::error::Error;
+
+pub enum Json {
+  Null,
+  #[expect(dead_code, reason = "`import` reads no flags")]
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Self>),
+  /// The members in the order of the file.
+  Object(Vec<(String, Self)>),
+}
+
+impl Json {
+  pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+    let mut parser = Parser { text, position: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < text.len() {
+      return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+  }
+
+  /// The member `key` of an object.
+  pub fn get(&self, key: &str) -> Option<&Self> {
+    match self {
+      Self::Object(members) => members
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Self::String(string) => Some(string),
+      _ => None,
+    }
+  }
+
+  pub const fn as_f64(&self) -> Option<f64> {
+    match self {
+      Self::Number(number) => Some(*number),
+      _ => None,
+    }
+  }
+
+  /// The elements of an array, nothing for other values.
+  pub fn elements(&self) -> &[Self] {
+    match self {
+      Self::Array(elements) => elements,
+      _ => &[],
+    }
+  }
+}
+
+struct Parser<'a> {
+  text: &'a str,
+  /// In bytes.
+  position: usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, what: &str) -> Box<dyn Error> {
+    let line = self.text[..self.position].matches('\n').count() + 1;
+    format!("invalid JSON, {what} on line {line}").into()
+  }
+
+  fn rest(&self) -> &str {
+    &self.text[self.position..]
+  }
+
+  fn skip_whitespace(&mut self) {
+    let rest = self.rest();
+    self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+  }
+
+  fn expect(&mut self, token: &str) -> Result<(), Box<dyn Error>> {
+    if self.rest().starts_with(token) {
+      self.position += token.len();
+      Ok(())
+    } else {
+      Err(self.error(&format!("expected `{token}`")))
+    }
+  }
+
+  fn value(&mut self) -> Result<Json, Box<dyn Error>> {
+    self.skip_whitespace();
+    match self.rest().as_bytes().first() {
+      Some(b'{') => self.object(),
+      Some(b'[') => self.array(),
+      Some(b'"') => self.string().map(Json::String),
+      Some(b't') => self.expect("true").map(|()| Json::Bool(true)),
+      Some(b'f') => self.expect("false").map(|()| Json::Bool(false)),
+      Some(b'n') => self.expect("null").map(|()| Json::Null),
+      Some(b'-' | b'0'..=b'9') => self.number(),
+      Some(_) => Err(self.error("unexpected character")),
+      None => Err(self.error("unexpected end")),
+    }
+  }
+
+  fn object(&mut self) -> Result<Json, Box<dyn Error>> {
+    self.expect("{")?;
+    let mut members = Vec::new();
+    self.skip_whitespace();
+    if self.expect("}").is_ok() {
+      return Ok(Json::Object(members));
+    }
+    loop {
+      self.skip_whitespace();
+      let key = self.string()?;
+      self.skip_whitespace();
+      self.expect(":")?;
+      members.push((key, self.value()?));
+      self.skip_whitespace();
+      if self.expect(",").is_err() {
+        self.expect("}")?;
+        return Ok(Json::Object(members));
+      }
+    }
+  }
+
+  fn array(&mut self) -> Result<Json, Box<dyn Error>> {
+    self.expect("[")?;
+    let mut elements = Vec::new();
+    self.skip_whitespace();
+    if self.expect("]").is_ok() {
+      return Ok(Json::Array(elements));
+    }
+    loop {
+      elements.push(self.value()?);
+      self.skip_whitespace();
+      if self.expect(",").is_err() {
+        self.expect("]")?;
+        return Ok(Json::Array(elements));
+      }
+    }
+  }
+
+  fn number(&mut self) -> Result<Json, Box<dyn Error>> {
+    let length = self
+      .rest()
+      .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+      .unwrap_or_else(|| self.rest().len());
+    let number = self.rest()[..length]
+      .parse()
+      .map_err(|_| self.error("invalid number"))?;
+    self.position += length;
+    Ok(Json::Number(number))
+  }
+
+  fn string(&mut self) -> Result<String, Box<dyn Error>> {
+    self.expect("\"")?;
+    let mut string = String::new();
+    loop {
+      let rest = self.rest();
+      let end = rest
+        .find(['"', '\\'])
+        .ok_or_else(|| self.error("unterminated string"))?;
+      string.push_str(&rest[..end]);
+      let closed = rest.as_bytes()[end] == b'"';
+      self.position += end + 1;
+      if closed {
+        return Ok(string);
+      }
+      let escape = self
+        .rest()
+        .chars()
+        .next()
+        .ok_or_else(|| self.error("unterminated string"))?;
+      self.position += escape.len_utf8();
+      string.push(match escape {
+        '"' | '\\' | '/' => escape,
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => self.unicode_escape()?,
+        _ => return Err(self.error("invalid escape")),
+      });
+    }
+  }
+
+  /// The character of a `\u` escape, whose `\u` was read already, including surrogate pairs.
+  fn unicode_escape(&mut self) -> Result<char, Box<dyn Error>> {
+    let high = self.hex4()?;
+    let code = if (0xD800..0xDC00).contains(&high) {
+      self.expect("\\u")?;
+      let low = self.hex4()?;
+      if !(0xDC00..0xE000).contains(&low) {
+        return Err(self.error("invalid surrogate pair"));
+      }
+      0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+    } else {
+      high
+    };
+    char::from_u32(code).ok_or_else(|| self.error("invalid character"))
+  }
+
+  fn hex4(&mut self) -> Result<u32, Box<dyn Error>> {
+    let digits = self
+      .rest()
+      .get(..4)
+      .ok_or_else(|| self.error("invalid escape"))?;
+    let value = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid escape"))?;
+    self.position += 4;
+    Ok(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn string(text: &str) -> String {
+    Json::parse(text).unwrap().as_str().unwrap().to_owned()
+  }
+
+  fn number(text: &str) -> f64 {
+    Json::parse(text).unwrap().as_f64().unwrap()
+  }
+
+  #[test]
+  fn strings_unescape() {
+    assert_eq!(string(r#""plain""#), "plain");
+    assert_eq!(string(r#""\"\\\/\b\f\n\r\t""#), "\"\\/\u{8}\u{c}\n\r\t");
+    assert_eq!(string(r#""caf\u00e9 \u00E9""#), "café é");
+    // A surrogate pair.
+    assert_eq!(string(r#""\ud83d\ude00""#), "\u{1F600}");
+    assert_eq!(string("\"déjà vu\""), "déjà vu");
+  }
+
+  #[test]
+  fn invalid_strings_are_rejected() {
+    for text in [
+      r#""open"#,
+      r#""\"#,
+      r#""\q""#,
+      r#""\u12""#,
+      r#""\u12g4""#,
+      r#""\ud83d""#,
+      r#""\ud83dA""#,
+    ] {
+      assert!(Json::parse(text).is_err(), "{text}");
+    }
+  }
+
+  #[test]
+  fn numbers_parse() {
+    assert!((number("0") - 0.0).abs() < f64::EPSILON);
+    assert!((number("-12") + 12.0).abs() < f64::EPSILON);
+    assert!((number("3.25") - 3.25).abs() < f64::EPSILON);
+    assert!((number("1e3") - 1000.0).abs() < f64::EPSILON);
+    assert!((number("-2.5E-2") + 0.025).abs() < f64::EPSILON);
+    assert!((number(" 7 ") - 7.0).abs() < f64::EPSILON);
+    for text in ["-", "1.2.3", "1e", "12a"] {
+      assert!(Json::parse(text).is_err(), "{text}");
+    }
+  }
+
+  #[test]
+  fn nested_values_parse() {
+    let json = Json::parse(
+      r#"{ "Shader": { "info": { "name": "Test", "likes": 3 },
+         "renderpass": [ { "code": "x" }, {} ], "flag": true, "none": null } }"#,
+    )
+    .unwrap();
+    let shader = json.get("Shader").unwrap();
+    let info = shader.get("info").unwrap();
+    assert_eq!(info.get("name").and_then(Json::as_str), Some("Test"));
+    assert_eq!(info.get("likes").and_then(Json::as_f64), Some(3.0));
+    let passes = shader.get("renderpass").unwrap().elements();
+    assert_eq!(passes.len(), 2);
+    assert_eq!(passes[0].get("code").and_then(Json::as_str), Some("x"));
+    assert!(passes[1].get("code").is_none());
+    assert!(matches!(shader.get("flag"), Some(Json::Bool(_))));
+    assert!(matches!(shader.get("none"), Some(Json::Null)));
+    assert!(Json::parse("[]").unwrap().elements().is_empty());
+  }
+
+  #[test]
+  fn errors_report_the_line() {
+    let error = Json::parse("{\n  \"a\": 1,\n  \"b\" 2\n}").err().unwrap();
+    assert_eq!(error.to_string(), "invalid JSON, expected `:` on line 3");
+    assert!(Json::parse("[1, 2] 3").is_err());
+    assert!(Json::parse("[1, 2").is_err());
+    assert!(Json::parse("").is_err());
+  }
+}
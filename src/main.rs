@@ -1,17 +1,69 @@
+extern crate alloc;
+
+use accumulation::Accumulation;
+use bytemuck::Zeroable;
+use channels::{Channels, StreamSizes, Streams};
+use core::{
+  future::Future,
+  sync::atomic::{AtomicBool, Ordering},
+  time::Duration,
+};
+use free_camera::FreeCameraController;
+#[cfg(not(target_arch = "wasm32"))]
 use futures::executor::block_on;
 use ouroboros::self_referencing;
+use params::{ParamEditor, ParamsBuffer};
+use quality::QualityController;
 use shadertoys_shaders::{shaders::SHADER_DEFINITIONS, shared_data::ShaderConstants};
-use std::{error::Error, time::Instant};
-use wgpu::{self, include_spirv, include_spirv_raw, InstanceDescriptor};
+use std::{error::Error, rc::Rc, sync::Arc};
+use stereo::StereoController;
+use web_time::Instant;
+use wgpu::{self, InstanceDescriptor};
 use winit::{
   application::ApplicationHandler,
   dpi::LogicalSize,
   event::{ElementState, KeyEvent, MouseButton, WindowEvent},
-  event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-  keyboard::{KeyCode, NamedKey, PhysicalKey},
+  event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+  keyboard::{KeyCode, ModifiersState, NamedKey, PhysicalKey},
   window::{Window, WindowAttributes, WindowId},
 };
 
+mod accumulation;
+mod audio;
+mod channels;
+#[cfg(not(target_arch = "wasm32"))]
+mod contact_sheet;
+#[cfg(not(target_arch = "wasm32"))]
+mod font;
+mod free_camera;
+#[cfg(not(target_arch = "wasm32"))]
+mod gallery;
+#[cfg(not(target_arch = "wasm32"))]
+mod image_writer;
+#[cfg(not(target_arch = "wasm32"))]
+mod import;
+#[cfg(not(target_arch = "wasm32"))]
+mod json;
+mod params;
+#[cfg(not(target_arch = "wasm32"))]
+mod poster;
+mod quality;
+#[cfg(not(target_arch = "wasm32"))]
+mod sound;
+#[cfg(not(target_arch = "wasm32"))]
+mod spirv_stats;
+mod stereo;
+#[cfg(not(target_arch = "wasm32"))]
+mod translate;
+mod video;
+#[cfg_attr(
+  target_arch = "wasm32",
+  allow(dead_code, reason = "the web build only reads audio files")
+)]
+mod wav;
+
+const WINDOW_TITLE: &str = "Rust GPU - wgpu";
+
 #[self_referencing]
 struct WindowSurface {
   window: Box<Window>,
@@ -20,13 +72,457 @@ struct WindowSurface {
   surface: wgpu::Surface<'this>,
 }
 
+/// How [`ShaderConstants`] are passed to the fragment shader.
+enum ConstantsBinding {
+  /// Push constants, used with `main_fs` whenever the adapter supports them.
+  PushConstants,
+  /// A uniform buffer in bind group 2, used with `main_fs_uniform` as a fallback.
+  UniformBuffer {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+  },
+}
+
+impl ConstantsBinding {
+  /// Uniform buffer sizes have to be a multiple of 16 bytes.
+  const UNIFORM_BUFFER_SIZE: u64 = (size_of::<ShaderConstants>() as u64).next_multiple_of(16);
+
+  /// Picks push constants when the adapter supports them, unless the
+  /// `SHADERTOYS_FORCE_UNIFORM_BUFFER` environment variable is set.
+  fn use_push_constants(adapter: &wgpu::Adapter) -> bool {
+    adapter.features().contains(wgpu::Features::PUSH_CONSTANTS)
+      && std::env::var_os("SHADERTOYS_FORCE_UNIFORM_BUFFER").is_none()
+  }
+
+  /// The pipeline layout of the shaders, with the channels in bind group 0, the params in bind
+  /// group 1 and the constants bound the chosen way.
+  fn create(
+    device: &wgpu::Device,
+    debug: DebugMode,
+    use_push_constants: bool,
+    channels: &Channels,
+    params: &ParamsBuffer,
+  ) -> (wgpu::PipelineLayout, Self) {
+    if use_push_constants {
+      let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: debug.label("shadertoys push constants pipeline layout"),
+        bind_group_layouts: &[&channels.bind_group_layout, &params.bind_group_layout],
+        push_constant_ranges: &[wgpu::PushConstantRange {
+          stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+          range: 0..std::mem::size_of::<ShaderConstants>() as u32,
+        }],
+      });
+      return (pipeline_layout, Self::PushConstants);
+    }
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: debug.label("shader constants bind group layout"),
+      entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      }],
+    });
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: debug.label("shader constants uniform buffer"),
+      size: Self::UNIFORM_BUFFER_SIZE,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: debug.label("shader constants bind group"),
+      layout: &bind_group_layout,
+      entries: &[wgpu::BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: debug.label("shadertoys uniform buffer pipeline layout"),
+      bind_group_layouts: &[
+        &channels.bind_group_layout,
+        &params.bind_group_layout,
+        &bind_group_layout,
+      ],
+      push_constant_ranges: &[],
+    });
+    (pipeline_layout, Self::UniformBuffer { buffer, bind_group })
+  }
+
+  const fn fragment_entry_point(&self) -> &'static str {
+    match self {
+      Self::PushConstants => "main_fs",
+      Self::UniformBuffer { .. } => "main_fs_uniform",
+    }
+  }
+
+  /// Writes `constants` to the uniform buffer, before the commands using them are submitted.
+  fn write(&self, queue: &wgpu::Queue, constants: &ShaderConstants) {
+    if let Self::UniformBuffer { buffer, .. } = self {
+      queue.write_buffer(buffer, 0, bytemuck::bytes_of(constants));
+    }
+  }
+
+  /// Binds `constants`, which have been passed to [`Self::write`] for the uniform buffer.
+  fn bind(&self, rpass: &mut wgpu::RenderPass<'_>, constants: &ShaderConstants) {
+    match self {
+      Self::PushConstants => rpass.set_push_constants(
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        0,
+        bytemuck::bytes_of(constants),
+      ),
+      Self::UniformBuffer { bind_group, .. } => {
+        rpass.set_bind_group(2, bind_group, &[]);
+      },
+    }
+  }
+}
+
+/// Requests a device with the features the shaders can use on `adapter`. Also returns whether
+/// the constants are passed as push constants, see [`ConstantsBinding`].
+async fn request_device(
+  adapter: &wgpu::Adapter,
+  debug: DebugMode,
+) -> Result<(wgpu::Device, wgpu::Queue, bool), wgpu::RequestDeviceError> {
+  let use_push_constants = ConstantsBinding::use_push_constants(adapter);
+  let mut required_features = wgpu::Features::empty();
+  let mut required_limits = wgpu::Limits::default();
+  if use_push_constants {
+    required_features |= wgpu::Features::PUSH_CONSTANTS;
+    required_limits.max_push_constant_size = 256;
+  }
+  if adapter
+    .features()
+    .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
+  {
+    required_features |= wgpu::Features::SPIRV_SHADER_PASSTHROUGH;
+  }
+  let (device, queue) = adapter
+    .request_device(&wgpu::DeviceDescriptor {
+      label: debug.label("shadertoys device"),
+      required_features,
+      required_limits,
+      ..Default::default()
+    })
+    .await?;
+  Ok((device, queue, use_push_constants))
+}
+
+/// Everything that depends on the device.
+struct Gpu {
+  device: wgpu::Device,
+  queue: wgpu::Queue,
+  config: wgpu::SurfaceConfiguration,
+  render_pipeline: wgpu::RenderPipeline,
+  /// Draws the param overlay on top, see [`ParamEditor`].
+  overlay_pipeline: wgpu::RenderPipeline,
+  constants_binding: ConstantsBinding,
+  /// Bind group 0, see [`Channels`].
+  channels: Channels,
+  /// Bind group 1.
+  params: ParamsBuffer,
+  /// Used while time is paused.
+  accumulation: Accumulation,
+  /// Set from the device lost callback, the app then rebuilds everything in here.
+  device_lost: Arc<AtomicBool>,
+  /// See [`DebugMode`].
+  debug: DebugMode,
+}
+
+/// Enabled with `--debug` or the `SHADERTOYS_DEBUG` environment variable. Turns on wgpu
+/// validation, labels every object and checks for errors with error scopes, naming the shader
+/// that caused them.
+#[derive(Clone, Copy)]
+struct DebugMode(bool);
+
+impl DebugMode {
+  fn from_args(args: &mut Vec<String>) -> Self {
+    let flag = args.iter().position(|arg| arg == "--debug");
+    if let Some(index) = flag {
+      args.remove(index);
+    }
+    Self(flag.is_some() || std::env::var_os("SHADERTOYS_DEBUG").is_some())
+  }
+
+  fn instance_flags(self) -> wgpu::InstanceFlags {
+    let mut instance_flags = wgpu::InstanceFlags::default();
+    if self.0 {
+      instance_flags |= wgpu::InstanceFlags::debugging();
+    } else {
+      // Turn off validation as the shaders are trusted.
+      instance_flags.remove(wgpu::InstanceFlags::VALIDATION);
+      // Disable debugging info to speed things up.
+      instance_flags.remove(wgpu::InstanceFlags::DEBUG);
+    }
+    instance_flags
+  }
+
+  /// Labels only cost time when nobody is looking at them.
+  const fn label(self, label: &'static str) -> Option<&'static str> {
+    if self.0 {
+      Some(label)
+    } else {
+      None
+    }
+  }
+
+  fn push_error_scope(self, device: &wgpu::Device) {
+    if self.0 {
+      device.push_error_scope(wgpu::ErrorFilter::Validation);
+    }
+  }
+
+  /// Pops the scope pushed by [`Self::push_error_scope`].
+  fn pop_error_scope(
+    self,
+    device: &wgpu::Device,
+  ) -> impl Future<Output = Option<wgpu::Error>> + use<> {
+    let error = self.0.then(|| device.pop_error_scope());
+    async move {
+      match error {
+        Some(error) => error.await,
+        None => None,
+      }
+    }
+  }
+
+  /// Pops the scope pushed by [`Self::push_error_scope`] and reports its error, if any. Blocks on
+  /// desktop, on the web the error is reported once it arrives.
+  fn report_error_scope(self, device: &wgpu::Device, context: impl FnOnce() -> String) {
+    if !self.0 {
+      return;
+    }
+    let context = context();
+    let error = self.pop_error_scope(device);
+    let report = async move {
+      if let Some(error) = error.await {
+        eprintln!("Error {context}: {error}");
+      }
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    block_on(report);
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(report);
+  }
+}
+
+impl Gpu {
+  async fn new(
+    instance: &wgpu::Instance,
+    window_surface: &WindowSurface,
+    debug: DebugMode,
+    stream_sizes: StreamSizes,
+  ) -> Result<Self, Box<dyn Error>> {
+    let window_size = window_surface.borrow_window().inner_size();
+    let surface = window_surface.borrow_surface();
+
+    let adapter = instance
+      .request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: Some(surface),
+        force_fallback_adapter: false,
+      })
+      .await?;
+    let (device, queue, use_push_constants) = request_device(&adapter, debug).await?;
+    let device_lost = Arc::new(AtomicBool::new(false));
+    {
+      let device_lost = Arc::clone(&device_lost);
+      device.set_device_lost_callback(move |reason, message| {
+        // `Destroyed` means we dropped the device ourselves.
+        if reason != wgpu::DeviceLostReason::Destroyed {
+          eprintln!("Device lost: {message}");
+          device_lost.store(true, Ordering::Release);
+        }
+      });
+    }
+    debug.push_error_scope(&device);
+    let shader_module = create_shader_module(&device, use_push_constants);
+    // The shaders output linear colors, so render through an sRGB view even if the surface
+    // itself is not sRGB (as is usually the case on the web).
+    let surface_format = surface.get_capabilities(&adapter).formats[0];
+    let swapchain_format = surface_format.add_srgb_suffix();
+    let channels = Channels::new(&device, &queue, debug, stream_sizes);
+    let params = ParamsBuffer::new(&device, debug);
+    let (pipeline_layout, constants_binding) =
+      ConstantsBinding::create(&device, debug, use_push_constants, &channels, &params);
+    let render_pipeline = create_render_pipeline(
+      &device,
+      debug.label("shadertoys render pipeline"),
+      &pipeline_layout,
+      &shader_module,
+      constants_binding.fragment_entry_point(),
+      swapchain_format,
+      Some(wgpu::BlendState::REPLACE),
+    );
+    // Uses the same layout, so the bind groups stay bound between the two draws.
+    let overlay_pipeline = create_render_pipeline(
+      &device,
+      debug.label("param overlay render pipeline"),
+      &pipeline_layout,
+      &shader_module,
+      "main_overlay_fs",
+      swapchain_format,
+      Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+    );
+    let accumulation = Accumulation::new(
+      &device,
+      debug,
+      &shader_module,
+      &pipeline_layout,
+      constants_binding.fragment_entry_point(),
+      swapchain_format,
+    );
+    if let Some(error) = debug.pop_error_scope(&device).await {
+      return Err(
+        format!(
+          "creating the render pipelines (`main_vs`, `{}`, `main_overlay_fs` and `main_blit_fs`) \
+           failed: {error}",
+          constants_binding.fragment_entry_point()
+        )
+        .into(),
+      );
+    }
+    let config = wgpu::SurfaceConfiguration {
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+      format: surface_format,
+      // The window may not have a size yet (e.g. while minimized or before the canvas is laid
+      // out), the surface is reconfigured on the next resize.
+      width: window_size.width.max(1),
+      height: window_size.height.max(1),
+      present_mode: wgpu::PresentMode::Fifo,
+      alpha_mode: wgpu::CompositeAlphaMode::Auto,
+      view_formats: if swapchain_format == surface_format {
+        vec![]
+      } else {
+        vec![swapchain_format]
+      },
+      desired_maximum_frame_latency: Default::default(),
+    };
+    surface.configure(&device, &config);
+
+    Ok(Self {
+      device,
+      queue,
+      config,
+      render_pipeline,
+      overlay_pipeline,
+      constants_binding,
+      channels,
+      params,
+      accumulation,
+      device_lost,
+      debug,
+    })
+  }
+}
+
+/// A pipeline drawing a full screen triangle with `fragment_entry_point`.
+fn create_render_pipeline(
+  device: &wgpu::Device,
+  label: Option<&str>,
+  layout: &wgpu::PipelineLayout,
+  shader_module: &wgpu::ShaderModule,
+  fragment_entry_point: &str,
+  format: wgpu::TextureFormat,
+  blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+  device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label,
+    layout: Some(layout),
+    vertex: wgpu::VertexState {
+      module: shader_module,
+      entry_point: Some("main_vs"),
+      buffers: &[],
+      compilation_options: wgpu::PipelineCompilationOptions::default(),
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: shader_module,
+      entry_point: Some(fragment_entry_point),
+      targets: &[Some(wgpu::ColorTargetState {
+        format,
+        blend,
+        write_mask: wgpu::ColorWrites::ALL,
+      })],
+      compilation_options: wgpu::PipelineCompilationOptions::default(),
+    }),
+    primitive: wgpu::PrimitiveState {
+      topology: wgpu::PrimitiveTopology::TriangleList,
+      ..Default::default()
+    },
+    depth_stencil: None,
+    multisample: wgpu::MultisampleState::default(),
+    multiview: None,
+    cache: None,
+  })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_shader_module(device: &wgpu::Device, use_push_constants: bool) -> wgpu::ShaderModule {
+  if device
+    .features()
+    .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
+  {
+    let x = wgpu::include_spirv_raw!(env!("shadertoys_shaders.spv"));
+    unsafe { device.create_shader_module_passthrough(x) }
+  } else if use_push_constants {
+    device.create_shader_module(wgpu::include_spirv!(env!("shadertoys_shaders.spv")))
+  } else {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some(env!("shadertoys_shaders.spv")),
+      source: uniform_buffer_source(),
+    })
+  }
+}
+
+/// Without `Features::PUSH_CONSTANTS` naga rejects the push constants of `main_fs`, so drop that
+/// entry point and demote its constants like `build.rs` does for the web. Parse errors are left
+/// for wgpu to report.
+#[cfg(not(target_arch = "wasm32"))]
+fn uniform_buffer_source() -> wgpu::ShaderSource<'static> {
+  use alloc::borrow::Cow;
+  use wgpu::naga::{front::spv, AddressSpace};
+
+  let options = spv::Options {
+    adjust_coordinate_space: false,
+    strict_capabilities: true,
+    block_ctx_dump_prefix: None,
+  };
+  let spirv = include_bytes!(env!("shadertoys_shaders.spv"));
+  let Ok(mut module) = spv::parse_u8_slice(spirv, &options) else {
+    return wgpu::include_spirv!(env!("shadertoys_shaders.spv")).source;
+  };
+  module
+    .entry_points
+    .retain(|entry_point| entry_point.name != "main_fs");
+  for (_, global) in module.global_variables.iter_mut() {
+    if global.space == AddressSpace::PushConstant {
+      global.space = AddressSpace::Private;
+    }
+  }
+  wgpu::ShaderSource::Naga(Cow::Owned(module))
+}
+
+/// WebGPU only accepts WGSL, `build.rs` translates the SPIR-V module when targeting the web.
+#[cfg(target_arch = "wasm32")]
+fn create_shader_module(device: &wgpu::Device, _use_push_constants: bool) -> wgpu::ShaderModule {
+  device.create_shader_module(wgpu::include_wgsl!(env!("shadertoys_shaders.wgsl")))
+}
+
+enum UserEvent {
+  /// Device setup is asynchronous on the web, so its result arrives as an event.
+  GpuReady(Result<Gpu, String>),
+}
+
 struct ShaderToyApp {
-  device: Option<wgpu::Device>,
-  queue: Option<wgpu::Queue>,
-  window_surface: Option<WindowSurface>,
-  config: Option<wgpu::SurfaceConfiguration>,
-  render_pipeline: Option<wgpu::RenderPipeline>,
-  shader_module: Option<wgpu::ShaderModule>,
+  proxy: EventLoopProxy<UserEvent>,
+  debug: DebugMode,
+  instance: Option<wgpu::Instance>,
+  window_surface: Option<Rc<WindowSurface>>,
+  gpu: Option<Gpu>,
   close_requested: bool,
   start: Instant,
 
@@ -43,17 +539,26 @@ struct ShaderToyApp {
   drag_end_y: f32,
   mouse_left_pressed: bool,
   mouse_left_clicked: bool,
+
+  free_camera: FreeCameraController,
+  params: ParamEditor,
+  modifiers: ModifiersState,
+  quality: QualityController,
+  stereo: StereoController,
+  /// The time the shaders see while time is paused, the frames are then accumulated.
+  paused_time: Option<f32>,
+  /// Fed into the channels every frame, see [`channels::Streams`].
+  streams: Streams,
 }
 
-impl Default for ShaderToyApp {
-  fn default() -> Self {
+impl ShaderToyApp {
+  fn new(proxy: EventLoopProxy<UserEvent>, debug: DebugMode, streams: Streams) -> Self {
     Self {
-      device: None,
-      queue: None,
+      proxy,
+      debug,
+      instance: None,
       window_surface: None,
-      config: None,
-      render_pipeline: None,
-      shader_module: None,
+      gpu: None,
       close_requested: false,
       start: Instant::now(),
       cursor_x: 0.0,
@@ -66,159 +571,224 @@ impl Default for ShaderToyApp {
       mouse_left_clicked: false,
       shader_to_show: 0,
       grid_mode: false,
+      free_camera: FreeCameraController::new(),
+      params: ParamEditor::new(),
+      modifiers: ModifiersState::empty(),
+      quality: QualityController::new(),
+      stereo: StereoController::new(),
+      paused_time: None,
+      streams,
     }
   }
-}
-
-impl ShaderToyApp {
-  async fn init(&mut self, event_loop: &ActiveEventLoop) -> Result<(), Box<dyn Error>> {
-    let window_attributes = WindowAttributes::default()
-      .with_title("Rust GPU - wgpu")
-      .with_inner_size(LogicalSize::new(1280.0, 720.0));
-    let window_box = Box::new(event_loop.create_window(window_attributes)?);
-    let mut instance_flags = wgpu::InstanceFlags::default();
-    // Turn off validation as the shaders are trusted.
-    instance_flags.remove(wgpu::InstanceFlags::VALIDATION);
-    // Disable debugging info to speed things up.
-    instance_flags.remove(wgpu::InstanceFlags::DEBUG);
-    let instance = wgpu::Instance::new(&InstanceDescriptor {
-      flags: instance_flags,
-      ..Default::default()
-    });
 
-    let window_surface = WindowSurfaceBuilder {
-      window: window_box,
-      surface_builder: |window| {
-        instance
-          .create_surface(window)
-          .expect("Failed to create surface")
-      },
+  /// Pauses or resumes time. While paused the frames are averaged, see [`Accumulation`].
+  fn toggle_pause(&mut self) {
+    if let Some(time) = self.paused_time.take() {
+      let now = Instant::now();
+      self.start = now
+        .checked_sub(Duration::from_secs_f32(time))
+        .unwrap_or(now);
+      println!("Time resumed");
+    } else {
+      self.paused_time = Some(self.start.elapsed().as_secs_f32());
+      println!("Time paused, accumulating frames");
     }
-    .build();
+  }
 
-    let window_size = window_surface.borrow_window().inner_size();
-    let surface = window_surface.borrow_surface();
+  /// The shader shown on its own, `None` in the grid view.
+  fn shown_shader(&self) -> Option<usize> {
+    (!self.grid_mode).then_some(self.shader_to_show as usize)
+  }
 
-    let adapter = instance
-      .request_adapter(&wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::HighPerformance,
-        compatible_surface: Some(surface),
-        force_fallback_adapter: false,
-      })
-      .await?;
-    let mut required_features = wgpu::Features::PUSH_CONSTANTS;
-    if adapter
-      .features()
-      .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
-    {
-      required_features |= wgpu::Features::SPIRV_SHADER_PASSTHROUGH;
+  /// Shows the param being edited in the title while the param overlay is shown.
+  fn update_title(&self) {
+    if let Some(ws) = &self.window_surface {
+      let title = self.params.status(self.shown_shader());
+      ws.borrow_window()
+        .set_title(title.as_deref().unwrap_or(WINDOW_TITLE));
     }
-    let required_limits = wgpu::Limits {
-      max_push_constant_size: 256,
-      ..Default::default()
-    };
-    let (device, queue) = adapter
-      .request_device(&wgpu::DeviceDescriptor {
-        label: None,
-        required_features,
-        required_limits,
-        ..Default::default()
-      })
-      .await?;
-    let shader_module = if device
-      .features()
-      .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
-    {
-      let x = include_spirv_raw!(env!("shadertoys_shaders.spv"));
-      unsafe { device.create_shader_module_passthrough(x) }
-    } else {
-      device.create_shader_module(include_spirv!(env!("shadertoys_shaders.spv")))
+  }
+
+  fn init(&mut self, event_loop: &ActiveEventLoop) -> Result<(), Box<dyn Error>> {
+    let window_attributes = WindowAttributes::default()
+      .with_title(WINDOW_TITLE)
+      .with_inner_size(LogicalSize::new(1280.0, 720.0));
+    #[cfg(target_arch = "wasm32")]
+    let window_attributes = {
+      use winit::platform::web::WindowAttributesExtWebSys;
+      window_attributes.with_append(true)
     };
-    let swapchain_format = surface.get_capabilities(&adapter).formats[0];
-    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-      label: None,
-      bind_group_layouts: &[],
-      push_constant_ranges: &[wgpu::PushConstantRange {
-        stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
-        range: 0..std::mem::size_of::<ShaderConstants>() as u32,
-      }],
-    });
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-      label: None,
-      layout: Some(&pipeline_layout),
-      vertex: wgpu::VertexState {
-        module: &shader_module,
-        entry_point: Some("main_vs"),
-        buffers: &[],
-        compilation_options: Default::default(),
-      },
-      fragment: Some(wgpu::FragmentState {
-        module: &shader_module,
-        entry_point: Some("main_fs"),
-        targets: &[Some(wgpu::ColorTargetState {
-          format: swapchain_format,
-          blend: Some(wgpu::BlendState::REPLACE),
-          write_mask: wgpu::ColorWrites::ALL,
-        })],
-        compilation_options: Default::default(),
-      }),
-      primitive: wgpu::PrimitiveState {
-        topology: wgpu::PrimitiveTopology::TriangleList,
-        ..Default::default()
-      },
-      depth_stencil: None,
-      multisample: wgpu::MultisampleState::default(),
-      multiview: None,
-      cache: None,
+    let window_box = Box::new(event_loop.create_window(window_attributes)?);
+    let instance = wgpu::Instance::new(&InstanceDescriptor {
+      flags: self.debug.instance_flags(),
+      ..Default::default()
     });
-    let config = wgpu::SurfaceConfiguration {
-      usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-      format: swapchain_format,
-      width: window_size.width,
-      height: window_size.height,
-      present_mode: wgpu::PresentMode::Fifo,
-      alpha_mode: wgpu::CompositeAlphaMode::Auto,
-      view_formats: vec![],
-      desired_maximum_frame_latency: Default::default(),
-    };
-    surface.configure(&device, &config);
 
-    self.device = Some(device);
-    self.queue = Some(queue);
+    let window_surface = Rc::new(
+      WindowSurfaceBuilder {
+        window: window_box,
+        surface_builder: |window| {
+          instance
+            .create_surface(window)
+            .expect("Failed to create surface")
+        },
+      }
+      .build(),
+    );
     self.window_surface = Some(window_surface);
-    self.config = Some(config);
-    self.render_pipeline = Some(render_pipeline);
-    self.shader_module = Some(shader_module);
+    self.instance = Some(instance);
     self.start = Instant::now();
+    self.create_gpu();
     Ok(())
   }
 
+  /// Sets up the device and pipelines, the result is delivered as [`UserEvent::GpuReady`].
+  fn create_gpu(&self) {
+    let (Some(instance), Some(window_surface)) = (&self.instance, &self.window_surface) else {
+      return;
+    };
+    let instance = instance.clone();
+    let window_surface = Rc::clone(window_surface);
+    let proxy = self.proxy.clone();
+    let debug = self.debug;
+    let stream_sizes = self.streams.sizes();
+    let create_gpu = async move {
+      let gpu = Gpu::new(&instance, &window_surface, debug, stream_sizes)
+        .await
+        .map_err(|e| e.to_string());
+      // Only fails if the event loop is already gone.
+      let _ = proxy.send_event(UserEvent::GpuReady(gpu));
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    block_on(create_gpu);
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(create_gpu);
+  }
+
   fn render(&mut self) {
-    let window_surface = match &self.window_surface {
-      Some(ws) => ws,
-      None => return,
+    if self
+      .gpu
+      .as_ref()
+      .is_some_and(|gpu| gpu.device_lost.load(Ordering::Acquire))
+    {
+      println!("Recreating the device");
+      self.gpu = None;
+      self.create_gpu();
+      return;
+    }
+    let (Some(window_surface), Some(gpu)) = (&self.window_surface, &mut self.gpu) else {
+      return;
     };
 
     let window = window_surface.borrow_window();
     let current_size = window.inner_size();
+    // Nothing to draw into while minimized.
+    if current_size.width == 0 || current_size.height == 0 {
+      return;
+    }
     let surface = window_surface.borrow_surface();
-    let device = self.device.as_ref().unwrap();
-    let queue = self.queue.as_ref().unwrap();
     let frame = match surface.get_current_texture() {
       Ok(frame) => frame,
-      Err(e) => {
-        eprintln!("Failed to acquire texture: {:?}", e);
+      Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+        // Reconfigure and try again with the next frame.
+        gpu.config.width = current_size.width;
+        gpu.config.height = current_size.height;
+        surface.configure(&gpu.device, &gpu.config);
+        return;
+      },
+      Err(wgpu::SurfaceError::OutOfMemory) => {
+        eprintln!("Out of memory while acquiring a frame, exiting");
+        self.close_requested = true;
+        return;
+      },
+      Err(e @ (wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Other)) => {
+        eprintln!("Failed to acquire texture: {e}");
         return;
       },
     };
-    let view = frame
-      .texture
-      .create_view(&wgpu::TextureViewDescriptor::default());
-    let mut encoder =
-      device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    let device = &gpu.device;
+    let queue = &gpu.queue;
+    let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+      format: Some(gpu.config.format.add_srgb_suffix()),
+      ..Default::default()
+    });
+    self.free_camera.update();
+    // Frame times don't matter while accumulating.
+    if self.paused_time.is_none() {
+      self.quality.update();
+    }
+    let time = self
+      .paused_time
+      .unwrap_or_else(|| self.start.elapsed().as_secs_f32());
+    if let Err(e) = self.streams.seek(time) {
+      eprintln!("Failed to decode a video frame: {e}");
+    }
+    gpu.channels.write_streams(queue, &self.streams);
+    let shown_shader = (!self.grid_mode).then_some(self.shader_to_show as usize);
+    let (channels_bind_group, channel_sources) =
+      gpu.channels.bind_group(device, gpu.debug, shown_shader);
+    let params = self.params.block(shown_shader);
+    gpu.params.write(queue, &params);
+    let mut constants = ShaderConstants {
+      width: current_size.width,
+      height: current_size.height,
+      time,
+      cursor_x: self.cursor_x,
+      cursor_y: self.cursor_y,
+      drag_start_x: self.drag_start_x,
+      drag_start_y: self.drag_start_y,
+      drag_end_x: self.drag_end_x,
+      drag_end_y: self.drag_end_y,
+      mouse_left_pressed: self.mouse_left_pressed as u32,
+      mouse_left_clicked: self.mouse_left_clicked as u32,
+      shader_to_show: self.shader_to_show,
+      grid_mode: self.grid_mode as u32,
+      channel_sources,
+      ..ShaderConstants::zeroed()
+    };
+    self
+      .streams
+      .write_channel_time(&mut constants, shown_shader, time);
+    self.free_camera.write_constants(&mut constants);
+    self.quality.write_constants(&mut constants);
+    self.stereo.write_constants(&mut constants);
+    self.mouse_left_clicked = false;
+    let sample = if self.paused_time.is_some() {
+      // The cursor only matters while dragging, which changes the drag positions.
+      let state = ShaderConstants {
+        cursor_x: 0.0,
+        cursor_y: 0.0,
+        ..constants
+      };
+      let state = [bytemuck::bytes_of(&state), params.values()].concat();
+      let size = (current_size.width, current_size.height);
+      let sample = gpu
+        .accumulation
+        .next_sample(device, gpu.debug, size, &state);
+      [constants.jitter_x, constants.jitter_y] = sample.unwrap_or_default();
+      sample.is_some()
+    } else {
+      gpu.accumulation.reset();
+      false
+    };
+    gpu.constants_binding.write(queue, &constants);
+    gpu.debug.push_error_scope(device);
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: gpu.debug.label("shadertoys frame encoder"),
+    });
+    let bind = |rpass: &mut wgpu::RenderPass<'_>| {
+      rpass.set_bind_group(0, channels_bind_group, &[]);
+      rpass.set_bind_group(1, &gpu.params.bind_group, &[]);
+      gpu.constants_binding.bind(rpass, &constants);
+    };
+    if sample {
+      let mut rpass = gpu.accumulation.begin_sample(&mut encoder, gpu.debug);
+      bind(&mut rpass);
+      rpass.draw(0..3, 0..1);
+    }
     {
       let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: None,
+        label: gpu.debug.label("shadertoys render pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
           view: &view,
           resolve_target: None,
@@ -239,43 +809,56 @@ impl ShaderToyApp {
         0.0,
         1.0,
       );
-      let push_constants = ShaderConstants {
-        width: current_size.width,
-        height: current_size.height,
-        time: self.start.elapsed().as_secs_f32(),
-        cursor_x: self.cursor_x,
-        cursor_y: self.cursor_y,
-        drag_start_x: self.drag_start_x,
-        drag_start_y: self.drag_start_y,
-        drag_end_x: self.drag_end_x,
-        drag_end_y: self.drag_end_y,
-        mouse_left_pressed: self.mouse_left_pressed as u32,
-        mouse_left_clicked: self.mouse_left_clicked as u32,
-        shader_to_show: self.shader_to_show,
-        grid_mode: self.grid_mode as u32,
-      };
-      self.mouse_left_clicked = false;
-      rpass.set_pipeline(self.render_pipeline.as_ref().unwrap());
-      rpass.set_push_constants(
-        wgpu::ShaderStages::VERTEX_FRAGMENT,
-        0,
-        bytemuck::bytes_of(&push_constants),
-      );
+      if self.paused_time.is_some() {
+        gpu.accumulation.blit(&mut rpass);
+      } else {
+        rpass.set_pipeline(&gpu.render_pipeline);
+        bind(&mut rpass);
+        rpass.draw(0..3, 0..1);
+      }
+      rpass.set_pipeline(&gpu.overlay_pipeline);
+      bind(&mut rpass);
       rpass.draw(0..3, 0..1);
     }
     queue.submit(Some(encoder.finish()));
+    if sample {
+      gpu.accumulation.end_sample();
+    }
+    gpu.debug.report_error_scope(device, || {
+      if self.grid_mode {
+        "while rendering the grid of all shaders".to_owned()
+      } else {
+        format!(
+          "while rendering shader `{}`",
+          SHADER_DEFINITIONS[self.shader_to_show as usize].name
+        )
+      }
+    });
     frame.present();
   }
 }
 
-impl ApplicationHandler for ShaderToyApp {
+impl ApplicationHandler<UserEvent> for ShaderToyApp {
   fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-    if let Err(e) = block_on(self.init(event_loop)) {
+    if self.window_surface.is_some() {
+      return;
+    }
+    if let Err(e) = self.init(event_loop) {
       eprintln!("Initialization error: {e}");
       event_loop.exit();
     }
   }
 
+  fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
+    match event {
+      UserEvent::GpuReady(Ok(gpu)) => self.gpu = Some(gpu),
+      UserEvent::GpuReady(Err(e)) => {
+        eprintln!("Initialization error: {e}");
+        event_loop.exit();
+      },
+    }
+  }
+
   fn window_event(
     &mut self,
     event_loop: &ActiveEventLoop,
@@ -284,21 +867,23 @@ impl ApplicationHandler for ShaderToyApp {
   ) {
     match event {
       WindowEvent::CloseRequested => self.close_requested = true,
+      // A zero sized surface can't be configured, this happens when minimizing on some
+      // platforms. Rendering is skipped until the window is restored.
+      WindowEvent::Resized(new_size) if new_size.width == 0 || new_size.height == 0 => {},
       WindowEvent::Resized(new_size) => {
-        if let Some(config) = self.config.as_mut() {
-          config.width = new_size.width;
-          config.height = new_size.height;
+        if let Some(gpu) = self.gpu.as_mut() {
+          gpu.config.width = new_size.width;
+          gpu.config.height = new_size.height;
           if let Some(ws) = &self.window_surface {
             let surface = ws.borrow_surface();
-            if let Some(device) = self.device.as_ref() {
-              surface.configure(device, config);
-            }
+            surface.configure(&gpu.device, &gpu.config);
           }
         }
       },
       WindowEvent::CursorMoved { position, .. } => {
         self.cursor_x = position.x as f32;
         self.cursor_y = position.y as f32;
+        self.free_camera.cursor_moved(self.cursor_x, self.cursor_y);
         if self.mouse_left_pressed {
           self.drag_end_x = self.cursor_x;
           self.drag_end_y = self.cursor_y;
@@ -314,6 +899,8 @@ impl ApplicationHandler for ShaderToyApp {
             self.drag_end_y = self.cursor_y;
             self.mouse_left_clicked = true;
           }
+        } else if button == MouseButton::Right {
+          self.free_camera.look(state == ElementState::Pressed);
         }
       },
       WindowEvent::MouseWheel { delta, .. } => {
@@ -322,47 +909,107 @@ impl ApplicationHandler for ShaderToyApp {
           self.drag_end_y = delta_y * 0.1;
         }
       },
-      WindowEvent::KeyboardInput { event, .. } => match event {
-        KeyEvent {
-          state: ElementState::Pressed,
-          ..
-        } if event.logical_key == NamedKey::Escape => {
-          self.close_requested = true;
-        },
-        KeyEvent {
-          state: ElementState::Pressed,
-          physical_key: PhysicalKey::Code(KeyCode::KeyE),
-          ..
-        } => {
-          self.grid_mode = false;
-          self.shader_to_show = (self.shader_to_show + 1) % SHADER_DEFINITIONS.len() as u32;
-          println!(
-            "Shader to show: {}",
-            SHADER_DEFINITIONS[self.shader_to_show as usize].name
-          );
-        },
-        KeyEvent {
-          state: ElementState::Pressed,
-          physical_key: PhysicalKey::Code(KeyCode::KeyQ),
-          ..
-        } => {
-          self.grid_mode = false;
-          self.shader_to_show = (self.shader_to_show + SHADER_DEFINITIONS.len() as u32 - 1)
-            % SHADER_DEFINITIONS.len() as u32;
-          println!(
-            "Shader to show: {}",
-            SHADER_DEFINITIONS[self.shader_to_show as usize].name
-          );
-        },
-        KeyEvent {
-          state: ElementState::Pressed,
-          physical_key: PhysicalKey::Code(KeyCode::KeyG),
-          ..
-        } => {
-          self.grid_mode = !self.grid_mode;
-          println!("Grid mode: {}", self.grid_mode);
-        },
-        _ => {},
+      WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+      WindowEvent::KeyboardInput { event, .. } => {
+        match event {
+          KeyEvent {
+            state: ElementState::Pressed,
+            ..
+          } if event.logical_key == NamedKey::Escape => {
+            self.close_requested = true;
+          },
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyE),
+            ..
+          } => {
+            self.grid_mode = false;
+            self.shader_to_show = (self.shader_to_show + 1) % SHADER_DEFINITIONS.len() as u32;
+            println!(
+              "Shader to show: {}",
+              SHADER_DEFINITIONS[self.shader_to_show as usize].name
+            );
+          },
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyQ),
+            ..
+          } => {
+            self.grid_mode = false;
+            self.shader_to_show = (self.shader_to_show + SHADER_DEFINITIONS.len() as u32 - 1)
+              % SHADER_DEFINITIONS.len() as u32;
+            println!(
+              "Shader to show: {}",
+              SHADER_DEFINITIONS[self.shader_to_show as usize].name
+            );
+          },
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyG),
+            ..
+          } => {
+            self.grid_mode = !self.grid_mode;
+            println!("Grid mode: {}", self.grid_mode);
+          },
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyF),
+            repeat: false,
+            ..
+          } => self.free_camera.toggle(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyR),
+            ..
+          } => self.free_camera.reset(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyP),
+            repeat: false,
+            ..
+          } => self.params.toggle_overlay(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyL),
+            repeat: false,
+            ..
+          } => self.quality.cycle(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyK),
+            repeat: false,
+            ..
+          } => self.quality.toggle_auto(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyT),
+            repeat: false,
+            ..
+          } => self.toggle_pause(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(KeyCode::KeyV),
+            repeat: false,
+            ..
+          } => self.stereo.cycle(),
+          KeyEvent {
+            state: ElementState::Pressed,
+            physical_key: PhysicalKey::Code(code @ (KeyCode::BracketLeft | KeyCode::BracketRight)),
+            ..
+          } => self.stereo.adjust_separation(code == KeyCode::BracketRight),
+          KeyEvent {
+            state,
+            physical_key: PhysicalKey::Code(code),
+            ..
+          } => {
+            let pressed = state == ElementState::Pressed;
+            if !(pressed && self.params.key(code, self.modifiers, self.shown_shader())) {
+              self.free_camera.key(code, pressed);
+            }
+          },
+          _ => {},
+        }
+        self.update_title();
       },
       WindowEvent::RedrawRequested => self.render(),
       _ => {},
@@ -386,8 +1033,49 @@ impl ApplicationHandler for ShaderToyApp {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+  #[cfg(not(target_arch = "wasm32"))]
   env_logger::init();
-  let event_loop = EventLoop::new()?;
-  let mut app = ShaderToyApp::default();
-  event_loop.run_app(&mut app).map_err(Into::into)
+  #[cfg(target_arch = "wasm32")]
+  std::panic::set_hook(Box::new(|info| {
+    web_sys::console::error_1(&info.to_string().into());
+  }));
+  let mut args: Vec<String> = std::env::args().skip(1).collect();
+  let debug = DebugMode::from_args(&mut args);
+  if let Some(command) = args.first().filter(|arg| !arg.starts_with("--")) {
+    match command.as_str() {
+      #[cfg(not(target_arch = "wasm32"))]
+      "spirv-stats" => return spirv_stats::run(&args[1..]),
+      #[cfg(not(target_arch = "wasm32"))]
+      "contact-sheet" => return contact_sheet::run(&args[1..], debug),
+      #[cfg(not(target_arch = "wasm32"))]
+      "gallery" => return gallery::run(&args[1..], debug),
+      #[cfg(not(target_arch = "wasm32"))]
+      "import" => return import::run(&args[1..]),
+      #[cfg(not(target_arch = "wasm32"))]
+      "poster" => return poster::run(&args[1..], debug),
+      #[cfg(not(target_arch = "wasm32"))]
+      "sound" => return sound::run(&args[1..], debug),
+      _ => return Err(format!("unknown command `{command}`").into()),
+    }
+  }
+  let mut streams = Streams::default();
+  for option in args.chunks(2) {
+    match option {
+      [option, value] if streams.parse_option(option, value)? => {},
+      _ => return Err(format!("usage: [--debug] {}", Streams::USAGE).into()),
+    }
+  }
+  let event_loop = EventLoop::with_user_event().build()?;
+  let app = ShaderToyApp::new(event_loop.create_proxy(), debug, streams);
+  #[cfg(not(target_arch = "wasm32"))]
+  {
+    let mut app = app;
+    event_loop.run_app(&mut app).map_err(Into::into)
+  }
+  #[cfg(target_arch = "wasm32")]
+  {
+    use winit::platform::web::EventLoopExtWebSys;
+    event_loop.spawn_app(app);
+    Ok(())
+  }
 }
@@ -0,0 +1,543 @@
+//! Host side of the shader params: editing them with the keyboard, the overlay, presets and the
+//! uniform buffer the values are passed in.
+//!
+//! The params live in descriptor set 1, see `shadertoys_shaders::params`. Every shader keeps its
+//! own values while switching between shaders, the grid view always shows the defaults.
+
+use crate::DebugMode;
+use bytemuck::{Pod, Zeroable};
+#[cfg(not(target_arch = "wasm32"))]
+use core::fmt::Write as _;
+use shadertoys_shaders::{
+  params::{Param, ParamKind, MAX_PARAMS},
+  shaders::SHADER_DEFINITIONS,
+  shared_data::ShaderParams,
+};
+use winit::keyboard::{KeyCode, ModifiersState};
+
+/// Steps from the minimum to the maximum of a float param, `Shift` moves ten times as far.
+const FLOAT_STEPS: f32 = 100.0;
+/// Steps around the color wheel.
+const HUE_STEPS: f32 = 36.0;
+const COARSE_FACTOR: f32 = 10.0;
+/// Where presets are saved, relative to the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+const PRESET_DIRECTORY: &str = "presets";
+
+/// [`ShaderParams`] as plain data.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct ParamsBlock {
+  values: [[f32; 4]; MAX_PARAMS],
+  sliders: [[f32; 4]; MAX_PARAMS / 4],
+  count: u32,
+  selected: u32,
+  overlay: u32,
+  padding: u32,
+}
+
+const _: () = assert!(size_of::<ParamsBlock>() == size_of::<ShaderParams>());
+
+/// The uniform buffer with the [`ShaderParams`] and its bind group.
+pub struct ParamsBuffer {
+  pub bind_group_layout: wgpu::BindGroupLayout,
+  buffer: wgpu::Buffer,
+  pub bind_group: wgpu::BindGroup,
+}
+
+impl ParamsBuffer {
+  pub fn new(device: &wgpu::Device, debug: DebugMode) -> Self {
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: debug.label("shader params bind group layout"),
+      entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      }],
+    });
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: debug.label("shader params uniform buffer"),
+      size: size_of::<ParamsBlock>() as u64,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: debug.label("shader params bind group"),
+      layout: &bind_group_layout,
+      entries: &[wgpu::BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+      }],
+    });
+    Self {
+      bind_group_layout,
+      buffer,
+      bind_group,
+    }
+  }
+
+  pub fn write(&self, queue: &wgpu::Queue, block: &ParamsBlock) {
+    queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(block));
+  }
+}
+
+impl ParamsBlock {
+  /// The param values, without the state of the overlay.
+  pub fn values(&self) -> &[u8] {
+    bytemuck::cast_slice(&self.values)
+  }
+}
+
+/// The param values of every shader and the state of the overlay.
+pub struct ParamEditor {
+  /// Per shader, in the order of its `ShaderDefinition::params`.
+  values: Vec<Vec<[f32; 4]>>,
+  selected: usize,
+  overlay: bool,
+}
+
+impl ParamEditor {
+  pub fn new() -> Self {
+    Self {
+      values: SHADER_DEFINITIONS
+        .iter()
+        .map(|definition| {
+          definition
+            .params
+            .iter()
+            .map(|param| param.default_value().to_array())
+            .collect()
+        })
+        .collect(),
+      selected: 0,
+      overlay: false,
+    }
+  }
+
+  /// The selection is kept when switching shaders, as far as the shown shader has params.
+  fn selected(&self, shader: usize) -> usize {
+    self
+      .selected
+      .min(SHADER_DEFINITIONS[shader].params.len().saturating_sub(1))
+  }
+
+  pub fn toggle_overlay(&mut self) {
+    self.overlay = !self.overlay;
+    println!("Param overlay: {}", self.overlay);
+  }
+
+  /// Handles the param keys while the overlay is shown, returns whether `code` was one of them.
+  /// `shader` is the shown shader, `None` in the grid view.
+  ///
+  /// - `Up` / `Down` select a param, `Left` / `Right` change it, ten times as much with `Shift`.
+  /// - `Backspace` resets the selected param.
+  /// - `1` to `9` load a preset, `Ctrl` + `1` to `9` save one.
+  pub fn key(&mut self, code: KeyCode, modifiers: ModifiersState, shader: Option<usize>) -> bool {
+    let Some(shader) = shader.filter(|_| self.overlay) else {
+      return false;
+    };
+    let params = SHADER_DEFINITIONS[shader].params;
+    let selected = self.selected(shader);
+    let coarse = modifiers.shift_key();
+    match code {
+      KeyCode::ArrowUp | KeyCode::ArrowDown if !params.is_empty() => {
+        let offset = if code == KeyCode::ArrowUp {
+          params.len() - 1
+        } else {
+          1
+        };
+        self.selected = (selected + offset) % params.len();
+      },
+      KeyCode::ArrowLeft | KeyCode::ArrowRight if !params.is_empty() => {
+        let direction = if code == KeyCode::ArrowLeft {
+          -1.0
+        } else {
+          1.0
+        };
+        let value = &mut self.values[shader][selected];
+        *value = adjust(&params[selected], *value, direction, coarse);
+      },
+      KeyCode::Backspace if !params.is_empty() => {
+        self.values[shader][selected] = params[selected].default_value().to_array();
+      },
+      _ => {
+        let Some(preset) = preset_number(code) else {
+          return false;
+        };
+        if modifiers.control_key() {
+          self.save_preset(shader, preset);
+        } else {
+          self.load_preset(shader, preset);
+        }
+        return true;
+      },
+    }
+    if let Some(status) = self.status(Some(shader)) {
+      println!("{status}");
+    }
+    true
+  }
+
+  /// The selected param of the shown shader and its value, for the window title while the
+  /// overlay is shown.
+  pub fn status(&self, shader: Option<usize>) -> Option<String> {
+    let shader = shader.filter(|_| self.overlay)?;
+    let definition = &SHADER_DEFINITIONS[shader];
+    let selected = self.selected(shader);
+    let Some(param) = definition.params.get(selected) else {
+      return Some(format!("{}: no params", definition.name));
+    };
+    Some(format!(
+      "{}: {} = {} ({}/{})",
+      definition.name,
+      param.name,
+      format_value(param, self.values[shader][selected]),
+      selected + 1,
+      definition.params.len(),
+    ))
+  }
+
+  /// The values of the shown shader, or the defaults in the grid view.
+  pub fn block(&self, shader: Option<usize>) -> ParamsBlock {
+    let mut block = ParamsBlock::zeroed();
+    if let Some(shader) = shader {
+      let params = SHADER_DEFINITIONS[shader].params;
+      for (i, (param, value)) in params.iter().zip(&self.values[shader]).enumerate() {
+        block.values[i] = *value;
+        block.sliders[i / 4][i % 4] = slider_position(param, *value);
+      }
+      block.count = params.len() as u32;
+      block.selected = self.selected(shader) as u32;
+      block.overlay = u32::from(self.overlay);
+    }
+    block
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn save_preset(&self, shader: usize, preset: u32) {
+    let definition = &SHADER_DEFINITIONS[shader];
+    let mut contents = String::new();
+    for (param, value) in definition.params.iter().zip(&self.values[shader]) {
+      let _ = writeln!(contents, "{} = {}", param.name, format_value(param, *value));
+    }
+    let path = preset_path(definition.name, preset);
+    let result =
+      std::fs::create_dir_all(PRESET_DIRECTORY).and_then(|()| std::fs::write(&path, contents));
+    match result {
+      Ok(()) => println!("Saved {}", path.display()),
+      Err(e) => eprintln!("Failed to save {}: {e}", path.display()),
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn load_preset(&mut self, shader: usize, preset: u32) {
+    let definition = &SHADER_DEFINITIONS[shader];
+    let path = preset_path(definition.name, preset);
+    let contents = match std::fs::read_to_string(&path) {
+      Ok(contents) => contents,
+      Err(e) => {
+        eprintln!("Failed to load {}: {e}", path.display());
+        return;
+      },
+    };
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+      let parsed = line.split_once('=').and_then(|(name, value)| {
+        let index = definition
+          .params
+          .iter()
+          .position(|param| param.name == name.trim())?;
+        Some((index, parse_value(&definition.params[index], value.trim())?))
+      });
+      match parsed {
+        Some((index, value)) => self.values[shader][index] = value,
+        None => eprintln!("{}: ignoring `{line}`", path.display()),
+      }
+    }
+    println!("Loaded {}", path.display());
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn save_preset(&self, _shader: usize, _preset: u32) {
+    eprintln!("Presets are not supported on the web");
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn load_preset(&mut self, _shader: usize, _preset: u32) {
+    eprintln!("Presets are not supported on the web");
+  }
+}
+
+fn preset_number(code: KeyCode) -> Option<u32> {
+  const DIGITS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+  ];
+  let index = DIGITS.iter().position(|digit| *digit == code)?;
+  Some(index as u32 + 1)
+}
+
+/// `presets/<shader name>-<preset>.txt`, e.g. `presets/a-lot-of-spheres-1.txt`.
+#[cfg(not(target_arch = "wasm32"))]
+fn preset_path(shader_name: &str, preset: u32) -> std::path::PathBuf {
+  let mut slug = String::new();
+  for c in shader_name.chars() {
+    if c.is_ascii_alphanumeric() {
+      slug.push(c.to_ascii_lowercase());
+    } else if !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+  std::path::Path::new(PRESET_DIRECTORY)
+    .join(format!("{}-{preset}.txt", slug.trim_end_matches('-')))
+}
+
+/// `value` moved one step in `direction` (`1.0` or `-1.0`), colors move around the color wheel.
+fn adjust(param: &Param, value: [f32; 4], direction: f32, coarse: bool) -> [f32; 4] {
+  let factor = if coarse { COARSE_FACTOR } else { 1.0 };
+  match param.kind {
+    ParamKind::Float { min, max, .. } => {
+      let step = (max - min) / FLOAT_STEPS * factor;
+      [(value[0] + direction * step).clamp(min, max), 0.0, 0.0, 0.0]
+    },
+    ParamKind::Int { min, max, .. } => {
+      let step = if coarse {
+        ((max - min) as f32 / COARSE_FACTOR).ceil()
+      } else {
+        1.0
+      };
+      let value = (value[0] + direction * step).clamp(min as f32, max as f32);
+      [value, 0.0, 0.0, 0.0]
+    },
+    ParamKind::Bool { .. } => [1.0 - value[0], 0.0, 0.0, 0.0],
+    ParamKind::Color { .. } => {
+      let [hue, saturation, brightness] = rgb_to_hsv([value[0], value[1], value[2]]);
+      let hue = (hue + direction * factor / HUE_STEPS).rem_euclid(1.0);
+      let [r, g, b] = hsv_to_rgb([hue, saturation, brightness]);
+      [r, g, b, 0.0]
+    },
+  }
+}
+
+/// Position of the overlay slider of `param`, in `[0, 1]`. Colors show their hue.
+fn slider_position(param: &Param, value: [f32; 4]) -> f32 {
+  let position = match param.kind {
+    ParamKind::Float { min, max, .. } => (value[0] - min) / (max - min),
+    ParamKind::Int { min, max, .. } => (value[0] - min as f32) / (max - min).max(1) as f32,
+    ParamKind::Bool { .. } => value[0],
+    ParamKind::Color { .. } => rgb_to_hsv([value[0], value[1], value[2]])[0],
+  };
+  position.clamp(0.0, 1.0)
+}
+
+/// The value as written to presets.
+fn format_value(param: &Param, value: [f32; 4]) -> String {
+  match param.kind {
+    ParamKind::Float { .. } => format!("{:.3}", value[0]),
+    ParamKind::Int { .. } => format!("{}", value[0] as i32),
+    ParamKind::Bool { .. } => format!("{}", value[0] != 0.0),
+    ParamKind::Color { .. } => format!("{:.3}, {:.3}, {:.3}", value[0], value[1], value[2]),
+  }
+}
+
+/// Reads what [`format_value`] wrote, clamped to the range of `param`.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_value(param: &Param, text: &str) -> Option<[f32; 4]> {
+  match param.kind {
+    ParamKind::Float { min, max, .. } => {
+      Some([text.parse::<f32>().ok()?.clamp(min, max), 0.0, 0.0, 0.0])
+    },
+    ParamKind::Int { min, max, .. } => Some([
+      text.parse::<i32>().ok()?.clamp(min, max) as f32,
+      0.0,
+      0.0,
+      0.0,
+    ]),
+    ParamKind::Bool { .. } => Some([
+      f32::from(u8::from(text.parse::<bool>().ok()?)),
+      0.0,
+      0.0,
+      0.0,
+    ]),
+    ParamKind::Color { .. } => {
+      let mut components = text.split(',').map(|c| c.trim().parse::<f32>());
+      let mut color = [0.0; 4];
+      for component in &mut color[..3] {
+        *component = components.next()?.ok()?.max(0.0);
+      }
+      components.next().is_none().then_some(color)
+    },
+  }
+}
+
+#[expect(clippy::float_cmp, reason = "`max` is one of the components")]
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+  let max = r.max(g).max(b);
+  let min = r.min(g).min(b);
+  let delta = max - min;
+  let hue = if delta <= 0.0 {
+    0.0
+  } else if max == r {
+    ((g - b) / delta).rem_euclid(6.0) / 6.0
+  } else if max == g {
+    ((b - r) / delta + 2.0) / 6.0
+  } else {
+    ((r - g) / delta + 4.0) / 6.0
+  };
+  let saturation = if max > 0.0 { delta / max } else { 0.0 };
+  [hue, saturation, max]
+}
+
+fn hsv_to_rgb([hue, saturation, value]: [f32; 3]) -> [f32; 3] {
+  let channel = |n: f32| {
+    let k = (n + hue * 6.0).rem_euclid(6.0);
+    value - value * saturation * (k.min(4.0 - k)).clamp(0.0, 1.0)
+  };
+  [channel(5.0), channel(3.0), channel(1.0)]
+}
+
+#[cfg(test)]
+#[expect(clippy::float_cmp, reason = "the values are exact in these tests")]
+mod tests {
+  use super::*;
+  use shadertoys_shaders::shader_prelude::vec3;
+
+  const FLOAT: Param = Param::float(0, "Speed", 0.5, -1.0, 2.0);
+  const INT: Param = Param::int(1, "Steps", 8, 1, 64);
+  const BOOL: Param = Param::bool(2, "Shadows", true);
+  const COLOR: Param = Param::color(3, "Water", vec3(0.8, 0.9, 0.6));
+
+  /// Writes `value` as a line of a preset and reads it back, like saving and loading do.
+  fn through_preset(param: &Param, value: [f32; 4]) -> [f32; 4] {
+    let line = format!("{} = {}", param.name, format_value(param, value));
+    let (name, text) = line.split_once('=').unwrap();
+    assert_eq!(name.trim(), param.name);
+    parse_value(param, text.trim()).unwrap()
+  }
+
+  fn assert_close(a: [f32; 4], b: [f32; 4], tolerance: f32) {
+    for (a, b) in a.iter().zip(b) {
+      assert!((a - b).abs() <= tolerance, "{a} != {b}");
+    }
+  }
+
+  #[test]
+  fn presets_round_trip() {
+    let params = SHADER_DEFINITIONS
+      .iter()
+      .flat_map(|definition| definition.params)
+      .chain([&FLOAT, &INT, &BOOL, &COLOR]);
+    for param in params {
+      let value = param.default_value().to_array();
+      let read = through_preset(param, value);
+      assert_close(read, value, 0.0005);
+      assert_eq!(format_value(param, read), format_value(param, value));
+      assert_eq!(through_preset(param, read), read, "{}", param.name);
+    }
+    assert_eq!(
+      through_preset(&FLOAT, [1.25, 0.0, 0.0, 0.0]),
+      [1.25, 0.0, 0.0, 0.0]
+    );
+    assert_eq!(
+      through_preset(&INT, [33.0, 0.0, 0.0, 0.0]),
+      [33.0, 0.0, 0.0, 0.0]
+    );
+    assert_eq!(through_preset(&BOOL, [0.0; 4]), [0.0; 4]);
+    assert_eq!(
+      through_preset(&COLOR, [0.25, 0.5, 1.0, 0.0]),
+      [0.25, 0.5, 1.0, 0.0]
+    );
+  }
+
+  #[test]
+  fn parsing_clamps_and_rejects() {
+    assert_eq!(parse_value(&FLOAT, "5"), Some([2.0, 0.0, 0.0, 0.0]));
+    assert_eq!(parse_value(&INT, "-3"), Some([1.0, 0.0, 0.0, 0.0]));
+    assert_eq!(
+      parse_value(&COLOR, "-1, 0.5, 2"),
+      Some([0.0, 0.5, 2.0, 0.0])
+    );
+    assert_eq!(parse_value(&FLOAT, "fast"), None);
+    assert_eq!(parse_value(&INT, "1.5"), None);
+    assert_eq!(parse_value(&BOOL, "1"), None);
+    assert_eq!(parse_value(&COLOR, "0.1, 0.2"), None);
+    assert_eq!(parse_value(&COLOR, "0.1, 0.2, 0.3, 0.4"), None);
+  }
+
+  #[test]
+  fn hsv_round_trips() {
+    assert_eq!(rgb_to_hsv([1.0, 0.0, 0.0]), [0.0, 1.0, 1.0]);
+    assert_close_3(rgb_to_hsv([0.0, 1.0, 0.0]), [1.0 / 3.0, 1.0, 1.0]);
+    assert_close_3(rgb_to_hsv([0.0, 0.0, 0.5]), [2.0 / 3.0, 1.0, 0.5]);
+    assert_eq!(rgb_to_hsv([0.4, 0.4, 0.4]), [0.0, 0.0, 0.4]);
+    assert_eq!(rgb_to_hsv([0.0; 3]), [0.0; 3]);
+    for rgb in [
+      [0.8, 0.9, 0.6],
+      [0.1, 0.2, 0.3],
+      [1.0, 0.5, 0.0],
+      [0.3, 0.0, 0.7],
+      [0.5, 0.5, 0.5],
+    ] {
+      assert_close_3(hsv_to_rgb(rgb_to_hsv(rgb)), rgb);
+    }
+  }
+
+  fn assert_close_3([a, b, c]: [f32; 3], [x, y, z]: [f32; 3]) {
+    assert_close([a, b, c, 0.0], [x, y, z, 0.0], 1e-5);
+  }
+
+  #[test]
+  fn adjusting_clamps_to_the_range() {
+    assert_eq!(adjust(&FLOAT, [1.99, 0.0, 0.0, 0.0], 1.0, false)[0], 2.0);
+    assert_eq!(adjust(&FLOAT, [-0.9, 0.0, 0.0, 0.0], -1.0, true)[0], -1.0);
+    assert_close(
+      adjust(&FLOAT, [0.5, 0.0, 0.0, 0.0], 1.0, false),
+      [0.53, 0.0, 0.0, 0.0],
+      1e-6,
+    );
+    assert_close(
+      adjust(&FLOAT, [0.5, 0.0, 0.0, 0.0], -1.0, true),
+      [0.2, 0.0, 0.0, 0.0],
+      1e-6,
+    );
+    assert_eq!(adjust(&INT, [64.0, 0.0, 0.0, 0.0], 1.0, false)[0], 64.0);
+    assert_eq!(adjust(&INT, [1.0, 0.0, 0.0, 0.0], -1.0, false)[0], 1.0);
+    assert_eq!(adjust(&BOOL, [1.0, 0.0, 0.0, 0.0], 1.0, false)[0], 0.0);
+    assert_eq!(adjust(&BOOL, [0.0, 0.0, 0.0, 0.0], -1.0, true)[0], 1.0);
+  }
+
+  #[test]
+  fn shift_steps_ints_by_a_tenth_of_the_range() {
+    assert_eq!(adjust(&INT, [8.0, 0.0, 0.0, 0.0], 1.0, false)[0], 9.0);
+    // (64 - 1) / 10 rounded up.
+    assert_eq!(adjust(&INT, [8.0, 0.0, 0.0, 0.0], 1.0, true)[0], 15.0);
+    assert_eq!(adjust(&INT, [8.0, 0.0, 0.0, 0.0], -1.0, true)[0], 1.0);
+    assert_eq!(adjust(&INT, [60.0, 0.0, 0.0, 0.0], 1.0, true)[0], 64.0);
+    let small = Param::int(0, "Small", 2, 0, 4);
+    assert_eq!(adjust(&small, [2.0, 0.0, 0.0, 0.0], 1.0, true)[0], 3.0);
+  }
+
+  #[test]
+  fn colors_turn_around_the_wheel() {
+    let red = [1.0, 0.0, 0.0, 0.0];
+    let [hue, saturation, value] = {
+      let [r, g, b, _] = adjust(&COLOR, red, 1.0, false);
+      rgb_to_hsv([r, g, b])
+    };
+    assert!((hue - 1.0 / HUE_STEPS).abs() < 1e-5);
+    assert!((saturation - 1.0).abs() < 1e-5 && (value - 1.0).abs() < 1e-5);
+    let [r, g, b, _] = adjust(&COLOR, red, -1.0, true);
+    assert!((rgb_to_hsv([r, g, b])[0] - (1.0 - COARSE_FACTOR / HUE_STEPS)).abs() < 1e-5);
+  }
+}
@@ -0,0 +1,598 @@
+//! Renders one frame of a shader at any resolution, e.g. 16384x16384 for a print, by splitting it
+//! into tiles. Every tile is rendered with the resolution of the whole frame and its offset in
+//! `ShaderConstants`, so shaders that normalize by the resolution line up across tiles. Each row
+//! of tiles is written out once it is done, see [`ImageWriter`].
+//!
+//! Run with `cargo run --release -- poster <shader> <output.png|output.exr> [options]`, where
+//! `<shader>` is the index or the name of a shader, or `grid`. The options are
+//!
+//! - `--size <width>x<height>`, 3840x2160 by default,
+//! - `--tile <pixels>`, the width and height of the tiles, 1024 by default,
+//! - `--time <seconds>`, 0 by default,
+//! - `--quality <low|medium|high|ultra>`, `ultra` by default,
+//! - `--samples <count>`, jittered samples averaged per pixel, 1 by default,
+//! - `--projection <screen|equirectangular|cubemap>`, see below,
+//! - `--audio <file.wav>` and `--channel<N> <video>`, files fed into the channels at `--time`,
+//!   see `channels::Streams`,
+//! - `--cpu` to run the shader on the CPU instead of the GPU.
+//!
+//! Shaders with a `ray_fn` can also be rendered all around their camera, see
+//! `shadertoys_shaders::projection`. `equirectangular` renders a 360° panorama, 4096x2048 by
+//! default. `cubemap` renders the six faces of a skybox, 2048x2048 each by default, into files
+//! named after the output with `-px`, `-nx`, `-py`, `-ny`, `-pz` and `-nz` appended.
+
+use crate::{
+  accumulation,
+  channels::{CpuChannel, Streams},
+  create_render_pipeline, create_shader_module,
+  image_writer::ImageWriter,
+  request_device, Channels, ConstantsBinding, DebugMode, ParamEditor, ParamsBuffer,
+};
+use bytemuck::Zeroable;
+use core::{error::Error, num::NonZero, time::Duration};
+use futures::executor::block_on;
+use shadertoys_shaders::{
+  params::MAX_PARAMS,
+  projection::Projection,
+  quality::Quality,
+  shader_prelude::{vec2, Vec4},
+  shaders::{HAS_RAY_FN, SHADER_DEFINITIONS},
+  shared_data::{ShaderConstants, ShaderParams},
+};
+use std::{
+  path::{Path, PathBuf},
+  sync::mpsc,
+  time::Instant,
+};
+
+/// Floats, so that `--samples` averages and `.exr` files keep the full range.
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+const PIXEL_SIZE: u32 = 16;
+/// File name suffixes of the cube faces, in the order of [`Projection::CUBE_FACES`].
+const CUBE_FACE_SUFFIXES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PosterProjection {
+  Screen,
+  Equirectangular,
+  Cubemap,
+}
+
+struct Options {
+  /// `None` for the grid.
+  shader: Option<usize>,
+  output: PathBuf,
+  projection: PosterProjection,
+  /// Per image.
+  width: u32,
+  height: u32,
+  tile: u32,
+  time: f32,
+  quality: Quality,
+  samples: u32,
+  streams: Streams,
+  cpu: bool,
+}
+
+impl Options {
+  fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+    let usage = format!(
+      "usage: poster <shader> <output.png|output.exr> [--size <width>x<height>] \
+       [--tile <pixels>] [--time <seconds>] [--quality <level>] [--samples <count>] \
+       [--projection <projection>] {} [--cpu]",
+      Streams::USAGE
+    );
+    let [shader, output, flags @ ..] = args else {
+      return Err(usage.into());
+    };
+    let mut options = Self {
+      shader: find_shader(shader)?,
+      output: output.into(),
+      projection: PosterProjection::Screen,
+      width: 0,
+      height: 0,
+      tile: 1024,
+      time: 0.0,
+      quality: Quality::Ultra,
+      samples: 1,
+      streams: Streams::default(),
+      cpu: false,
+    };
+    let mut size = None;
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+      if flag == "--cpu" {
+        options.cpu = true;
+        continue;
+      }
+      let value = flags
+        .next()
+        .ok_or_else(|| format!("`{flag}` needs a value, {usage}"))?;
+      let invalid = || format!("invalid value `{value}` for `{flag}`");
+      match flag.as_str() {
+        "--size" => size = Some(parse_size(value).ok_or_else(invalid)?),
+        "--tile" => options.tile = value.parse().map_err(|_| invalid())?,
+        "--time" => options.time = value.parse().map_err(|_| invalid())?,
+        "--quality" => options.quality = parse_quality(value).ok_or_else(invalid)?,
+        "--samples" => options.samples = value.parse().map_err(|_| invalid())?,
+        "--projection" => {
+          options.projection = match value.as_str() {
+            "screen" => PosterProjection::Screen,
+            "equirectangular" => PosterProjection::Equirectangular,
+            "cubemap" => PosterProjection::Cubemap,
+            _ => return Err(invalid().into()),
+          };
+        },
+        _ if options.streams.parse_option(flag, value)? => {},
+        _ => return Err(format!("unknown option `{flag}`, {usage}").into()),
+      }
+    }
+    (options.width, options.height) = size.unwrap_or(match options.projection {
+      PosterProjection::Screen => (3840, 2160),
+      PosterProjection::Equirectangular => (4096, 2048),
+      PosterProjection::Cubemap => (2048, 2048),
+    });
+    if options.width == 0 || options.height == 0 || options.tile == 0 || options.samples == 0 {
+      return Err("the size, the tile size and the sample count must not be zero".into());
+    }
+    if options.projection != PosterProjection::Screen {
+      let has_ray_fn = options.shader.is_some_and(|shader| HAS_RAY_FN[shader]);
+      if !has_ray_fn {
+        return Err(format!("`{shader}` can only be rendered to the screen").into());
+      }
+    }
+    Ok(options)
+  }
+
+  /// The images to render and where to write them.
+  fn images(&self) -> Vec<(Projection, PathBuf)> {
+    match self.projection {
+      PosterProjection::Screen => vec![(Projection::Screen, self.output.clone())],
+      PosterProjection::Equirectangular => {
+        vec![(Projection::Equirectangular, self.output.clone())]
+      },
+      PosterProjection::Cubemap => {
+        let stem = self
+          .output
+          .file_stem()
+          .unwrap_or_default()
+          .to_string_lossy();
+        let extension = self.output.extension().unwrap_or_default();
+        Projection::CUBE_FACES
+          .into_iter()
+          .zip(CUBE_FACE_SUFFIXES)
+          .map(|(face, suffix)| {
+            let path = self
+              .output
+              .with_file_name(format!("{stem}-{suffix}"))
+              .with_extension(extension);
+            (face, path)
+          })
+          .collect()
+      },
+    }
+  }
+}
+
+/// Parses `<width>x<height>`.
+pub fn parse_size(value: &str) -> Option<(u32, u32)> {
+  let (width, height) = value.split_once('x')?;
+  Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Parses a quality level by name, ignoring case.
+pub fn parse_quality(value: &str) -> Option<Quality> {
+  Quality::ALL
+    .iter()
+    .find(|level| format!("{level:?}").eq_ignore_ascii_case(value))
+    .copied()
+}
+
+/// Looks up a shader by index or name, ignoring case, spaces and punctuation (so that
+/// `a_lot_of_spheres` finds "A Lot of Spheres"). `grid` is the grid of all shaders.
+fn find_shader(shader: &str) -> Result<Option<usize>, Box<dyn Error>> {
+  if shader == "grid" {
+    return Ok(None);
+  }
+  if let Ok(index) = shader.parse::<usize>() {
+    if index < SHADER_DEFINITIONS.len() {
+      return Ok(Some(index));
+    }
+  }
+  SHADER_DEFINITIONS
+    .iter()
+    .position(|definition| simplify_name(definition.name) == simplify_name(shader))
+    .map(Some)
+    .ok_or_else(|| format!("no shader named `{shader}`").into())
+}
+
+/// `name` in lowercase without spaces and punctuation, for looking up shaders and sounds.
+pub fn simplify_name(name: &str) -> String {
+  name
+    .chars()
+    .filter(char::is_ascii_alphanumeric)
+    .map(|c| c.to_ascii_lowercase())
+    .collect()
+}
+
+pub fn run(args: &[String], debug: DebugMode) -> Result<(), Box<dyn Error>> {
+  let mut options = Options::parse(args)?;
+  // The same audio and video frames for every tile.
+  options.streams.seek(options.time)?;
+  let options = options;
+  let mut renderer = if options.cpu {
+    Renderer::Cpu(options.streams.cpu_channels(options.shader, options.time))
+  } else {
+    let renderer = block_on(GpuRenderer::new(
+      debug,
+      options.shader,
+      options.tile,
+      &options.streams,
+    ))?;
+    Renderer::Gpu(Box::new(renderer))
+  };
+  for (projection, path) in options.images() {
+    render_image(&mut renderer, &options, projection, &path)?;
+  }
+  Ok(())
+}
+
+/// Renders one image tile by tile and writes it to `path`.
+fn render_image(
+  renderer: &mut Renderer<'_>,
+  options: &Options,
+  projection: Projection,
+  path: &Path,
+) -> Result<(), Box<dyn Error>> {
+  let mut constants = ShaderConstants {
+    width: options.width,
+    height: options.height,
+    time: options.time,
+    grid_mode: u32::from(options.shader.is_none()),
+    shader_to_show: options.shader.unwrap_or_default() as u32,
+    quality: options.quality as u32,
+    projection: projection as u32,
+    ..ShaderConstants::zeroed()
+  };
+  options
+    .streams
+    .write_channel_time(&mut constants, options.shader, options.time);
+  let start = Instant::now();
+  let mut writer = ImageWriter::create(path, options.width, options.height)?;
+  let width = options.width as usize;
+  let mut band = Vec::new();
+  for tile_y in (0..options.height).step_by(options.tile as usize) {
+    let tile_height = options.tile.min(options.height - tile_y);
+    band.clear();
+    band.resize(width * tile_height as usize, [0.0; 4]);
+    for tile_x in (0..options.width).step_by(options.tile as usize) {
+      let tile_width = options.tile.min(options.width - tile_x);
+      for sample in 0..options.samples {
+        let [jitter_x, jitter_y] = accumulation::jitter(sample);
+        let tile = renderer.render(
+          ShaderConstants {
+            tile_offset_x: tile_x as f32,
+            tile_offset_y: tile_y as f32,
+            jitter_x,
+            jitter_y,
+            ..constants
+          },
+          tile_width,
+          tile_height,
+        )?;
+        // A running average, like the accumulation in the viewer.
+        let weight = 1.0 / (sample + 1) as f32;
+        for (tile_row, band_row) in tile
+          .chunks_exact(tile_width as usize)
+          .zip(band.chunks_exact_mut(width))
+        {
+          let band_row = &mut band_row[tile_x as usize..][..tile_width as usize];
+          for (average, pixel) in band_row.iter_mut().zip(tile_row) {
+            for (average, value) in average.iter_mut().zip(pixel) {
+              *average += (value - *average) * weight;
+            }
+          }
+        }
+      }
+    }
+    writer.write_rows(&band)?;
+    println!(
+      "Rendered {} of {} rows",
+      tile_y + tile_height,
+      options.height
+    );
+  }
+  writer.finish()?;
+  println!("Wrote {} in {:.1?}", path.display(), start.elapsed());
+  Ok(())
+}
+
+pub enum Renderer<'a> {
+  Gpu(Box<GpuRenderer>),
+  Cpu([CpuChannel<'a>; 4]),
+}
+
+impl Renderer<'_> {
+  /// Switches to rendering `shader` with its default params, which the CPU always uses.
+  pub fn show(&mut self, shader: Option<usize>) {
+    if let Self::Gpu(renderer) = self {
+      renderer.show(shader);
+    }
+  }
+
+  /// Renders the `width` x `height` pixels at the tile offset of `constants`, row by row.
+  pub fn render(
+    &mut self,
+    constants: ShaderConstants,
+    width: u32,
+    height: u32,
+  ) -> Result<Vec<[f32; 4]>, Box<dyn Error>> {
+    self
+      .render_timed(constants, width, height)
+      .map(|(pixels, _)| pixels)
+  }
+
+  /// [`Renderer::render`], and how long the shader ran: on the GPU from submitting the draw to
+  /// its completion, without the readback, on the CPU the time of all threads.
+  pub fn render_timed(
+    &mut self,
+    constants: ShaderConstants,
+    width: u32,
+    height: u32,
+  ) -> Result<(Vec<[f32; 4]>, Duration), Box<dyn Error>> {
+    match self {
+      Self::Gpu(renderer) => renderer.render(constants, width, height),
+      Self::Cpu(channels) => {
+        let start = Instant::now();
+        let pixels = render_cpu(&constants, *channels, width, height);
+        Ok((pixels, start.elapsed()))
+      },
+    }
+  }
+}
+
+/// Runs [`shadertoys_shaders::fs`] for every pixel, spread over all cores. The params are the
+/// defaults and the channels without a stream are unbound, so they use their procedural sources.
+fn render_cpu(
+  constants: &ShaderConstants,
+  channels: [CpuChannel<'_>; 4],
+  width: u32,
+  height: u32,
+) -> Vec<[f32; 4]> {
+  let params = ShaderParams {
+    values: [Vec4::ZERO; MAX_PARAMS],
+    sliders: [Vec4::ZERO; MAX_PARAMS / 4],
+    count: 0,
+    selected: 0,
+    overlay: 0,
+    padding: 0,
+  };
+  let width = width as usize;
+  let mut pixels = vec![[0.0; 4]; width * height as usize];
+  let threads = std::thread::available_parallelism().map_or(1, NonZero::get);
+  let rows_per_thread = (height as usize).div_ceil(threads);
+  std::thread::scope(|scope| {
+    for (chunk_index, chunk) in pixels.chunks_mut(rows_per_thread * width).enumerate() {
+      let params = &params;
+      scope.spawn(move || {
+        for (i, pixel) in chunk.iter_mut().enumerate() {
+          let x = i % width;
+          let y = chunk_index * rows_per_thread + i / width;
+          // The center of the pixel, like `frag_coord` on the GPU.
+          let frag_coord = vec2(x as f32 + 0.5, y as f32 + 0.5);
+          *pixel = shadertoys_shaders::fs(constants, params, frag_coord, channels).to_array();
+        }
+      });
+    }
+  });
+  pixels
+}
+
+/// Renders tiles into a float texture without a window and reads them back.
+pub struct GpuRenderer {
+  device: wgpu::Device,
+  queue: wgpu::Queue,
+  pipeline: wgpu::RenderPipeline,
+  constants_binding: ConstantsBinding,
+  channels: Channels,
+  params: ParamsBuffer,
+  shader: Option<usize>,
+  texture: wgpu::Texture,
+  readback: wgpu::Buffer,
+  /// Row pitch of `readback`, which has to be a multiple of 256 bytes.
+  bytes_per_row: u32,
+  debug: DebugMode,
+}
+
+impl GpuRenderer {
+  pub async fn new(
+    debug: DebugMode,
+    shader: Option<usize>,
+    tile: u32,
+    streams: &Streams,
+  ) -> Result<Self, Box<dyn Error>> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+      flags: debug.instance_flags(),
+      ..Default::default()
+    });
+    let adapter = instance
+      .request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+      })
+      .await?;
+    let (device, queue, use_push_constants) = request_device(&adapter, debug).await?;
+    let max_size = device.limits().max_texture_dimension_2d;
+    if tile > max_size {
+      return Err(format!("the tile size must be at most {max_size} on this GPU").into());
+    }
+    let bytes_per_row = (tile * PIXEL_SIZE).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let readback_size = u64::from(bytes_per_row) * u64::from(tile);
+    let max_buffer_size = device.limits().max_buffer_size;
+    if readback_size > max_buffer_size {
+      return Err(
+        format!(
+          "reading back a tile of {tile}x{tile} takes {readback_size} bytes, more than the \
+           {max_buffer_size} of a buffer on this GPU, use a smaller --tile"
+        )
+        .into(),
+      );
+    }
+    debug.push_error_scope(&device);
+    let shader_module = create_shader_module(&device, use_push_constants);
+    let channels = Channels::new(&device, &queue, debug, streams.sizes());
+    channels.write_streams(&queue, streams);
+    let params = ParamsBuffer::new(&device, debug);
+    params.write(&queue, &ParamEditor::new().block(shader));
+    let (pipeline_layout, constants_binding) =
+      ConstantsBinding::create(&device, debug, use_push_constants, &channels, &params);
+    // Float formats are not blendable everywhere, averaging happens on the CPU.
+    let pipeline = create_render_pipeline(
+      &device,
+      debug.label("poster render pipeline"),
+      &pipeline_layout,
+      &shader_module,
+      constants_binding.fragment_entry_point(),
+      FORMAT,
+      None,
+    );
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: debug.label("poster tile texture"),
+      size: wgpu::Extent3d {
+        width: tile,
+        height: tile,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: FORMAT,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+      view_formats: &[],
+    });
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+      label: debug.label("poster readback buffer"),
+      size: readback_size,
+      usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    if let Some(error) = debug.pop_error_scope(&device).await {
+      return Err(format!("creating the poster pipeline failed: {error}").into());
+    }
+    Ok(Self {
+      device,
+      queue,
+      pipeline,
+      constants_binding,
+      channels,
+      params,
+      shader,
+      texture,
+      readback,
+      bytes_per_row,
+      debug,
+    })
+  }
+
+  fn show(&mut self, shader: Option<usize>) {
+    self.shader = shader;
+    self
+      .params
+      .write(&self.queue, &ParamEditor::new().block(shader));
+  }
+
+  fn render(
+    &mut self,
+    mut constants: ShaderConstants,
+    width: u32,
+    height: u32,
+  ) -> Result<(Vec<[f32; 4]>, Duration), Box<dyn Error>> {
+    let (device, queue, debug) = (&self.device, &self.queue, self.debug);
+    let (channels_bind_group, channel_sources) =
+      self.channels.bind_group(device, debug, self.shader);
+    constants.channel_sources = channel_sources;
+    self.constants_binding.write(queue, &constants);
+    debug.push_error_scope(device);
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: debug.label("poster tile encoder"),
+    });
+    {
+      let view = self
+        .texture
+        .create_view(&wgpu::TextureViewDescriptor::default());
+      let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: debug.label("poster tile render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: &view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+      });
+      rpass.set_pipeline(&self.pipeline);
+      rpass.set_bind_group(0, channels_bind_group, &[]);
+      rpass.set_bind_group(1, &self.params.bind_group, &[]);
+      self.constants_binding.bind(&mut rpass, &constants);
+      rpass.draw(0..3, 0..1);
+    }
+    // The draw goes on its own, so that waiting for it times the shader alone.
+    let start = Instant::now();
+    queue.submit(Some(encoder.finish()));
+    device.poll(wgpu::PollType::Wait)?;
+    let render_time = start.elapsed();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: debug.label("poster readback encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+      self.texture.as_image_copy(),
+      wgpu::TexelCopyBufferInfo {
+        buffer: &self.readback,
+        layout: wgpu::TexelCopyBufferLayout {
+          offset: 0,
+          bytes_per_row: Some(self.bytes_per_row),
+          rows_per_image: None,
+        },
+      },
+      wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+    );
+    queue.submit(Some(encoder.finish()));
+    debug.report_error_scope(device, || {
+      format!(
+        "rendering the tile at {}x{}",
+        constants.tile_offset_x, constants.tile_offset_y
+      )
+    });
+
+    let slice = self.readback.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+      let _ = sender.send(result);
+    });
+    device.poll(wgpu::PollType::Wait)?;
+    receiver.recv()??;
+    let pixels = {
+      let mapped = slice.get_mapped_range();
+      let mut pixels = Vec::with_capacity(width as usize * height as usize);
+      for row in mapped
+        .chunks_exact(self.bytes_per_row as usize)
+        .take(height as usize)
+      {
+        let row = &row[..(width * PIXEL_SIZE) as usize];
+        pixels.extend(bytemuck::pod_collect_to_vec::<u8, [f32; 4]>(row));
+      }
+      pixels
+    };
+    self.readback.unmap();
+    Ok((pixels, render_time))
+  }
+}
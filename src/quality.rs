@@ -0,0 +1,117 @@
+//! Picks the quality level of the shaders, see `shadertoys_shaders::quality::Quality`.
+//!
+//! By default the level follows the frame rate: it drops when frames take too long and goes back
+//! up after a while of smooth rendering. A level that was too slow is not tried again for
+//! [`RETRY_AFTER`]. The target is 60 frames per second, or `SHADERTOYS_TARGET_FPS`.
+
+use core::time::Duration;
+use shadertoys_shaders::{quality::Quality, shared_data::ShaderConstants};
+use web_time::Instant;
+
+const DEFAULT_TARGET_FPS: f32 = 60.0;
+/// Frame times below `target * RAISE_THRESHOLD` count as keeping up, with some slack for timer
+/// jitter.
+const RAISE_THRESHOLD: f32 = 1.05;
+/// Frame times above `target * LOWER_THRESHOLD` are too slow.
+const LOWER_THRESHOLD: f32 = 1.2;
+/// Seconds to wait after a change before judging the new level.
+const SETTLE_TIME: f32 = 1.0;
+/// Seconds of keeping up before trying the next level.
+const RAISE_AFTER: f32 = 5.0;
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Weight of the newest frame in the average frame time.
+const SMOOTHING: f32 = 0.1;
+/// Longer frames (e.g. while the window was hidden) are ignored.
+const MAX_FRAME_TIME: f32 = 0.5;
+
+pub struct QualityController {
+  level: Quality,
+  /// Whether the level follows the frame rate.
+  auto: bool,
+  /// Seconds per frame.
+  target_frame_time: f32,
+  average_frame_time: f32,
+  /// Seconds since the level last changed.
+  since_change: f32,
+  last_frame: Option<Instant>,
+  /// The last level that was too slow and until when to stay below it.
+  too_slow: Option<(Quality, Instant)>,
+}
+
+impl QualityController {
+  pub fn new() -> Self {
+    let target_fps = std::env::var("SHADERTOYS_TARGET_FPS")
+      .ok()
+      .and_then(|fps| fps.parse::<f32>().ok())
+      .filter(|fps| *fps > 0.0)
+      .unwrap_or(DEFAULT_TARGET_FPS);
+    Self {
+      level: Quality::High,
+      auto: true,
+      target_frame_time: 1.0 / target_fps,
+      average_frame_time: 1.0 / target_fps,
+      since_change: 0.0,
+      last_frame: None,
+      too_slow: None,
+    }
+  }
+
+  /// Switches to the next level, wrapping around, and stops following the frame rate.
+  pub fn cycle(&mut self) {
+    let index = Quality::ALL.iter().position(|level| *level == self.level);
+    let next = index.map_or(0, |index| (index + 1) % Quality::ALL.len());
+    self.auto = false;
+    self.set_level(Quality::ALL[next]);
+  }
+
+  pub fn toggle_auto(&mut self) {
+    self.auto = !self.auto;
+    self.too_slow = None;
+    self.since_change = 0.0;
+    println!("Automatic quality: {}", self.auto);
+  }
+
+  fn set_level(&mut self, level: Quality) {
+    self.level = level;
+    self.since_change = 0.0;
+    println!("Quality: {level:?}");
+  }
+
+  /// Measures the frame time and adjusts the level, once per frame.
+  pub fn update(&mut self) {
+    let now = Instant::now();
+    let Some(last_frame) = self.last_frame.replace(now) else {
+      return;
+    };
+    let frame_time = (now - last_frame).as_secs_f32();
+    if frame_time > MAX_FRAME_TIME {
+      return;
+    }
+    self.average_frame_time += (frame_time - self.average_frame_time) * SMOOTHING;
+    self.since_change += frame_time;
+    if !self.auto || self.since_change < SETTLE_TIME {
+      return;
+    }
+
+    let index = self.level as usize;
+    if self.average_frame_time > self.target_frame_time * LOWER_THRESHOLD && index > 0 {
+      self.too_slow = Some((self.level, now + RETRY_AFTER));
+      self.set_level(Quality::ALL[index - 1]);
+    } else if self.average_frame_time < self.target_frame_time * RAISE_THRESHOLD
+      && self.since_change > RAISE_AFTER
+      && index + 1 < Quality::ALL.len()
+    {
+      let next = Quality::ALL[index + 1];
+      let blocked = self
+        .too_slow
+        .is_some_and(|(level, until)| next >= level && now < until);
+      if !blocked {
+        self.set_level(next);
+      }
+    }
+  }
+
+  pub fn write_constants(&self, constants: &mut ShaderConstants) {
+    constants.quality = self.level as u32;
+  }
+}
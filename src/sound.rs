@@ -0,0 +1,289 @@
+//! Renders a sound shader to a stereo 44.1 kHz WAV file, see `shadertoys_shaders::sounds`.
+//!
+//! Run with `cargo run --release -- sound <sound> <output.wav> [options]`, where `<sound>` is the
+//! index or the name of a sound. The options are
+//!
+//! - `--duration <seconds>`, 10 by default,
+//! - `--cpu` to run the sound shader on the CPU instead of the GPU.
+//!
+//! The GPU evaluates the samples in blocks with the `main_sound_cs` compute shader.
+
+use crate::{create_shader_module, poster::simplify_name, request_device, wav, DebugMode};
+use core::{error::Error, num::NonZero};
+use futures::executor::block_on;
+use shadertoys_shaders::{
+  shared_data::SoundConstants,
+  sounds::{self, SOUND_DEFINITIONS},
+};
+use std::{path::PathBuf, sync::mpsc, time::Instant};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Samples per dispatch, about 24 seconds.
+const BLOCK_SAMPLES: u32 = 1 << 20;
+/// Matches `threads(64)` of `main_sound_cs`.
+const WORKGROUP_SIZE: u32 = 64;
+/// Bytes per sample, a `Vec2` of the left and right channel.
+const FRAME_SIZE: u64 = 8;
+
+struct Options {
+  sound: usize,
+  output: PathBuf,
+  duration: f32,
+  cpu: bool,
+}
+
+impl Options {
+  fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+    let usage = "usage: sound <sound> <output.wav> [--duration <seconds>] [--cpu]";
+    let [sound, output, flags @ ..] = args else {
+      return Err(usage.into());
+    };
+    let mut options = Self {
+      sound: find_sound(sound)?,
+      output: output.into(),
+      duration: 10.0,
+      cpu: false,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+      if flag == "--cpu" {
+        options.cpu = true;
+        continue;
+      }
+      let value = flags
+        .next()
+        .ok_or_else(|| format!("`{flag}` needs a value, {usage}"))?;
+      let invalid = || format!("invalid value `{value}` for `{flag}`");
+      match flag.as_str() {
+        "--duration" => options.duration = value.parse().map_err(|_| invalid())?,
+        _ => return Err(format!("unknown option `{flag}`, {usage}").into()),
+      }
+    }
+    if !options.duration.is_finite() || options.duration <= 0.0 {
+      return Err("the duration must be positive".into());
+    }
+    Ok(options)
+  }
+
+  #[expect(clippy::cast_sign_loss, reason = "the duration is positive")]
+  fn sample_count(&self) -> u32 {
+    (self.duration * SAMPLE_RATE as f32).round() as u32
+  }
+}
+
+/// Looks up a sound by index or name, like `poster::find_shader`.
+fn find_sound(sound: &str) -> Result<usize, Box<dyn Error>> {
+  if let Ok(index) = sound.parse::<usize>() {
+    if index < SOUND_DEFINITIONS.len() {
+      return Ok(index);
+    }
+  }
+  SOUND_DEFINITIONS
+    .iter()
+    .position(|definition| simplify_name(definition.name) == simplify_name(sound))
+    .ok_or_else(|| {
+      let names: Vec<&str> = SOUND_DEFINITIONS.iter().map(|sound| sound.name).collect();
+      format!(
+        "no sound named `{sound}`, the sounds are {}",
+        names.join(", ")
+      )
+      .into()
+    })
+}
+
+pub fn run(args: &[String], debug: DebugMode) -> Result<(), Box<dyn Error>> {
+  let options = Options::parse(args)?;
+  let start = Instant::now();
+  let sample_count = options.sample_count();
+  let frames = if options.cpu {
+    render_cpu(options.sound, sample_count)
+  } else {
+    block_on(GpuRenderer::new(debug))?.render(options.sound, sample_count)?
+  };
+  wav::write(&options.output, SAMPLE_RATE, &frames)?;
+  println!(
+    "Wrote {} seconds of {} to {} in {:.1?}",
+    options.duration,
+    SOUND_DEFINITIONS[options.sound].name,
+    options.output.display(),
+    start.elapsed()
+  );
+  Ok(())
+}
+
+fn constants(sound: usize, first_sample: u32, sample_count: u32) -> SoundConstants {
+  SoundConstants {
+    sound_index: sound as u32,
+    first_sample,
+    sample_count,
+    sample_rate: SAMPLE_RATE as f32,
+  }
+}
+
+/// Runs [`sounds::sound`] for every sample, spread over all cores.
+fn render_cpu(sound: usize, sample_count: u32) -> Vec<[f32; 2]> {
+  let mut frames = vec![[0.0; 2]; sample_count as usize];
+  let threads = std::thread::available_parallelism().map_or(1, NonZero::get);
+  let frames_per_thread = frames.len().div_ceil(threads).max(1);
+  let constants = constants(sound, 0, sample_count);
+  std::thread::scope(|scope| {
+    for (chunk_index, chunk) in frames.chunks_mut(frames_per_thread).enumerate() {
+      let constants = &constants;
+      scope.spawn(move || {
+        let first = (chunk_index * frames_per_thread) as u32;
+        for (index, frame) in (first..).zip(chunk) {
+          *frame = sounds::sound(constants, index).to_array();
+        }
+      });
+    }
+  });
+  frames
+}
+
+/// Evaluates the sound shaders with a compute pipeline and reads the samples back.
+struct GpuRenderer {
+  device: wgpu::Device,
+  queue: wgpu::Queue,
+  pipeline: wgpu::ComputePipeline,
+  bind_group: wgpu::BindGroup,
+  constants: wgpu::Buffer,
+  samples: wgpu::Buffer,
+  readback: wgpu::Buffer,
+  debug: DebugMode,
+}
+
+impl GpuRenderer {
+  async fn new(debug: DebugMode) -> Result<Self, Box<dyn Error>> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+      flags: debug.instance_flags(),
+      ..Default::default()
+    });
+    let adapter = instance
+      .request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+      })
+      .await?;
+    let (device, queue, use_push_constants) = request_device(&adapter, debug).await?;
+    debug.push_error_scope(&device);
+    let shader_module = create_shader_module(&device, use_push_constants);
+    let buffer_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility: wgpu::ShaderStages::COMPUTE,
+      ty: wgpu::BindingType::Buffer {
+        ty,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      },
+      count: None,
+    };
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: debug.label("sound bind group layout"),
+      entries: &[
+        buffer_entry(0, wgpu::BufferBindingType::Uniform),
+        buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: false }),
+      ],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: debug.label("sound pipeline layout"),
+      bind_group_layouts: &[&bind_group_layout],
+      push_constant_ranges: &[],
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+      label: debug.label("sound pipeline"),
+      layout: Some(&pipeline_layout),
+      module: &shader_module,
+      entry_point: Some("main_sound_cs"),
+      compilation_options: wgpu::PipelineCompilationOptions::default(),
+      cache: None,
+    });
+    let constants = device.create_buffer(&wgpu::BufferDescriptor {
+      label: debug.label("sound constants buffer"),
+      size: size_of::<SoundConstants>() as u64,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let samples = device.create_buffer(&wgpu::BufferDescriptor {
+      label: debug.label("sound samples buffer"),
+      size: u64::from(BLOCK_SAMPLES) * FRAME_SIZE,
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+      label: debug.label("sound readback buffer"),
+      size: u64::from(BLOCK_SAMPLES) * FRAME_SIZE,
+      usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: debug.label("sound bind group"),
+      layout: &bind_group_layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: constants.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: samples.as_entire_binding(),
+        },
+      ],
+    });
+    if let Some(error) = debug.pop_error_scope(&device).await {
+      return Err(format!("creating the sound pipeline (`main_sound_cs`) failed: {error}").into());
+    }
+    Ok(Self {
+      device,
+      queue,
+      pipeline,
+      bind_group,
+      constants,
+      samples,
+      readback,
+      debug,
+    })
+  }
+
+  fn render(&self, sound: usize, sample_count: u32) -> Result<Vec<[f32; 2]>, Box<dyn Error>> {
+    let (device, queue, debug) = (&self.device, &self.queue, self.debug);
+    let mut frames = Vec::with_capacity(sample_count as usize);
+    for first_sample in (0..sample_count).step_by(BLOCK_SAMPLES as usize) {
+      let block_samples = BLOCK_SAMPLES.min(sample_count - first_sample);
+      let constants = constants(sound, first_sample, block_samples);
+      queue.write_buffer(&self.constants, 0, bytemuck::bytes_of(&constants));
+      debug.push_error_scope(device);
+      let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: debug.label("sound encoder"),
+      });
+      {
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+          label: debug.label("sound compute pass"),
+          timestamp_writes: None,
+        });
+        cpass.set_pipeline(&self.pipeline);
+        cpass.set_bind_group(0, &self.bind_group, &[]);
+        cpass.dispatch_workgroups(block_samples.div_ceil(WORKGROUP_SIZE), 1, 1);
+      }
+      let size = u64::from(block_samples) * FRAME_SIZE;
+      encoder.copy_buffer_to_buffer(&self.samples, 0, &self.readback, 0, size);
+      queue.submit(Some(encoder.finish()));
+      debug.report_error_scope(device, || {
+        format!("rendering the samples from {first_sample}")
+      });
+
+      let slice = self.readback.slice(..size);
+      let (sender, receiver) = mpsc::channel();
+      slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+      });
+      device.poll(wgpu::PollType::Wait)?;
+      receiver.recv()??;
+      frames.extend(bytemuck::pod_collect_to_vec::<u8, [f32; 2]>(
+        &slice.get_mapped_range(),
+      ));
+      self.readback.unmap();
+    }
+    Ok(frames)
+  }
+}
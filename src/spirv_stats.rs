@@ -0,0 +1,406 @@
+//! Validation and size statistics for the compiled shader module.
+//!
+//! Run with `cargo run --release -- spirv-stats [path/to/module.spv]`. Without a path the
+//! module embedded into the viewer at build time is inspected.
+
+use core::{cmp::Reverse, error::Error};
+use std::{
+  collections::HashMap,
+  fs, io,
+  process::{self, Command},
+};
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+// The handful of opcodes we care about, see the SPIR-V specification section 3.52.
+const OP_NAME: u16 = 5;
+const OP_ENTRY_POINT: u16 = 15;
+const OP_FUNCTION: u16 = 54;
+const OP_FUNCTION_END: u16 = 56;
+const OP_FUNCTION_CALL: u16 = 57;
+const OP_LOOP_MERGE: u16 = 246;
+const OP_SELECTION_MERGE: u16 = 247;
+const OP_LABEL: u16 = 248;
+const OP_BRANCH_CONDITIONAL: u16 = 250;
+const OP_SWITCH: u16 = 251;
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+  functions: usize,
+  instructions: usize,
+  blocks: usize,
+  loops: usize,
+  selections: usize,
+  conditional_branches: usize,
+  switches: usize,
+}
+
+impl Counts {
+  fn add(&mut self, other: &Self) {
+    self.functions += other.functions;
+    self.instructions += other.instructions;
+    self.blocks += other.blocks;
+    self.loops += other.loops;
+    self.selections += other.selections;
+    self.conditional_branches += other.conditional_branches;
+    self.switches += other.switches;
+  }
+}
+
+struct Function {
+  id: u32,
+  counts: Counts,
+  callees: Vec<u32>,
+}
+
+struct EntryPoint {
+  name: String,
+  execution_model: u32,
+  function: u32,
+}
+
+struct Module {
+  version: (u32, u32),
+  bound: u32,
+  total_instructions: usize,
+  names: HashMap<u32, String>,
+  entry_points: Vec<EntryPoint>,
+  functions: Vec<Function>,
+}
+
+fn words_from_bytes(bytes: &[u8]) -> Result<Vec<u32>, Box<dyn Error>> {
+  if !bytes.len().is_multiple_of(4) || bytes.len() < 20 {
+    return Err("SPIR-V module size is not a multiple of four bytes or too short".into());
+  }
+  let mut words: Vec<u32> = bytes
+    .chunks_exact(4)
+    .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+    .collect();
+  if words[0] == SPIRV_MAGIC.swap_bytes() {
+    for word in &mut words {
+      *word = word.swap_bytes();
+    }
+  }
+  if words[0] != SPIRV_MAGIC {
+    return Err(format!("bad SPIR-V magic number {:#010x}", words[0]).into());
+  }
+  Ok(words)
+}
+
+/// Decodes a nul-terminated literal string operand.
+fn literal_string(operands: &[u32]) -> String {
+  let bytes: Vec<u8> = operands
+    .iter()
+    .flat_map(|word| word.to_le_bytes())
+    .take_while(|&byte| byte != 0)
+    .collect();
+  String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn parse(words: &[u32]) -> Result<Module, Box<dyn Error>> {
+  let version = ((words[1] >> 16) & 0xff, (words[1] >> 8) & 0xff);
+  let mut module = Module {
+    version,
+    bound: words[3],
+    total_instructions: 0,
+    names: HashMap::new(),
+    entry_points: Vec::new(),
+    functions: Vec::new(),
+  };
+
+  let mut current: Option<Function> = None;
+  let mut offset = 5;
+  while offset < words.len() {
+    let word_count = (words[offset] >> 16) as usize;
+    let opcode = (words[offset] & 0xffff) as u16;
+    let start = offset;
+    let malformed = || format!("malformed instruction at word {start}");
+    if word_count == 0 || offset + word_count > words.len() {
+      return Err(malformed().into());
+    }
+    let operands = &words[offset + 1..offset + word_count];
+    offset += word_count;
+    module.total_instructions += 1;
+
+    match opcode {
+      OP_NAME => {
+        let [target, name @ ..] = operands else {
+          return Err(malformed().into());
+        };
+        module.names.insert(*target, literal_string(name));
+      },
+      OP_ENTRY_POINT => {
+        let [execution_model, function, name @ ..] = operands else {
+          return Err(malformed().into());
+        };
+        module.entry_points.push(EntryPoint {
+          name: literal_string(name),
+          execution_model: *execution_model,
+          function: *function,
+        });
+      },
+      OP_FUNCTION => {
+        let [_, id, ..] = operands else {
+          return Err(malformed().into());
+        };
+        current = Some(Function {
+          id: *id,
+          counts: Counts {
+            functions: 1,
+            ..Counts::default()
+          },
+          callees: Vec::new(),
+        });
+      },
+      OP_FUNCTION_END => {
+        if let Some(mut function) = current.take() {
+          function.counts.instructions += 1;
+          module.functions.push(function);
+        }
+      },
+      _ => {},
+    }
+
+    if let Some(function) = current.as_mut() {
+      let counts = &mut function.counts;
+      counts.instructions += 1;
+      match opcode {
+        OP_FUNCTION_CALL => {
+          let [_, _, callee, ..] = operands else {
+            return Err(malformed().into());
+          };
+          function.callees.push(*callee);
+        },
+        OP_LABEL => counts.blocks += 1,
+        OP_LOOP_MERGE => counts.loops += 1,
+        OP_SELECTION_MERGE => counts.selections += 1,
+        OP_BRANCH_CONDITIONAL => counts.conditional_branches += 1,
+        OP_SWITCH => counts.switches += 1,
+        _ => {},
+      }
+    }
+  }
+  Ok(module)
+}
+
+const fn execution_model_name(model: u32) -> &'static str {
+  match model {
+    0 => "vertex",
+    4 => "fragment",
+    5 => "compute",
+    _ => "other",
+  }
+}
+
+/// Shader ports live in `shadertoys_shaders::shaders::<port>`, attribute each function to the
+/// port it was defined in.
+fn port_of(function_name: &str) -> Option<&str> {
+  let rest =
+    &function_name[function_name.find("shaders::shaders::")? + "shaders::shaders::".len()..];
+  let end = rest.find("::").unwrap_or(rest.len());
+  Some(&rest[..end])
+}
+
+fn print_header(first_column: &str) {
+  println!(
+    "{first_column:<32} {:>6} {:>8} {:>7} {:>6} {:>6} {:>8} {:>6}",
+    "fns", "instrs", "blocks", "loops", "ifs", "condbr", "switch"
+  );
+}
+
+fn print_row(label: &str, counts: &Counts) {
+  println!(
+    "{label:<32} {:>6} {:>8} {:>7} {:>6} {:>6} {:>8} {:>6}",
+    counts.functions,
+    counts.instructions,
+    counts.blocks,
+    counts.loops,
+    counts.selections,
+    counts.conditional_branches,
+    counts.switches
+  );
+}
+
+fn print_statistics(module: &Module) {
+  println!(
+    "SPIR-V {}.{}, id bound {}, {} instructions, {} functions",
+    module.version.0,
+    module.version.1,
+    module.bound,
+    module.total_instructions,
+    module.functions.len()
+  );
+
+  let by_id: HashMap<u32, &Function> = module.functions.iter().map(|f| (f.id, f)).collect();
+
+  println!();
+  print_header("entry point");
+  for entry_point in &module.entry_points {
+    // Sum over every function reachable from the entry point.
+    let mut total = Counts::default();
+    let mut visited = vec![entry_point.function];
+    let mut stack = vec![entry_point.function];
+    while let Some(id) = stack.pop() {
+      if let Some(function) = by_id.get(&id) {
+        total.add(&function.counts);
+        for &callee in &function.callees {
+          if !visited.contains(&callee) {
+            visited.push(callee);
+            stack.push(callee);
+          }
+        }
+      }
+    }
+    let label = format!(
+      "{} ({})",
+      entry_point.name,
+      execution_model_name(entry_point.execution_model)
+    );
+    print_row(&label, &total);
+  }
+
+  let mut per_port: HashMap<&str, Counts> = HashMap::new();
+  for function in &module.functions {
+    let port = module
+      .names
+      .get(&function.id)
+      .and_then(|name| port_of(name))
+      .unwrap_or("<shared>");
+    per_port.entry(port).or_default().add(&function.counts);
+  }
+  let mut per_port: Vec<_> = per_port.into_iter().collect();
+  per_port.sort_by_key(|(port, counts)| (Reverse(counts.instructions), *port));
+
+  // Functions that got inlined are counted towards their caller.
+  println!();
+  print_header("shader");
+  for (port, counts) in &per_port {
+    print_row(port, counts);
+  }
+}
+
+fn validate_with_naga(bytes: &[u8]) -> Result<(), String> {
+  use wgpu::naga::{front::spv, valid};
+
+  let module = spv::parse_u8_slice(bytes, &spv::Options::default())
+    .map_err(|e| format!("naga failed to parse the module: {e}"))?;
+  valid::Validator::new(valid::ValidationFlags::all(), valid::Capabilities::all())
+    .validate(&module)
+    .map_err(|e| format!("naga validation failed: {}", e.as_inner()))?;
+  Ok(())
+}
+
+fn validate_with_spirv_val(bytes: &[u8]) -> io::Result<Option<String>> {
+  // Unique per process, so that concurrent runs don't overwrite each other's module.
+  let path = std::env::temp_dir().join(format!("shadertoys_shaders_stats_{}.spv", process::id()));
+  fs::write(&path, bytes)?;
+  let output = Command::new("spirv-val")
+    .args(["--target-env", "vulkan1.2"])
+    .arg(&path)
+    .output();
+  let _ = fs::remove_file(&path);
+  let output = match output {
+    Ok(output) => output,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(e),
+  };
+  if output.status.success() {
+    Ok(Some(String::new()))
+  } else {
+    Ok(Some(format!(
+      "{}{}",
+      String::from_utf8_lossy(&output.stdout),
+      String::from_utf8_lossy(&output.stderr)
+    )))
+  }
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+  let bytes = match args.first() {
+    Some(path) => fs::read(path)?,
+    None => include_bytes!(env!("shadertoys_shaders.spv")).to_vec(),
+  };
+  let words = words_from_bytes(&bytes)?;
+  let module = parse(&words)?;
+  print_statistics(&module);
+
+  println!();
+  let mut valid = true;
+  match validate_with_naga(&bytes) {
+    Ok(()) => println!("naga: ok"),
+    Err(e) => {
+      valid = false;
+      println!("naga: {e}");
+    },
+  }
+  match validate_with_spirv_val(&bytes)? {
+    None => println!("spirv-val: not installed, skipped"),
+    Some(errors) if errors.is_empty() => println!("spirv-val: ok"),
+    Some(errors) => {
+      valid = false;
+      println!("spirv-val: {errors}");
+    },
+  }
+
+  if valid {
+    Ok(())
+  } else {
+    Err("SPIR-V validation failed".into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A module with the header and `instructions`, each an opcode and its operands.
+  fn module(instructions: &[(u16, &[u32])]) -> Vec<u32> {
+    let mut words = vec![SPIRV_MAGIC, 0x0001_0300, 0, 16, 0];
+    for (opcode, operands) in instructions {
+      words.push(((operands.len() as u32 + 1) << 16) | u32::from(*opcode));
+      words.extend_from_slice(operands);
+    }
+    words
+  }
+
+  #[test]
+  fn functions_and_calls_are_counted() {
+    let words = module(&[
+      (OP_NAME, &[2, u32::from_le_bytes(*b"main"), 0]),
+      (OP_ENTRY_POINT, &[4, 2, u32::from_le_bytes(*b"main"), 0]),
+      (OP_FUNCTION, &[1, 2, 0, 3]),
+      (OP_LABEL, &[5]),
+      (OP_FUNCTION_CALL, &[1, 6, 7]),
+      (OP_FUNCTION_END, &[]),
+    ]);
+    let module = parse(&words).unwrap();
+    assert_eq!(module.version, (1, 3));
+    assert_eq!(module.total_instructions, 6);
+    assert_eq!(module.names[&2], "main");
+    assert_eq!(module.entry_points[0].name, "main");
+    let [function] = &module.functions[..] else {
+      panic!("{} functions", module.functions.len());
+    };
+    assert_eq!(function.id, 2);
+    assert_eq!(function.callees, [7]);
+    assert_eq!(function.counts.blocks, 1);
+    assert_eq!(function.counts.instructions, 4);
+  }
+
+  #[test]
+  fn truncated_instructions_are_malformed() {
+    for instruction in [
+      (OP_NAME, &[][..]),
+      (OP_ENTRY_POINT, &[4]),
+      (OP_FUNCTION, &[1]),
+    ] {
+      let error = parse(&module(&[instruction])).err().unwrap();
+      assert_eq!(error.to_string(), "malformed instruction at word 5");
+    }
+    let call = module(&[(OP_FUNCTION, &[1, 2, 0, 3]), (OP_FUNCTION_CALL, &[1, 6])]);
+    assert!(parse(&call).is_err());
+    let mut overlong = module(&[(OP_LABEL, &[5])]);
+    overlong[5] += 1 << 16;
+    assert!(parse(&overlong).is_err());
+  }
+}
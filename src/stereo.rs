@@ -0,0 +1,47 @@
+//! Picks the stereo mode and the eye separation, see `shadertoys_shaders::stereo::StereoMode`.
+//!
+//! Scenes have their own scale, so the separation is adjusted by a factor at a time, starting
+//! from about the distance between human eyes if a unit of the scene were a metre.
+
+use shadertoys_shaders::{shared_data::ShaderConstants, stereo::StereoMode};
+
+const DEFAULT_EYE_SEPARATION: f32 = 0.065;
+/// Factor per key press.
+const EYE_SEPARATION_STEP: f32 = 1.25;
+
+pub struct StereoController {
+  mode: StereoMode,
+  eye_separation: f32,
+}
+
+impl StereoController {
+  pub const fn new() -> Self {
+    Self {
+      mode: StereoMode::Off,
+      eye_separation: DEFAULT_EYE_SEPARATION,
+    }
+  }
+
+  /// Switches to the next mode, wrapping around.
+  pub fn cycle(&mut self) {
+    let index = StereoMode::ALL.iter().position(|mode| *mode == self.mode);
+    let next = index.map_or(0, |index| (index + 1) % StereoMode::ALL.len());
+    self.mode = StereoMode::ALL[next];
+    println!("Stereo: {:?}", self.mode);
+  }
+
+  /// Moves the eyes apart, or closer together if `wider` is false.
+  pub fn adjust_separation(&mut self, wider: bool) {
+    if wider {
+      self.eye_separation *= EYE_SEPARATION_STEP;
+    } else {
+      self.eye_separation /= EYE_SEPARATION_STEP;
+    }
+    println!("Eye separation: {:.4}", self.eye_separation);
+  }
+
+  pub fn write_constants(&self, constants: &mut ShaderConstants) {
+    constants.stereo = self.mode as u32;
+    constants.eye_separation = self.eye_separation;
+  }
+}
@@ -0,0 +1,2185 @@
+//! Rust from the syntax tree. Types are inferred along the way, to pick between the methods of
+//! scalars and vectors and to turn the constructors of GLSL into those of glam.
+//!
+//! Functions using the uniforms become methods of `Inputs`, functions using global variables
+//! methods of `State`, which holds the globals and the `Inputs`, as in the hand-written ports.
+
+use super::{
+  camel_case,
+  lex::Comment,
+  parse::{Decl, Expr, Function, Item, ItemKind, Qualifier, Stmt, StmtKind, Type},
+  screaming_snake_case, snake_case,
+  usage::{self, Usage},
+  Definition, Flag,
+};
+use core::fmt::Write as _;
+use std::collections::HashMap;
+
+// Precedences of Rust expressions, higher binds tighter.
+const IF: u8 = 0;
+const RANGE: u8 = 1;
+const OR: u8 = 2;
+const AND: u8 = 3;
+const COMPARE: u8 = 4;
+const BIT_OR: u8 = 5;
+const BIT_XOR: u8 = 6;
+const BIT_AND: u8 = 7;
+const SHIFT: u8 = 8;
+const SUM: u8 = 9;
+const PRODUCT: u8 = 10;
+const CAST: u8 = 11;
+const UNARY: u8 = 12;
+const POSTFIX: u8 = 13;
+const PRIMARY: u8 = 14;
+
+/// An expression in Rust.
+struct Code {
+  text: String,
+  ty: Type,
+  precedence: u8,
+}
+
+impl Code {
+  fn new(text: impl Into<String>, ty: Type, precedence: u8) -> Self {
+    Self {
+      text: text.into(),
+      ty,
+      precedence,
+    }
+  }
+
+  /// The text, in parentheses if it binds looser than `precedence`.
+  fn at(&self, precedence: u8) -> String {
+    if self.precedence < precedence {
+      format!("({})", self.text)
+    } else {
+      self.text.clone()
+    }
+  }
+}
+
+/// Where a function lives, in the order of what it can reach.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Receiver {
+  Free,
+  Inputs,
+  State,
+  StateMut,
+}
+
+struct Signature {
+  rust_name: String,
+  params: Vec<(Type, Qualifier)>,
+  ret: Type,
+  receiver: Receiver,
+}
+
+/// The fields of `Inputs`, as far as the shader uses them, in the order of `ShaderInput`.
+#[derive(Default)]
+struct Uniforms {
+  fields: Vec<&'static str>,
+}
+
+impl Uniforms {
+  const ORDER: [&'static str; 4] = ["resolution", "time", "mouse", "channels"];
+
+  fn insert(&mut self, field: &'static str) {
+    if !self.fields.contains(&field) {
+      self.fields.push(field);
+      self
+        .fields
+        .sort_by_key(|field| Self::ORDER.iter().position(|f| f == field));
+    }
+  }
+
+  fn any(&self) -> bool {
+    !self.fields.is_empty()
+  }
+
+  fn channels(&self) -> bool {
+    self.fields.contains(&"channels")
+  }
+
+  /// `Inputs` with its generic parameter.
+  fn ty(&self) -> &'static str {
+    if self.channels() {
+      "Inputs<C>"
+    } else {
+      "Inputs"
+    }
+  }
+}
+
+/// The field of `Inputs` behind a Shadertoy uniform.
+fn uniform_field(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "iResolution" => "resolution",
+    "iTime" | "iGlobalTime" | "iFrame" => "time",
+    "iMouse" => "mouse",
+    "iChannel0" | "iChannel1" | "iChannel2" | "iChannel3" | "iChannelTime"
+    | "iChannelResolution" => "channels",
+    _ => return None,
+  })
+}
+
+/// What the items of the shader declare.
+#[derive(Default)]
+struct Module {
+  structs: HashMap<String, Vec<(Type, String)>>,
+  functions: HashMap<String, Vec<Signature>>,
+  /// Rust names and types of constants.
+  consts: HashMap<String, (String, Type)>,
+  /// Rust names and types of the fields of `State`.
+  globals: HashMap<String, (String, Type)>,
+  uniforms: Uniforms,
+}
+
+impl Module {
+  fn is_out(&self, function: &str, index: usize) -> bool {
+    self.functions.get(function).is_some_and(|overloads| {
+      overloads.iter().any(|signature| {
+        signature
+          .params
+          .get(index)
+          .is_some_and(|(_, qualifier)| *qualifier != Qualifier::In)
+      })
+    })
+  }
+
+  fn state_type(&self) -> &'static str {
+    if self.uniforms.channels() {
+      "State<C>"
+    } else {
+      "State"
+    }
+  }
+}
+
+/// A variable in scope.
+struct Local {
+  glsl: String,
+  rust: String,
+  ty: Type,
+  /// An `out` or `inout` parameter, a `&mut`.
+  out: bool,
+}
+
+/// Writes Rust for the items of a shader.
+pub fn emit(
+  items: &[Item],
+  definition: &Definition<'_>,
+  flags: &mut Vec<Flag>,
+) -> Result<String, Flag> {
+  let mut module = Module::default();
+  // Constants and globals first, functions reference them.
+  for item in items {
+    match &item.kind {
+      ItemKind::Struct(struct_name, fields) => {
+        module.structs.insert(struct_name.clone(), fields.clone());
+      },
+      ItemKind::Const(ty, decl) => {
+        let ty = ty.clone().unwrap_or_else(|| {
+          let mut emitter = Emitter::new(&module, flags, Receiver::Free, item.line);
+          decl
+            .init
+            .as_ref()
+            .map_or(Type::Float, |init| emitter.expr(init).ty)
+        });
+        if is_constant(decl.init.as_ref(), &module) {
+          module
+            .consts
+            .insert(decl.name.clone(), (screaming_snake_case(&decl.name), ty));
+        } else {
+          module
+            .globals
+            .insert(decl.name.clone(), (snake_case(&decl.name), ty));
+        }
+      },
+      ItemKind::Global(decl) => {
+        module
+          .globals
+          .insert(decl.name.clone(), (snake_case(&decl.name), decl.ty.clone()));
+      },
+      ItemKind::Function(function) => {
+        let overloads = module.functions.entry(function.name.clone()).or_default();
+        let mut rust_name = snake_case(&function.name);
+        if !overloads.is_empty() {
+          for param in &function.params {
+            let _ = write!(rust_name, "_{}", type_suffix(&param.decl.ty));
+          }
+        }
+        overloads.push(Signature {
+          rust_name,
+          params: function
+            .params
+            .iter()
+            .map(|param| (param.decl.ty.clone(), param.qualifier))
+            .collect(),
+          ret: function.ret.clone(),
+          receiver: Receiver::Free,
+        });
+      },
+      ItemKind::Empty => {},
+    }
+  }
+  receivers(items, &mut module);
+
+  let main = module
+    .functions
+    .get("mainImage")
+    .and_then(|overloads| overloads.first())
+    .ok_or_else(|| Flag::new(1, "no `mainImage` function"))?;
+  let main_receiver = main.receiver;
+  let inputs = module.uniforms.any();
+  let generic = if module.uniforms.channels() {
+    "<C>"
+  } else {
+    ""
+  };
+
+  let mut out = String::from("use crate::shader_prelude::*;\n\n");
+  let _ = writeln!(out, "{definition}");
+  out.push_str(
+    "pub fn shader_fn<C: Channel>(\n  render_instruction: &ShaderInput<C>,\n  render_result: &mut \
+     ShaderResult,\n) {\n  let color = &mut render_result.color;\n  let &ShaderInput {\n",
+  );
+  let fields = &module.uniforms.fields;
+  for field in ["resolution", "time", "frag_coord", "mouse", "channels"] {
+    if field == "frag_coord" || fields.contains(&field) {
+      let _ = writeln!(out, "    {field},");
+    }
+  }
+  out.push_str("    ..\n  } = render_instruction;\n");
+  let inputs_value = format!("Inputs {{ {} }}", fields.join(", "));
+  let _ = match main_receiver {
+    Receiver::Free => writeln!(out, "  main_image(color, frag_coord);"),
+    Receiver::Inputs => writeln!(out, "  {inputs_value}.main_image(color, frag_coord);"),
+    Receiver::State | Receiver::StateMut => {
+      let argument = if inputs { inputs_value.as_str() } else { "" };
+      writeln!(
+        out,
+        "  State::new({argument}).main_image(color, frag_coord);"
+      )
+    },
+  };
+  out.push_str("}\n");
+
+  if inputs {
+    let _ = writeln!(out, "\nstruct Inputs{generic} {{");
+    for field in fields {
+      let ty = match *field {
+        "resolution" => "Vec3",
+        "time" => "f32",
+        "mouse" => "Vec4",
+        _ => "[C; 4]",
+      };
+      let _ = writeln!(out, "  {field}: {ty},");
+    }
+    out.push_str("}\n");
+  }
+
+  if !module.globals.is_empty() {
+    state(items, &module, flags, &mut out);
+  }
+
+  // The items in order, with the methods of consecutive functions in one `impl`.
+  let mut open_impl: Option<Receiver> = None;
+  for (index, item) in items.iter().enumerate() {
+    // Global variables are fields of `State`, with their comments.
+    let field = match &item.kind {
+      ItemKind::Global(_) => true,
+      ItemKind::Const(_, decl) => !module.consts.contains_key(&decl.name),
+      _ => false,
+    };
+    if field {
+      continue;
+    }
+    // The comment at the top, usually the title and the license, is left to the header.
+    let comments = if index == 0 {
+      &[][..]
+    } else {
+      &item.comments[..]
+    };
+    let receiver = match &item.kind {
+      ItemKind::Function(function) => Some(signature(&module, function).receiver),
+      _ => None,
+    };
+    let impl_receiver = receiver
+      .filter(|&receiver| receiver != Receiver::Free)
+      .map(|receiver| {
+        if receiver == Receiver::Inputs {
+          Receiver::Inputs
+        } else {
+          Receiver::State
+        }
+      });
+    if open_impl.is_some() && open_impl != impl_receiver {
+      out.push_str("}\n");
+      open_impl = None;
+    }
+    let indent = usize::from(impl_receiver.is_some());
+    let mut code = String::new();
+    match &item.kind {
+      ItemKind::Const(_, decl) if module.consts.contains_key(&decl.name) => {
+        let (rust_name, ty) = &module.consts[&decl.name];
+        let mut emitter = Emitter::new(&module, flags, Receiver::Free, item.line);
+        let value = decl
+          .init
+          .as_ref()
+          .map(|init| emitter.coerce(init, ty).text)
+          .unwrap_or_default();
+        let ty = emitter.rust_type(ty);
+        let todos = core::mem::take(&mut emitter.todos);
+        for todo in todos {
+          let _ = writeln!(code, "// TODO: {todo}");
+        }
+        let _ = writeln!(code, "const {rust_name}: {ty} = {value};");
+      },
+      ItemKind::Struct(struct_name, fields) => {
+        let mut emitter = Emitter::new(&module, flags, Receiver::Free, item.line);
+        let _ = writeln!(
+          code,
+          "#[derive(Copy, Clone, Default)]\nstruct {} {{",
+          camel_case(struct_name)
+        );
+        for (ty, field) in fields {
+          let _ = writeln!(code, "  {}: {},", snake_case(field), emitter.rust_type(ty));
+        }
+        code.push_str("}\n");
+      },
+      ItemKind::Function(function) => {
+        let signature = signature(&module, function);
+        let mut emitter = Emitter::new(&module, flags, signature.receiver, item.line);
+        emitter.indent = indent;
+        emitter.function(function, signature);
+        code = emitter.out;
+      },
+      ItemKind::Const(..) | ItemKind::Global(_) | ItemKind::Empty => {},
+    }
+    if code.is_empty() && comments.is_empty() {
+      continue;
+    }
+    // Constants stay together as in the original, everything else is set apart.
+    let grouped = matches!(item.kind, ItemKind::Const(..) | ItemKind::Empty)
+      && !item.blank_line_before
+      && index > 0
+      && matches!(items[index - 1].kind, ItemKind::Const(..) | ItemKind::Empty);
+    if let Some(impl_receiver) = impl_receiver.filter(|_| open_impl.is_none()) {
+      let ty = if impl_receiver == Receiver::Inputs {
+        module.uniforms.ty()
+      } else {
+        module.state_type()
+      };
+      let bound = if module.uniforms.channels() {
+        "<C: Channel>"
+      } else {
+        ""
+      };
+      let _ = writeln!(out, "\nimpl{bound} {ty} {{");
+      open_impl = Some(impl_receiver);
+    } else if !grouped || out.ends_with("}\n") {
+      out.push('\n');
+    }
+    write_comments(&mut out, comments, indent);
+    out.push_str(&code);
+  }
+  if open_impl.is_some() {
+    out.push_str("}\n");
+  }
+  Ok(out)
+}
+
+/// The signature of `function` in the module.
+fn signature<'a>(module: &'a Module, function: &Function) -> &'a Signature {
+  let overloads = &module.functions[&function.name];
+  overloads
+    .iter()
+    .find(|signature| {
+      signature.params.len() == function.params.len()
+        && signature
+          .params
+          .iter()
+          .zip(&function.params)
+          .all(|((ty, _), param)| *ty == param.decl.ty)
+    })
+    .unwrap_or(&overloads[0])
+}
+
+/// The suffix that tells overloads apart.
+fn type_suffix(ty: &Type) -> String {
+  match ty {
+    Type::Void => "void".into(),
+    Type::Bool => "bool".into(),
+    Type::Int => "int".into(),
+    Type::Uint => "uint".into(),
+    Type::Float => "float".into(),
+    Type::Vec(n) => format!("vec{n}"),
+    Type::Mat(n) => format!("mat{n}"),
+    Type::Array(element, _) => format!("{}s", type_suffix(element)),
+    Type::Sampler => "sampler".into(),
+    Type::Struct(name) | Type::Other(name) => snake_case(name),
+  }
+}
+
+/// Whether a constant can stay a `const`: literals and constants in arithmetic on scalars, and
+/// constructors of those.
+fn is_constant(init: Option<&Expr>, module: &Module) -> bool {
+  fn scalar(expr: &Expr, module: &Module) -> bool {
+    match expr {
+      Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) => true,
+      Expr::Ident(name) => module
+        .consts
+        .get(name)
+        .is_some_and(|(_, ty)| ty.components() == Some(1)),
+      Expr::Unary(_, operand) => scalar(operand, module),
+      Expr::Binary(_, lhs, rhs) => scalar(lhs, module) && scalar(rhs, module),
+      _ => false,
+    }
+  }
+  fn constructor(expr: &Expr, module: &Module) -> bool {
+    match expr {
+      Expr::Call(name, args)
+        if matches!(name.as_str(), "vec2" | "vec3" | "vec4" | "mat2" | "mat3") =>
+      {
+        args.iter().all(|arg| scalar(arg, module))
+      },
+      Expr::Ident(name) => module.consts.contains_key(name),
+      _ => scalar(expr, module),
+    }
+  }
+  match init {
+    Some(Expr::ArrayConstructor(_, values)) => {
+      values.iter().all(|value| constructor(value, module))
+    },
+    Some(init) => constructor(init, module),
+    None => false,
+  }
+}
+
+/// Works out the receivers of the functions and the uniforms used, following the calls.
+fn receivers(items: &[Item], module: &mut Module) {
+  struct Uses {
+    receiver: Receiver,
+    calls: Vec<String>,
+  }
+  let mut uses = Vec::new();
+  for item in items {
+    let ItemKind::Function(function) = &item.kind else {
+      continue;
+    };
+    let mut locals: Vec<&str> = function
+      .params
+      .iter()
+      .map(|p| p.decl.name.as_str())
+      .collect();
+    visit_stmts(&function.body, &mut |stmt| {
+      if let StmtKind::Decl(decls) = &stmt.kind {
+        locals.extend(decls.iter().map(|decl| decl.name.as_str()));
+      }
+    });
+    let mut receiver = Receiver::Free;
+    let mut calls = Vec::new();
+    let module_ref: &Module = module;
+    let mut fields = Vec::new();
+    let mut global = |name: &str, write: bool, receiver: &mut Receiver| {
+      if locals.contains(&name) {
+        return;
+      }
+      if module_ref.globals.contains_key(name) {
+        *receiver = (*receiver).max(if write {
+          Receiver::StateMut
+        } else {
+          Receiver::State
+        });
+      } else if let Some(field) = uniform_field(name) {
+        *receiver = (*receiver).max(Receiver::Inputs);
+        fields.push(field);
+      }
+    };
+    visit_stmts(&function.body, &mut |stmt| {
+      visit_stmt_exprs(stmt, &mut |expr| match expr {
+        Expr::Ident(name) => global(name, false, &mut receiver),
+        Expr::Assign(_, target, _) | Expr::Step(_, _, target) => {
+          if let Some(name) = root(target) {
+            global(name, true, &mut receiver);
+          }
+        },
+        Expr::Call(name, args) => {
+          calls.push(name.clone());
+          for (index, arg) in args.iter().enumerate() {
+            if module_ref.is_out(name, index) {
+              if let Some(name) = root(arg) {
+                global(name, true, &mut receiver);
+              }
+            }
+          }
+        },
+        _ => {},
+      });
+    });
+    for field in fields {
+      module.uniforms.insert(field);
+    }
+    uses.push((function, Uses { receiver, calls }));
+  }
+  // Callers reach what their callees reach.
+  loop {
+    let mut changed = false;
+    for (function, uses) in &mut uses {
+      for call in &uses.calls {
+        let callee = module
+          .functions
+          .get(call)
+          .and_then(|overloads| overloads.iter().map(|signature| signature.receiver).max());
+        if let Some(callee) = callee.filter(|&callee| callee > uses.receiver) {
+          uses.receiver = callee;
+          changed = true;
+        }
+      }
+      let signature_receiver = &mut signature_mut(module, function).receiver;
+      if *signature_receiver != uses.receiver {
+        *signature_receiver = uses.receiver;
+        changed = true;
+      }
+    }
+    if !changed {
+      break;
+    }
+  }
+}
+
+fn signature_mut<'a>(module: &'a mut Module, function: &Function) -> &'a mut Signature {
+  let overloads = module.functions.get_mut(&function.name).unwrap();
+  let index = overloads
+    .iter()
+    .position(|signature| {
+      signature.params.len() == function.params.len()
+        && signature
+          .params
+          .iter()
+          .zip(&function.params)
+          .all(|((ty, _), param)| *ty == param.decl.ty)
+    })
+    .unwrap_or(0);
+  &mut overloads[index]
+}
+
+/// The variable an assignment target belongs to.
+fn root(expr: &Expr) -> Option<&str> {
+  match expr {
+    Expr::Ident(name) => Some(name),
+    Expr::Member(base, _) | Expr::Index(base, _) => root(base),
+    _ => None,
+  }
+}
+
+fn visit_stmts<'a>(stmts: &'a [Stmt], f: &mut impl FnMut(&'a Stmt)) {
+  for stmt in stmts {
+    visit_stmt(stmt, f);
+  }
+}
+
+fn visit_stmt<'a>(stmt: &'a Stmt, f: &mut impl FnMut(&'a Stmt)) {
+  f(stmt);
+  match &stmt.kind {
+    StmtKind::Block(stmts, _) => visit_stmts(stmts, f),
+    StmtKind::If(_, then, otherwise) => {
+      visit_stmt(then, f);
+      if let Some(otherwise) = otherwise {
+        visit_stmt(otherwise, f);
+      }
+    },
+    StmtKind::For { init, body, .. } => {
+      if let Some(init) = init {
+        visit_stmt(init, f);
+      }
+      visit_stmt(body, f);
+    },
+    StmtKind::While(_, body) | StmtKind::DoWhile(body, _) => visit_stmt(body, f),
+    _ => {},
+  }
+}
+
+/// The expressions directly in `stmt`, not in the statements nested in it, and all their
+/// subexpressions.
+fn visit_stmt_exprs(stmt: &Stmt, f: &mut impl FnMut(&Expr)) {
+  match &stmt.kind {
+    StmtKind::Decl(decls) => {
+      for decl in decls {
+        if let Some(init) = &decl.init {
+          visit_expr(init, f);
+        }
+      }
+    },
+    StmtKind::Expr(expr)
+    | StmtKind::If(expr, ..)
+    | StmtKind::While(expr, _)
+    | StmtKind::DoWhile(_, expr)
+    | StmtKind::Return(Some(expr)) => visit_expr(expr, f),
+    StmtKind::For {
+      condition, step, ..
+    } => {
+      for expr in [condition, step].into_iter().flatten() {
+        visit_expr(expr, f);
+      }
+    },
+    _ => {},
+  }
+}
+
+fn visit_expr(expr: &Expr, f: &mut impl FnMut(&Expr)) {
+  f(expr);
+  match expr {
+    Expr::Call(_, args) | Expr::ArrayConstructor(_, args) => {
+      for arg in args {
+        visit_expr(arg, f);
+      }
+    },
+    Expr::Member(base, _) | Expr::Unary(_, base) | Expr::Step(_, _, base) => visit_expr(base, f),
+    Expr::Index(lhs, rhs)
+    | Expr::Binary(_, lhs, rhs)
+    | Expr::Assign(_, lhs, rhs)
+    | Expr::Sequence(lhs, rhs) => {
+      visit_expr(lhs, f);
+      visit_expr(rhs, f);
+    },
+    Expr::Conditional(condition, then, otherwise) => {
+      visit_expr(condition, f);
+      visit_expr(then, f);
+      visit_expr(otherwise, f);
+    },
+    Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) | Expr::Ident(_) => {},
+  }
+}
+
+/// `State` with the global variables and its constructor.
+fn state(items: &[Item], module: &Module, flags: &mut Vec<Flag>, out: &mut String) {
+  let inputs = module.uniforms.any();
+  let _ = writeln!(out, "\nstruct {} {{", module.state_type());
+  if inputs {
+    let _ = writeln!(out, "  inputs: {},\n", module.uniforms.ty());
+  }
+  let mut values = String::new();
+  for (index, item) in items.iter().enumerate() {
+    let (ItemKind::Global(decl) | ItemKind::Const(_, decl)) = &item.kind else {
+      continue;
+    };
+    let Some((field, ty)) = module.globals.get(&decl.name) else {
+      continue;
+    };
+    let mut emitter = Emitter::new(module, flags, Receiver::Free, item.line);
+    emitter.constant = true;
+    let value = match &decl.init {
+      Some(init) => emitter.coerce(init, ty).text,
+      None => emitter.zero(ty),
+    };
+    let rust_type = emitter.rust_type(ty);
+    for todo in core::mem::take(&mut emitter.todos) {
+      let _ = writeln!(values, "      // TODO: {todo}");
+    }
+    if index > 0 {
+      write_comments(out, &item.comments, 1);
+    }
+    let _ = writeln!(out, "  {field}: {rust_type},");
+    let _ = writeln!(values, "      {field}: {value},");
+  }
+  out.push_str("}\n");
+  let bound = if module.uniforms.channels() {
+    "<C: Channel>"
+  } else {
+    ""
+  };
+  let _ = writeln!(
+    out,
+    "\nimpl{bound} {} {{\n  #[must_use]",
+    module.state_type()
+  );
+  if inputs {
+    let _ = writeln!(
+      out,
+      "  fn new(inputs: {}) -> Self {{\n    Self {{\n      inputs,\n",
+      module.uniforms.ty()
+    );
+  } else {
+    out.push_str("  fn new() -> Self {\n    Self {\n");
+  }
+  out.push_str(&values);
+  out.push_str("    }\n  }\n}\n");
+}
+
+/// Writes comments at `indent` levels, keeping trailing ones on their own lines.
+fn write_comments(out: &mut String, comments: &[Comment], indent: usize) {
+  for comment in comments {
+    for line in &comment.lines {
+      let _ = writeln!(out, "{}//{line}", "  ".repeat(indent));
+    }
+  }
+}
+
+/// A Rust float literal for a GLSL one: `1.` is `1.0` and `.5` is `0.5`.
+fn float_literal(text: &str) -> String {
+  let mut literal = String::new();
+  if text.starts_with('.') {
+    literal.push('0');
+  }
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    literal.push(c);
+    if c == '.' && !chars.peek().is_some_and(char::is_ascii_digit) {
+      literal.push('0');
+    }
+  }
+  if !literal.contains(['.', 'e', 'E']) {
+    literal.push_str(".0");
+  }
+  literal
+}
+
+/// The float literal for the integer literal `text`.
+fn int_as_float(text: &str) -> String {
+  super::lex::parse_int(text).map_or_else(|| format!("{text}.0"), |value| format!("{value}.0"))
+}
+
+/// The Rust swizzle of a GLSL one, `rgb` is `xyz`, or `None` if it is not one.
+fn swizzle(field: &str, size: u8) -> Option<String> {
+  let components = ["xyzw", "rgba", "stpq"];
+  let set = components
+    .iter()
+    .find(|set| field.chars().all(|c| set.contains(c)))?;
+  if field.is_empty() || field.len() > 4 {
+    return None;
+  }
+  field
+    .chars()
+    .map(|c| {
+      let index = set.find(c)?;
+      (index < usize::from(size)).then(|| char::from(b"xyzw"[index]))
+    })
+    .collect()
+}
+
+/// Writes Rust for one function or initializer.
+struct Emitter<'a> {
+  module: &'a Module,
+  flags: &'a mut Vec<Flag>,
+  receiver: Receiver,
+  usage: HashMap<usize, Usage>,
+  scopes: Vec<Vec<Local>>,
+  out: String,
+  indent: usize,
+  /// The line of the statement being written, for flags.
+  line: usize,
+  /// Flags of the statement being written, which go into comments before it.
+  todos: Vec<String>,
+  ret: Type,
+  /// In the initializer of a constant or a global, where there are no uniforms.
+  constant: bool,
+}
+
+impl<'a> Emitter<'a> {
+  fn new(module: &'a Module, flags: &'a mut Vec<Flag>, receiver: Receiver, line: usize) -> Self {
+    Self {
+      module,
+      flags,
+      receiver,
+      usage: HashMap::new(),
+      scopes: vec![Vec::new()],
+      out: String::new(),
+      indent: 0,
+      line,
+      todos: Vec::new(),
+      ret: Type::Void,
+      constant: false,
+    }
+  }
+
+  fn flag(&mut self, message: impl Into<String>) {
+    let message = message.into();
+    self.flags.push(Flag::new(self.line, message.clone()));
+    self.todos.push(message);
+  }
+
+  /// A placeholder for an expression without a translation.
+  fn todo(&mut self, message: impl Into<String>, ty: Type) -> Code {
+    self.flag(message);
+    Code::new("todo!()", ty, PRIMARY)
+  }
+
+  fn write_line(&mut self, text: &str) {
+    let _ = writeln!(self.out, "{}{text}", "  ".repeat(self.indent));
+  }
+
+  fn rust_type(&mut self, ty: &Type) -> String {
+    match ty {
+      Type::Void => "()".into(),
+      Type::Bool => "bool".into(),
+      Type::Int => "i32".into(),
+      Type::Uint => "u32".into(),
+      Type::Float => "f32".into(),
+      Type::Vec(n) => format!("Vec{n}"),
+      Type::Mat(n) => format!("Mat{n}"),
+      Type::Struct(name) => camel_case(name),
+      Type::Array(element, length) => {
+        let element = self.rust_type(element);
+        let length = self.as_usize(length);
+        format!("[{element}; {length}]")
+      },
+      Type::Sampler => "S".into(),
+      Type::Other(name) => {
+        self.flag(format!("unsupported type `{name}`"));
+        name.clone()
+      },
+    }
+  }
+
+  /// An array length or index, a `usize`.
+  fn as_usize(&mut self, expr: &Expr) -> String {
+    if let Expr::Int(text) = expr {
+      return text.clone();
+    }
+    let code = self.expr(expr);
+    format!("{} as usize", code.at(CAST))
+  }
+
+  /// The value of `ty` before anything is assigned.
+  fn zero(&mut self, ty: &Type) -> String {
+    match ty {
+      Type::Bool => "false".into(),
+      Type::Int | Type::Uint => "0".into(),
+      Type::Float => "0.0".into(),
+      Type::Vec(n) => format!("Vec{n}::ZERO"),
+      Type::Mat(n) => format!("Mat{n}::ZERO"),
+      Type::Struct(name) => format!("{}::default()", camel_case(name)),
+      Type::Array(element, length) => {
+        let element = self.zero(element);
+        let length = self.as_usize(length);
+        format!("[{element}; {length}]")
+      },
+      _ => {
+        self
+          .todo(format!("no zero value of `{ty:?}`"), ty.clone())
+          .text
+      },
+    }
+  }
+
+  fn find_local(&self, name: &str) -> Option<&Local> {
+    self
+      .scopes
+      .iter()
+      .rev()
+      .flat_map(|scope| scope.iter().rev())
+      .find(|local| local.glsl == name)
+  }
+
+  /// Adds a variable to the scope, under a name that doesn't hide another variable whose GLSL
+  /// name only differs in case.
+  fn declare(&mut self, glsl: &str, ty: Type, out: bool, used: bool) -> String {
+    let mut rust = snake_case(glsl);
+    while self
+      .scopes
+      .iter()
+      .flatten()
+      .any(|local| local.rust == rust && local.glsl != glsl)
+    {
+      rust.push('_');
+    }
+    if !used {
+      rust.insert(0, '_');
+    }
+    if let Some(scope) = self.scopes.last_mut() {
+      scope.push(Local {
+        glsl: glsl.to_owned(),
+        rust: rust.clone(),
+        ty,
+        out,
+      });
+    }
+    rust
+  }
+
+  /// How the uniforms are reached from here.
+  fn inputs(&self) -> &'static str {
+    if self.receiver == Receiver::Inputs {
+      "self"
+    } else {
+      "self.inputs"
+    }
+  }
+
+  fn function(&mut self, function: &Function, signature: &Signature) {
+    let module = self.module;
+    self.usage = usage::analyze(function, &|name, index| module.is_out(name, index));
+    self.ret = function.ret.clone();
+    let mut params = Vec::new();
+    match signature.receiver {
+      Receiver::Free => {},
+      Receiver::Inputs | Receiver::State => params.push("&self".to_owned()),
+      Receiver::StateMut => params.push("&mut self".to_owned()),
+    }
+    let mut sampler = false;
+    for param in &function.params {
+      let usage = self.usage.get(&param.decl.id).copied().unwrap_or_default();
+      let out = param.qualifier != Qualifier::In;
+      let rust_type = self.rust_type(&param.decl.ty);
+      sampler |= param.decl.ty == Type::Sampler;
+      let name = self.declare(
+        &param.decl.name,
+        param.decl.ty.clone(),
+        out,
+        usage.read() || out,
+      );
+      params.push(if out {
+        format!("{name}: &mut {rust_type}")
+      } else if usage.assigned() {
+        format!("mut {name}: {rust_type}")
+      } else {
+        format!("{name}: {rust_type}")
+      });
+    }
+    let generic = if sampler { "<S: Channel>" } else { "" };
+    let ret = if function.ret == Type::Void {
+      String::new()
+    } else {
+      format!(" -> {}", self.rust_type(&function.ret))
+    };
+    let header = format!(
+      "fn {}{generic}({}){ret} {{",
+      signature.rust_name,
+      params.join(", ")
+    );
+    let todos = core::mem::take(&mut self.todos);
+    for todo in todos {
+      self.write_line(&format!("// TODO: {todo}"));
+    }
+    self.write_line(&header);
+    self.indent += 1;
+    let mut body = &function.body[..];
+    // A `return;` at the end is implied in Rust.
+    if let [rest @ .., last] = body {
+      if matches!(last.kind, StmtKind::Return(None)) && last.comments.is_empty() {
+        body = rest;
+      }
+    }
+    let tail = function.ret != Type::Void;
+    self.block(body, &function.end_comments, tail);
+    self.indent -= 1;
+    self.write_line("}");
+  }
+
+  /// The statements of a block, with a tail expression for a `return` at the end if `tail`.
+  fn block(&mut self, stmts: &[Stmt], end_comments: &[Comment], tail: bool) {
+    self.scopes.push(Vec::new());
+    for (index, stmt) in stmts.iter().enumerate() {
+      let last = index + 1 == stmts.len();
+      self.statement(stmt, tail && last && tail_ok(stmt));
+    }
+    let indent = self.indent;
+    write_comments(&mut self.out, end_comments, indent);
+    self.scopes.pop();
+  }
+
+  /// A statement as the body of an `if` or a loop, in braces.
+  fn body(&mut self, stmt: &Stmt, tail: bool) {
+    self.indent += 1;
+    match &stmt.kind {
+      StmtKind::Block(stmts, end_comments) if stmt.comments.is_empty() => {
+        self.block(stmts, end_comments, tail);
+      },
+      _ => self.block(core::slice::from_ref(stmt), &[], tail),
+    }
+    self.indent -= 1;
+  }
+
+  fn statement(&mut self, stmt: &Stmt, tail: bool) {
+    self.line = stmt.line;
+    if stmt.blank_line_before && !self.out.is_empty() && !self.out.ends_with("{\n") {
+      self.out.push('\n');
+    }
+    let (trailing, leading): (Vec<_>, Vec<_>) = stmt
+      .comments
+      .iter()
+      .cloned()
+      .partition(|comment| comment.trailing);
+    let indent = self.indent;
+    write_comments(&mut self.out, &leading, indent);
+    let outer_todos = core::mem::take(&mut self.todos);
+    let start = self.out.len();
+    self.statement_kind(stmt, tail);
+    let todos = core::mem::replace(&mut self.todos, outer_todos);
+    let prefix = "  ".repeat(self.indent);
+    let mut todo_lines = String::new();
+    for todo in &todos {
+      let _ = writeln!(todo_lines, "{prefix}// TODO: {todo}");
+    }
+    self.out.insert_str(start, &todo_lines);
+    let written = &self.out[start + todo_lines.len()..];
+    if written.lines().count() == 1 && !trailing.is_empty() {
+      self.out.pop();
+      let mut lines = trailing.iter().flat_map(|comment| comment.lines.iter());
+      if let Some(first) = lines.next() {
+        let _ = writeln!(self.out, " //{first}");
+      }
+      for line in lines {
+        let _ = writeln!(self.out, "{prefix}//{line}");
+      }
+    } else {
+      write_comments(&mut self.out, &trailing, indent);
+    }
+  }
+
+  fn statement_kind(&mut self, stmt: &Stmt, tail: bool) {
+    match &stmt.kind {
+      StmtKind::Decl(decls) => {
+        for decl in decls {
+          self.declaration(decl);
+        }
+      },
+      StmtKind::Expr(expr) => {
+        for line in self.expr_statement(expr) {
+          self.write_line(&line);
+        }
+      },
+      StmtKind::Block(stmts, end_comments) => {
+        self.write_line("{");
+        self.indent += 1;
+        self.block(stmts, end_comments, tail);
+        self.indent -= 1;
+        self.write_line("}");
+      },
+      StmtKind::If(..) => self.if_chain(stmt, tail, ""),
+      StmtKind::For {
+        init,
+        condition,
+        step,
+        body,
+      } => self.for_loop(init.as_deref(), condition.as_ref(), step.as_ref(), body),
+      StmtKind::While(condition, body) => {
+        let condition = self.coerce(condition, &Type::Bool);
+        self.write_line(&format!("while {} {{", condition.at(RANGE)));
+        self.body(body, false);
+        self.write_line("}");
+      },
+      StmtKind::DoWhile(body, condition) => {
+        self.write_line("loop {");
+        if contains_continue(body) {
+          self.flag("`continue` in a `do`-`while` skips the condition");
+        }
+        self.body(body, false);
+        self.indent += 1;
+        let condition = self.coerce(condition, &Type::Bool);
+        self.write_line(&format!("if !{} {{", condition.at(UNARY)));
+        self.write_line("  break;");
+        self.write_line("}");
+        self.indent -= 1;
+        self.write_line("}");
+      },
+      StmtKind::Return(value) => match value {
+        Some(value) => {
+          let ty = self.ret.clone();
+          let value = self.coerce(value, &ty);
+          if tail {
+            self.write_line(&value.text);
+          } else {
+            self.write_line(&format!("return {};", value.text));
+          }
+        },
+        None => self.write_line("return;"),
+      },
+      StmtKind::Break => self.write_line("break;"),
+      StmtKind::Continue => self.write_line("continue;"),
+      StmtKind::Discard => {
+        if self.ret != Type::Void {
+          self.flag("`discard` in a function returning a value");
+        }
+        self.write_line("discard();");
+        self.write_line("return;");
+      },
+      StmtKind::Unsupported(word) => {
+        let todo = self.todo(format!("unsupported `{word}` statement"), Type::Void);
+        self.write_line(&format!("{};", todo.text));
+      },
+    }
+  }
+
+  fn if_chain(&mut self, stmt: &Stmt, tail: bool, prefix: &str) {
+    let StmtKind::If(condition, then, otherwise) = &stmt.kind else {
+      unreachable!()
+    };
+    let condition = self.coerce(condition, &Type::Bool);
+    self.write_line(&format!("{prefix}if {} {{", condition.at(RANGE)));
+    self.body(then, tail);
+    match otherwise {
+      Some(otherwise)
+        if matches!(otherwise.kind, StmtKind::If(..)) && otherwise.comments.is_empty() =>
+      {
+        self.if_chain(otherwise, tail, "} else ");
+      },
+      Some(otherwise) => {
+        self.write_line("} else {");
+        self.body(otherwise, tail);
+        self.write_line("}");
+      },
+      None => self.write_line("}"),
+    }
+  }
+
+  fn declaration(&mut self, decl: &Decl) {
+    let usage = self.usage.get(&decl.id).copied().unwrap_or_default();
+    let rust_type = self.rust_type(&decl.ty);
+    let value = match &decl.init {
+      Some(init) => Some(self.coerce(init, &decl.ty).text),
+      None if usage.read_uninitialized() => Some(self.zero(&decl.ty)),
+      None => None,
+    };
+    let mutable = if decl.init.is_some() || usage.read_uninitialized() {
+      usage.assigned()
+    } else {
+      usage.reassigned()
+    };
+    let name = self.declare(&decl.name, decl.ty.clone(), false, usage.read());
+    let mutable = if mutable { "mut " } else { "" };
+    match value {
+      Some(value) => self.write_line(&format!("let {mutable}{name}: {rust_type} = {value};")),
+      None => self.write_line(&format!("let {mutable}{name}: {rust_type};")),
+    }
+  }
+
+  /// `for` loops counting up by one become ranges, others `while` loops.
+  fn for_loop(
+    &mut self,
+    init: Option<&Stmt>,
+    condition: Option<&Expr>,
+    step: Option<&Expr>,
+    body: &Stmt,
+  ) {
+    self.scopes.push(Vec::new());
+    let module = self.module;
+    if let Some((decl, end, inclusive)) = range(init, condition, step, body, &|name, index| {
+      module.is_out(name, index)
+    }) {
+      let start = self.coerce(decl.init.as_ref().unwrap(), &Type::Int);
+      let end = self.coerce(end, &Type::Int);
+      // The step reads the variable, the body may not.
+      let used = mentions(body, &decl.name);
+      let name = self.declare(&decl.name, Type::Int, false, used);
+      let name = if used { name } else { "_".into() };
+      let dots = if inclusive { "..=" } else { ".." };
+      self.write_line(&format!(
+        "for {name} in {}{dots}{} {{",
+        start.at(SUM),
+        end.at(SUM)
+      ));
+      self.body(body, false);
+      self.write_line("}");
+      self.scopes.pop();
+      return;
+    }
+    if let Some(init) = init {
+      self.statement(init, false);
+    }
+    let condition = condition.map(|condition| self.coerce(condition, &Type::Bool));
+    match condition {
+      Some(condition) => self.write_line(&format!("while {} {{", condition.at(RANGE))),
+      None => self.write_line("loop {"),
+    }
+    if step.is_some() && contains_continue(body) {
+      self.flag("`continue` in a `for` loop skips the step");
+    }
+    self.body(body, false);
+    if let Some(step) = step {
+      self.indent += 1;
+      for line in self.expr_statement(step) {
+        self.write_line(&line);
+      }
+      self.indent -= 1;
+    }
+    self.write_line("}");
+    self.scopes.pop();
+  }
+
+  /// The lines of an expression statement.
+  fn expr_statement(&mut self, expr: &Expr) -> Vec<String> {
+    match expr {
+      Expr::Assign(op, target, value) => vec![self.assignment(op, target, value)],
+      Expr::Step(op, _, target) => {
+        let target_code = self.place(target);
+        let one = if target_code.ty == Type::Float {
+          "1.0"
+        } else {
+          "1"
+        };
+        let op = if *op == "++" { "+=" } else { "-=" };
+        vec![format!("{} {op} {one};", target_code.text)]
+      },
+      Expr::Sequence(first, rest) => {
+        let mut lines = self.expr_statement(first);
+        lines.extend(self.expr_statement(rest));
+        lines
+      },
+      Expr::Call(..) => vec![format!("{};", self.expr(expr).text)],
+      _ => {
+        let code = self.expr(expr);
+        vec![format!("let _ = {};", code.text)]
+      },
+    }
+  }
+
+  fn assignment(&mut self, op: &str, target: &Expr, value: &Expr) -> String {
+    // Assigning to several components, `v.xy = ...`, replaces them with `with_xy`.
+    if let Expr::Member(base, field) = target {
+      let base_code = self.base(base);
+      if let Type::Vec(size) = base_code.ty {
+        if field.len() > 1 {
+          let Some(swizzle) = swizzle(field, size) else {
+            return format!(
+              "{};",
+              self
+                .todo(format!("unknown swizzle `{field}`"), Type::Void)
+                .text
+            );
+          };
+          let place = self.place(base);
+          let new = if op == "=" {
+            self.coerce(value, &Type::Vec(field.len() as u8)).text
+          } else {
+            self.binary(&op[..op.len() - 1], target, value).text
+          };
+          return format!(
+            "{} = {}.with_{swizzle}({new});",
+            place.text,
+            base_code.at(POSTFIX)
+          );
+        }
+      }
+    }
+    let place = self.place(target);
+    let value_code = self.expr(value);
+    // `v *= m` multiplies the row vector `v`.
+    if op == "*=" && matches!(place.ty, Type::Vec(_)) && matches!(value_code.ty, Type::Mat(_)) {
+      return format!(
+        "{} = {}.transpose() * {};",
+        place.text,
+        value_code.at(POSTFIX),
+        place.at(PRODUCT + 1)
+      );
+    }
+    let ty = if matches!(place.ty, Type::Vec(_) | Type::Mat(_)) && op != "=" {
+      value_code.ty.clone()
+    } else {
+      place.ty.clone()
+    };
+    let value_code = Self::convert(value_code, value, &ty);
+    format!("{} {op} {};", place.text, value_code.text)
+  }
+
+  /// The target of an assignment.
+  fn place(&mut self, expr: &Expr) -> Code {
+    match expr {
+      Expr::Ident(name) => {
+        if let Some(local) = self.find_local(name) {
+          if local.out {
+            return Code::new(format!("*{}", local.rust), local.ty.clone(), UNARY);
+          }
+          return Code::new(local.rust.clone(), local.ty.clone(), PRIMARY);
+        }
+        if let Some((field, ty)) = self.module.globals.get(name) {
+          return Code::new(format!("self.{field}"), ty.clone(), POSTFIX);
+        }
+        self.todo(
+          format!("assignment to `{name}`"),
+          Type::Other(String::new()),
+        )
+      },
+      Expr::Index(base, index) => {
+        let base_code = self.place_base(base);
+        // The columns of a matrix are fields or `col_mut`.
+        if let Type::Mat(n) = base_code.ty {
+          let axis = if let Expr::Int(column) = &**index {
+            ["x_axis", "y_axis", "z_axis", "w_axis"]
+              .get(column.parse::<usize>().unwrap_or(4))
+              .copied()
+          } else {
+            None
+          };
+          if let Some(axis) = axis {
+            return Code::new(
+              format!("{}.{axis}", base_code.at(POSTFIX)),
+              Type::Vec(n),
+              POSTFIX,
+            );
+          }
+          let index = self.as_usize(index);
+          return Code::new(
+            format!("*{}.col_mut({index})", base_code.at(POSTFIX)),
+            Type::Vec(n),
+            UNARY,
+          );
+        }
+        self.index(&base_code, index)
+      },
+      Expr::Member(base, field) => {
+        let base_code = self.place_base(base);
+        self.member(base_code, field)
+      },
+      _ => self.todo("unsupported assignment", Type::Other(String::new())),
+    }
+  }
+
+  /// The base of an element or a component of a place.
+  fn place_base(&mut self, expr: &Expr) -> Code {
+    match expr {
+      Expr::Index(..) | Expr::Member(..) => self.place(expr),
+      _ => self.base(expr),
+    }
+  }
+
+  /// A value as the receiver of a method or the base of a field, where `out` parameters are
+  /// dereferenced by Rust.
+  fn base(&mut self, expr: &Expr) -> Code {
+    let code = self.expr(expr);
+    self.auto_deref(code, expr)
+  }
+
+  /// `code` of `expr` without the `*` of an `out` parameter, for a method or a field.
+  fn auto_deref(&self, code: Code, expr: &Expr) -> Code {
+    match expr {
+      Expr::Ident(name) if self.find_local(name).is_some_and(|local| local.out) => {
+        Code::new(code.text.trim_start_matches('*'), code.ty, PRIMARY)
+      },
+      _ => code,
+    }
+  }
+
+  /// `expr` converted to `ty` where GLSL converts implicitly or shaders rely on it.
+  fn coerce(&mut self, expr: &Expr, ty: &Type) -> Code {
+    let code = self.expr(expr);
+    Self::convert(code, expr, ty)
+  }
+
+  fn convert(code: Code, expr: &Expr, ty: &Type) -> Code {
+    match (&code.ty, ty) {
+      (Type::Int, Type::Float) => match expr {
+        Expr::Int(text) => Code::new(int_as_float(text), Type::Float, PRIMARY),
+        Expr::Unary("-", operand) if matches!(**operand, Expr::Int(_)) => {
+          let Expr::Int(text) = &**operand else {
+            unreachable!()
+          };
+          Code::new(format!("-{}", int_as_float(text)), Type::Float, UNARY)
+        },
+        _ => Code::new(format!("{} as f32", code.at(CAST)), Type::Float, CAST),
+      },
+      (Type::Int, Type::Uint) => match expr {
+        Expr::Int(_) => Code::new(code.text, Type::Uint, PRIMARY),
+        _ => Code::new(format!("{} as u32", code.at(CAST)), Type::Uint, CAST),
+      },
+      (Type::Uint, Type::Float) => {
+        Code::new(format!("{} as f32", code.at(CAST)), Type::Float, CAST)
+      },
+      _ => code,
+    }
+  }
+
+  fn expr(&mut self, expr: &Expr) -> Code {
+    match expr {
+      Expr::Int(text) => {
+        let ty = match super::lex::parse_int(text) {
+          Some(value) if value > i64::from(i32::MAX) => Type::Uint,
+          _ => Type::Int,
+        };
+        Code::new(text.clone(), ty, PRIMARY)
+      },
+      Expr::Float(text) => Code::new(float_literal(text), Type::Float, PRIMARY),
+      Expr::Bool(value) => Code::new(value.to_string(), Type::Bool, PRIMARY),
+      Expr::Ident(name) => self.ident(name),
+      Expr::Call(name, args) => self.call(name, args),
+      Expr::ArrayConstructor(element, values) => {
+        let values: Vec<_> = values
+          .iter()
+          .map(|value| self.coerce(value, element).text)
+          .collect();
+        Code::new(
+          format!("[{}]", values.join(", ")),
+          Type::Array(
+            Box::new(element.clone()),
+            Box::new(Expr::Int(values.len().to_string())),
+          ),
+          PRIMARY,
+        )
+      },
+      Expr::Member(base, field) => {
+        let code = self.expr(base);
+        // `p.xy` of a `vec2` is `p`.
+        if matches!(code.ty, Type::Vec(size) if "xyzw".get(..usize::from(size)) == swizzle(field, size).as_deref())
+        {
+          return code;
+        }
+        let base_code = self.auto_deref(code, base);
+        self.member(base_code, field)
+      },
+      Expr::Index(base, index) => {
+        if let Expr::Ident(name) = &**base {
+          if matches!(name.as_str(), "iChannelTime" | "iChannelResolution") && !self.constant {
+            let channel = self.as_usize(index);
+            let inputs = self.inputs();
+            return if name == "iChannelTime" {
+              Code::new(
+                format!("{inputs}.channels[{channel}].time()"),
+                Type::Float,
+                POSTFIX,
+              )
+            } else {
+              Code::new(
+                format!("{inputs}.channels[{channel}].resolution()"),
+                Type::Vec(3),
+                POSTFIX,
+              )
+            };
+          }
+        }
+        let base_code = self.base(base);
+        self.index(&base_code, index)
+      },
+      Expr::Unary(op, operand) => {
+        let code = self.expr(operand);
+        let op = if *op == "~" { "!" } else { op };
+        Code::new(format!("{op}{}", code.at(UNARY)), code.ty, UNARY)
+      },
+      Expr::Binary(op, lhs, rhs) => self.binary(op, lhs, rhs),
+      Expr::Conditional(condition, then, otherwise) => {
+        let condition = self.coerce(condition, &Type::Bool);
+        let then_code = self.expr(then);
+        let otherwise_code = self.expr(otherwise);
+        let (then_code, otherwise_code) = Self::unify(then_code, then, otherwise_code, otherwise);
+        Code::new(
+          format!(
+            "if {} {{ {} }} else {{ {} }}",
+            condition.at(RANGE),
+            then_code.text,
+            otherwise_code.text
+          ),
+          then_code.ty,
+          IF,
+        )
+      },
+      Expr::Assign(..) | Expr::Step(..) => self.todo(
+        "assignment inside an expression",
+        Type::Other(String::new()),
+      ),
+      Expr::Sequence(..) => self.todo("comma operator", Type::Other(String::new())),
+    }
+  }
+
+  fn ident(&mut self, name: &str) -> Code {
+    if let Some(local) = self.find_local(name) {
+      return if local.out {
+        Code::new(format!("*{}", local.rust), local.ty.clone(), UNARY)
+      } else {
+        Code::new(local.rust.clone(), local.ty.clone(), PRIMARY)
+      };
+    }
+    if let Some((rust, ty)) = self.module.consts.get(name) {
+      return Code::new(rust.clone(), ty.clone(), PRIMARY);
+    }
+    if let Some((field, ty)) = self.module.globals.get(name) {
+      if self.constant {
+        return self.todo(format!("global `{name}` in an initializer"), ty.clone());
+      }
+      return Code::new(format!("self.{field}"), ty.clone(), POSTFIX);
+    }
+    if self.constant && uniform_field(name).is_some() {
+      return self.todo(
+        format!("`{name}` in an initializer"),
+        Type::Other(String::new()),
+      );
+    }
+    let inputs = self.inputs();
+    match name {
+      "iResolution" => Code::new(format!("{inputs}.resolution"), Type::Vec(3), POSTFIX),
+      "iTime" | "iGlobalTime" => Code::new(format!("{inputs}.time"), Type::Float, POSTFIX),
+      "iMouse" => Code::new(format!("{inputs}.mouse"), Type::Vec(4), POSTFIX),
+      "iChannel0" | "iChannel1" | "iChannel2" | "iChannel3" => Code::new(
+        format!("{inputs}.channels[{}]", &name[8..]),
+        Type::Sampler,
+        POSTFIX,
+      ),
+      "iFrame" => {
+        self.flag("`iFrame` counted at 60 frames per second");
+        Code::new(format!("({inputs}.time * 60.0) as i32"), Type::Int, CAST)
+      },
+      "iTimeDelta" => {
+        self.flag("`iTimeDelta` taken as 1/60 s");
+        Code::new("(1.0 / 60.0)", Type::Float, PRIMARY)
+      },
+      "iFrameRate" => {
+        self.flag("`iFrameRate` taken as 60");
+        Code::new("60.0", Type::Float, PRIMARY)
+      },
+      "iDate" => {
+        self.flag("`iDate` taken as zero");
+        Code::new("Vec4::ZERO", Type::Vec(4), POSTFIX)
+      },
+      "iSampleRate" => {
+        self.flag("`iSampleRate` taken as 44100");
+        Code::new("44100.0", Type::Float, PRIMARY)
+      },
+      _ => self.todo(format!("unknown name `{name}`"), Type::Other(String::new())),
+    }
+  }
+
+  fn member(&mut self, base: Code, field: &str) -> Code {
+    match &base.ty {
+      Type::Vec(size) => match swizzle(field, *size) {
+        Some(swizzle) if swizzle.len() == 1 => Code::new(
+          format!("{}.{swizzle}", base.at(POSTFIX)),
+          Type::Float,
+          POSTFIX,
+        ),
+        Some(swizzle) => Code::new(
+          format!("{}.{swizzle}()", base.at(POSTFIX)),
+          Type::Vec(swizzle.len() as u8),
+          POSTFIX,
+        ),
+        None => self.todo(
+          format!("unknown swizzle `{field}`"),
+          Type::Other(String::new()),
+        ),
+      },
+      Type::Float if swizzle(field, 1).is_some() => {
+        if field.len() == 1 {
+          base
+        } else {
+          Code::new(
+            format!("Vec{}::splat({})", field.len(), base.text),
+            Type::Vec(field.len() as u8),
+            POSTFIX,
+          )
+        }
+      },
+      Type::Struct(name) => {
+        let ty = self
+          .module
+          .structs
+          .get(name)
+          .and_then(|fields| fields.iter().find(|(_, f)| f == field))
+          .map(|(ty, _)| ty.clone());
+        match ty {
+          Some(ty) => Code::new(
+            format!("{}.{}", base.at(POSTFIX), snake_case(field)),
+            ty,
+            POSTFIX,
+          ),
+          None => self.todo(
+            format!("unknown field `{field}`"),
+            Type::Other(String::new()),
+          ),
+        }
+      },
+      _ => Code::new(
+        format!("{}.{}", base.at(POSTFIX), snake_case(field)),
+        Type::Other(String::new()),
+        POSTFIX,
+      ),
+    }
+  }
+
+  fn index(&mut self, base: &Code, index: &Expr) -> Code {
+    let index_code = self.as_usize(index);
+    match &base.ty {
+      Type::Vec(_) => Code::new(
+        format!("{}[{index_code}]", base.at(POSTFIX)),
+        Type::Float,
+        POSTFIX,
+      ),
+      Type::Mat(n) => Code::new(
+        format!("{}.col({index_code})", base.at(POSTFIX)),
+        Type::Vec(*n),
+        POSTFIX,
+      ),
+      Type::Array(element, _) => Code::new(
+        format!("{}[{index_code}]", base.at(POSTFIX)),
+        (**element).clone(),
+        POSTFIX,
+      ),
+      _ => Code::new(
+        format!("{}[{index_code}]", base.at(POSTFIX)),
+        Type::Other(String::new()),
+        POSTFIX,
+      ),
+    }
+  }
+
+  /// Converts integer operands to floats where the other operand is a float, as in
+  /// `2 * x`.
+  fn unify(lhs: Code, lhs_expr: &Expr, rhs: Code, rhs_expr: &Expr) -> (Code, Code) {
+    let floating = |ty: &Type| matches!(ty, Type::Float | Type::Vec(_) | Type::Mat(_));
+    if floating(&lhs.ty) && matches!(rhs.ty, Type::Int | Type::Uint) {
+      let rhs = Self::convert(rhs, rhs_expr, &Type::Float);
+      (lhs, rhs)
+    } else if floating(&rhs.ty) && matches!(lhs.ty, Type::Int | Type::Uint) {
+      let lhs = Self::convert(lhs, lhs_expr, &Type::Float);
+      (lhs, rhs)
+    } else if lhs.ty == Type::Uint && rhs.ty == Type::Int {
+      let rhs = Self::convert(rhs, rhs_expr, &Type::Uint);
+      (lhs, rhs)
+    } else if rhs.ty == Type::Uint && lhs.ty == Type::Int {
+      let lhs = Self::convert(lhs, lhs_expr, &Type::Uint);
+      (lhs, rhs)
+    } else {
+      (lhs, rhs)
+    }
+  }
+
+  fn binary(&mut self, op: &str, lhs_expr: &Expr, rhs_expr: &Expr) -> Code {
+    let lhs = self.expr(lhs_expr);
+    let rhs = self.expr(rhs_expr);
+    let (lhs, rhs) = Self::unify(lhs, lhs_expr, rhs, rhs_expr);
+    let (rust_op, precedence) = match op {
+      "||" => ("||", OR),
+      "&&" => ("&&", AND),
+      "^^" => ("!=", COMPARE),
+      "==" | "!=" | "<" | ">" | "<=" | ">=" => (op, COMPARE),
+      "|" => ("|", BIT_OR),
+      "^" => ("^", BIT_XOR),
+      "&" => ("&", BIT_AND),
+      "<<" | ">>" => (op, SHIFT),
+      "+" | "-" => (op, SUM),
+      _ => (op, PRODUCT),
+    };
+    if precedence <= COMPARE {
+      // Casts before `<` would read as generics, and comparisons don't chain.
+      let lhs_text = if lhs.precedence == CAST {
+        format!("({})", lhs.text)
+      } else {
+        lhs.at(precedence + 1)
+      };
+      return Code::new(
+        format!("{lhs_text} {rust_op} {}", rhs.at(precedence + 1)),
+        Type::Bool,
+        precedence,
+      );
+    }
+    let ty = match (&lhs.ty, &rhs.ty) {
+      // A row vector times a matrix.
+      (Type::Vec(_), Type::Mat(_)) if op == "*" => {
+        return Code::new(
+          format!("{}.transpose() * {}", rhs.at(POSTFIX), lhs.at(PRODUCT + 1)),
+          lhs.ty,
+          PRODUCT,
+        );
+      },
+      (Type::Mat(_), Type::Vec(_)) | (Type::Float | Type::Int, Type::Vec(_) | Type::Mat(_)) => {
+        rhs.ty.clone()
+      },
+      _ => lhs.ty.clone(),
+    };
+    Code::new(
+      format!(
+        "{} {rust_op} {}",
+        lhs.at(precedence),
+        rhs.at(precedence + 1)
+      ),
+      ty,
+      precedence,
+    )
+  }
+
+  fn call(&mut self, name: &str, args: &[Expr]) -> Code {
+    if self.module.functions.contains_key(name) {
+      return self.user_call(name, args);
+    }
+    if let Some(ty) = Type::builtin(name) {
+      return self.constructor(&ty, args);
+    }
+    if self.module.structs.contains_key(name) {
+      let fields = self.module.structs[name].clone();
+      if fields.len() != args.len() {
+        return self.todo(
+          format!("`{name}` takes {} values", fields.len()),
+          Type::Struct(name.to_owned()),
+        );
+      }
+      let values: Vec<_> = fields
+        .iter()
+        .zip(args)
+        .map(|((ty, field), arg)| format!("{}: {}", snake_case(field), self.coerce(arg, ty).text))
+        .collect();
+      return Code::new(
+        format!("{} {{ {} }}", camel_case(name), values.join(", ")),
+        Type::Struct(name.to_owned()),
+        PRIMARY,
+      );
+    }
+    self.builtin(name, args)
+  }
+
+  fn user_call(&mut self, name: &str, args: &[Expr]) -> Code {
+    let codes: Vec<Code> = args.iter().map(|arg| self.expr(arg)).collect();
+    let overloads = &self.module.functions[name];
+    let matches = |signature: &&Signature, exact: bool| {
+      signature.params.len() == codes.len()
+        && signature.params.iter().zip(&codes).all(|((ty, _), code)| {
+          *ty == code.ty || (!exact && matches!((ty, &code.ty), (Type::Float, Type::Int)))
+        })
+    };
+    let signature = overloads
+      .iter()
+      .find(|signature| matches(signature, true))
+      .or_else(|| overloads.iter().find(|signature| matches(signature, false)))
+      .or_else(|| {
+        overloads
+          .iter()
+          .find(|signature| signature.params.len() == codes.len())
+      })
+      .unwrap_or(&overloads[0]);
+    let mut values = Vec::new();
+    for ((code, arg), (ty, qualifier)) in codes.into_iter().zip(args).zip(&signature.params) {
+      if *qualifier == Qualifier::In {
+        values.push(Self::convert(code, arg, ty).text);
+        continue;
+      }
+      // An `out` parameter passed on is a `&mut` already.
+      if let Expr::Ident(local) = arg {
+        if let Some(local) = self.find_local(local).filter(|local| local.out) {
+          values.push(local.rust.clone());
+          continue;
+        }
+      }
+      if signature.receiver >= Receiver::State
+        && root(arg).is_some_and(|root| self.module.globals.contains_key(root))
+      {
+        self.flag(format!("global passed to `{name}`, which borrows `self`"));
+      }
+      if matches!(arg, Expr::Member(_, field) if field.len() > 1) {
+        self.flag(format!("swizzle passed as an `out` parameter of `{name}`"));
+      }
+      let place = self.place(arg);
+      let place = place
+        .text
+        .strip_prefix('*')
+        .map_or_else(|| place.text.clone(), str::to_owned);
+      values.push(format!("&mut {place}"));
+    }
+    let callee = match (self.receiver, signature.receiver) {
+      (_, Receiver::Free) => String::new(),
+      (Receiver::State | Receiver::StateMut, Receiver::Inputs) => "self.inputs.".into(),
+      _ => "self.".into(),
+    };
+    Code::new(
+      format!("{callee}{}({})", signature.rust_name, values.join(", ")),
+      signature.ret.clone(),
+      POSTFIX,
+    )
+  }
+
+  /// Constructors of scalars, vectors and matrices.
+  fn constructor(&mut self, ty: &Type, args: &[Expr]) -> Code {
+    let codes: Vec<Code> = args.iter().map(|arg| self.expr(arg)).collect();
+    match ty {
+      Type::Float | Type::Int | Type::Uint | Type::Bool => {
+        let [code] = <[Code; 1]>::try_from(codes).unwrap_or_else(|codes| {
+          let text = codes
+            .into_iter()
+            .next()
+            .map_or_else(String::new, |code| code.text);
+          [Code::new(text, ty.clone(), PRIMARY)]
+        });
+        let code = if let Type::Vec(_) = code.ty {
+          Code::new(format!("{}.x", code.at(POSTFIX)), Type::Float, POSTFIX)
+        } else {
+          code
+        };
+        Self::cast(code, &args[0], ty)
+      },
+      Type::Vec(n) => self.vector(*n, codes, args),
+      Type::Mat(n) => self.matrix(*n, codes, args),
+      _ => self.todo(format!("unsupported constructor `{ty:?}`"), ty.clone()),
+    }
+  }
+
+  /// `float(x)`, `int(x)` and so on.
+  fn cast(code: Code, expr: &Expr, ty: &Type) -> Code {
+    let rust_type = match ty {
+      Type::Float => "f32",
+      Type::Int => "i32",
+      Type::Uint => "u32",
+      _ => "bool",
+    };
+    match (&code.ty, ty) {
+      (from, to) if from == to => code,
+      (Type::Int, Type::Float) => Self::convert(code, expr, ty),
+      (Type::Bool, Type::Float) => Code::new(
+        format!("if {} {{ 1.0 }} else {{ 0.0 }}", code.at(RANGE)),
+        Type::Float,
+        IF,
+      ),
+      (Type::Bool, Type::Int | Type::Uint) => Code::new(
+        format!("{rust_type}::from({})", code.text),
+        ty.clone(),
+        POSTFIX,
+      ),
+      (Type::Float, Type::Bool) => Code::new(
+        format!("{} != 0.0", code.at(COMPARE + 1)),
+        Type::Bool,
+        COMPARE,
+      ),
+      (Type::Int | Type::Uint, Type::Bool) => Code::new(
+        format!("{} != 0", code.at(COMPARE + 1)),
+        Type::Bool,
+        COMPARE,
+      ),
+      _ => Code::new(
+        format!("{} as {rust_type}", code.at(CAST)),
+        ty.clone(),
+        CAST,
+      ),
+    }
+  }
+
+  fn vector(&mut self, n: u8, codes: Vec<Code>, args: &[Expr]) -> Code {
+    let ty = Type::Vec(n);
+    let codes: Vec<Code> = codes
+      .into_iter()
+      .zip(args)
+      .map(|(code, arg)| Self::convert(code, arg, &Type::Float))
+      .collect();
+    let sizes: Vec<u8> = codes
+      .iter()
+      .map(|code| code.ty.components().unwrap_or(0))
+      .collect();
+    let texts: Vec<String> = codes.iter().map(|code| code.text.clone()).collect();
+    match sizes[..] {
+      [1] => {
+        let constant = match &args[0] {
+          Expr::Float(text) | Expr::Int(text) => text.parse::<f32>().ok(),
+          _ => None,
+        };
+        let text = match constant {
+          Some(0.0) => format!("Vec{n}::ZERO"),
+          Some(1.0) => format!("Vec{n}::ONE"),
+          _ => format!("Vec{n}::splat({})", texts[0]),
+        };
+        return Code::new(text, ty, POSTFIX);
+      },
+      [m] if m == n => return codes.into_iter().next().unwrap(),
+      [m] if m > n => {
+        let swizzle = &"xyzw"[..usize::from(n)];
+        return Code::new(format!("{}.{swizzle}()", codes[0].at(POSTFIX)), ty, POSTFIX);
+      },
+      _ => {},
+    }
+    if sizes.iter().all(|&size| size == 1) && sizes.len() == usize::from(n) {
+      return Code::new(format!("vec{n}({})", texts.join(", ")), ty, POSTFIX);
+    }
+    if sizes.len() == 2 && sizes[0] + 1 == n && sizes[1] == 1 {
+      return Code::new(
+        format!("{}.extend({})", codes[0].at(POSTFIX), texts[1]),
+        ty,
+        POSTFIX,
+      );
+    }
+    if n == 4 && matches!(sizes[..], [1, 3] | [2, 1, 1] | [2, 2]) {
+      return Code::new(format!("Vec4::from(({}))", texts.join(", ")), ty, POSTFIX);
+    }
+    // Otherwise the components one by one, of plain variables.
+    if sizes.iter().map(|&size| usize::from(size)).sum::<usize>() == usize::from(n) {
+      let simple = |text: &str| {
+        text
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+      };
+      if codes
+        .iter()
+        .all(|code| code.ty == Type::Float || simple(&code.text))
+      {
+        let components: Vec<String> = codes
+          .iter()
+          .flat_map(|code| match code.ty {
+            Type::Vec(size) => "xyzw"[..usize::from(size)]
+              .chars()
+              .map(|c| format!("{}.{c}", code.at(POSTFIX)))
+              .collect(),
+            _ => vec![code.text.clone()],
+          })
+          .collect();
+        return Code::new(format!("vec{n}({})", components.join(", ")), ty, POSTFIX);
+      }
+    }
+    self.todo(format!("unsupported `vec{n}` constructor"), ty)
+  }
+
+  fn matrix(&mut self, n: u8, codes: Vec<Code>, args: &[Expr]) -> Code {
+    let ty = Type::Mat(n);
+    let codes: Vec<Code> = codes
+      .into_iter()
+      .zip(args)
+      .map(|(code, arg)| Self::convert(code, arg, &Type::Float))
+      .collect();
+    let texts: Vec<String> = codes.iter().map(|code| code.text.clone()).collect();
+    if let [code] = &codes[..] {
+      if code.ty == Type::Float {
+        return Code::new(
+          format!("Mat{n}::from_diagonal(Vec{n}::splat({}))", code.text),
+          ty,
+          POSTFIX,
+        );
+      }
+      if code.ty == ty {
+        return codes.into_iter().next().unwrap();
+      }
+    }
+    if codes.len() == usize::from(n) && codes.iter().all(|code| code.ty == Type::Vec(n)) {
+      return Code::new(
+        format!("Mat{n}::from_cols({})", texts.join(", ")),
+        ty,
+        POSTFIX,
+      );
+    }
+    if codes.len() == usize::from(n * n) && codes.iter().all(|code| code.ty == Type::Float) {
+      return Code::new(
+        format!("Mat{n}::from_cols_array(&[{}])", texts.join(", ")),
+        ty,
+        POSTFIX,
+      );
+    }
+    self.todo(format!("unsupported `mat{n}` constructor"), ty)
+  }
+
+  /// Built-in functions, as methods where glam or the prelude have them.
+  fn builtin(&mut self, name: &str, args: &[Expr]) -> Code {
+    let codes: Vec<Code> = args.iter().map(|arg| self.expr(arg)).collect();
+    let codes: Vec<Code> = codes
+      .into_iter()
+      .zip(args)
+      .map(|(code, arg)| Self::convert(code, arg, &Type::Float))
+      .collect();
+    let ty = codes.first().map_or(Type::Float, |code| code.ty.clone());
+    let scalar = ty == Type::Float;
+    let method = |code: &Code, method: &str, ty: Type| {
+      Code::new(format!("{}.{method}()", code.at(POSTFIX)), ty, POSTFIX)
+    };
+    let method_with = |code: &Code, method: &str, args: &[String], ty: Type| {
+      Code::new(
+        format!("{}.{method}({})", code.at(POSTFIX), args.join(", ")),
+        ty,
+        POSTFIX,
+      )
+    };
+    let function = |function: &str, codes: &[Code], ty: Type| {
+      let args: Vec<_> = codes.iter().map(|code| code.text.clone()).collect();
+      Code::new(format!("{function}({})", args.join(", ")), ty, POSTFIX)
+    };
+    // A vector of the size of the first argument, from a float.
+    let splat = |code: &Code| match (&ty, code.text.as_str()) {
+      (Type::Vec(n), "0.0") => format!("Vec{n}::ZERO"),
+      (Type::Vec(n), "1.0") => format!("Vec{n}::ONE"),
+      (Type::Vec(n), text) if code.ty == Type::Float => format!("Vec{n}::splat({text})"),
+      _ => code.text.clone(),
+    };
+    match (name, &codes[..]) {
+      ("sin" | "cos" | "exp" | "sqrt" | "abs" | "floor" | "ceil" | "round" | "trunc", [x]) => {
+        method(x, name, ty)
+      },
+      ("tan" | "asin" | "acos" | "sinh" | "cosh" | "tanh" | "asinh" | "acosh" | "atanh", [x]) => {
+        if scalar {
+          method(x, name, ty)
+        } else {
+          Code::new(format!("{}.map(f32::{name})", x.at(POSTFIX)), ty, POSTFIX)
+        }
+      },
+      ("atan", [x]) if scalar => method(x, "atan", ty),
+      ("atan", [x]) => Code::new(format!("{}.map(f32::atan)", x.at(POSTFIX)), ty, POSTFIX),
+      ("atan", [y, x]) if scalar => method_with(y, "atan2", core::slice::from_ref(&x.text), ty),
+      ("atan", [..]) => function("atan2", &codes, ty),
+      ("log", [x]) => method(x, "ln", ty),
+      ("exp2" | "log2", [x]) if scalar => method(x, name, ty),
+      ("mix", [_, _, t]) if t.ty == Type::Bool => self.todo("`mix` with a `bool`", ty),
+      ("exp2" | "log2" | "inversesqrt", [_])
+      | ("mix" | "reflect" | "refract" | "faceforward", [..]) => function(name, &codes, ty),
+      ("sign", [x]) => method(x, "sign_gl", ty),
+      ("fract", [x]) => method(x, "fract_gl", ty),
+      ("mod", [x, y]) => method_with(x, "rem_euclid", &[splat(y)], ty),
+      ("min" | "max", [x, y]) => method_with(x, name, &[splat(y)], ty),
+      ("clamp", [x, low, high]) => method_with(x, "clamp", &[splat(low), splat(high)], ty),
+      ("pow", [x, y]) if scalar => method_with(x, "powf", core::slice::from_ref(&y.text), ty),
+      ("pow", [x, y]) => method_with(x, "powf_vec", &[splat(y)], ty),
+      // The result has the type of the value, the last argument of `smoothstep`.
+      ("smoothstep", [.., x]) | ("step", [_, x]) => {
+        let ty = x.ty.clone();
+        function(name, &codes, ty)
+      },
+      ("length", [x]) if scalar => method(x, "abs", Type::Float),
+      ("length", [x]) => method(x, "length", Type::Float),
+      ("distance", [a, b]) if scalar => Code::new(
+        format!("({} - {}).abs()", a.at(SUM), b.at(SUM + 1)),
+        Type::Float,
+        POSTFIX,
+      ),
+      ("distance", [a, b]) => {
+        method_with(a, "distance", core::slice::from_ref(&b.text), Type::Float)
+      },
+      ("dot", [a, b]) if scalar => Code::new(
+        format!("{} * {}", a.at(PRODUCT), b.at(PRODUCT + 1)),
+        Type::Float,
+        PRODUCT,
+      ),
+      ("dot", [a, b]) => method_with(a, "dot", core::slice::from_ref(&b.text), Type::Float),
+      ("cross", [a, b]) => method_with(a, "cross", core::slice::from_ref(&b.text), Type::Vec(3)),
+      ("normalize", [x]) if scalar => method(x, "sign_gl", ty),
+      ("normalize", [x]) => method(x, "normalize", ty),
+      ("radians" | "degrees", [x]) if scalar => method(
+        x,
+        if name == "radians" {
+          "to_radians"
+        } else {
+          "to_degrees"
+        },
+        ty,
+      ),
+      ("radians", [x]) => Code::new(format!("{} * (PI / 180.0)", x.at(PRODUCT)), ty, PRODUCT),
+      ("degrees", [x]) => Code::new(format!("{} * (180.0 / PI)", x.at(PRODUCT)), ty, PRODUCT),
+      ("transpose" | "inverse" | "determinant", [m]) => {
+        let ty = if name == "determinant" {
+          Type::Float
+        } else {
+          ty
+        };
+        method(m, name, ty)
+      },
+      ("matrixCompMult", [..]) => function("matrix_comp_mult", &codes, ty),
+      ("dFdx" | "dFdy" | "fwidth", [x]) => method(x, &name.to_lowercase(), ty),
+      ("floatBitsToUint", [x]) => method(x, "to_bits", Type::Uint),
+      ("floatBitsToInt", [x]) => Code::new(
+        format!("{}.to_bits() as i32", x.at(POSTFIX)),
+        Type::Int,
+        CAST,
+      ),
+      ("uintBitsToFloat", [x]) => {
+        Code::new(format!("f32::from_bits({})", x.text), Type::Float, POSTFIX)
+      },
+      ("intBitsToFloat", [x]) => Code::new(
+        format!("f32::from_bits({} as u32)", x.at(CAST)),
+        Type::Float,
+        POSTFIX,
+      ),
+      (
+        "texture" | "texture2D" | "textureCube" | "textureLod" | "texture2DLod" | "textureCubeLod"
+        | "textureGrad",
+        [channel, uv, rest @ ..],
+      ) if channel.ty == Type::Sampler => {
+        // A `vec3` looks up a cubemap, the common case of Shadertoy.
+        let cube = uv.ty == Type::Vec(3);
+        let (method, lod) = match (name, rest) {
+          ("textureLod" | "texture2DLod" | "textureCubeLod", [lod]) => ("texture_lod", Some(lod)),
+          (_, []) => ("texture", None),
+          _ => {
+            self.flag(format!("bias or gradients of `{name}` ignored"));
+            ("texture", None)
+          },
+        };
+        let method = if cube {
+          method.replace("texture", "texture_cube")
+        } else {
+          method.to_owned()
+        };
+        let args = match lod {
+          Some(lod) => format!("{}, {}", uv.text, lod.text),
+          None => uv.text.clone(),
+        };
+        Code::new(
+          format!("{}.{method}({args})", channel.at(POSTFIX)),
+          Type::Vec(4),
+          POSTFIX,
+        )
+      },
+      _ => self.todo(format!("unsupported function `{name}`"), ty),
+    }
+  }
+}
+
+/// The range of a `for` loop that counts an `int` up by one, `for (int i = a; i < b; i++)`,
+/// as the variable, the end and whether the end is included.
+fn range<'a>(
+  init: Option<&'a Stmt>,
+  condition: Option<&'a Expr>,
+  step: Option<&Expr>,
+  body: &Stmt,
+  is_out: &dyn Fn(&str, usize) -> bool,
+) -> Option<(&'a Decl, &'a Expr, bool)> {
+  let StmtKind::Decl(decls) = &init?.kind else {
+    return None;
+  };
+  let [decl] = &decls[..] else {
+    return None;
+  };
+  if decl.ty != Type::Int || decl.init.is_none() {
+    return None;
+  }
+  let is_var = |expr: &Expr| matches!(expr, Expr::Ident(name) if *name == decl.name);
+  let (end, inclusive) = match condition? {
+    Expr::Binary(op @ ("<" | "<="), var, end) if is_var(var) => (&**end, *op == "<="),
+    Expr::Binary(op @ (">" | ">="), end, var) if is_var(var) => (&**end, *op == ">="),
+    _ => return None,
+  };
+  let counts_up = match step? {
+    Expr::Step("++", _, var) => is_var(var),
+    Expr::Assign("+=", var, one) => is_var(var) && **one == Expr::Int("1".into()),
+    _ => false,
+  };
+  // The variable and the end must not change in the body.
+  let mut names = vec![decl.name.clone()];
+  visit_expr(end, &mut |expr| {
+    if let Expr::Ident(name) = expr {
+      names.push(name.clone());
+    }
+  });
+  let mut changed = false;
+  visit_stmt(body, &mut |stmt| {
+    visit_stmt_exprs(stmt, &mut |expr| match expr {
+      Expr::Assign(_, target, _) | Expr::Step(_, _, target) => {
+        changed |= root(target).is_some_and(|name| names.iter().any(|n| n == name));
+      },
+      Expr::Call(function, args) => {
+        changed |= args.iter().enumerate().any(|(index, arg)| {
+          is_out(function, index) && root(arg).is_some_and(|name| names.iter().any(|n| n == name))
+        });
+      },
+      _ => {},
+    });
+  });
+  (counts_up && !changed).then_some((decl, end, inclusive))
+}
+
+/// Whether `stmt` uses the variable `name`.
+fn mentions(stmt: &Stmt, name: &str) -> bool {
+  let mut found = false;
+  visit_stmt(stmt, &mut |stmt| {
+    visit_stmt_exprs(stmt, &mut |expr| {
+      found |= matches!(expr, Expr::Ident(ident) if ident == name);
+    });
+  });
+  found
+}
+
+/// Whether `stmt` can end in a tail expression: a `return` with a value, or an `if` whose
+/// branches all end in one.
+fn tail_ok(stmt: &Stmt) -> bool {
+  match &stmt.kind {
+    StmtKind::Return(Some(_)) => true,
+    StmtKind::If(_, then, Some(otherwise)) => tail_ok(then) && tail_ok(otherwise),
+    StmtKind::Block(stmts, _) => stmts.last().is_some_and(tail_ok),
+    _ => false,
+  }
+}
+
+/// Whether `stmt` has a `continue` for the loop around it.
+fn contains_continue(stmt: &Stmt) -> bool {
+  match &stmt.kind {
+    StmtKind::Continue => true,
+    StmtKind::Block(stmts, _) => stmts.iter().any(contains_continue),
+    StmtKind::If(_, then, otherwise) => {
+      contains_continue(then) || otherwise.as_deref().is_some_and(contains_continue)
+    },
+    _ => false,
+  }
+}
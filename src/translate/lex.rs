@@ -0,0 +1,630 @@
+//! The tokens of GLSL and the preprocessor. Macros are expanded and `#if` branches picked here,
+//! except for `#define`s of plain constants, which become `Token::Const` items so they stay
+//! named constants in the port.
+
+use super::Flag;
+use std::collections::HashMap;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+  Ident(String),
+  Int(String),
+  Float(String),
+  Punct(&'static str),
+  /// A preprocessor line without the `#`, before preprocessing.
+  Directive(String),
+  /// Starts `NAME = <value> ;`, the item of a `#define` of a constant.
+  Const(String),
+}
+
+/// A comment, as the lines after `//`.
+#[derive(Clone, Debug)]
+pub struct Comment {
+  pub lines: Vec<String>,
+  /// Whether the comment is on the line of the previous token.
+  pub trailing: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+  pub kind: TokenKind,
+  pub line: usize,
+  /// The comments between the previous token and this one.
+  pub comments: Vec<Comment>,
+  /// Whether an empty line separates this token from the previous one.
+  pub blank_line_before: bool,
+}
+
+impl Token {
+  pub fn is(&self, punct: &str) -> bool {
+    matches!(self.kind, TokenKind::Punct(p) if p == punct)
+  }
+}
+
+/// Longest first, so `<<=` is not read as `<` `<=`.
+const PUNCTUATION: &[&str] = &[
+  "<<=", ">>=", "++", "--", "<=", ">=", "==", "!=", "&&", "||", "^^", "+=", "-=", "*=", "/=", "%=",
+  "&=", "|=", "^=", "<<", ">>", "+", "-", "*", "/", "%", "<", ">", "=", "!", "~", "&", "|", "^",
+  "?", ":", ";", ",", ".", "(", ")", "{", "}", "[", "]",
+];
+
+fn lex(source: &str, first_line: usize) -> Result<Vec<Token>, Flag> {
+  let bytes = source.as_bytes();
+  let mut tokens = Vec::new();
+  let mut comments = Vec::new();
+  let mut line = first_line;
+  let mut last_token_line = 0;
+  let mut blank_line_before = false;
+  let mut line_start = true;
+  let mut i = 0;
+  while i < bytes.len() {
+    let c = bytes[i];
+    if c == b'\n' {
+      if line_start && (tokens.is_empty() || last_token_line < line) {
+        blank_line_before = true;
+      }
+      line += 1;
+      line_start = true;
+      i += 1;
+      continue;
+    }
+    if c.is_ascii_whitespace() {
+      i += 1;
+      continue;
+    }
+    let trailing = last_token_line == line && !tokens.is_empty();
+    if source[i..].starts_with("//") {
+      let end = source[i..].find('\n').map_or(source.len(), |end| i + end);
+      let text = source[i + 2..end].trim_end().to_owned();
+      comments.push(Comment {
+        lines: vec![text],
+        trailing,
+      });
+      line_start = false;
+      i = end;
+      continue;
+    }
+    if source[i..].starts_with("/*") {
+      let end = source[i + 2..]
+        .find("*/")
+        .map(|end| i + 2 + end)
+        .ok_or_else(|| Flag::new(line, "unterminated comment"))?;
+      let lines = source[i + 2..end]
+        .lines()
+        .map(|text| {
+          let text = text.trim();
+          let text = text.strip_prefix('*').map_or(text, str::trim_start);
+          if text.is_empty() {
+            String::new()
+          } else {
+            format!(" {text}")
+          }
+        })
+        .skip_while(String::is_empty)
+        .collect::<Vec<_>>();
+      let mut lines = lines;
+      while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+      }
+      line += source[i..end].matches('\n').count();
+      comments.push(Comment { lines, trailing });
+      line_start = false;
+      i = end + 2;
+      continue;
+    }
+    let start = i;
+    let kind = if c == b'#' && line_start {
+      // The directive goes on over lines ending in `\`.
+      let mut text = String::new();
+      i += 1;
+      loop {
+        let end = source[i..].find('\n').map_or(source.len(), |end| i + end);
+        let part = &source[i..end];
+        i = end;
+        if let Some(part) = part.trim_end().strip_suffix('\\') {
+          text.push_str(part);
+          text.push(' ');
+          if i < source.len() {
+            i += 1;
+            line += 1;
+          }
+        } else {
+          text.push_str(part);
+          break;
+        }
+      }
+      TokenKind::Directive(text)
+    } else if c.is_ascii_alphabetic() || c == b'_' {
+      while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+        i += 1;
+      }
+      TokenKind::Ident(source[start..i].to_owned())
+    } else if c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+    {
+      number(source, &mut i)
+    } else {
+      let punct = PUNCTUATION
+        .iter()
+        .find(|p| source[i..].starts_with(**p))
+        .ok_or_else(|| {
+          // Decoded, so that characters beyond ASCII show as themselves.
+          let c = source[i..].chars().next().unwrap_or_default();
+          Flag::new(line, format!("unexpected character `{c}`"))
+        })?;
+      i += punct.len();
+      TokenKind::Punct(punct)
+    };
+    tokens.push(Token {
+      kind,
+      line,
+      comments: core::mem::take(&mut comments),
+      blank_line_before: core::mem::take(&mut blank_line_before),
+    });
+    last_token_line = line;
+    line_start = false;
+  }
+  if !comments.is_empty() {
+    // The comments at the end hang on a last `;`, which the parser skips like an empty
+    // declaration.
+    tokens.push(Token {
+      kind: TokenKind::Punct(";"),
+      line,
+      comments,
+      blank_line_before,
+    });
+  }
+  Ok(tokens)
+}
+
+/// An integer (`12`, `0x1F`, `3u`) or a float (`1.`, `.5`, `1e-3`, `2.0f`), without suffixes.
+fn number(source: &str, i: &mut usize) -> TokenKind {
+  let bytes = source.as_bytes();
+  let start = *i;
+  if source[start..].starts_with("0x") || source[start..].starts_with("0X") {
+    *i += 2;
+    while *i < bytes.len() && bytes[*i].is_ascii_hexdigit() {
+      *i += 1;
+    }
+    let text = source[start..*i].to_owned();
+    if bytes.get(*i).is_some_and(|b| matches!(b, b'u' | b'U')) {
+      *i += 1;
+    }
+    return TokenKind::Int(text);
+  }
+  let mut float = false;
+  while *i < bytes.len() {
+    match bytes[*i] {
+      b'0'..=b'9' => {},
+      b'.' => float = true,
+      b'e' | b'E' => {
+        float = true;
+        if bytes.get(*i + 1).is_some_and(|b| matches!(b, b'+' | b'-')) {
+          *i += 1;
+        }
+      },
+      _ => break,
+    }
+    *i += 1;
+  }
+  let text = source[start..*i].to_owned();
+  match bytes.get(*i) {
+    Some(b'f' | b'F') => {
+      *i += 1;
+      TokenKind::Float(text)
+    },
+    Some(b'u' | b'U') => {
+      *i += 1;
+      TokenKind::Int(text)
+    },
+    _ if float => TokenKind::Float(text),
+    _ => TokenKind::Int(text),
+  }
+}
+
+struct Macro {
+  params: Option<Vec<String>>,
+  body: Vec<Token>,
+  /// Kept as a named constant rather than expanded.
+  constant: bool,
+}
+
+/// One level of `#if`.
+struct Condition {
+  active: bool,
+  /// Whether a branch of this `#if` was taken already.
+  taken: bool,
+  outer_active: bool,
+}
+
+/// Lexes `source` and runs the preprocessor over it.
+pub fn preprocess(source: &str, flags: &mut Vec<Flag>) -> Result<Vec<Token>, Flag> {
+  let mut macros: HashMap<String, Macro> = HashMap::new();
+  let mut conditions: Vec<Condition> = Vec::new();
+  let mut output = Vec::new();
+  // Tokens since the last `#define`, expanded with the macros of that point.
+  let mut pending = Vec::new();
+  // The comments and blank lines of directives go to the next token.
+  let mut comments = Vec::new();
+  let mut blank_line = false;
+  let mut depth = 0_i32;
+  for mut token in lex(source, 1)? {
+    let active = conditions.last().is_none_or(|condition| condition.active);
+    let TokenKind::Directive(text) = &token.kind else {
+      if active {
+        if token.is("{") {
+          depth += 1;
+        } else if token.is("}") {
+          depth -= 1;
+        }
+        comments.append(&mut token.comments);
+        token.comments = core::mem::take(&mut comments);
+        token.blank_line_before |= core::mem::take(&mut blank_line);
+        pending.push(token);
+      }
+      continue;
+    };
+    if active {
+      comments.append(&mut token.comments);
+      blank_line |= token.blank_line_before;
+    }
+    let line = token.line;
+    let text = text.trim();
+    let (directive, rest) = text
+      .split_once(|c: char| !c.is_ascii_alphanumeric())
+      .map_or((text, ""), |(directive, _)| {
+        (directive, text[directive.len()..].trim())
+      });
+    match directive {
+      "if" | "ifdef" | "ifndef" => {
+        let value = active && evaluate(directive, rest, line, &macros, flags);
+        conditions.push(Condition {
+          active: value,
+          taken: value,
+          outer_active: active,
+        });
+      },
+      "elif" | "else" => {
+        let Some(condition) = conditions.last_mut() else {
+          return Err(Flag::new(line, format!("`#{directive}` without `#if`")));
+        };
+        let value = condition.outer_active
+          && !condition.taken
+          && (directive == "else" || evaluate("if", rest, line, &macros, flags));
+        condition.active = value;
+        condition.taken |= value;
+      },
+      "endif" => {
+        if conditions.pop().is_none() {
+          return Err(Flag::new(line, "`#endif` without `#if`"));
+        }
+      },
+      _ if !active => {},
+      "define" => {
+        output.extend(expand(core::mem::take(&mut pending), &macros, &[]));
+        let name_end = rest
+          .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+          .unwrap_or(rest.len());
+        let name = rest[..name_end].to_owned();
+        if name.is_empty() {
+          return Err(Flag::new(line, "`#define` without a name"));
+        }
+        let (params, body) = match rest[name_end..].strip_prefix('(') {
+          Some(rest) => {
+            let (params, body) = rest
+              .split_once(')')
+              .ok_or_else(|| Flag::new(line, "unterminated macro parameters"))?;
+            let params = params
+              .split(',')
+              .map(|param| param.trim().to_owned())
+              .filter(|param| !param.is_empty())
+              .collect();
+            (Some(params), body)
+          },
+          None => (None, &rest[name_end..]),
+        };
+        let body = lex(body, line)?;
+        let constant = params.is_none() && depth == 0 && is_constant(&body, &macros);
+        if constant {
+          output.push(Token {
+            kind: TokenKind::Const(name.clone()),
+            line,
+            comments: core::mem::take(&mut comments),
+            blank_line_before: core::mem::take(&mut blank_line),
+          });
+          output.extend(body.iter().map(|body_token| Token {
+            line,
+            comments: Vec::new(),
+            blank_line_before: false,
+            ..body_token.clone()
+          }));
+          output.push(punct_token(";", line));
+        }
+        macros.insert(
+          name,
+          Macro {
+            params,
+            body,
+            constant,
+          },
+        );
+      },
+      "undef" => {
+        output.extend(expand(core::mem::take(&mut pending), &macros, &[]));
+        macros.remove(rest);
+      },
+      "version" | "extension" | "pragma" => {},
+      _ => flags.push(Flag::new(line, format!("skipped `#{text}`"))),
+    }
+  }
+  if !conditions.is_empty() {
+    return Err(Flag::new(source.lines().count(), "`#if` without `#endif`"));
+  }
+  output.extend(expand(pending, &macros, &[]));
+  Ok(output)
+}
+
+fn punct_token(punct: &'static str, line: usize) -> Token {
+  Token {
+    kind: TokenKind::Punct(punct),
+    line,
+    comments: Vec::new(),
+    blank_line_before: false,
+  }
+}
+
+/// Whether a `#define` body is a constant: literals and other constants in arithmetic, or a
+/// vector of literals.
+fn is_constant(body: &[Token], macros: &HashMap<String, Macro>) -> bool {
+  let (body, vector) = match body {
+    [first, second, rest @ ..]
+      if second.is("(")
+        && matches!(&first.kind, TokenKind::Ident(name) if matches!(name.as_str(), "vec2" | "vec3" | "vec4")) =>
+    {
+      (rest, true)
+    },
+    _ => (body, false),
+  };
+  !body.is_empty()
+    && body.iter().all(|token| match &token.kind {
+      TokenKind::Int(_) | TokenKind::Float(_) => true,
+      TokenKind::Ident(name) => {
+        matches!(name.as_str(), "true" | "false")
+          || (!vector && macros.get(name).is_some_and(|m| m.constant))
+      },
+      TokenKind::Punct(punct) => matches!(*punct, "(" | ")" | "+" | "-" | "*" | "/" | ","),
+      TokenKind::Directive(_) | TokenKind::Const(_) => false,
+    })
+}
+
+/// Expands the macros in `tokens`, except those in `disabled`, which are being expanded.
+fn expand(tokens: Vec<Token>, macros: &HashMap<String, Macro>, disabled: &[&str]) -> Vec<Token> {
+  let mut output = Vec::new();
+  let mut tokens = tokens.into_iter().peekable();
+  while let Some(token) = tokens.next() {
+    let TokenKind::Ident(name) = &token.kind else {
+      output.push(token);
+      continue;
+    };
+    let Some(definition) = macros
+      .get(name)
+      .filter(|m| !m.constant && !disabled.contains(&name.as_str()))
+    else {
+      output.push(token);
+      continue;
+    };
+    let mut body = definition.body.clone();
+    if let Some(params) = &definition.params {
+      if !tokens.peek().is_some_and(|next| next.is("(")) {
+        output.push(token);
+        continue;
+      }
+      tokens.next();
+      // The arguments, split at the commas outside of parentheses.
+      let mut args = vec![Vec::new()];
+      let mut depth = 0;
+      for arg_token in tokens.by_ref() {
+        if arg_token.is("(") {
+          depth += 1;
+        } else if arg_token.is(")") {
+          if depth == 0 {
+            break;
+          }
+          depth -= 1;
+        } else if arg_token.is(",") && depth == 0 {
+          args.push(Vec::new());
+          continue;
+        }
+        args.last_mut().unwrap().push(arg_token);
+      }
+      body = body
+        .into_iter()
+        .flat_map(|body_token| {
+          let arg = match &body_token.kind {
+            TokenKind::Ident(ident) => params.iter().position(|param| param == ident),
+            _ => None,
+          };
+          match arg {
+            // Arguments are wrapped in parentheses, so `a * x` with `x + 1` keeps its meaning.
+            Some(arg) => {
+              let mut tokens = vec![punct_token("(", token.line)];
+              tokens.extend(args.get(arg).cloned().unwrap_or_default());
+              tokens.push(punct_token(")", token.line));
+              tokens
+            },
+            None => vec![body_token],
+          }
+        })
+        .collect();
+    }
+    let mut inner = disabled.to_vec();
+    inner.push(name);
+    let mut expanded = expand(
+      body
+        .into_iter()
+        .map(|body_token| Token {
+          line: token.line,
+          comments: Vec::new(),
+          blank_line_before: false,
+          ..body_token
+        })
+        .collect(),
+      macros,
+      &inner,
+    );
+    if let Some(first) = expanded.first_mut() {
+      first.comments.clone_from(&token.comments);
+      first.blank_line_before = token.blank_line_before;
+    }
+    output.extend(expanded);
+  }
+  output
+}
+
+/// The value of an `#if`, `#ifdef` or `#ifndef`. Conditions it can't evaluate are flagged and
+/// taken as true.
+fn evaluate(
+  directive: &str,
+  condition: &str,
+  line: usize,
+  macros: &HashMap<String, Macro>,
+  flags: &mut Vec<Flag>,
+) -> bool {
+  match directive {
+    "ifdef" => return macros.contains_key(condition),
+    "ifndef" => return !macros.contains_key(condition),
+    _ => {},
+  }
+  let value = lex(condition, line).ok().and_then(|tokens| {
+    // `defined` is resolved before macros are expanded.
+    let mut resolved = Vec::new();
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+      if token.kind != TokenKind::Ident("defined".into()) {
+        resolved.push(token);
+        continue;
+      }
+      let mut name = tokens.next()?;
+      let parenthesized = name.is("(");
+      if parenthesized {
+        name = tokens.next()?;
+      }
+      let TokenKind::Ident(name) = name.kind else {
+        return None;
+      };
+      if parenthesized && !tokens.next()?.is(")") {
+        return None;
+      }
+      let value = i64::from(macros.contains_key(&name));
+      resolved.push(Token {
+        kind: TokenKind::Int(value.to_string()),
+        ..token
+      });
+    }
+    // Constants are expanded too in conditions.
+    let mut expanded = Vec::new();
+    for token in expand(resolved, macros, &[]) {
+      match &token.kind {
+        TokenKind::Ident(name) if macros.get(name).is_some_and(|m| m.constant) => {
+          expanded.push(punct_token("(", line));
+          expanded.extend(expand(macros[name].body.clone(), macros, &[]));
+          expanded.push(punct_token(")", line));
+        },
+        _ => expanded.push(token),
+      }
+    }
+    let mut evaluator = Evaluator {
+      tokens: &expanded,
+      position: 0,
+    };
+    let value = evaluator.binary(0)?;
+    (evaluator.position == expanded.len()).then_some(value)
+  });
+  value.map_or_else(
+    || {
+      flags.push(Flag::new(
+        line,
+        format!("could not evaluate `#{directive} {condition}`, took it as true"),
+      ));
+      true
+    },
+    |value| value != 0,
+  )
+}
+
+/// Integer arithmetic of `#if` conditions.
+struct Evaluator<'a> {
+  tokens: &'a [Token],
+  position: usize,
+}
+
+impl Evaluator<'_> {
+  fn next(&mut self) -> Option<&Token> {
+    let token = self.tokens.get(self.position);
+    self.position += 1;
+    token
+  }
+
+  fn binary(&mut self, min_precedence: u8) -> Option<i64> {
+    let mut lhs = self.unary()?;
+    loop {
+      let Some(TokenKind::Punct(op)) = self.tokens.get(self.position).map(|t| &t.kind) else {
+        return Some(lhs);
+      };
+      let precedence = match *op {
+        "||" => 1,
+        "&&" => 2,
+        "==" | "!=" => 3,
+        "<" | ">" | "<=" | ">=" => 4,
+        "+" | "-" => 5,
+        "*" | "/" | "%" => 6,
+        _ => return Some(lhs),
+      };
+      if precedence < min_precedence {
+        return Some(lhs);
+      }
+      let op = *op;
+      self.position += 1;
+      let rhs = self.binary(precedence + 1)?;
+      lhs = match op {
+        "||" => i64::from(lhs != 0 || rhs != 0),
+        "&&" => i64::from(lhs != 0 && rhs != 0),
+        "==" => i64::from(lhs == rhs),
+        "!=" => i64::from(lhs != rhs),
+        "<" => i64::from(lhs < rhs),
+        ">" => i64::from(lhs > rhs),
+        "<=" => i64::from(lhs <= rhs),
+        ">=" => i64::from(lhs >= rhs),
+        "+" => lhs.checked_add(rhs)?,
+        "-" => lhs.checked_sub(rhs)?,
+        "*" => lhs.checked_mul(rhs)?,
+        "/" => lhs.checked_div(rhs)?,
+        _ => lhs.checked_rem(rhs)?,
+      };
+    }
+  }
+
+  fn unary(&mut self) -> Option<i64> {
+    let token = self.next()?.clone();
+    match &token.kind {
+      TokenKind::Int(text) => parse_int(text),
+      // Undefined names are 0, as in C.
+      TokenKind::Ident(_) => Some(0),
+      TokenKind::Punct("(") => {
+        let value = self.binary(0)?;
+        self.next()?.is(")").then_some(value)
+      },
+      TokenKind::Punct("!") => Some(i64::from(self.unary()? == 0)),
+      TokenKind::Punct("-") => self.unary()?.checked_neg(),
+      TokenKind::Punct("+") => self.unary(),
+      _ => None,
+    }
+  }
+}
+
+/// The value of a GLSL integer literal.
+pub fn parse_int(text: &str) -> Option<i64> {
+  match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+    Some(hex) => i64::from_str_radix(hex, 16).ok(),
+    None => text.parse().ok(),
+  }
+}
@@ -0,0 +1,173 @@
+//! A translator from the GLSL of Shadertoy to the Rust of the ports, to start a port from. It
+//! covers the bulk of the mechanical work: `vec3(...)` constructors, swizzles like `.xyz()`,
+//! `mod` as `rem_euclid`, `fract` as `fract_gl`, `out` parameters as `&mut`, the uniforms as
+//! fields of `Inputs` and global variables as fields of a `State`, as the ports by hand do.
+//!
+//! What it can't translate is reported as a [`Flag`] and marked with a `// TODO:` comment in the
+//! output, `todo!()` standing in for untranslatable expressions, so the result compiles or fails
+//! to compile where a human has to look. The output is meant to be reviewed, not trusted: GLSL
+//! and Rust differ in the details of integer division, `%` on negative numbers and the like.
+
+mod emit;
+mod lex;
+mod parse;
+#[cfg(test)]
+mod tests;
+mod usage;
+
+use core::fmt;
+
+/// Something that needs a human, at a line of the GLSL.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Flag {
+  pub line: usize,
+  pub message: String,
+}
+
+impl Flag {
+  pub fn new(line: usize, message: impl Into<String>) -> Self {
+    Self {
+      line,
+      message: message.into(),
+    }
+  }
+}
+
+impl fmt::Display for Flag {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+/// What goes into the `ShaderDefinition` of a port, see
+/// [`ShaderDefinition`](shadertoys_shaders::shader_prelude::ShaderDefinition).
+pub struct Definition<'a> {
+  pub name: &'a str,
+  pub author: &'a str,
+  pub license: &'a str,
+  pub link: Option<&'a str>,
+}
+
+impl fmt::Display for Definition<'_> {
+  /// The `SHADER_DEFINITION` constant, without params.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "pub const SHADER_DEFINITION: ShaderDefinition = ShaderDefinition {{"
+    )?;
+    writeln!(f, "  name: {:?},", self.name)?;
+    writeln!(f, "  author: {:?},", self.author)?;
+    writeln!(f, "  license: {:?},", self.license)?;
+    match self.link {
+      Some(link) => writeln!(f, "  link: Some({link:?}),")?,
+      None => writeln!(f, "  link: None,")?,
+    }
+    writeln!(f, "  params: &[],")?;
+    writeln!(f, "  channels: [ChannelInput::Procedural; 4],")?;
+    writeln!(f, "}};")
+  }
+}
+
+/// The module of a port and what to look at in it.
+pub struct Translation {
+  pub rust: String,
+  pub flags: Vec<Flag>,
+}
+
+/// Translates the GLSL of an image pass, with its common code in front, into the module of a
+/// port with `definition`: everything after the `//!` header.
+///
+/// # Errors
+///
+/// Returns a [`Flag`] with the line if the GLSL doesn't parse or has no `mainImage`.
+pub fn translate(glsl: &str, definition: &Definition<'_>) -> Result<Translation, Flag> {
+  let mut flags = Vec::new();
+  let tokens = lex::preprocess(glsl, &mut flags)?;
+  let items = parse::Parser::new(tokens).parse()?;
+  let rust = emit::emit(&items, definition, &mut flags)?;
+  flags.sort_by_key(|flag| flag.line);
+  Ok(Translation { rust, flags })
+}
+
+/// Keywords of Rust, which need another name as identifiers.
+pub const KEYWORDS: &[&str] = &[
+  "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do",
+  "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+  "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+  "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe",
+  "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// `name` with an `_` after it if it is a keyword.
+fn escape(name: String) -> String {
+  if KEYWORDS.contains(&name.as_str()) {
+    name + "_"
+  } else {
+    name
+  }
+}
+
+/// The words of a GLSL name: `rayMarchHDR2` is `ray`, `March`, `HDR2`.
+fn words(name: &str) -> Vec<String> {
+  let chars: Vec<char> = name.chars().collect();
+  let mut words = Vec::new();
+  let mut word = String::new();
+  for (index, &c) in chars.iter().enumerate() {
+    if c == '_' {
+      if !word.is_empty() {
+        words.push(core::mem::take(&mut word));
+      }
+      continue;
+    }
+    let previous = index.checked_sub(1).map(|index| chars[index]);
+    let next = chars.get(index + 1);
+    let starts = c.is_ascii_uppercase()
+      && previous.is_some_and(|previous| {
+        previous.is_ascii_lowercase()
+          || previous.is_ascii_digit()
+          || (previous.is_ascii_uppercase() && next.is_some_and(char::is_ascii_lowercase))
+      });
+    if starts && !word.is_empty() {
+      words.push(core::mem::take(&mut word));
+    }
+    word.push(c);
+  }
+  if !word.is_empty() {
+    words.push(word);
+  }
+  words
+}
+
+/// The Rust name of a variable or function: `rayMarch` is `ray_march`.
+fn snake_case(name: &str) -> String {
+  let snake = words(name)
+    .iter()
+    .map(|word| word.to_ascii_lowercase())
+    .collect::<Vec<_>>()
+    .join("_");
+  // Names of a single `_` or starting with one keep it.
+  let snake = if name.starts_with('_') {
+    format!("_{snake}")
+  } else {
+    snake
+  };
+  escape(snake)
+}
+
+/// The Rust name of a constant: `maxSteps` is `MAX_STEPS`.
+fn screaming_snake_case(name: &str) -> String {
+  snake_case(name).trim_end_matches('_').to_ascii_uppercase()
+}
+
+/// The Rust name of a struct: `hit_info` is `HitInfo`.
+fn camel_case(name: &str) -> String {
+  words(name)
+    .iter()
+    .map(|word| {
+      let mut chars = word.chars();
+      chars.next().map_or_else(String::new, |first| {
+        first.to_ascii_uppercase().to_string() + chars.as_str()
+      })
+    })
+    .collect()
+}
@@ -0,0 +1,824 @@
+//! The syntax tree of GLSL ES and its parser.
+
+use super::{
+  lex::{Comment, Token, TokenKind},
+  Flag,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Type {
+  Void,
+  Bool,
+  Int,
+  Uint,
+  Float,
+  Vec(u8),
+  Mat(u8),
+  Struct(String),
+  /// The element type and the length.
+  Array(Box<Self>, Box<Expr>),
+  Sampler,
+  /// Types without a translation, like `ivec2` or `mat4`, and unknown types.
+  Other(String),
+}
+
+impl Type {
+  /// The type named `name`, if it is a built-in type.
+  pub fn builtin(name: &str) -> Option<Self> {
+    Some(match name {
+      "void" => Self::Void,
+      "bool" => Self::Bool,
+      "int" => Self::Int,
+      "uint" => Self::Uint,
+      "float" => Self::Float,
+      "vec2" => Self::Vec(2),
+      "vec3" => Self::Vec(3),
+      "vec4" => Self::Vec(4),
+      "mat2" | "mat2x2" => Self::Mat(2),
+      "mat3" | "mat3x3" => Self::Mat(3),
+      "sampler2D" | "samplerCube" | "sampler3D" => Self::Sampler,
+      "mat4" | "mat4x4" | "mat2x3" | "mat2x4" | "mat3x2" | "mat3x4" | "mat4x2" | "mat4x3"
+      | "ivec2" | "ivec3" | "ivec4" | "uvec2" | "uvec3" | "uvec4" | "bvec2" | "bvec3" | "bvec4" => {
+        Self::Other(name.to_owned())
+      },
+      _ => return None,
+    })
+  }
+
+  /// The number of components of a scalar or a vector.
+  pub const fn components(&self) -> Option<u8> {
+    match self {
+      Self::Float | Self::Int | Self::Uint | Self::Bool => Some(1),
+      Self::Vec(n) => Some(*n),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expr {
+  Int(String),
+  Float(String),
+  Bool(bool),
+  Ident(String),
+  /// Functions and constructors.
+  Call(String, Vec<Self>),
+  /// `float[3](a, b, c)`.
+  ArrayConstructor(Type, Vec<Self>),
+  Member(Box<Self>, String),
+  Index(Box<Self>, Box<Self>),
+  Unary(&'static str, Box<Self>),
+  Binary(&'static str, Box<Self>, Box<Self>),
+  /// `=` or a compound assignment like `+=`.
+  Assign(&'static str, Box<Self>, Box<Self>),
+  /// `++` or `--`, and whether it is a prefix.
+  Step(&'static str, bool, Box<Self>),
+  Conditional(Box<Self>, Box<Self>, Box<Self>),
+  /// `a, b`.
+  Sequence(Box<Self>, Box<Self>),
+}
+
+/// A variable, unique in the shader by `id`.
+#[derive(Clone, Debug)]
+pub struct Decl {
+  pub id: usize,
+  pub ty: Type,
+  pub name: String,
+  pub init: Option<Expr>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Qualifier {
+  In,
+  Out,
+  InOut,
+}
+
+#[derive(Clone, Debug)]
+pub struct Param {
+  pub decl: Decl,
+  pub qualifier: Qualifier,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stmt {
+  pub kind: StmtKind,
+  pub line: usize,
+  pub comments: Vec<Comment>,
+  pub blank_line_before: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum StmtKind {
+  Decl(Vec<Decl>),
+  Expr(Expr),
+  /// The statements and the comments before the closing brace.
+  Block(Vec<Stmt>, Vec<Comment>),
+  If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+  For {
+    init: Option<Box<Stmt>>,
+    condition: Option<Expr>,
+    step: Option<Expr>,
+    body: Box<Stmt>,
+  },
+  While(Expr, Box<Stmt>),
+  DoWhile(Box<Stmt>, Expr),
+  Return(Option<Expr>),
+  Break,
+  Continue,
+  Discard,
+  /// `switch` and other statements without a translation, as their first token.
+  Unsupported(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Function {
+  pub ret: Type,
+  pub name: String,
+  pub params: Vec<Param>,
+  pub body: Vec<Stmt>,
+  pub end_comments: Vec<Comment>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ItemKind {
+  /// A `const` global or a `#define` of a constant, whose type is then `None`.
+  Const(Option<Type>, Decl),
+  Global(Decl),
+  Struct(String, Vec<(Type, String)>),
+  Function(Function),
+  /// A declaration that only carries comments, like `precision` or a prototype.
+  Empty,
+}
+
+#[derive(Clone, Debug)]
+pub struct Item {
+  pub kind: ItemKind,
+  pub line: usize,
+  pub comments: Vec<Comment>,
+  pub blank_line_before: bool,
+}
+
+pub struct Parser {
+  tokens: Vec<Token>,
+  position: usize,
+  structs: Vec<String>,
+  next_id: usize,
+}
+
+type Result<T> = core::result::Result<T, Flag>;
+
+/// Precision qualifiers and other words that don't change the translation.
+const IGNORED_QUALIFIERS: &[&str] = &["lowp", "mediump", "highp", "invariant", "flat", "smooth"];
+
+impl Parser {
+  pub fn new(tokens: Vec<Token>) -> Self {
+    Self {
+      tokens,
+      position: 0,
+      structs: Vec::new(),
+      next_id: 0,
+    }
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position)
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<&Token> {
+    self.tokens.get(self.position + offset)
+  }
+
+  fn line(&self) -> usize {
+    self
+      .peek()
+      .or_else(|| self.tokens.last())
+      .map_or(1, |token| token.line)
+  }
+
+  fn error(&self, message: impl Into<String>) -> Flag {
+    Flag::new(self.line(), message)
+  }
+
+  fn next(&mut self) -> Result<Token> {
+    let token = self
+      .peek()
+      .cloned()
+      .ok_or_else(|| self.error("unexpected end"))?;
+    self.position += 1;
+    Ok(token)
+  }
+
+  fn at(&self, punct: &str) -> bool {
+    self.peek().is_some_and(|token| token.is(punct))
+  }
+
+  fn at_ident(&self, name: &str) -> bool {
+    matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Ident(ident)) if ident == name)
+  }
+
+  fn eat(&mut self, punct: &str) -> bool {
+    let found = self.at(punct);
+    if found {
+      self.position += 1;
+    }
+    found
+  }
+
+  fn expect(&mut self, punct: &str) -> Result<()> {
+    if self.eat(punct) {
+      Ok(())
+    } else {
+      Err(self.error(format!("expected `{punct}`")))
+    }
+  }
+
+  fn ident(&mut self) -> Result<String> {
+    if let TokenKind::Ident(name) = self.next()?.kind {
+      return Ok(name);
+    }
+    self.position -= 1;
+    Err(self.error("expected a name"))
+  }
+
+  /// The comments before the next token, leaving those trailing the previous one.
+  fn comments(&mut self) -> (Vec<Comment>, bool) {
+    match self.tokens.get_mut(self.position) {
+      Some(token) => (
+        core::mem::take(&mut token.comments),
+        token.blank_line_before,
+      ),
+      None => (Vec::new(), false),
+    }
+  }
+
+  /// Moves the comments trailing the last statement, those on its line, to `comments`.
+  fn trailing_comments(&mut self, comments: &mut Vec<Comment>) {
+    if let Some(token) = self.tokens.get_mut(self.position) {
+      let count = token
+        .comments
+        .iter()
+        .take_while(|comment| comment.trailing)
+        .count();
+      comments.extend(token.comments.drain(..count));
+    }
+  }
+
+  fn new_decl(&mut self, ty: Type, name: String, init: Option<Expr>) -> Decl {
+    self.next_id += 1;
+    Decl {
+      id: self.next_id,
+      ty,
+      name,
+      init,
+    }
+  }
+
+  fn is_type_name(&self, name: &str) -> bool {
+    Type::builtin(name).is_some() || self.structs.iter().any(|s| s == name)
+  }
+
+  /// Whether a declaration starts here.
+  fn at_declaration(&self) -> bool {
+    let Some(TokenKind::Ident(name)) = self.peek().map(|t| &t.kind) else {
+      return false;
+    };
+    if matches!(name.as_str(), "const" | "struct" | "precision")
+      || IGNORED_QUALIFIERS.contains(&name.as_str())
+    {
+      return true;
+    }
+    self.is_type_name(name)
+      && self
+        .peek_at(1)
+        .is_some_and(|next| matches!(next.kind, TokenKind::Ident(_)) || next.is("["))
+  }
+
+  pub fn parse(mut self) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    while self.peek().is_some() {
+      let (comments, blank_line_before) = self.comments();
+      let line = self.line();
+      let kinds = self.item()?;
+      let mut item_comments = comments;
+      for kind in kinds {
+        items.push(Item {
+          kind,
+          line,
+          comments: core::mem::take(&mut item_comments),
+          blank_line_before,
+        });
+      }
+      if let Some(item) = items.last_mut() {
+        self.trailing_comments(&mut item.comments);
+      }
+    }
+    Ok(items)
+  }
+
+  fn item(&mut self) -> Result<Vec<ItemKind>> {
+    if let Some(TokenKind::Const(name)) = self.peek().map(|t| t.kind.clone()) {
+      self.position += 1;
+      let value = self.expression()?;
+      self.expect(";")?;
+      let decl = self.new_decl(Type::Other(String::new()), name, Some(value));
+      return Ok(vec![ItemKind::Const(None, decl)]);
+    }
+    if self.eat(";") {
+      return Ok(vec![ItemKind::Empty]);
+    }
+    if self.at_ident("precision") {
+      while !self.eat(";") {
+        self.next()?;
+      }
+      return Ok(vec![ItemKind::Empty]);
+    }
+    if self.at_ident("struct") {
+      return self.structure().map(|item| vec![item]);
+    }
+    let mut constant = false;
+    loop {
+      match self.peek().map(|t| &t.kind) {
+        Some(TokenKind::Ident(word)) if word == "const" => constant = true,
+        Some(TokenKind::Ident(word)) if IGNORED_QUALIFIERS.contains(&word.as_str()) => {},
+        Some(TokenKind::Ident(word)) if matches!(word.as_str(), "uniform" | "in" | "out") => {
+          return Err(self.error(format!("unsupported `{word}` global")));
+        },
+        _ => break,
+      }
+      self.position += 1;
+    }
+    let ty = self.ty()?;
+    let name = self.ident()?;
+    if self.eat("(") {
+      return self.function(ty, name).map(|item| vec![item]);
+    }
+    let decls = self.declarators(&ty, name)?;
+    Ok(
+      decls
+        .into_iter()
+        .map(|decl| {
+          if constant {
+            ItemKind::Const(Some(decl.ty.clone()), decl)
+          } else {
+            ItemKind::Global(decl)
+          }
+        })
+        .collect(),
+    )
+  }
+
+  fn structure(&mut self) -> Result<ItemKind> {
+    self.position += 1;
+    let name = self.ident()?;
+    self.structs.push(name.clone());
+    self.expect("{")?;
+    let mut fields = Vec::new();
+    while !self.eat("}") {
+      let ty = self.ty()?;
+      loop {
+        let field = self.ident()?;
+        fields.push((self.array_suffix(ty.clone())?, field));
+        if !self.eat(",") {
+          break;
+        }
+      }
+      self.expect(";")?;
+    }
+    if !self.eat(";") {
+      return Err(self.error("declarations after a `struct` are not supported"));
+    }
+    Ok(ItemKind::Struct(name, fields))
+  }
+
+  fn ty(&mut self) -> Result<Type> {
+    while self.peek().is_some_and(
+      |t| matches!(&t.kind, TokenKind::Ident(word) if IGNORED_QUALIFIERS.contains(&word.as_str())),
+    ) {
+      self.position += 1;
+    }
+    let name = self.ident()?;
+    let ty = match Type::builtin(&name) {
+      Some(ty) => ty,
+      None if self.structs.contains(&name) => Type::Struct(name),
+      None => {
+        self.position -= 1;
+        return Err(self.error(format!("unknown type `{name}`")));
+      },
+    };
+    self.array_suffix(ty)
+  }
+
+  /// `ty` followed by `[length]`, if there is one. `[]` has the length `0` until the
+  /// initializer is known.
+  fn array_suffix(&mut self, ty: Type) -> Result<Type> {
+    if !self.eat("[") {
+      return Ok(ty);
+    }
+    let length = if self.at("]") {
+      Expr::Int("0".into())
+    } else {
+      self.expression()?
+    };
+    self.expect("]")?;
+    Ok(Type::Array(Box::new(ty), Box::new(length)))
+  }
+
+  /// The declarators after the type and the first name, up to the `;`.
+  fn declarators(&mut self, ty: &Type, first: String) -> Result<Vec<Decl>> {
+    let mut decls = Vec::new();
+    let mut name = first;
+    loop {
+      let mut decl_ty = self.array_suffix(ty.clone())?;
+      let init = if self.eat("=") {
+        Some(self.assignment()?)
+      } else {
+        None
+      };
+      // `float a[] = float[](1., 2.)` takes its length from the initializer.
+      if let (Type::Array(element, length), Some(Expr::ArrayConstructor(_, values))) =
+        (&decl_ty, &init)
+      {
+        if **length == Expr::Int("0".into()) {
+          decl_ty = Type::Array(
+            element.clone(),
+            Box::new(Expr::Int(values.len().to_string())),
+          );
+        }
+      }
+      decls.push(self.new_decl(decl_ty, name, init));
+      if !self.eat(",") {
+        break;
+      }
+      name = self.ident()?;
+    }
+    self.expect(";")?;
+    Ok(decls)
+  }
+
+  fn function(&mut self, ret: Type, name: String) -> Result<ItemKind> {
+    let mut params = Vec::new();
+    if self.at_ident("void") && self.peek_at(1).is_some_and(|t| t.is(")")) {
+      self.position += 1;
+    }
+    while !self.eat(")") {
+      let mut qualifier = Qualifier::In;
+      loop {
+        match self.peek().map(|t| &t.kind) {
+          Some(TokenKind::Ident(word)) if word == "in" || word == "const" => {},
+          Some(TokenKind::Ident(word)) if word == "out" => qualifier = Qualifier::Out,
+          Some(TokenKind::Ident(word)) if word == "inout" => qualifier = Qualifier::InOut,
+          _ => break,
+        }
+        self.position += 1;
+      }
+      let ty = self.ty()?;
+      // Prototypes may leave out the names.
+      let param_name = if matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Ident(_))) {
+        self.ident()?
+      } else {
+        String::new()
+      };
+      let ty = self.array_suffix(ty)?;
+      let decl = self.new_decl(ty, param_name, None);
+      params.push(Param { decl, qualifier });
+      if !self.eat(",") {
+        self.expect(")")?;
+        break;
+      }
+    }
+    if self.eat(";") {
+      return Ok(ItemKind::Empty);
+    }
+    self.expect("{")?;
+    let (body, end_comments) = self.block_rest()?;
+    Ok(ItemKind::Function(Function {
+      ret,
+      name,
+      params,
+      body,
+      end_comments,
+    }))
+  }
+
+  /// The statements after a `{`, up to and with the `}`.
+  fn block_rest(&mut self) -> Result<(Vec<Stmt>, Vec<Comment>)> {
+    let mut stmts: Vec<Stmt> = Vec::new();
+    loop {
+      if let Some(last) = stmts.last_mut() {
+        self.trailing_comments(&mut last.comments);
+      }
+      if self.at("}") {
+        let (comments, _) = self.comments();
+        self.position += 1;
+        return Ok((stmts, comments));
+      }
+      stmts.push(self.statement()?);
+    }
+  }
+
+  fn statement(&mut self) -> Result<Stmt> {
+    let (comments, blank_line_before) = self.comments();
+    let line = self.line();
+    let kind = self.statement_kind()?;
+    Ok(Stmt {
+      kind,
+      line,
+      comments,
+      blank_line_before,
+    })
+  }
+
+  fn statement_kind(&mut self) -> Result<StmtKind> {
+    if self.eat("{") {
+      let (stmts, comments) = self.block_rest()?;
+      return Ok(StmtKind::Block(stmts, comments));
+    }
+    if self.eat(";") {
+      return Ok(StmtKind::Block(Vec::new(), Vec::new()));
+    }
+    if self.at_declaration() {
+      while self.at_ident("const")
+        || self.peek().is_some_and(|t| {
+          matches!(&t.kind, TokenKind::Ident(word) if IGNORED_QUALIFIERS.contains(&word.as_str()))
+        })
+      {
+        self.position += 1;
+      }
+      let ty = self.ty()?;
+      let name = self.ident()?;
+      return self.declarators(&ty, name).map(StmtKind::Decl);
+    }
+    let Some(TokenKind::Ident(word)) = self.peek().map(|t| t.kind.clone()) else {
+      let expr = self.expression()?;
+      self.expect(";")?;
+      return Ok(StmtKind::Expr(expr));
+    };
+    match word.as_str() {
+      "if" => {
+        self.position += 1;
+        self.expect("(")?;
+        let condition = self.expression()?;
+        self.expect(")")?;
+        let then = self.statement()?;
+        let otherwise = if self.at_ident("else") {
+          self.position += 1;
+          Some(Box::new(self.statement()?))
+        } else {
+          None
+        };
+        Ok(StmtKind::If(condition, Box::new(then), otherwise))
+      },
+      "for" => {
+        self.position += 1;
+        self.expect("(")?;
+        let init = if self.eat(";") {
+          None
+        } else {
+          Some(Box::new(self.statement()?))
+        };
+        let condition = if self.at(";") {
+          None
+        } else {
+          Some(self.expression()?)
+        };
+        self.expect(";")?;
+        let step = if self.at(")") {
+          None
+        } else {
+          Some(self.expression()?)
+        };
+        self.expect(")")?;
+        let body = Box::new(self.statement()?);
+        Ok(StmtKind::For {
+          init,
+          condition,
+          step,
+          body,
+        })
+      },
+      "while" => {
+        self.position += 1;
+        self.expect("(")?;
+        let condition = self.expression()?;
+        self.expect(")")?;
+        Ok(StmtKind::While(condition, Box::new(self.statement()?)))
+      },
+      "do" => {
+        self.position += 1;
+        let body = self.statement()?;
+        if !self.at_ident("while") {
+          return Err(self.error("expected `while`"));
+        }
+        self.position += 1;
+        self.expect("(")?;
+        let condition = self.expression()?;
+        self.expect(")")?;
+        self.expect(";")?;
+        Ok(StmtKind::DoWhile(Box::new(body), condition))
+      },
+      "return" => {
+        self.position += 1;
+        let value = if self.at(";") {
+          None
+        } else {
+          Some(self.expression()?)
+        };
+        self.expect(";")?;
+        Ok(StmtKind::Return(value))
+      },
+      "break" | "continue" | "discard" => {
+        self.position += 1;
+        self.expect(";")?;
+        Ok(match word.as_str() {
+          "break" => StmtKind::Break,
+          "continue" => StmtKind::Continue,
+          _ => StmtKind::Discard,
+        })
+      },
+      "switch" => {
+        // Skipped as a whole, up to the end of its body.
+        self.position += 1;
+        let mut depth = 0;
+        loop {
+          let token = self.next()?;
+          if token.is("{") {
+            depth += 1;
+          } else if token.is("}") {
+            depth -= 1;
+            if depth == 0 {
+              break;
+            }
+          }
+        }
+        Ok(StmtKind::Unsupported(word))
+      },
+      _ => {
+        let expr = self.expression()?;
+        self.expect(";")?;
+        Ok(StmtKind::Expr(expr))
+      },
+    }
+  }
+
+  pub fn expression(&mut self) -> Result<Expr> {
+    let first = self.assignment()?;
+    if self.eat(",") {
+      let rest = self.expression()?;
+      return Ok(Expr::Sequence(Box::new(first), Box::new(rest)));
+    }
+    Ok(first)
+  }
+
+  fn assignment(&mut self) -> Result<Expr> {
+    let target = self.conditional()?;
+    let Some(TokenKind::Punct(op)) = self.peek().map(|t| t.kind.clone()) else {
+      return Ok(target);
+    };
+    if !matches!(
+      op,
+      "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>="
+    ) {
+      return Ok(target);
+    }
+    self.position += 1;
+    let value = self.assignment()?;
+    Ok(Expr::Assign(op, Box::new(target), Box::new(value)))
+  }
+
+  fn conditional(&mut self) -> Result<Expr> {
+    let condition = self.binary(0)?;
+    if !self.eat("?") {
+      return Ok(condition);
+    }
+    let then = self.assignment()?;
+    self.expect(":")?;
+    let otherwise = self.assignment()?;
+    Ok(Expr::Conditional(
+      Box::new(condition),
+      Box::new(then),
+      Box::new(otherwise),
+    ))
+  }
+
+  fn binary(&mut self, min_precedence: u8) -> Result<Expr> {
+    let mut lhs = self.unary()?;
+    loop {
+      let Some(TokenKind::Punct(op)) = self.peek().map(|t| t.kind.clone()) else {
+        return Ok(lhs);
+      };
+      let Some(precedence) = binary_precedence(op) else {
+        return Ok(lhs);
+      };
+      if precedence < min_precedence {
+        return Ok(lhs);
+      }
+      self.position += 1;
+      let rhs = self.binary(precedence + 1)?;
+      lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+    }
+  }
+
+  fn unary(&mut self) -> Result<Expr> {
+    if let Some(TokenKind::Punct(op @ ("-" | "+" | "!" | "~" | "++" | "--"))) =
+      self.peek().map(|t| t.kind.clone())
+    {
+      self.position += 1;
+      let operand = self.unary()?;
+      return Ok(match op {
+        "+" => operand,
+        "++" | "--" => Expr::Step(op, true, Box::new(operand)),
+        _ => Expr::Unary(op, Box::new(operand)),
+      });
+    }
+    let mut expr = self.primary()?;
+    loop {
+      if self.eat(".") {
+        expr = Expr::Member(Box::new(expr), self.ident()?);
+      } else if self.eat("[") {
+        let index = self.expression()?;
+        self.expect("]")?;
+        expr = Expr::Index(Box::new(expr), Box::new(index));
+      } else if self.at("++") || self.at("--") {
+        let op = if self.at("++") { "++" } else { "--" };
+        self.position += 1;
+        expr = Expr::Step(op, false, Box::new(expr));
+      } else if self.at("(") {
+        return Err(self.error("unsupported method call"));
+      } else {
+        return Ok(expr);
+      }
+    }
+  }
+
+  fn arguments(&mut self) -> Result<Vec<Expr>> {
+    let mut args = Vec::new();
+    if self.at_ident("void") && self.peek_at(1).is_some_and(|t| t.is(")")) {
+      self.position += 1;
+    }
+    while !self.eat(")") {
+      args.push(self.assignment()?);
+      if !self.eat(",") {
+        self.expect(")")?;
+        break;
+      }
+    }
+    Ok(args)
+  }
+
+  fn primary(&mut self) -> Result<Expr> {
+    let token = self.next()?;
+    match token.kind {
+      TokenKind::Int(text) => Ok(Expr::Int(text)),
+      TokenKind::Float(text) => Ok(Expr::Float(text)),
+      TokenKind::Ident(name) if name == "true" || name == "false" => Ok(Expr::Bool(name == "true")),
+      TokenKind::Ident(name) => {
+        if self.at("[") && self.is_type_name(&name) {
+          let ty = self.array_suffix(named_type(&name))?;
+          self.expect("(")?;
+          let args = self.arguments()?;
+          let Type::Array(element, _) = ty else {
+            unreachable!()
+          };
+          return Ok(Expr::ArrayConstructor(*element, args));
+        }
+        if self.eat("(") {
+          return Ok(Expr::Call(name, self.arguments()?));
+        }
+        Ok(Expr::Ident(name))
+      },
+      TokenKind::Punct("(") => {
+        let expr = self.expression()?;
+        self.expect(")")?;
+        Ok(expr)
+      },
+      _ => {
+        self.position -= 1;
+        Err(self.error("expected an expression"))
+      },
+    }
+  }
+}
+
+/// The type called `name`, a struct unless it is built in.
+fn named_type(name: &str) -> Type {
+  Type::builtin(name).unwrap_or_else(|| Type::Struct(name.to_owned()))
+}
+
+/// The precedence of a binary operator in GLSL, higher binds tighter.
+const fn binary_precedence(op: &str) -> Option<u8> {
+  Some(match op.as_bytes() {
+    b"||" => 1,
+    b"^^" => 2,
+    b"&&" => 3,
+    b"|" => 4,
+    b"^" => 5,
+    b"&" => 6,
+    b"==" | b"!=" => 7,
+    b"<" | b">" | b"<=" | b">=" => 8,
+    b"<<" | b">>" => 9,
+    b"+" | b"-" => 10,
+    b"*" | b"/" | b"%" => 11,
+    _ => return None,
+  })
+}
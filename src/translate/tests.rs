@@ -0,0 +1,322 @@
+//! Tests for the `translate` module, on the GLSL of shaders ported by hand.
+
+use super::{translate, Definition, Translation};
+
+/// The original of `moving_square.rs`.
+const MOVING_SQUARE: &str = r"vec4 rect(vec2 uv, vec2 pos, float r) {
+	vec2 re_c = abs(uv - pos);
+    vec2 dif1 = re_c - r/2.;
+    vec2 dif2 = clamp(re_c - r/2., 0., 1.);
+    float d1 = clamp(dif1.x + dif1.y, 0., 1.);
+    float d2 = clamp(dif2.x + dif2.y, 0., 1.);
+
+    return vec4(d1);
+}
+
+void mainImage( out vec4 fragColor, in vec2 fragCoord )
+{
+	vec2 uv = fragCoord.xy;
+    float t = sin(iTime);
+
+    vec2 c = iResolution.xy * 0.5;// + sin(iTime) * 50.;
+
+    uv = mat2(cos(t), -sin(t), sin(t), cos(t)) * (uv - c) + c;
+
+	fragColor = rect(uv, c, sin(iTime * 10.) * 50. + 50.);
+    fragColor *= vec4(0.5, 0.2, 1., 1.);
+    fragColor += rect(uv, c, sin(iTime) * 50. + 50.);
+    fragColor *= vec4(0.5, 0.8, 1., 1.);
+}
+";
+
+/// The original of `heart.rs`.
+const HEART: &str = r"// Created by inigo quilez - iq/2013
+// License Creative Commons Attribution-NonCommercial-ShareAlike 3.0 Unported License.
+
+void mainImage( out vec4 fragColor, in vec2 fragCoord )
+{
+	vec2 p = (2.0*fragCoord-iResolution.xy)/min(iResolution.y,iResolution.x);
+
+    // background color
+    vec3 bcol = vec3(1.0,0.8,0.7-0.07*p.y)*(1.0-0.25*length(p));
+
+    // animate
+    float tt = mod(iTime,1.5)/1.5;
+    float ss = pow(tt,.2)*0.5 + 0.5;
+    ss = 1.0 + ss*0.5*sin(tt*6.2831*3.0 + p.y*0.5)*exp(-tt*4.0);
+    p *= vec2(0.5,1.5) + ss*vec2(0.5,-0.5);
+
+    // shape
+#if 0
+    p *= 0.8;
+    p.y = -0.1 - p.y*1.2 + abs(p.x)*(1.0-abs(p.x));
+    float r = length(p);
+	float d = 0.5;
+#else
+	p.y -= 0.25;
+    float a = atan(p.x,p.y)/3.141593;
+    float r = length(p);
+    float h = abs(a);
+    float d = (13.0*h - 22.0*h*h + 10.0*h*h*h)/(6.0-5.0*h);
+#endif
+
+	// color
+	float s = 0.75 + 0.75*p.x;
+	s *= 1.0-0.4*r;
+	s = 0.3 + 0.7*s;
+	s *= 0.5+0.5*pow( 1.0-clamp(r/d, 0.0, 1.0 ), 0.1 );
+	vec3 hcol = vec3(1.0,0.5*r,0.3)*s;
+
+    vec3 col = mix( bcol, hcol, smoothstep( -0.01, 0.01, d-r) );
+
+    fragColor = vec4(col,1.0);
+}
+";
+
+const TEST: Definition<'static> = Definition {
+  name: "Test",
+  author: "Someone",
+  license: "CC BY-NC-SA 3.0",
+  link: None,
+};
+
+/// Translates a shader that has everything needed.
+fn rust(glsl: &str) -> String {
+  let Translation { rust, flags } = translate(glsl, &TEST).unwrap();
+  assert!(flags.is_empty(), "{flags:?}");
+  rust
+}
+
+/// Asserts that `rust` has all `lines`, ignoring indentation.
+fn assert_lines(rust: &str, lines: &[&str]) {
+  for line in lines {
+    assert!(
+      rust.lines().any(|rust_line| rust_line.trim() == *line),
+      "no `{line}` in\n{rust}"
+    );
+  }
+}
+
+#[test]
+fn moving_square_round_trips() {
+  let rust = rust(MOVING_SQUARE);
+  assert_lines(
+    &rust,
+    &[
+      "use crate::shader_prelude::*;",
+      "name: \"Test\",",
+      "author: \"Someone\",",
+      "link: None,",
+      "Inputs { resolution, time }.main_image(color, frag_coord);",
+      "fn rect(uv: Vec2, pos: Vec2, r: f32) -> Vec4 {",
+      "let re_c: Vec2 = (uv - pos).abs();",
+      "let dif2: Vec2 = (re_c - r / 2.0).clamp(Vec2::ZERO, Vec2::ONE);",
+      "let _d2: f32 = (dif2.x + dif2.y).clamp(0.0, 1.0);",
+      "Vec4::splat(d1)",
+      "impl Inputs {",
+      "fn main_image(&self, frag_color: &mut Vec4, frag_coord: Vec2) {",
+      "let mut uv: Vec2 = frag_coord;",
+      "let c: Vec2 = self.resolution.xy() * 0.5; // + sin(iTime) * 50.;",
+      "uv = Mat2::from_cols_array(&[t.cos(), -t.sin(), t.sin(), t.cos()]) * (uv - c) + c;",
+      "*frag_color = rect(uv, c, (self.time * 10.0).sin() * 50.0 + 50.0);",
+      "*frag_color *= vec4(0.5, 0.2, 1.0, 1.0);",
+    ],
+  );
+}
+
+#[test]
+fn heart_round_trips() {
+  let rust = rust(HEART);
+  assert_lines(
+    &rust,
+    &[
+      "// background color",
+      "let bcol: Vec3 = vec3(1.0, 0.8, 0.7 - 0.07 * p.y) * (1.0 - 0.25 * p.length());",
+      "let tt: f32 = self.time.rem_euclid(1.5) / 1.5;",
+      "let mut ss: f32 = tt.powf(0.2) * 0.5 + 0.5;",
+      "p *= vec2(0.5, 1.5) + ss * vec2(0.5, -0.5);",
+      "let a: f32 = p.x.atan2(p.y) / 3.141593;",
+      "s *= 0.5 + 0.5 * (1.0 - (r / d).clamp(0.0, 1.0)).powf(0.1);",
+      "let col: Vec3 = mix(bcol, hcol, smoothstep(-0.01, 0.01, d - r));",
+      "*frag_color = col.extend(1.0);",
+    ],
+  );
+  // The `#if 0` branch is left out, its comment stays.
+  assert!(!rust.contains("p *= 0.8;"));
+  assert_lines(&rust, &["// shape", "p.y -= 0.25;"]);
+  // The license goes to the header of the port.
+  assert!(!rust.contains("inigo quilez"));
+}
+
+#[test]
+fn swizzles_are_assigned_with_with() {
+  let rust = rust(
+    "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+      vec3 v = vec3(fragCoord, 0);
+      v.zx = fract(v.xy);
+      v.xy *= mat2(1, 2, 3, 4);
+      fragColor.rgb = v;
+    }",
+  );
+  assert_lines(
+    &rust,
+    &[
+      "let mut v: Vec3 = frag_coord.extend(0.0);",
+      "v = v.with_zx(v.xy().fract_gl());",
+      "v = v.with_xy(Mat2::from_cols_array(&[1.0, 2.0, 3.0, 4.0]).transpose() * v.xy());",
+      "*frag_color = frag_color.with_xyz(v);",
+    ],
+  );
+}
+
+#[test]
+fn out_parameters_are_mutable_references() {
+  let rust = rust(
+    "void split(in vec2 p, out float x, inout float y) { x = p.x; y += p.y; }
+    void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+      float x, y = 1.;
+      split(fragCoord, x, y);
+      fragColor = vec4(x, y, mod(x, y), 1);
+    }",
+  );
+  assert_lines(
+    &rust,
+    &[
+      "fn split(p: Vec2, x: &mut f32, y: &mut f32) {",
+      "*x = p.x;",
+      "*y += p.y;",
+      "main_image(color, frag_coord);",
+      "let mut x: f32 = 0.0;",
+      "let mut y: f32 = 1.0;",
+      "split(frag_coord, &mut x, &mut y);",
+      "*frag_color = vec4(x, y, x.rem_euclid(y), 1.0);",
+    ],
+  );
+}
+
+#[test]
+fn globals_move_into_state() {
+  let rust = rust(
+    "#define STEPS 4
+    float total;
+    vec3 glow = vec3(.1);
+    void add(float v) { total += v * iTime; }
+    void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+      for (int i = 0; i < STEPS; i++) add(float(i));
+      fragColor = vec4(glow * total, 1);
+    }",
+  );
+  assert_lines(
+    &rust,
+    &[
+      "const STEPS: i32 = 4;",
+      "State::new(Inputs { time }).main_image(color, frag_coord);",
+      "struct State {",
+      "inputs: Inputs,",
+      "total: f32,",
+      "fn new(inputs: Inputs) -> Self {",
+      "total: 0.0,",
+      "glow: Vec3::splat(0.1),",
+      "impl State {",
+      "fn add(&mut self, v: f32) {",
+      "self.total += v * self.inputs.time;",
+      "for i in 0..STEPS {",
+      "self.add(i as f32);",
+      "fn main_image(&mut self, frag_color: &mut Vec4, _frag_coord: Vec2) {",
+      "*frag_color = (self.glow * self.total).extend(1.0);",
+    ],
+  );
+}
+
+#[test]
+fn macros_and_overloads_are_resolved() {
+  let rust = rust(
+    "#define SQ(x) ((x) * (x))
+    #ifdef UNDEFINED
+    #error not taken
+    #endif
+    float len2(vec2 p) { return dot(p, p); }
+    float len2(vec3 p) { return dot(p, p); }
+    void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+      fragColor = vec4(len2(fragCoord) + len2(vec3(SQ(fragCoord.x))));
+    }",
+  );
+  assert_lines(
+    &rust,
+    &[
+      "fn len2(p: Vec2) -> f32 {",
+      "fn len2_vec3(p: Vec3) -> f32 {",
+      "*frag_color = Vec4::splat(len2(frag_coord) + len2_vec3(Vec3::splat(frag_coord.x * \
+       frag_coord.x)));",
+    ],
+  );
+}
+
+#[test]
+fn untranslatable_code_is_flagged() {
+  let Translation { rust, flags } = translate(
+    "float pick(int i) {
+      switch (i) { case 0: return 1.; default: return 0.; }
+    }
+    void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+      fragColor = vec4(pick(int(iDate.w)));
+    }",
+    &TEST,
+  )
+  .unwrap();
+  let flags: Vec<String> = flags.iter().map(ToString::to_string).collect();
+  assert_eq!(
+    flags,
+    [
+      "line 2: unsupported `switch` statement",
+      "line 5: `iDate` taken as zero"
+    ]
+  );
+  assert_lines(
+    &rust,
+    &[
+      "// TODO: unsupported `switch` statement",
+      "todo!();",
+      "// TODO: `iDate` taken as zero",
+    ],
+  );
+}
+
+#[test]
+fn errors_have_lines() {
+  let error = translate(
+    "void mainImage(out vec4 c, in vec2 p) {\n  c = vec4(1) +;\n}",
+    &TEST,
+  )
+  .err()
+  .unwrap();
+  assert_eq!(error.line, 2);
+  let error = translate("float f() { return 1.; }", &TEST).err().unwrap();
+  assert_eq!(error.message, "no `mainImage` function");
+  let error = translate("float café = 1.;", &TEST).err().unwrap();
+  assert_eq!(error.message, "unexpected character `é`");
+}
+
+#[test]
+fn overflowing_conditions_are_flagged() {
+  for condition in [
+    "0x7fffffffffffffff + 1",
+    "-0x7fffffffffffffff - 2",
+    "0x7fffffffffffffff * 2",
+    "-(-0x7fffffffffffffff - 1)",
+    "1 / 0",
+  ] {
+    let Translation { flags, .. } = translate(
+      &format!("#if {condition}\n#endif\nvoid mainImage(out vec4 c, in vec2 p) {{ c = vec4(1); }}"),
+      &TEST,
+    )
+    .unwrap();
+    let flags: Vec<String> = flags.iter().map(ToString::to_string).collect();
+    assert_eq!(
+      flags,
+      [format!(
+        "line 1: could not evaluate `#if {condition}`, took it as true"
+      )]
+    );
+  }
+}
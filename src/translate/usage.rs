@@ -0,0 +1,334 @@
+//! How the variables of a function are read and assigned, so declarations get `mut` only where
+//! needed, stay uninitialized where Rust can prove the assignments, and unused ones get a `_`.
+
+use super::parse::{Decl, Expr, Function, Stmt, StmtKind};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Usage {
+  read: Read,
+  assigned: Assigned,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Read {
+  #[default]
+  Never,
+  Initialized,
+  /// Read or borrowed where it may not be assigned yet, which needs an initializer.
+  Uninitialized,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Assigned {
+  #[default]
+  Never,
+  Once,
+  /// Assigned where it may hold a value already, which needs `mut` without an initializer.
+  Again,
+}
+
+impl Usage {
+  pub fn read(self) -> bool {
+    self.read != Read::Never
+  }
+
+  pub fn assigned(self) -> bool {
+    self.assigned != Assigned::Never
+  }
+
+  pub fn reassigned(self) -> bool {
+    self.assigned == Assigned::Again
+  }
+
+  pub fn read_uninitialized(self) -> bool {
+    self.read == Read::Uninitialized
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Init {
+  No,
+  Yes,
+  Maybe,
+}
+
+/// What is assigned on the way to a statement.
+#[derive(Clone, Default)]
+struct Flow {
+  init: HashMap<usize, Init>,
+  /// After a `return`, `break` or `continue`.
+  dead: bool,
+}
+
+impl Flow {
+  fn merge(self, other: Self) -> Self {
+    if self.dead {
+      return other;
+    }
+    if other.dead {
+      return self;
+    }
+    let mut init = self.init;
+    for (id, state) in other.init {
+      let merged = match init.get(&id) {
+        Some(&current) if current == state => state,
+        _ => Init::Maybe,
+      };
+      init.insert(id, merged);
+    }
+    Self { init, dead: false }
+  }
+}
+
+struct Analysis<'a> {
+  usage: HashMap<usize, Usage>,
+  scopes: Vec<Vec<(&'a str, usize)>>,
+  /// Whether argument `index` of a call of `name` is an `out` or `inout` parameter.
+  is_out: &'a dyn Fn(&str, usize) -> bool,
+}
+
+/// The usage of the parameters and variables of `function` by their [`Decl::id`].
+pub fn analyze(function: &Function, is_out: &dyn Fn(&str, usize) -> bool) -> HashMap<usize, Usage> {
+  let mut analysis = Analysis {
+    usage: HashMap::new(),
+    scopes: vec![function
+      .params
+      .iter()
+      .map(|param| (param.decl.name.as_str(), param.decl.id))
+      .collect()],
+    is_out,
+  };
+  let mut flow = Flow::default();
+  for param in &function.params {
+    flow.init.insert(param.decl.id, Init::Yes);
+  }
+  analysis.block(&function.body, &mut flow);
+  analysis.usage
+}
+
+impl<'a> Analysis<'a> {
+  fn resolve(&self, name: &str) -> Option<usize> {
+    self
+      .scopes
+      .iter()
+      .rev()
+      .flat_map(|scope| scope.iter().rev())
+      .find(|(local, _)| *local == name)
+      .map(|&(_, id)| id)
+  }
+
+  fn read(&mut self, name: &str, flow: &Flow) {
+    if let Some(id) = self.resolve(name) {
+      let usage = self.usage.entry(id).or_default();
+      let read = if flow.init.get(&id) == Some(&Init::Yes) {
+        Read::Initialized
+      } else {
+        Read::Uninitialized
+      };
+      usage.read = usage.read.max(read);
+    }
+  }
+
+  fn write(&mut self, name: &str, flow: &mut Flow) {
+    if let Some(id) = self.resolve(name) {
+      let usage = self.usage.entry(id).or_default();
+      let assigned = if flow.init.get(&id) == Some(&Init::No) {
+        Assigned::Once
+      } else {
+        Assigned::Again
+      };
+      usage.assigned = usage.assigned.max(assigned);
+      if !flow.dead {
+        flow.init.insert(id, Init::Yes);
+      }
+    }
+  }
+
+  /// An assignment to `target`, which reads it first for compound assignments.
+  fn assign(&mut self, target: &Expr, reads: bool, flow: &mut Flow) {
+    match target {
+      Expr::Ident(name) => {
+        if reads {
+          self.read(name, flow);
+        }
+        self.write(name, flow);
+      },
+      Expr::Member(base, _) => self.assign_part(base, flow),
+      Expr::Index(base, index) => {
+        self.expr(index, flow);
+        self.assign_part(base, flow);
+      },
+      _ => self.expr(target, flow),
+    }
+  }
+
+  /// An assignment to a component or element of `base`, which Rust only allows once it is
+  /// initialized.
+  fn assign_part(&mut self, base: &Expr, flow: &mut Flow) {
+    match base {
+      Expr::Ident(name) => {
+        self.read(name, flow);
+        self.write(name, flow);
+      },
+      Expr::Member(inner, _) => self.assign_part(inner, flow),
+      Expr::Index(inner, index) => {
+        self.expr(index, flow);
+        self.assign_part(inner, flow);
+      },
+      _ => self.expr(base, flow),
+    }
+  }
+
+  fn expr(&mut self, expr: &Expr, flow: &mut Flow) {
+    match expr {
+      Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) => {},
+      Expr::Ident(name) => self.read(name, flow),
+      Expr::Call(name, args) => {
+        for (index, arg) in args.iter().enumerate() {
+          if (self.is_out)(name, index) {
+            // Passed as `&mut`, which needs a value.
+            self.assign(arg, true, flow);
+          } else {
+            self.expr(arg, flow);
+          }
+        }
+      },
+      Expr::ArrayConstructor(_, args) => {
+        for arg in args {
+          self.expr(arg, flow);
+        }
+      },
+      Expr::Member(base, _) | Expr::Unary(_, base) => self.expr(base, flow),
+      Expr::Index(base, index) => {
+        self.expr(base, flow);
+        self.expr(index, flow);
+      },
+      Expr::Binary(_, lhs, rhs) | Expr::Sequence(lhs, rhs) => {
+        self.expr(lhs, flow);
+        self.expr(rhs, flow);
+      },
+      Expr::Assign(op, target, value) => {
+        self.expr(value, flow);
+        self.assign(target, *op != "=", flow);
+      },
+      Expr::Step(_, _, target) => self.assign(target, true, flow),
+      Expr::Conditional(condition, then, otherwise) => {
+        self.expr(condition, flow);
+        let mut other = flow.clone();
+        self.expr(then, flow);
+        self.expr(otherwise, &mut other);
+        *flow = core::mem::take(flow).merge(other);
+      },
+    }
+  }
+
+  fn declare(&mut self, decl: &'a Decl, flow: &mut Flow) {
+    if let Some(init) = &decl.init {
+      self.expr(init, flow);
+    }
+    self.usage.entry(decl.id).or_default();
+    let init = if decl.init.is_some() {
+      Init::Yes
+    } else {
+      Init::No
+    };
+    flow.init.insert(decl.id, init);
+    if let Some(scope) = self.scopes.last_mut() {
+      scope.push((&decl.name, decl.id));
+    }
+  }
+
+  fn block(&mut self, stmts: &'a [Stmt], flow: &mut Flow) {
+    self.scopes.push(Vec::new());
+    for stmt in stmts {
+      self.stmt(stmt, flow);
+    }
+    self.scopes.pop();
+  }
+
+  /// A statement in its own scope, like the branch of an `if`.
+  fn scoped(&mut self, stmt: &'a Stmt, flow: &mut Flow) {
+    self.block(core::slice::from_ref(stmt), flow);
+  }
+
+  /// A loop body, which may run any number of times: twice finds the assignments that repeat,
+  /// and merging with the flow before it covers no run at all.
+  fn repeat(
+    &mut self,
+    body: &'a Stmt,
+    step: Option<&Expr>,
+    condition: Option<&Expr>,
+    flow: &mut Flow,
+  ) {
+    let before = flow.clone();
+    let mut inner = flow.clone();
+    for _ in 0..2 {
+      self.scoped(body, &mut inner);
+      inner.dead = false;
+      if let Some(step) = step {
+        self.expr(step, &mut inner);
+      }
+      if let Some(condition) = condition {
+        self.expr(condition, &mut inner);
+      }
+    }
+    *flow = before.merge(inner);
+  }
+
+  fn stmt(&mut self, stmt: &'a Stmt, flow: &mut Flow) {
+    match &stmt.kind {
+      StmtKind::Decl(decls) => {
+        for decl in decls {
+          self.declare(decl, flow);
+        }
+      },
+      StmtKind::Expr(expr) => self.expr(expr, flow),
+      StmtKind::Block(stmts, _) => self.block(stmts, flow),
+      StmtKind::If(condition, then, otherwise) => {
+        self.expr(condition, flow);
+        let mut other = flow.clone();
+        self.scoped(then, flow);
+        if let Some(otherwise) = otherwise {
+          self.scoped(otherwise, &mut other);
+        }
+        *flow = core::mem::take(flow).merge(other);
+      },
+      StmtKind::For {
+        init,
+        condition,
+        step,
+        body,
+      } => {
+        self.scopes.push(Vec::new());
+        if let Some(init) = init {
+          self.stmt(init, flow);
+        }
+        if let Some(condition) = condition {
+          self.expr(condition, flow);
+        }
+        self.repeat(body, step.as_ref(), condition.as_ref(), flow);
+        self.scopes.pop();
+      },
+      StmtKind::While(condition, body) => {
+        self.expr(condition, flow);
+        self.repeat(body, None, Some(condition), flow);
+      },
+      StmtKind::DoWhile(body, condition) => {
+        // The first run always happens.
+        self.scoped(body, flow);
+        flow.dead = false;
+        self.expr(condition, flow);
+        self.repeat(body, None, Some(condition), flow);
+      },
+      StmtKind::Return(value) => {
+        if let Some(value) = value {
+          self.expr(value, flow);
+        }
+        flow.dead = true;
+      },
+      StmtKind::Break | StmtKind::Continue | StmtKind::Discard => flow.dead = true,
+      StmtKind::Unsupported(_) => {},
+    }
+  }
+}
@@ -0,0 +1,436 @@
+//! Videos for the channels, decoded into RGBA frames at the time of the shader: uncompressed
+//! YUV4MPEG2 (`.y4m`) files, e.g. from `ffmpeg -i clip.mp4 -pix_fmt yuv420p clip.y4m`, and image
+//! sequences of PNG files.
+//!
+//! An image sequence is either a directory, whose PNG files play in the order of their names, or
+//! a pattern like `frames/%04d.png` numbered from 0 or 1. Sequences play at [`SEQUENCE_RATE`],
+//! `.y4m` files at the frame rate of their header. Videos loop, like on Shadertoy.
+//!
+//! Frames are decoded when the time reaches them and kept until the next one, with their rows
+//! from the bottom up like the flipped videos of Shadertoy, so that `texture(iChannel0, uv)` with
+//! `uv.y` growing upwards shows them upright.
+
+use core::error::Error;
+use std::{
+  fs::File,
+  io::{BufRead, BufReader, Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+};
+
+/// Frames per second of image sequences.
+pub const SEQUENCE_RATE: f32 = 30.0;
+
+pub struct Video {
+  frames: Frames,
+  width: u32,
+  height: u32,
+  frame_rate: f32,
+  /// The frame in [`Video::rgba`], or the one that failed to decode.
+  current: Option<usize>,
+  rgba: Vec<u8>,
+}
+
+enum Frames {
+  Y4m {
+    file: BufReader<File>,
+    format: Y4mFormat,
+    /// Where the planes of every frame start.
+    offsets: Vec<u64>,
+  },
+  Images(Vec<PathBuf>),
+}
+
+impl Video {
+  /// Opens a `.y4m` file, or an image sequence for any other path.
+  pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+    let context = |e: Box<dyn Error>| format!("{}: {e}", path.display());
+    let is_y4m = path
+      .extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("y4m"));
+    let mut video = if is_y4m {
+      Self::open_y4m(path).map_err(context)?
+    } else {
+      Self::open_sequence(path).map_err(context)?
+    };
+    video.seek(0.0).map_err(context)?;
+    Ok(video)
+  }
+
+  fn open_y4m(path: &Path) -> Result<Self, Box<dyn Error>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut header = Vec::new();
+    file.read_until(b'\n', &mut header)?;
+    let (format, frame_rate) = Y4mFormat::parse(&header)?;
+    let frame_size = format.frame_size() as u64;
+    let mut offsets = Vec::new();
+    let mut position = header.len() as u64;
+    let mut frame_header = Vec::new();
+    let length = file.get_ref().metadata()?.len();
+    loop {
+      frame_header.clear();
+      let read = file.read_until(b'\n', &mut frame_header)?;
+      if read == 0 || !frame_header.starts_with(b"FRAME") {
+        break;
+      }
+      position += read as u64;
+      // A file that was cut short ends at its last complete frame.
+      if position + frame_size > length {
+        break;
+      }
+      offsets.push(position);
+      position += frame_size;
+      file.seek(SeekFrom::Start(position))?;
+    }
+    if offsets.is_empty() {
+      return Err("no frames".into());
+    }
+    Ok(Self {
+      width: format.width,
+      height: format.height,
+      frames: Frames::Y4m {
+        file,
+        format,
+        offsets,
+      },
+      frame_rate,
+      current: None,
+      rgba: Vec::new(),
+    })
+  }
+
+  fn open_sequence(path: &Path) -> Result<Self, Box<dyn Error>> {
+    let paths = if path.is_dir() {
+      let mut paths: Vec<PathBuf> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+      paths.retain(|path| {
+        path
+          .extension()
+          .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+      });
+      paths.sort();
+      paths
+    } else {
+      numbered_paths(path)?
+    };
+    let Some(first) = paths.first() else {
+      return Err("no PNG files".into());
+    };
+    let (width, height, _) = decode_png(first)?;
+    Ok(Self {
+      frames: Frames::Images(paths),
+      width,
+      height,
+      frame_rate: SEQUENCE_RATE,
+      current: None,
+      rgba: Vec::new(),
+    })
+  }
+
+  pub const fn size(&self) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+      width: self.width,
+      height: self.height,
+      depth_or_array_layers: 1,
+    }
+  }
+
+  fn frame_count(&self) -> usize {
+    match &self.frames {
+      Frames::Y4m { offsets, .. } => offsets.len(),
+      Frames::Images(paths) => paths.len(),
+    }
+  }
+
+  /// `iChannelTime` at `time`, how far the current loop of the video has played.
+  pub fn playback_time(&self, time: f32) -> f32 {
+    time.rem_euclid(self.frame_count() as f32 / self.frame_rate)
+  }
+
+  /// The RGBA texels of the frame of the last [`Video::seek`], rows from the bottom up.
+  pub fn rgba(&self) -> &[u8] {
+    &self.rgba
+  }
+
+  /// Decodes the frame at `time` unless it is the current one already. If that fails the
+  /// previous frame stays, and the error is only returned once per frame.
+  #[expect(clippy::cast_sign_loss, reason = "the playback time is not negative")]
+  pub fn seek(&mut self, time: f32) -> Result<(), Box<dyn Error>> {
+    let index = ((self.playback_time(time) * self.frame_rate) as usize).min(self.frame_count() - 1);
+    if self.current == Some(index) {
+      return Ok(());
+    }
+    self.current = Some(index);
+    let (width, height) = (self.width, self.height);
+    let rgba = match &mut self.frames {
+      Frames::Y4m {
+        file,
+        format,
+        offsets,
+      } => {
+        let mut planes = vec![0; format.frame_size()];
+        file.seek(SeekFrom::Start(offsets[index]))?;
+        file.read_exact(&mut planes)?;
+        format.to_rgba(&planes)
+      },
+      Frames::Images(paths) => {
+        let (frame_width, frame_height, rgba) = decode_png(&paths[index])?;
+        if (frame_width, frame_height) != (width, height) {
+          return Err(
+            format!(
+              "{} is {frame_width}x{frame_height}, the first frame {width}x{height}",
+              paths[index].display()
+            )
+            .into(),
+          );
+        }
+        rgba
+      },
+    };
+    self.rgba = rgba;
+    Ok(())
+  }
+}
+
+/// The files of a pattern like `frames/%04d.png`, from 0 or 1 up to the first missing number.
+fn numbered_paths(pattern: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+  let pattern = pattern.to_str().ok_or("the path is not valid UTF-8")?;
+  let invalid = || "not a directory or a pattern with one `%d` or `%0<width>d`";
+  let (prefix, rest) = pattern.split_once('%').ok_or_else(invalid)?;
+  let (width, suffix) = rest.split_once('d').ok_or_else(invalid)?;
+  let width: usize = if width.is_empty() {
+    0
+  } else {
+    width.parse().map_err(|_| invalid())?
+  };
+  let path = |number: usize| PathBuf::from(format!("{prefix}{number:0width$}{suffix}"));
+  let first = (0..=1)
+    .find(|&number| path(number).is_file())
+    .ok_or("no files")?;
+  let mut paths = Vec::new();
+  for number in first.. {
+    let path = path(number);
+    if !path.is_file() {
+      break;
+    }
+    paths.push(path);
+  }
+  Ok(paths)
+}
+
+/// The size and the RGBA texels of a PNG file, rows from the bottom up.
+#[cfg(not(target_arch = "wasm32"))]
+fn decode_png(path: &Path) -> Result<(u32, u32, Vec<u8>), Box<dyn Error>> {
+  let context = |e: png::DecodingError| format!("{}: {e}", path.display());
+  let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+  decoder.set_transformations(png::Transformations::normalize_to_color8());
+  let mut reader = decoder.read_info().map_err(context)?;
+  let mut buffer = vec![
+    0;
+    reader
+      .output_buffer_size()
+      .ok_or("the image is too large")?
+  ];
+  let info = reader.next_frame(&mut buffer).map_err(context)?;
+  let expand: fn(&[u8]) -> [u8; 4] = match info.color_type {
+    png::ColorType::Rgba => |p| [p[0], p[1], p[2], p[3]],
+    png::ColorType::Rgb => |p| [p[0], p[1], p[2], 255],
+    png::ColorType::GrayscaleAlpha => |p| [p[0], p[0], p[0], p[1]],
+    png::ColorType::Grayscale => |p| [p[0], p[0], p[0], 255],
+    png::ColorType::Indexed => return Err("indexed colors were not expanded".into()),
+  };
+  let channels = info.color_type.samples();
+  let rgba = buffer[..info.buffer_size()]
+    .chunks_exact(info.line_size)
+    .rev()
+    .flat_map(|row| row.chunks_exact(channels).flat_map(expand))
+    .collect();
+  Ok((info.width, info.height, rgba))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn decode_png(_: &Path) -> Result<(u32, u32, Vec<u8>), Box<dyn Error>> {
+  Err("image sequences need the native build".into())
+}
+
+/// The layout of the planes of a `.y4m` file, 8 bits per sample.
+struct Y4mFormat {
+  width: u32,
+  height: u32,
+  /// Luma samples per chroma sample, horizontally and vertically, `None` for `mono`.
+  subsampling: Option<(u32, u32)>,
+  /// Whether the samples use all of `0..=255`, rather than `16..=235` for luma.
+  full_range: bool,
+}
+
+impl Y4mFormat {
+  /// Parses the header line and returns the format and the frame rate.
+  fn parse(header: &[u8]) -> Result<(Self, f32), Box<dyn Error>> {
+    let header = core::str::from_utf8(header).map_err(|_| "not a YUV4MPEG2 file")?;
+    let mut params = header.trim_end().split(' ');
+    if params.next() != Some("YUV4MPEG2") {
+      return Err("not a YUV4MPEG2 file".into());
+    }
+    let mut format = Self {
+      width: 0,
+      height: 0,
+      subsampling: Some((2, 2)),
+      full_range: false,
+    };
+    let mut frame_rate = 25.0;
+    for param in params {
+      let invalid = || format!("invalid header parameter `{param}`");
+      let (tag, value) = param.split_at_checked(1).ok_or_else(invalid)?;
+      match tag {
+        "W" => format.width = value.parse().map_err(|_| invalid())?,
+        "H" => format.height = value.parse().map_err(|_| invalid())?,
+        "F" => {
+          let (numerator, denominator) = value.split_once(':').ok_or_else(invalid)?;
+          let numerator: f32 = numerator.parse().map_err(|_| invalid())?;
+          let denominator: f32 = denominator.parse().map_err(|_| invalid())?;
+          frame_rate = numerator / denominator;
+        },
+        "C" => {
+          format.subsampling = match value {
+            "420" | "420jpeg" | "420paldv" | "420mpeg2" => Some((2, 2)),
+            "422" => Some((2, 1)),
+            "444" => Some((1, 1)),
+            "mono" => None,
+            _ => return Err(format!("unsupported color space `{value}`, use 8-bit 4:2:0").into()),
+          }
+        },
+        "X" => format.full_range |= value == "COLORRANGE=FULL",
+        _ => {},
+      }
+    }
+    if format.width == 0 || format.height == 0 {
+      return Err("no frame size".into());
+    }
+    if !frame_rate.is_finite() || frame_rate <= 0.0 {
+      return Err("invalid frame rate".into());
+    }
+    Ok((format, frame_rate))
+  }
+
+  /// The size of a chroma plane.
+  fn chroma_size(&self) -> (usize, usize) {
+    self.subsampling.map_or((0, 0), |(x, y)| {
+      (
+        self.width.div_ceil(x) as usize,
+        self.height.div_ceil(y) as usize,
+      )
+    })
+  }
+
+  fn frame_size(&self) -> usize {
+    let (chroma_width, chroma_height) = self.chroma_size();
+    self.width as usize * self.height as usize + 2 * chroma_width * chroma_height
+  }
+
+  /// Converts the planes of a frame with the BT.601 matrix.
+  fn to_rgba(&self, planes: &[u8]) -> Vec<u8> {
+    let (width, height) = (self.width as usize, self.height as usize);
+    let (chroma_width, chroma_height) = self.chroma_size();
+    let (luma, chroma) = planes.split_at(width * height);
+    let (u_plane, v_plane) = chroma.split_at(chroma_width * chroma_height);
+    let (x_step, y_step) = self.subsampling.unwrap_or((1, 1));
+    let (luma_scale, chroma_scale, black) = if self.full_range {
+      (1.0, 1.0, 0.0)
+    } else {
+      (255.0 / 219.0, 255.0 / 224.0, 16.0)
+    };
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in (0..height).rev() {
+      for x in 0..width {
+        let luma = (f32::from(luma[y * width + x]) - black) * luma_scale;
+        let (u, v) = if self.subsampling.is_some() {
+          let index = y / y_step as usize * chroma_width + x / x_step as usize;
+          (
+            (f32::from(u_plane[index]) - 128.0) * chroma_scale,
+            (f32::from(v_plane[index]) - 128.0) * chroma_scale,
+          )
+        } else {
+          (0.0, 0.0)
+        };
+        rgba.extend([
+          to_u8(1.402f32.mul_add(v, luma)),
+          to_u8(0.714_136f32.mul_add(-v, 0.344_136f32.mul_add(-u, luma))),
+          to_u8(1.772f32.mul_add(u, luma)),
+          255,
+        ]);
+      }
+    }
+    rgba
+  }
+}
+
+#[expect(clippy::cast_sign_loss, reason = "clamped to `[0, 255]` first")]
+fn to_u8(value: f32) -> u8 {
+  value.round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn y4m_headers_parse() {
+    let (format, frame_rate) =
+      Y4mFormat::parse(b"YUV4MPEG2 W5 H3 F30000:1001 Ip A1:1 C422 XCOLORRANGE=FULL\n").unwrap();
+    assert_eq!((format.width, format.height), (5, 3));
+    assert_eq!(format.subsampling, Some((2, 1)));
+    assert!(format.full_range);
+    assert!((frame_rate - 29.97).abs() < 0.01, "{frame_rate}");
+    // Odd sizes round the chroma planes up.
+    assert_eq!(format.chroma_size(), (3, 3));
+    assert_eq!(format.frame_size(), 15 + 2 * 9);
+
+    let (format, frame_rate) = Y4mFormat::parse(b"YUV4MPEG2 W4 H2 Cmono XYSCSS=MONO").unwrap();
+    assert_eq!(format.subsampling, None);
+    assert!(!format.full_range);
+    assert!((frame_rate - 25.0).abs() < f32::EPSILON);
+    assert_eq!(format.frame_size(), 8);
+
+    let (format, _) = Y4mFormat::parse(b"YUV4MPEG2 W4 H4 C420jpeg XCOLORRANGE=LIMITED").unwrap();
+    assert_eq!(format.subsampling, Some((2, 2)));
+    assert!(!format.full_range);
+  }
+
+  #[test]
+  fn invalid_y4m_headers_are_rejected() {
+    for header in [
+      &b"YUV4MPEG W4 H4"[..],
+      b"YUV4MPEG2 W4",
+      b"YUV4MPEG2 W4 H4 C420p10",
+      b"YUV4MPEG2 W4 H4 F30",
+      b"YUV4MPEG2 W4 H4 F30:0",
+      b"YUV4MPEG2 W4 H4  F30:1",
+      b"YUV4MPEG2 Wfour H4",
+    ] {
+      assert!(
+        Y4mFormat::parse(header).is_err(),
+        "{}",
+        String::from_utf8_lossy(header)
+      );
+    }
+  }
+
+  #[test]
+  fn y4m_frames_convert_bottom_up() {
+    let (format, _) = Y4mFormat::parse(b"YUV4MPEG2 W2 H2 C420 XCOLORRANGE=FULL").unwrap();
+    // A white top row and a black bottom row, without color.
+    let rgba = format.to_rgba(&[255, 255, 0, 0, 128, 128]);
+    assert_eq!(rgba[..8], [0, 0, 0, 255, 0, 0, 0, 255]);
+    assert_eq!(rgba[8..], [255, 255, 255, 255, 255, 255, 255, 255]);
+
+    let (format, _) = Y4mFormat::parse(b"YUV4MPEG2 W1 H1 C444").unwrap();
+    // Limited range black and full red.
+    assert_eq!(format.to_rgba(&[16, 128, 128]), [0, 0, 0, 255]);
+    let red = format.to_rgba(&[81, 90, 240]);
+    let expected = [255, 0, 0, 255];
+    assert!(
+      red.iter().zip(expected).all(|(&a, b)| a.abs_diff(b) <= 1),
+      "{red:?}"
+    );
+  }
+}
@@ -0,0 +1,179 @@
+//! WAV files: stereo 16-bit PCM out, most uncompressed formats in.
+
+use core::error::Error;
+use std::{
+  fs::File,
+  io::{self, BufWriter, Write},
+  path::Path,
+};
+
+const CHANNELS: u16 = 2;
+const BITS_PER_SAMPLE: u16 = 16;
+/// `WAVE_FORMAT_PCM`.
+const FORMAT_PCM: u16 = 1;
+/// `WAVE_FORMAT_IEEE_FLOAT`.
+const FORMAT_FLOAT: u16 = 3;
+/// `WAVE_FORMAT_EXTENSIBLE`, the actual format is at the start of the sub format GUID.
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// A decoded WAV file.
+pub struct Wav {
+  pub sample_rate: u32,
+  /// Samples in `[-1, 1]`, the average of the channels.
+  pub mono: Vec<f32>,
+}
+
+/// Decodes a file with 8, 16, 24 or 32-bit integer or 32-bit float samples.
+pub fn read(bytes: &[u8]) -> Result<Wav, Box<dyn Error>> {
+  let invalid = |what: &str| format!("not a supported WAV file, {what}");
+  if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+    return Err(invalid("no RIFF header").into());
+  }
+  let mut format = None;
+  let mut data = None;
+  let mut rest = &bytes[12..];
+  while rest.len() >= 8 {
+    let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+    // Files that were not finished properly have shorter data chunks than they say.
+    let body = rest.get(8..8 + size).unwrap_or_else(|| &rest[8..]);
+    match &rest[..4] {
+      b"fmt " => format = Some(body),
+      b"data" => data = Some(body),
+      _ => {},
+    }
+    // Chunks are padded to an even size.
+    rest = rest.get(8 + size + size % 2..).unwrap_or_default();
+  }
+  let format = format
+    .filter(|format| format.len() >= 16)
+    .ok_or_else(|| invalid("no format chunk"))?;
+  let data = data.ok_or_else(|| invalid("no data chunk"))?;
+  let u16_at = |at: usize| u16::from_le_bytes([format[at], format[at + 1]]);
+  let mut tag = u16_at(0);
+  if tag == FORMAT_EXTENSIBLE && format.len() >= 26 {
+    tag = u16_at(24);
+  }
+  let channels = usize::from(u16_at(2));
+  let sample_rate = u32::from_le_bytes([format[4], format[5], format[6], format[7]]);
+  let bits = u16_at(14);
+  let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+    (FORMAT_PCM, 8) => |s| (f32::from(s[0]) - 128.0) / 128.0,
+    (FORMAT_PCM, 16) => |s| f32::from(i16::from_le_bytes([s[0], s[1]])) / 32_768.0,
+    (FORMAT_PCM, 24) => |s| (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f32 / 8_388_608.0,
+    (FORMAT_PCM, 32) => |s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2_147_483_648.0,
+    (FORMAT_FLOAT, 32) => |s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]),
+    _ => return Err(invalid(&format!("format {tag} with {bits} bits per sample")).into()),
+  };
+  if channels == 0 || sample_rate == 0 {
+    return Err(invalid("no channels or no sample rate").into());
+  }
+  let sample_size = usize::from(bits / 8);
+  let mono = data
+    .chunks_exact(channels * sample_size)
+    .map(|frame| frame.chunks_exact(sample_size).map(decode).sum::<f32>() / channels as f32)
+    .collect();
+  Ok(Wav { sample_rate, mono })
+}
+
+/// Writes `frames`, left and right samples in `[-1, 1]`, to a WAV file at `path`.
+pub fn write(path: &Path, sample_rate: u32, frames: &[[f32; 2]]) -> io::Result<()> {
+  let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+  let data_size = u32::try_from(frames.len() * usize::from(block_align))
+    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too long for a WAV file"))?;
+  let mut file = BufWriter::new(File::create(path)?);
+  file.write_all(b"RIFF")?;
+  // The size of everything after this field.
+  file.write_all(&(36 + data_size).to_le_bytes())?;
+  file.write_all(b"WAVE")?;
+
+  file.write_all(b"fmt ")?;
+  file.write_all(&16_u32.to_le_bytes())?;
+  file.write_all(&FORMAT_PCM.to_le_bytes())?;
+  file.write_all(&CHANNELS.to_le_bytes())?;
+  file.write_all(&sample_rate.to_le_bytes())?;
+  file.write_all(&(sample_rate * u32::from(block_align)).to_le_bytes())?;
+  file.write_all(&block_align.to_le_bytes())?;
+  file.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+  file.write_all(b"data")?;
+  file.write_all(&data_size.to_le_bytes())?;
+  for sample in frames.iter().flatten() {
+    file.write_all(&pcm16(*sample).to_le_bytes())?;
+  }
+  file.flush()
+}
+
+fn pcm16(sample: f32) -> i16 {
+  (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)).round() as i16
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::{env, fs, process};
+
+  /// A file with a single `fmt ` chunk of `format`, `channels` and `bits`, then `data`.
+  fn wav_bytes(format: u16, channels: u16, bits: u16, data: &[u8]) -> Vec<u8> {
+    let mut bytes = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    bytes.extend_from_slice(&format.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&8_000_u32.to_le_bytes());
+    bytes.extend_from_slice(&[0; 6]);
+    bytes.extend_from_slice(&bits.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+  }
+
+  #[test]
+  fn written_files_read_back() {
+    let path = env::temp_dir().join(format!("shadertoys_wav_{}.wav", process::id()));
+    let frames = [
+      [0.0, 0.0],
+      [1.0, 0.5],
+      [-1.0, -0.5],
+      [2.0, 2.0],
+      [0.25, -0.25],
+    ];
+    write(&path, 22_050, &frames).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(bytes.len(), 44 + frames.len() * 4);
+    let wav = read(&bytes).unwrap();
+    assert_eq!(wav.sample_rate, 22_050);
+    // The channels are averaged, and clamped on the way out.
+    let expected = [0.0, 0.75, -0.75, 1.0, 0.0];
+    assert_eq!(wav.mono.len(), expected.len());
+    for (sample, expected) in wav.mono.iter().zip(expected) {
+      assert!((sample - expected).abs() < 1e-4, "{sample} != {expected}");
+    }
+  }
+
+  #[test]
+  fn other_sample_formats_decode() {
+    let float = wav_bytes(FORMAT_FLOAT, 1, 32, &0.5_f32.to_le_bytes());
+    assert_eq!(read(&float).unwrap().mono, [0.5]);
+    let unsigned = wav_bytes(FORMAT_PCM, 1, 8, &[0, 128, 192]);
+    assert_eq!(read(&unsigned).unwrap().mono, [-1.0, 0.0, 0.5]);
+    let packed = wav_bytes(FORMAT_PCM, 1, 24, &[0, 0, 0xC0]);
+    assert_eq!(read(&packed).unwrap().mono, [-0.5]);
+  }
+
+  #[test]
+  fn truncated_data_is_kept() {
+    let mut bytes = wav_bytes(FORMAT_PCM, 1, 16, &[0, 0x40, 0, 0xC0]);
+    // Claims more samples than the file holds.
+    let size_at = bytes.len() - 8;
+    bytes[size_at..size_at + 4].copy_from_slice(&100_u32.to_le_bytes());
+    assert_eq!(read(&bytes).unwrap().mono, [0.5, -0.5]);
+  }
+
+  #[test]
+  fn unsupported_files_are_rejected() {
+    assert!(read(b"RIFX\0\0\0\0WAVE").is_err());
+    assert!(read(&wav_bytes(FORMAT_FLOAT, 1, 64, &[0; 8])).is_err());
+    assert!(read(&wav_bytes(FORMAT_PCM, 0, 16, &[0; 2])).is_err());
+  }
+}